@@ -137,6 +137,16 @@ function shellQuote(value: string): string {
   return `'${value.replace(/'/g, `'\"'\"'`)}'`;
 }
 
+// PremiumName: disc(8) name_hash(32) owner(32) nft_mint(32).
+// SubName: disc(8) parent_hash(32) label_hash(32) owner(32) transfer_policy(1)
+// parent_owner(32) transfers_enabled(1) created_at(8) nft_mint(32).
+function readIssuedNftMint(data: Buffer, premium: boolean): PublicKey | null {
+  const offset = premium ? 8 + 64 : 8 + 138;
+  if (data.length < offset + 32) return null;
+  const mint = new PublicKey(data.subarray(offset, offset + 32));
+  return mint.equals(PublicKey.default) ? null : mint;
+}
+
 function derivePdas(programId: PublicKey, name: string, walletPubkey?: string | null) {
   const normalized = normalizeDnsName(name);
   const parsed = splitDnsName(normalized);
@@ -147,18 +157,14 @@ function derivePdas(programId: PublicKey, name: string, walletPubkey?: string |
   if (parsed.mode === "premium") {
     const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), nameHash], programId);
     const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), nameHash], programId);
-    const [premiumMintPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_nft_mint"), nameHash], programId);
     pdas.premium_name = premiumPda.toBase58();
     pdas.parent_policy = policyPda.toBase58();
-    pdas.nft_mint = premiumMintPda.toBase58();
   } else {
     const parentHash = Buffer.from(awaitableSha256(parsed.parent || ""));
     const labelHash = Buffer.from(awaitableSha256(parsed.label));
     const [subPda] = PublicKey.findProgramAddressSync([Buffer.from("sub"), parentHash, labelHash], programId);
-    const [subMintPda] = PublicKey.findProgramAddressSync([Buffer.from("sub_nft_mint"), parentHash, labelHash], programId);
     const [custodyAuthorityPda] = PublicKey.findProgramAddressSync([Buffer.from("nft_custody_authority")], programId);
     pdas.sub_name = subPda.toBase58();
-    pdas.nft_mint = subMintPda.toBase58();
     pdas.nft_custody_authority = custodyAuthorityPda.toBase58();
   }
   if (walletPubkey) {
@@ -280,6 +286,12 @@ export async function getNamesAvailability(
       ? new PublicKey(pdas.premium_name)
       : new PublicKey(pdas.sub_name);
   const registrationAccount = await connection.getAccountInfo(registrationPda);
+  if (registrationAccount) {
+    // The NFT mint is seeded by the registration's created_at, so it is only
+    // known once the name is registered; report it when one was issued.
+    const mint = readIssuedNftMint(registrationAccount.data, parsed.mode === "premium");
+    if (mint) pdas.nft_mint = mint.toBase58();
+  }
 
   const available = Boolean(programAccount?.executable) && Boolean(configAccount) && !registrationAccount;
   let reason = "available";
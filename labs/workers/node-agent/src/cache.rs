@@ -33,6 +33,11 @@ impl Cache {
     self.entries.get(key).cloned()
   }
 
+  pub fn keys(&mut self) -> Vec<String> {
+    self.evict_expired();
+    self.entries.keys().cloned().collect()
+  }
+
   pub fn set(&mut self, key: String, body: String, ttl: Duration, result_hash: String) {
     let bytes = body.len();
     if bytes > self.max_bytes {
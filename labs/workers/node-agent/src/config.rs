@@ -9,12 +9,114 @@ pub struct RegistryConfig {
   pub poll_interval_seconds: u64,
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct QuorumConfig {
+  pub enabled: bool,
+  /// Number of upstreams queried per name; a majority of them must agree.
+  pub sample_size: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SolanaConfig {
+  pub rpc_url: String,
+  pub registry_program_id: String,
+  pub watchdog_policy_program_id: String,
+  pub operators_program_id: String,
+}
+
+impl Default for SolanaConfig {
+  fn default() -> Self {
+    Self {
+      rpc_url: "https://api.devnet.solana.com".to_string(),
+      registry_program_id: "GDVsLPGxcZLqxj45UujJfEWpXiMwJid8B5a428VZ12QL".to_string(),
+      watchdog_policy_program_id: "Bf3Who54hZMHxubT3YgPZRHNitdQLqKA8HR9Hzbmog3e".to_string(),
+      operators_program_id: "Dg8GH1TpVak87wdysAG1UhKCW3w5osMg87KZsSSMVzP5".to_string(),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+  pub enabled: bool,
+  pub interval_seconds: u64,
+  /// Digests are submitted once the current epoch has this many slots or fewer left.
+  pub submit_margin_slots: u64,
+}
+
+impl Default for WatchdogConfig {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      interval_seconds: 120,
+      submit_margin_slots: 300,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuarantineAction {
+  /// Do not serve quarantined names at all.
+  Refuse,
+  /// Serve with the policy's TTL cap and a warning header, like `Warn`.
+  Downgrade,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolicyConfig {
+  pub enabled: bool,
+  pub poll_interval_seconds: u64,
+  pub quarantine_action: QuarantineAction,
+}
+
+impl Default for PolicyConfig {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      poll_interval_seconds: 60,
+      quarantine_action: QuarantineAction::Refuse,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperatorConfig {
+  pub enabled: bool,
+  /// Public DoH URL; advertised on-chain as its sha256.
+  pub public_doh_url: Option<String>,
+  /// Public IPs; when empty, the `listen_addr` IP is used if it is routable.
+  pub public_ips: Vec<String>,
+  /// Region code stored with every endpoint (0 = unknown).
+  pub region: u16,
+  pub sync_interval_seconds: u64,
+}
+
+impl Default for OperatorConfig {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      public_doh_url: None,
+      public_ips: Vec::new(),
+      region: 0,
+      sync_interval_seconds: 300,
+    }
+  }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
   pub listen_addr: String,
   pub data_dir: String,
   pub coordinator_url: String,
   pub resolver_url: String,
+  #[serde(default)]
+  pub upstreams: Vec<String>,
+  #[serde(default)]
+  pub upstream_quorum: QuorumConfig,
   pub hot_names: Vec<String>,
   pub prefetch_interval_seconds: u64,
   pub request_timeout_ms: u64,
@@ -22,6 +124,14 @@ pub struct Config {
   pub max_cached_bytes: usize,
   pub rate_limit_rps: u32,
   pub registry: RegistryConfig,
+  #[serde(default)]
+  pub solana: SolanaConfig,
+  #[serde(default)]
+  pub watchdog: WatchdogConfig,
+  #[serde(default)]
+  pub policy: PolicyConfig,
+  #[serde(default)]
+  pub operator: OperatorConfig,
 }
 
 impl Default for Config {
@@ -31,6 +141,8 @@ impl Default for Config {
       data_dir: "/var/lib/ddns-node".to_string(),
       coordinator_url: "https://your-coordinator.example/receipts".to_string(),
       resolver_url: "https://your-gateway.example/resolve".to_string(),
+      upstreams: Vec::new(),
+      upstream_quorum: QuorumConfig { enabled: false, sample_size: 3 },
       hot_names: vec!["example.com".to_string()],
       prefetch_interval_seconds: 60,
       request_timeout_ms: 5000,
@@ -42,6 +154,21 @@ impl Default for Config {
         root_url: "https://your-gateway.example/registry/root".to_string(),
         poll_interval_seconds: 60,
       },
+      solana: SolanaConfig::default(),
+      watchdog: WatchdogConfig::default(),
+      policy: PolicyConfig::default(),
+      operator: OperatorConfig::default(),
+    }
+  }
+}
+
+impl Config {
+  /// Upstream gateways used for prefetch; falls back to `resolver_url`.
+  pub fn upstream_urls(&self) -> Vec<String> {
+    if self.upstreams.is_empty() {
+      vec![self.resolver_url.clone()]
+    } else {
+      self.upstreams.clone()
     }
   }
 }
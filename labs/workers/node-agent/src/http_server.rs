@@ -1,10 +1,13 @@
 use axum::{extract::{Query, State}, http::StatusCode, response::IntoResponse, routing::{get, post}, Json, Router};
 use governor::{Quota, RateLimiter};
-use std::{num::NonZeroU32, sync::Arc, time::{SystemTime, UNIX_EPOCH}};
+use std::{num::NonZeroU32, sync::Arc, time::{Instant, SystemTime, UNIX_EPOCH}};
 use tokio::sync::Mutex;
 use tracing::warn;
 
-use crate::{cache::Cache, coordinator::CoordinatorClient, receipts::{Receipt, ReceiptRequest, sign_receipt}, tasks::storage::respond_to_audit_stub};
+use crate::{cache::Cache, config::QuarantineAction, coordinator::CoordinatorClient, onchain::{decode_record_set, hash_name, normalize_name, record_set_address}, receipts::{Receipt, ReceiptRequest, sign_receipt}, tasks::storage::respond_to_audit_stub};
+use crate::solana::{Pubkey, RpcClient};
+use crate::tasks::operator::{operators_in_region, AdvertisedEndpoint, OperatorDirectory};
+use crate::tasks::policy::{decide, PolicyMap, ServeDecision};
 use ed25519_dalek::SigningKey;
 
 #[derive(Clone)]
@@ -14,6 +17,12 @@ pub struct AppState {
   pub signing_key: Arc<SigningKey>,
   pub node_id: String,
   pub limiter: Arc<RateLimiter<governor::state::direct::NotKeyed, governor::state::InMemoryState, governor::clock::DefaultClock>>,
+  pub policies: PolicyMap,
+  pub quarantine_action: QuarantineAction,
+  pub advertised_endpoints: Arc<Vec<AdvertisedEndpoint>>,
+  pub operator_directory: OperatorDirectory,
+  pub rpc: Arc<RpcClient>,
+  pub registry_program: Pubkey,
 }
 
 #[derive(serde::Deserialize)]
@@ -21,11 +30,26 @@ struct ResolveParams {
   name: String,
 }
 
+#[derive(serde::Deserialize)]
+struct RecordsParams {
+  name: String,
+  #[serde(rename = "type")]
+  rrtype: String,
+}
+
+#[derive(serde::Deserialize)]
+struct OperatorsParams {
+  region: Option<u16>,
+}
+
 pub fn build_router(state: AppState) -> Router {
   Router::new()
     .route("/healthz", get(healthz))
     .route("/resolve", get(resolve))
     .route("/audit", post(audit_stub))
+    .route("/operator", get(operator_self))
+    .route("/operators", get(operators))
+    .route("/records", get(records))
     .with_state(state)
 }
 
@@ -44,12 +68,76 @@ async fn audit_stub(State(state): State<AppState>) -> impl IntoResponse {
   }
 }
 
+async fn operator_self(State(state): State<AppState>) -> impl IntoResponse {
+  Json(serde_json::json!({
+    "operator_wallet": Pubkey::from_signing_key(&state.signing_key).to_string(),
+    "endpoints": state.advertised_endpoints.as_ref(),
+  }))
+}
+
+async fn operators(State(state): State<AppState>, Query(params): Query<OperatorsParams>) -> impl IntoResponse {
+  if state.limiter.check().is_err() {
+    return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({"error": "rate_limited"}))).into_response();
+  }
+  let directory = state.operator_directory.read().await;
+  (StatusCode::OK, Json(serde_json::json!({"operators": operators_in_region(&directory, params.region)}))).into_response()
+}
+
+/// Finalized on-chain record set for `name` and `type` (e.g. `A`, `MX`).
+async fn records(State(state): State<AppState>, Query(params): Query<RecordsParams>) -> impl IntoResponse {
+  if state.limiter.check().is_err() {
+    return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({"error": "rate_limited"}))).into_response();
+  }
+  let Some(rrtype) = ddns_rrset::type_from_name(&params.rrtype) else {
+    return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "unsupported_type"}))).into_response();
+  };
+  let Ok(name) = normalize_name(&params.name) else {
+    return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "invalid_name"}))).into_response();
+  };
+  let address = record_set_address(&state.registry_program, &hash_name(&name), rrtype);
+  let data = match state.rpc.get_account_data(&address).await {
+    Ok(Some(data)) => data,
+    Ok(None) => return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "not_found"}))).into_response(),
+    Err(err) => {
+      warn!("record set fetch failed: {}", err);
+      return (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": "rpc_failed"}))).into_response();
+    }
+  };
+  match decode_record_set(&data) {
+    Ok(set) => (StatusCode::OK, Json(serde_json::json!({
+      "name": name,
+      "type": ddns_rrset::type_name(set.rrtype),
+      "ttl_s": set.ttl_s,
+      "version": set.version,
+      "rrset_hash": hex::encode(set.rrset_hash),
+      "records": set.records.iter().map(|r| r.to_string()).collect::<Vec<_>>(),
+      "updated_at_slot": set.updated_at_slot,
+    }))).into_response(),
+    Err(err) => {
+      warn!("invalid record set account {}: {}", address, err);
+      (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": "invalid_record_set"}))).into_response()
+    }
+  }
+}
+
 async fn resolve(State(state): State<AppState>, Query(params): Query<ResolveParams>) -> impl IntoResponse {
   if state.limiter.check().is_err() {
     return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({"error": "rate_limited"}))).into_response();
   }
   let mut cache = state.cache.lock().await;
   if let Some(item) = cache.get(&params.name) {
+    let remaining_ttl_s = item.expires_at.saturating_duration_since(Instant::now()).as_secs();
+    let decision = {
+      let policies = state.policies.read().await;
+      let policy = normalize_name(&params.name).ok().and_then(|name| policies.get(&name));
+      decide(policy, remaining_ttl_s, state.quarantine_action)
+    };
+    let (max_age_s, applied) = match decision {
+      ServeDecision::Refuse(applied) => {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"error": "quarantined", "policy": applied}))).into_response();
+      }
+      ServeDecision::Serve { max_age_s, policy } => (max_age_s, policy),
+    };
     let receipt = Receipt {
       receipt_type: "SERVE".to_string(),
       node_id: state.node_id.clone(),
@@ -57,7 +145,7 @@ async fn resolve(State(state): State<AppState>, Query(params): Query<ResolvePara
       request: Some(ReceiptRequest { name: params.name.clone() }),
       result_hash: Some(item.result_hash.clone()),
       bytes: Some(item.bytes as u64),
-      details: None,
+      details: applied.as_ref().map(|p| serde_json::json!({ "policy": p })),
     };
     let envelope = match sign_receipt(&state.signing_key, receipt) {
       Ok(env) => env,
@@ -70,7 +158,16 @@ async fn resolve(State(state): State<AppState>, Query(params): Query<ResolvePara
     tokio::spawn(async move {
       let _ = coordinator.post_receipt(&envelope).await;
     });
-    return (StatusCode::OK, [ ("content-type", "application/json") ], item.body).into_response();
+    let cache_control = format!("max-age={}", max_age_s);
+    if let Some(applied) = applied {
+      let policy_header = format!("{}; penalty_bps={}", applied.status.as_str(), applied.penalty_bps);
+      return (
+        StatusCode::OK,
+        [ ("content-type", "application/json".to_string()), ("cache-control", cache_control), ("x-ddns-policy", policy_header) ],
+        item.body,
+      ).into_response();
+    }
+    return (StatusCode::OK, [ ("content-type", "application/json".to_string()), ("cache-control", cache_control) ], item.body).into_response();
   }
   (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "not_cached"}))).into_response()
 }
@@ -5,8 +5,11 @@ mod cache;
 mod http_server;
 mod http_client;
 mod keys;
+mod onchain;
 mod receipts;
+mod solana;
 mod tasks;
+mod upstreams;
 mod verify;
 #[cfg(test)]
 mod tests;
@@ -22,8 +25,11 @@ use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 use tracing::info;
 use tracing_subscriber::EnvFilter;
-use tasks::{prefetch::run_prefetch_loop, registry_root::run_registry_root_loop};
+use tasks::{prefetch::{run_prefetch_loop, PrefetchContext}, registry_root::run_registry_root_loop};
 use tasks::registry_root::RegistryRoot;
+use tasks::operator::{derive_endpoints, run_operator_loop, OperatorContext, OperatorDirectory};
+use tasks::policy::{run_policy_loop, PolicyMap};
+use tasks::watchdog::{run_watchdog_loop, WatchdogContext};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -69,21 +75,24 @@ async fn run_agent(config: Config) -> Result<()> {
   let registry_state: Arc<RwLock<Option<RegistryRoot>>> = Arc::new(RwLock::new(None));
 
   let client = http_client::build_client(config.request_timeout_ms)?;
+  let rpc = Arc::new(solana::RpcClient::new(client.clone(), config.solana.rpc_url.clone()));
+  let policies: PolicyMap = Arc::new(RwLock::new(Default::default()));
+  let operator_directory: OperatorDirectory = Arc::new(RwLock::new(Vec::new()));
+  let advertised_endpoints = Arc::new(derive_endpoints(&config.operator, &config.listen_addr));
 
-  let prefetch_cache = cache.clone();
-  let prefetch_client = client.clone();
+  let prefetch_ctx = PrefetchContext {
+    cache: cache.clone(),
+    client: client.clone(),
+    coordinator: coordinator.clone(),
+    signing_key: Arc::new(keys.signing.clone()),
+    node_id: material.public_key_b64.clone(),
+    upstreams: Arc::new(Mutex::new(upstreams::UpstreamPool::new(config.upstream_urls()))),
+    quorum: config.upstream_quorum.clone(),
+  };
   let prefetch_config = config.clone();
-  let prefetch_coordinator = coordinator.clone();
-  let prefetch_signing = Arc::new(keys.signing.clone());
-  let prefetch_node_id = material.public_key_b64.clone();
   tokio::spawn(async move {
     run_prefetch_loop(
-      prefetch_cache,
-      prefetch_client,
-      prefetch_coordinator,
-      prefetch_signing,
-      prefetch_node_id,
-      prefetch_config.resolver_url.clone(),
+      prefetch_ctx,
       prefetch_config.hot_names.clone(),
       prefetch_config.prefetch_interval_seconds,
       prefetch_config.prefetch_interval_seconds,
@@ -99,12 +108,60 @@ async fn run_agent(config: Config) -> Result<()> {
     });
   }
 
+  if config.watchdog.enabled {
+    let watchdog_ctx = WatchdogContext {
+      client: client.clone(),
+      rpc: rpc.clone(),
+      signing_key: Arc::new(keys.signing.clone()),
+      upstreams: config.upstream_urls(),
+      names: config.hot_names.clone(),
+      registry_program: config.solana.registry_program_id.parse()?,
+      policy_program: config.solana.watchdog_policy_program_id.parse()?,
+      submit_margin_slots: config.watchdog.submit_margin_slots,
+    };
+    let interval_seconds = config.watchdog.interval_seconds;
+    tokio::spawn(async move {
+      run_watchdog_loop(watchdog_ctx, interval_seconds).await;
+    });
+  }
+
+  if config.policy.enabled {
+    let policy_cache = cache.clone();
+    let policy_rpc = rpc.clone();
+    let policy_program = config.solana.watchdog_policy_program_id.parse()?;
+    let policy_state = policies.clone();
+    let interval_seconds = config.policy.poll_interval_seconds;
+    tokio::spawn(async move {
+      run_policy_loop(policy_cache, policy_rpc, policy_program, interval_seconds, policy_state).await;
+    });
+  }
+
+  if config.operator.enabled {
+    let operator_ctx = OperatorContext {
+      rpc: rpc.clone(),
+      signing_key: Arc::new(keys.signing.clone()),
+      operators_program: config.solana.operators_program_id.parse()?,
+      advertised: advertised_endpoints.clone(),
+      directory: operator_directory.clone(),
+    };
+    let interval_seconds = config.operator.sync_interval_seconds;
+    tokio::spawn(async move {
+      run_operator_loop(operator_ctx, interval_seconds).await;
+    });
+  }
+
   let state = AppState {
     cache,
     coordinator,
     signing_key: Arc::new(keys.signing),
     node_id: material.public_key_b64,
     limiter,
+    policies,
+    quarantine_action: config.policy.quarantine_action,
+    advertised_endpoints,
+    operator_directory,
+    rpc,
+    registry_program: config.solana.registry_program_id.parse()?,
   };
 
   let app = build_router(state);
@@ -0,0 +1,422 @@
+use crate::solana::{anchor_discriminator, find_program_address, AccountMeta, AccountReader, Instruction, Pubkey, SYSTEM_PROGRAM_ID};
+pub use ddns_names_core::{hash_name, NameError};
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+
+/// Normalization used for on-chain `name_hash` (see PROTOCOL_WATCHDOG_ATTESTATION).
+/// Names `ddns_names_core` rejects (e.g. `_dmarc` labels) can never be on chain.
+pub fn normalize_name(name: &str) -> Result<String, NameError> {
+  ddns_names_core::normalize_name(name)
+}
+
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+  Sha256::digest(bytes).into()
+}
+
+// ---- ddns_registry ----
+
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct CanonicalRoute {
+  pub name_hash: [u8; 32],
+  pub dest_hash: [u8; 32],
+  pub ttl_s: u32,
+  pub version: u64,
+  pub updated_at_slot: u64,
+}
+
+pub fn canonical_route_address(registry_program: &Pubkey, name_hash: &[u8; 32]) -> Pubkey {
+  find_program_address(&[b"canonical", name_hash], registry_program).0
+}
+
+pub fn decode_canonical_route(data: &[u8]) -> anyhow::Result<CanonicalRoute> {
+  let mut r = AccountReader::new(data, "CanonicalRoute")?;
+  Ok(CanonicalRoute {
+    name_hash: r.bytes32()?,
+    dest_hash: r.bytes32()?,
+    ttl_s: r.u32()?,
+    version: r.u64()?,
+    updated_at_slot: r.u64()?,
+  })
+}
+
+/// Decoded `ddns_registry::RecordSet`: every record of one type for a name.
+#[derive(Clone, Debug)]
+pub struct RecordSet {
+  pub rrtype: u16,
+  pub ttl_s: u32,
+  pub version: u64,
+  pub rrset_hash: [u8; 32],
+  pub records: Vec<ddns_rrset::Record>,
+  pub updated_at_slot: u64,
+}
+
+pub fn record_set_address(registry_program: &Pubkey, name_hash: &[u8; 32], rrtype: u16) -> Pubkey {
+  find_program_address(&[b"rrset", name_hash, &rrtype.to_le_bytes()], registry_program).0
+}
+
+pub fn decode_record_set(data: &[u8]) -> anyhow::Result<RecordSet> {
+  let mut r = AccountReader::new(data, "RecordSet")?;
+  let _name_hash = r.bytes32()?;
+  let rrtype = r.u16()?;
+  let ttl_s = r.u32()?;
+  let version = r.u64()?;
+  let rrset_hash = r.bytes32()?;
+  let encoded = r.byte_vec()?;
+  let updated_at_slot = r.u64()?;
+  let rrset = ddns_rrset::decode_rrset(&encoded)?;
+  if rrset.rrtype != rrtype || ddns_rrset::rrset_hash(&encoded) != rrset_hash {
+    anyhow::bail!("record set data does not match its header");
+  }
+  Ok(RecordSet { rrtype, ttl_s, version, rrset_hash, records: rrset.records, updated_at_slot })
+}
+
+// ---- ddns_watchdog_policy ----
+
+#[derive(Clone, Debug)]
+pub struct PolicyConfig {
+  pub epoch_len_slots: u64,
+  pub allowlisted_watchdogs: Vec<Pubkey>,
+}
+
+pub fn policy_config_address(policy_program: &Pubkey) -> Pubkey {
+  find_program_address(&[b"policy_config"], policy_program).0
+}
+
+pub fn decode_policy_config(data: &[u8]) -> anyhow::Result<PolicyConfig> {
+  let mut r = AccountReader::new(data, "PolicyConfig")?;
+  let _authority = r.pubkey()?;
+  let epoch_len_slots = r.u64()?;
+  let _attestation_max_age_secs = r.u32()?;
+  let _min_watchdogs = r.u16()?;
+  let _warn_threshold_bps = r.u16()?;
+  let _quarantine_threshold_bps = r.u16()?;
+  let allowlisted_watchdogs = r.pubkey_vec()?;
+  Ok(PolicyConfig { epoch_len_slots, allowlisted_watchdogs })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PolicyStatus {
+  Ok,
+  Warn,
+  Quarantine,
+}
+
+impl PolicyStatus {
+  fn from_u8(v: u8) -> Self {
+    match v {
+      1 => PolicyStatus::Warn,
+      2 => PolicyStatus::Quarantine,
+      _ => PolicyStatus::Ok,
+    }
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PolicyStatus::Ok => "OK",
+      PolicyStatus::Warn => "WARN",
+      PolicyStatus::Quarantine => "QUARANTINE",
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct NamePolicyState {
+  pub status: PolicyStatus,
+  pub confidence_bps: u16,
+  pub reason_flags: u32,
+  pub penalty_bps: u16,
+  pub recommended_ttl_cap: u32,
+}
+
+pub fn name_policy_address(policy_program: &Pubkey, name_hash: &[u8; 32]) -> Pubkey {
+  find_program_address(&[b"name_policy", name_hash], policy_program).0
+}
+
+pub fn decode_name_policy_state(data: &[u8]) -> anyhow::Result<NamePolicyState> {
+  let mut r = AccountReader::new(data, "NamePolicyState")?;
+  let _name_hash = r.bytes32()?;
+  let status = PolicyStatus::from_u8(r.u8()?);
+  let confidence_bps = r.u16()?;
+  let reason_flags = r.u32()?;
+  let _last_updated_unix = r.i64()?;
+  let _last_epoch_id = r.u64()?;
+  let _rolling_ok = r.u32()?;
+  let _rolling_fail = r.u32()?;
+  let _rolling_mismatch = r.u32()?;
+  let _distinct_watchdogs_last_epoch = r.u16()?;
+  let penalty_bps = r.u16()?;
+  let recommended_ttl_cap = r.u32()?;
+  Ok(NamePolicyState {
+    status,
+    confidence_bps,
+    reason_flags,
+    penalty_bps,
+    recommended_ttl_cap,
+  })
+}
+
+/// Arguments of `ddns_watchdog_policy::submit_attestation_digest`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttestationDigest {
+  pub epoch_id: u64,
+  pub name_hash: [u8; 32],
+  pub kind: u8,
+  pub outcome: u8,
+  pub reason_flags: u32,
+  pub confidence_bps: u16,
+  pub rrset_hash: [u8; 32],
+  pub observed_at_unix: i64,
+  pub last_root: [u8; 32],
+}
+
+pub fn submit_attestation_digest_ix(
+  policy_program: &Pubkey,
+  watchdog: &Pubkey,
+  submitter: &Pubkey,
+  digest: &AttestationDigest,
+) -> Instruction {
+  let epoch = digest.epoch_id.to_le_bytes();
+  let config = policy_config_address(policy_program);
+  let (watchdog_state, _) = find_program_address(&[b"watchdog", watchdog.as_ref()], policy_program);
+  let (name_policy, _) = find_program_address(&[b"name_policy", &digest.name_hash], policy_program);
+  let (attest_log, _) = find_program_address(&[b"attest_log", &epoch, &digest.name_hash], policy_program);
+  let (attest_mark, _) = find_program_address(
+    &[b"attest_mark", &epoch, &digest.name_hash, watchdog.as_ref()],
+    policy_program,
+  );
+
+  let mut data = anchor_discriminator("global", "submit_attestation_digest").to_vec();
+  data.extend_from_slice(&epoch);
+  data.extend_from_slice(&digest.name_hash);
+  data.push(digest.kind);
+  data.push(digest.outcome);
+  data.extend_from_slice(&digest.reason_flags.to_le_bytes());
+  data.extend_from_slice(&digest.confidence_bps.to_le_bytes());
+  data.extend_from_slice(&digest.rrset_hash);
+  data.extend_from_slice(&digest.observed_at_unix.to_le_bytes());
+  data.extend_from_slice(&digest.last_root);
+
+  Instruction {
+    program_id: *policy_program,
+    accounts: vec![
+      AccountMeta::new_readonly(config, false),
+      AccountMeta::new_readonly(*watchdog, false),
+      AccountMeta::new(watchdog_state, false),
+      AccountMeta::new(name_policy, false),
+      AccountMeta::new(attest_log, false),
+      AccountMeta::new(attest_mark, false),
+      AccountMeta::new(*submitter, true),
+      AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ],
+    data,
+  }
+}
+
+// ---- ddns_operators ----
+
+pub const MAX_OPERATOR_ENDPOINTS: usize = 8;
+/// 8-byte discriminator + `Operator::SIZE`.
+pub const OPERATOR_ACCOUNT_LEN: usize = 8 + 32 + 1 + 1 + 8 + 32 + 1 + MAX_OPERATOR_ENDPOINTS * (1 + 32 + 2) + 8 + 1 + 1;
+
+pub const ENDPOINT_DOH_URL_HASH: u8 = 0;
+pub const ENDPOINT_IPV4: u8 = 1;
+pub const ENDPOINT_IPV6: u8 = 2;
+
+pub const OPERATOR_STATUS_ACTIVE: u8 = 1;
+pub const OPERATOR_STATUS_SLASHED: u8 = 3;
+
+/// `ddns_operators::Endpoint`. IP endpoints store the address octets
+/// left-aligned and zero-padded in `value`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Endpoint {
+  pub endpoint_kind: u8,
+  pub value: [u8; 32],
+  pub region: u16,
+}
+
+impl Endpoint {
+  pub fn doh_url(url: &str, region: u16) -> Self {
+    Self { endpoint_kind: ENDPOINT_DOH_URL_HASH, value: sha256(url.trim().as_bytes()), region }
+  }
+
+  pub fn ip(addr: IpAddr, region: u16) -> Self {
+    let mut value = [0u8; 32];
+    let endpoint_kind = match addr {
+      IpAddr::V4(v4) => {
+        value[..4].copy_from_slice(&v4.octets());
+        ENDPOINT_IPV4
+      }
+      IpAddr::V6(v6) => {
+        value[..16].copy_from_slice(&v6.octets());
+        ENDPOINT_IPV6
+      }
+    };
+    Self { endpoint_kind, value, region }
+  }
+
+  /// Human-readable value: the IP for IP endpoints, hex hash otherwise.
+  pub fn display_value(&self) -> String {
+    match self.endpoint_kind {
+      ENDPOINT_IPV4 => {
+        let octets: [u8; 4] = self.value[..4].try_into().unwrap_or_default();
+        IpAddr::from(octets).to_string()
+      }
+      ENDPOINT_IPV6 => {
+        let octets: [u8; 16] = self.value[..16].try_into().unwrap_or_default();
+        IpAddr::from(octets).to_string()
+      }
+      _ => hex::encode(self.value),
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct Operator {
+  pub operator_wallet: Pubkey,
+  pub kind: u8,
+  pub status: u8,
+  pub stake_amount_lamports: u64,
+  pub endpoints: Vec<Endpoint>,
+}
+
+pub fn operator_address(operators_program: &Pubkey, wallet: &Pubkey) -> Pubkey {
+  find_program_address(&[b"operator", wallet.as_ref()], operators_program).0
+}
+
+pub fn decode_operator(data: &[u8]) -> anyhow::Result<Operator> {
+  let mut r = AccountReader::new(data, "Operator")?;
+  let operator_wallet = r.pubkey()?;
+  let kind = r.u8()?;
+  let status = r.u8()?;
+  let stake_amount_lamports = r.u64()?;
+  let _payout_token_account = r.pubkey()?;
+  let endpoint_count = (r.u8()? as usize).min(MAX_OPERATOR_ENDPOINTS);
+  let mut endpoints = Vec::with_capacity(endpoint_count);
+  for i in 0..MAX_OPERATOR_ENDPOINTS {
+    let endpoint = Endpoint { endpoint_kind: r.u8()?, value: r.bytes32()?, region: r.u16()? };
+    if i < endpoint_count {
+      endpoints.push(endpoint);
+    }
+  }
+  Ok(Operator { operator_wallet, kind, status, stake_amount_lamports, endpoints })
+}
+
+pub fn update_endpoints_ix(operators_program: &Pubkey, wallet: &Pubkey, endpoints: &[Endpoint]) -> Instruction {
+  let (config, _) = find_program_address(&[b"operators_config"], operators_program);
+  let mut data = anchor_discriminator("global", "update_endpoints").to_vec();
+  data.extend_from_slice(&(endpoints.len() as u32).to_le_bytes());
+  for ep in endpoints {
+    data.push(ep.endpoint_kind);
+    data.extend_from_slice(&ep.value);
+    data.extend_from_slice(&ep.region.to_le_bytes());
+  }
+  Instruction {
+    program_id: *operators_program,
+    accounts: vec![
+      AccountMeta::new_readonly(*wallet, true),
+      AccountMeta::new_readonly(config, false),
+      AccountMeta::new(operator_address(operators_program, wallet), false),
+    ],
+    data,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn normalize_name_rejects_names_that_cannot_be_on_chain() {
+    assert_eq!(normalize_name("Example.DNS.").as_deref(), Ok("example.dns"));
+    assert!(normalize_name("_dmarc.example.dns").is_err());
+  }
+
+  #[test]
+  fn decode_name_policy_state_layout() {
+    let mut data = anchor_discriminator("account", "NamePolicyState").to_vec();
+    data.extend_from_slice(&[1u8; 32]);
+    data.push(2);
+    data.extend_from_slice(&7_500u16.to_le_bytes());
+    data.extend_from_slice(&4u32.to_le_bytes());
+    data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    data.extend_from_slice(&[0u8; 8 + 4 * 3 + 2]);
+    data.extend_from_slice(&2_500u16.to_le_bytes());
+    data.extend_from_slice(&60u32.to_le_bytes());
+    data.push(255);
+    let state = decode_name_policy_state(&data).unwrap();
+    assert_eq!(state.status, PolicyStatus::Quarantine);
+    assert_eq!(state.penalty_bps, 2_500);
+    assert_eq!(state.recommended_ttl_cap, 60);
+  }
+
+  #[test]
+  fn decode_record_set_layout() {
+    let records = [ddns_rrset::Record::A("203.0.113.7".parse().unwrap())];
+    let encoded = ddns_rrset::encode_rrset(ddns_rrset::TYPE_A, &records).unwrap();
+    let mut data = anchor_discriminator("account", "RecordSet").to_vec();
+    data.extend_from_slice(&[1u8; 32]);
+    data.extend_from_slice(&ddns_rrset::TYPE_A.to_le_bytes());
+    data.extend_from_slice(&300u32.to_le_bytes());
+    data.extend_from_slice(&2u64.to_le_bytes());
+    data.extend_from_slice(&ddns_rrset::rrset_hash(&encoded));
+    data.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+    data.extend_from_slice(&encoded);
+    data.extend_from_slice(&42u64.to_le_bytes());
+    data.extend_from_slice(&[0u8; 32 + 1]);
+    let set = decode_record_set(&data).unwrap();
+    assert_eq!(set.ttl_s, 300);
+    assert_eq!(set.version, 2);
+    assert_eq!(set.records, records);
+    assert_eq!(set.updated_at_slot, 42);
+
+    let hash_at = data.len() - 33 - 8 - encoded.len() - 4 - 32;
+    data[hash_at] ^= 1;
+    assert!(decode_record_set(&data).is_err());
+  }
+
+  #[test]
+  fn decode_operator_keeps_only_counted_endpoints() {
+    let mut data = anchor_discriminator("account", "Operator").to_vec();
+    data.extend_from_slice(&[5u8; 32]);
+    data.push(0);
+    data.push(OPERATOR_STATUS_ACTIVE);
+    data.extend_from_slice(&1_000u64.to_le_bytes());
+    data.extend_from_slice(&[0u8; 32]);
+    data.push(1);
+    let ep = Endpoint::ip("203.0.113.7".parse().unwrap(), 3);
+    for i in 0..MAX_OPERATOR_ENDPOINTS {
+      let e = if i == 0 { ep } else { Endpoint { endpoint_kind: 0, value: [0u8; 32], region: 0 } };
+      data.push(e.endpoint_kind);
+      data.extend_from_slice(&e.value);
+      data.extend_from_slice(&e.region.to_le_bytes());
+    }
+    data.extend_from_slice(&[0u8; 8 + 1 + 1]);
+    assert_eq!(data.len(), OPERATOR_ACCOUNT_LEN);
+    let op = decode_operator(&data).unwrap();
+    assert_eq!(op.endpoints, vec![ep]);
+    assert_eq!(op.endpoints[0].display_value(), "203.0.113.7");
+  }
+
+  #[test]
+  fn digest_instruction_layout() {
+    let program = Pubkey([7u8; 32]);
+    let watchdog = Pubkey([9u8; 32]);
+    let digest = AttestationDigest {
+      epoch_id: 3,
+      name_hash: [1u8; 32],
+      kind: 2,
+      outcome: 0,
+      reason_flags: 5,
+      confidence_bps: 6_000,
+      rrset_hash: [2u8; 32],
+      observed_at_unix: 1_700_000_000,
+      last_root: [4u8; 32],
+    };
+    let ix = submit_attestation_digest_ix(&program, &watchdog, &watchdog, &digest);
+    assert_eq!(ix.accounts.len(), 8);
+    assert_eq!(ix.data.len(), 8 + 8 + 32 + 1 + 1 + 4 + 2 + 32 + 8 + 32);
+    assert_eq!(&ix.data[8..16], &3u64.to_le_bytes());
+  }
+}
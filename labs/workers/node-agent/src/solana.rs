@@ -0,0 +1,360 @@
+use anyhow::{anyhow, bail, Context};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use ed25519_dalek::{Signer, SigningKey};
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey([0u8; 32]);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pubkey(pub [u8; 32]);
+
+impl Pubkey {
+  pub fn from_signing_key(key: &SigningKey) -> Self {
+    Pubkey(key.verifying_key().to_bytes())
+  }
+}
+
+impl AsRef<[u8]> for Pubkey {
+  fn as_ref(&self) -> &[u8] {
+    &self.0
+  }
+}
+
+impl FromStr for Pubkey {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> anyhow::Result<Self> {
+    let bytes = bs58::decode(s.trim()).into_vec().context("invalid base58 pubkey")?;
+    let arr: [u8; 32] = bytes.as_slice().try_into().map_err(|_| anyhow!("pubkey must be 32 bytes"))?;
+    Ok(Pubkey(arr))
+  }
+}
+
+impl fmt::Display for Pubkey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", bs58::encode(self.0).into_string())
+  }
+}
+
+/// Same derivation as `Pubkey::find_program_address` in the Solana SDK.
+pub fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+  for bump in (0..=255u8).rev() {
+    let mut hasher = Sha256::new();
+    for seed in seeds {
+      hasher.update(seed);
+    }
+    hasher.update([bump]);
+    hasher.update(program_id.0);
+    hasher.update(b"ProgramDerivedAddress");
+    let hash: [u8; 32] = hasher.finalize().into();
+    if CompressedEdwardsY(hash).decompress().is_none() {
+      return (Pubkey(hash), bump);
+    }
+  }
+  unreachable!("no viable bump seed")
+}
+
+/// First 8 bytes of `sha256("<namespace>:<name>")`, as Anchor uses for
+/// instruction (`global`) and account (`account`) discriminators.
+pub fn anchor_discriminator(namespace: &str, name: &str) -> [u8; 8] {
+  let hash = Sha256::digest(format!("{}:{}", namespace, name).as_bytes());
+  let mut out = [0u8; 8];
+  out.copy_from_slice(&hash[..8]);
+  out
+}
+
+#[derive(Clone, Debug)]
+pub struct AccountMeta {
+  pub pubkey: Pubkey,
+  pub is_signer: bool,
+  pub is_writable: bool,
+}
+
+impl AccountMeta {
+  pub fn new(pubkey: Pubkey, is_signer: bool) -> Self {
+    Self { pubkey, is_signer, is_writable: true }
+  }
+
+  pub fn new_readonly(pubkey: Pubkey, is_signer: bool) -> Self {
+    Self { pubkey, is_signer, is_writable: false }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct Instruction {
+  pub program_id: Pubkey,
+  pub accounts: Vec<AccountMeta>,
+  pub data: Vec<u8>,
+}
+
+fn push_compact_u16(out: &mut Vec<u8>, mut value: usize) {
+  loop {
+    let mut byte = (value & 0x7f) as u8;
+    value >>= 7;
+    if value == 0 {
+      out.push(byte);
+      return;
+    }
+    byte |= 0x80;
+    out.push(byte);
+  }
+}
+
+/// Builds and signs a legacy transaction with `payer` as the only signer.
+pub fn build_signed_transaction(
+  payer: &SigningKey,
+  instructions: &[Instruction],
+  recent_blockhash: [u8; 32],
+) -> anyhow::Result<Vec<u8>> {
+  let payer_key = Pubkey::from_signing_key(payer);
+  let mut metas: Vec<AccountMeta> = vec![AccountMeta::new(payer_key, true)];
+  let mut merge = |meta: AccountMeta| {
+    if let Some(existing) = metas.iter_mut().find(|m| m.pubkey == meta.pubkey) {
+      existing.is_signer |= meta.is_signer;
+      existing.is_writable |= meta.is_writable;
+    } else {
+      metas.push(meta);
+    }
+  };
+  for ix in instructions {
+    for meta in &ix.accounts {
+      merge(meta.clone());
+    }
+    merge(AccountMeta::new_readonly(ix.program_id, false));
+  }
+
+  let group = |m: &AccountMeta| match (m.is_signer, m.is_writable) {
+    (true, true) => 0,
+    (true, false) => 1,
+    (false, true) => 2,
+    (false, false) => 3,
+  };
+  metas.sort_by_key(|m| (group(m), m.pubkey != payer_key));
+  if metas.iter().any(|m| m.is_signer && m.pubkey != payer_key) {
+    bail!("only the payer may sign");
+  }
+
+  let keys: Vec<Pubkey> = metas.iter().map(|m| m.pubkey).collect();
+  let index_of = |key: &Pubkey| keys.iter().position(|k| k == key).map(|i| i as u8);
+
+  let mut message = vec![
+    metas.iter().filter(|m| m.is_signer).count() as u8,
+    metas.iter().filter(|m| m.is_signer && !m.is_writable).count() as u8,
+    metas.iter().filter(|m| !m.is_signer && !m.is_writable).count() as u8,
+  ];
+  push_compact_u16(&mut message, keys.len());
+  for key in &keys {
+    message.extend_from_slice(&key.0);
+  }
+  message.extend_from_slice(&recent_blockhash);
+  push_compact_u16(&mut message, instructions.len());
+  for ix in instructions {
+    message.push(index_of(&ix.program_id).context("missing program key")?);
+    push_compact_u16(&mut message, ix.accounts.len());
+    for meta in &ix.accounts {
+      message.push(index_of(&meta.pubkey).context("missing account key")?);
+    }
+    push_compact_u16(&mut message, ix.data.len());
+    message.extend_from_slice(&ix.data);
+  }
+
+  let signature = payer.sign(&message);
+  let mut tx = Vec::with_capacity(1 + 64 + message.len());
+  push_compact_u16(&mut tx, 1);
+  tx.extend_from_slice(&signature.to_bytes());
+  tx.extend_from_slice(&message);
+  Ok(tx)
+}
+
+/// Minimal JSON-RPC client for the handful of calls the agent needs.
+pub struct RpcClient {
+  client: Client,
+  url: String,
+}
+
+impl RpcClient {
+  pub fn new(client: Client, url: String) -> Self {
+    Self { client, url }
+  }
+
+  async fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+    let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+    let resp: Value = self.client.post(&self.url).json(&body).send().await?.json().await?;
+    if let Some(err) = resp.get("error") {
+      bail!("rpc {} failed: {}", method, err);
+    }
+    resp.get("result").cloned().ok_or_else(|| anyhow!("rpc {} returned no result", method))
+  }
+
+  pub async fn get_slot(&self) -> anyhow::Result<u64> {
+    let result = self.call("getSlot", json!([{"commitment": "confirmed"}])).await?;
+    result.as_u64().ok_or_else(|| anyhow!("invalid slot"))
+  }
+
+  pub async fn get_account_data(&self, key: &Pubkey) -> anyhow::Result<Option<Vec<u8>>> {
+    let result = self
+      .call("getAccountInfo", json!([key.to_string(), {"encoding": "base64", "commitment": "confirmed"}]))
+      .await?;
+    let value = match result.get("value") {
+      Some(Value::Null) | None => return Ok(None),
+      Some(v) => v,
+    };
+    let data = value
+      .get("data")
+      .and_then(|d| d.get(0))
+      .and_then(|d| d.as_str())
+      .ok_or_else(|| anyhow!("invalid account data"))?;
+    Ok(Some(STANDARD.decode(data)?))
+  }
+
+  /// Accounts owned by `program` with the given size and Anchor account name.
+  pub async fn get_program_accounts(
+    &self,
+    program: &Pubkey,
+    data_size: usize,
+    account_name: &str,
+  ) -> anyhow::Result<Vec<(Pubkey, Vec<u8>)>> {
+    let discriminator = bs58::encode(anchor_discriminator("account", account_name)).into_string();
+    let result = self
+      .call(
+        "getProgramAccounts",
+        json!([program.to_string(), {
+          "encoding": "base64",
+          "commitment": "confirmed",
+          "filters": [{"dataSize": data_size}, {"memcmp": {"offset": 0, "bytes": discriminator}}],
+        }]),
+      )
+      .await?;
+    let entries = result.as_array().ok_or_else(|| anyhow!("invalid program accounts"))?;
+    let mut out = Vec::with_capacity(entries.len());
+    for entry in entries {
+      let key = entry.get("pubkey").and_then(|v| v.as_str()).ok_or_else(|| anyhow!("missing pubkey"))?;
+      let data = entry
+        .get("account")
+        .and_then(|a| a.get("data"))
+        .and_then(|d| d.get(0))
+        .and_then(|d| d.as_str())
+        .ok_or_else(|| anyhow!("missing account data"))?;
+      out.push((Pubkey::from_str(key)?, STANDARD.decode(data)?));
+    }
+    Ok(out)
+  }
+
+  pub async fn get_latest_blockhash(&self) -> anyhow::Result<[u8; 32]> {
+    let result = self.call("getLatestBlockhash", json!([{"commitment": "confirmed"}])).await?;
+    let hash = result
+      .get("value")
+      .and_then(|v| v.get("blockhash"))
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| anyhow!("invalid blockhash"))?;
+    Ok(Pubkey::from_str(hash)?.0)
+  }
+
+  pub async fn send_transaction(&self, tx: &[u8]) -> anyhow::Result<String> {
+    let result = self
+      .call("sendTransaction", json!([STANDARD.encode(tx), {"encoding": "base64"}]))
+      .await?;
+    result.as_str().map(|s| s.to_string()).ok_or_else(|| anyhow!("invalid signature"))
+  }
+
+  pub async fn send_instructions(&self, payer: &SigningKey, instructions: &[Instruction]) -> anyhow::Result<String> {
+    let blockhash = self.get_latest_blockhash().await?;
+    let tx = build_signed_transaction(payer, instructions, blockhash)?;
+    self.send_transaction(&tx).await
+  }
+}
+
+/// Cursor over Borsh-encoded Anchor account data.
+pub struct AccountReader<'a> {
+  data: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> AccountReader<'a> {
+  /// Starts after the 8-byte discriminator, checking it matches `account_name`.
+  pub fn new(data: &'a [u8], account_name: &str) -> anyhow::Result<Self> {
+    if data.len() < 8 || data[..8] != anchor_discriminator("account", account_name) {
+      bail!("not a {} account", account_name);
+    }
+    Ok(Self { data, offset: 8 })
+  }
+
+  fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+    let end = self.offset.checked_add(len).filter(|end| *end <= self.data.len());
+    let end = end.ok_or_else(|| anyhow!("account data too short"))?;
+    let out = &self.data[self.offset..end];
+    self.offset = end;
+    Ok(out)
+  }
+
+  pub fn u8(&mut self) -> anyhow::Result<u8> {
+    Ok(self.take(1)?[0])
+  }
+
+  pub fn u16(&mut self) -> anyhow::Result<u16> {
+    Ok(u16::from_le_bytes(self.take(2)?.try_into()?))
+  }
+
+  pub fn u32(&mut self) -> anyhow::Result<u32> {
+    Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+  }
+
+  pub fn u64(&mut self) -> anyhow::Result<u64> {
+    Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+  }
+
+  pub fn i64(&mut self) -> anyhow::Result<i64> {
+    Ok(i64::from_le_bytes(self.take(8)?.try_into()?))
+  }
+
+  pub fn bytes32(&mut self) -> anyhow::Result<[u8; 32]> {
+    Ok(self.take(32)?.try_into()?)
+  }
+
+  pub fn pubkey(&mut self) -> anyhow::Result<Pubkey> {
+    Ok(Pubkey(self.bytes32()?))
+  }
+
+  pub fn pubkey_vec(&mut self) -> anyhow::Result<Vec<Pubkey>> {
+    let len = self.u32()? as usize;
+    (0..len).map(|_| self.pubkey()).collect()
+  }
+
+  pub fn byte_vec(&mut self) -> anyhow::Result<Vec<u8>> {
+    let len = self.u32()? as usize;
+    Ok(self.take(len)?.to_vec())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pubkey_base58_round_trip() {
+    let key = Pubkey::from_str("Bf3Who54hZMHxubT3YgPZRHNitdQLqKA8HR9Hzbmog3e").unwrap();
+    assert_eq!(key.to_string(), "Bf3Who54hZMHxubT3YgPZRHNitdQLqKA8HR9Hzbmog3e");
+  }
+
+  #[test]
+  fn pda_matches_sdk_derivation() {
+    let program = Pubkey::from_str("Bf3Who54hZMHxubT3YgPZRHNitdQLqKA8HR9Hzbmog3e").unwrap();
+    let (pda, bump) = find_program_address(&[b"policy_config"], &program);
+    assert_eq!(pda.to_string(), "9Y8USDt5xAGjtGsLmiREfB86rLAfCQncgs8bcqdEFGfC");
+    assert_eq!(bump, 252);
+  }
+
+  #[test]
+  fn compact_u16_encoding() {
+    let mut out = Vec::new();
+    push_compact_u16(&mut out, 0x7f);
+    push_compact_u16(&mut out, 0x80);
+    push_compact_u16(&mut out, 0x3fff);
+    assert_eq!(out, vec![0x7f, 0x80, 0x01, 0xff, 0x7f]);
+  }
+}
@@ -1,3 +1,6 @@
+pub mod operator;
+pub mod policy;
 pub mod prefetch;
 pub mod registry_root;
 pub mod storage;
+pub mod watchdog;
@@ -0,0 +1,259 @@
+use crate::config::OperatorConfig;
+use crate::onchain::{
+  decode_operator, operator_address, update_endpoints_ix, Endpoint, Operator, ENDPOINT_IPV4, ENDPOINT_IPV6,
+  MAX_OPERATOR_ENDPOINTS, OPERATOR_ACCOUNT_LEN, OPERATOR_STATUS_ACTIVE, OPERATOR_STATUS_SLASHED,
+};
+use crate::solana::{Pubkey, RpcClient};
+use ed25519_dalek::SigningKey;
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// Active operators from the last `getProgramAccounts` scan.
+pub type OperatorDirectory = Arc<RwLock<Vec<Operator>>>;
+
+/// An endpoint as this node advertises it, with the plaintext value that
+/// clients can check against the on-chain hash.
+#[derive(Clone, Debug, Serialize)]
+pub struct AdvertisedEndpoint {
+  pub kind: &'static str,
+  pub value: String,
+  pub region: u16,
+  pub onchain_value: String,
+  #[serde(skip)]
+  pub endpoint: Endpoint,
+}
+
+pub struct OperatorContext {
+  pub rpc: Arc<RpcClient>,
+  pub signing_key: Arc<SigningKey>,
+  pub operators_program: Pubkey,
+  pub advertised: Arc<Vec<AdvertisedEndpoint>>,
+  pub directory: OperatorDirectory,
+}
+
+/// Endpoint set derived from config: the DoH URL (if any) followed by the
+/// public IPs, or the `listen_addr` IP when it is routable. Capped at the
+/// program's `MAX_OPERATOR_ENDPOINTS`; the rest are dropped with a warning.
+pub fn derive_endpoints(cfg: &OperatorConfig, listen_addr: &str) -> Vec<AdvertisedEndpoint> {
+  let mut out = Vec::new();
+  if let Some(url) = cfg.public_doh_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+    let endpoint = Endpoint::doh_url(url, cfg.region);
+    out.push(AdvertisedEndpoint {
+      kind: "doh",
+      value: url.to_string(),
+      region: cfg.region,
+      onchain_value: endpoint.display_value(),
+      endpoint,
+    });
+  }
+
+  let mut ips: Vec<IpAddr> = cfg.public_ips.iter().filter_map(|ip| ip.trim().parse().ok()).collect();
+  if ips.is_empty() {
+    if let Ok(addr) = listen_addr.parse::<SocketAddr>() {
+      let ip = addr.ip();
+      if !ip.is_unspecified() && !ip.is_loopback() {
+        ips.push(ip);
+      }
+    }
+  }
+  for ip in ips {
+    let endpoint = Endpoint::ip(ip, cfg.region);
+    out.push(AdvertisedEndpoint {
+      kind: if ip.is_ipv4() { "ipv4" } else { "ipv6" },
+      value: ip.to_string(),
+      region: cfg.region,
+      onchain_value: endpoint.display_value(),
+      endpoint,
+    });
+  }
+  if out.len() > MAX_OPERATOR_ENDPOINTS {
+    warn!(
+      "{} operator endpoints configured; advertising the first {}",
+      out.len(),
+      MAX_OPERATOR_ENDPOINTS
+    );
+    out.truncate(MAX_OPERATOR_ENDPOINTS);
+  }
+  out
+}
+
+/// The endpoint list to write on chain: the advertised set when it differs
+/// from `onchain`. An empty advertised set is never written, so a node with
+/// nothing to advertise leaves the registered endpoints alone.
+pub fn endpoints_to_sync(onchain: &[Endpoint], advertised: &[AdvertisedEndpoint]) -> Option<Vec<Endpoint>> {
+  if advertised.is_empty() || !endpoints_drifted(onchain, advertised) {
+    return None;
+  }
+  Some(advertised.iter().map(|e| e.endpoint).collect())
+}
+
+/// True when the on-chain endpoint list differs from the advertised set,
+/// ignoring order.
+pub fn endpoints_drifted(onchain: &[Endpoint], advertised: &[AdvertisedEndpoint]) -> bool {
+  let mut a: Vec<Endpoint> = onchain.to_vec();
+  let mut b: Vec<Endpoint> = advertised.iter().map(|e| e.endpoint).collect();
+  a.sort();
+  b.sort();
+  a != b
+}
+
+pub async fn run_operator_loop(ctx: OperatorContext, interval_seconds: u64) {
+  loop {
+    if let Err(err) = sync_endpoints(&ctx).await {
+      warn!("operator endpoint sync failed: {}", err);
+    }
+    match ctx
+      .rpc
+      .get_program_accounts(&ctx.operators_program, OPERATOR_ACCOUNT_LEN, "Operator")
+      .await
+    {
+      Ok(accounts) => {
+        let active: Vec<Operator> = accounts
+          .iter()
+          .filter_map(|(_, data)| decode_operator(data).ok())
+          .filter(|op| op.status == OPERATOR_STATUS_ACTIVE)
+          .collect();
+        *ctx.directory.write().await = active;
+      }
+      Err(err) => warn!("operator directory refresh failed: {}", err),
+    }
+    sleep(Duration::from_secs(interval_seconds)).await;
+  }
+}
+
+async fn sync_endpoints(ctx: &OperatorContext) -> anyhow::Result<()> {
+  let wallet = Pubkey::from_signing_key(&ctx.signing_key);
+  let data = match ctx.rpc.get_account_data(&operator_address(&ctx.operators_program, &wallet)).await? {
+    Some(data) => data,
+    None => {
+      warn!("node key {} is not registered as an operator", wallet);
+      return Ok(());
+    }
+  };
+  let operator = decode_operator(&data)?;
+  if operator.status == OPERATOR_STATUS_SLASHED {
+    warn!("operator {} is slashed; not updating endpoints", wallet);
+    return Ok(());
+  }
+  if ctx.advertised.is_empty() {
+    warn!("no public endpoint configured (public_doh_url, public_ips or a routable listen_addr); not updating endpoints");
+    return Ok(());
+  }
+  let Some(endpoints) = endpoints_to_sync(&operator.endpoints, &ctx.advertised) else {
+    return Ok(());
+  };
+  let ix = update_endpoints_ix(&ctx.operators_program, &wallet, &endpoints);
+  let sig = ctx.rpc.send_instructions(&ctx.signing_key, &[ix]).await?;
+  info!("updated operator endpoints ({}) sig={}", endpoints.len(), sig);
+  Ok(())
+}
+
+/// Directory entries with at least one endpoint in `region` (all when
+/// `None`), highest stake first.
+pub fn operators_in_region(directory: &[Operator], region: Option<u16>) -> Vec<serde_json::Value> {
+  let mut matching: Vec<&Operator> = directory
+    .iter()
+    .filter(|op| region.map(|r| op.endpoints.iter().any(|e| e.region == r)).unwrap_or(true))
+    .collect();
+  matching.sort_by_key(|op| std::cmp::Reverse(op.stake_amount_lamports));
+  matching
+    .into_iter()
+    .map(|op| {
+      let endpoints: Vec<serde_json::Value> = op
+        .endpoints
+        .iter()
+        .filter(|e| region.map(|r| e.region == r).unwrap_or(true))
+        .map(|e| {
+          let kind = match e.endpoint_kind {
+            ENDPOINT_IPV4 => "ipv4",
+            ENDPOINT_IPV6 => "ipv6",
+            _ => "doh_hash",
+          };
+          serde_json::json!({"kind": kind, "value": e.display_value(), "region": e.region})
+        })
+        .collect();
+      serde_json::json!({
+        "operator_wallet": op.operator_wallet.to_string(),
+        "kind": op.kind,
+        "stake_amount_lamports": op.stake_amount_lamports,
+        "endpoints": endpoints,
+      })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cfg(ips: Vec<&str>) -> OperatorConfig {
+    OperatorConfig {
+      enabled: true,
+      public_doh_url: Some("https://node.example/dns-query".to_string()),
+      public_ips: ips.into_iter().map(|s| s.to_string()).collect(),
+      region: 7,
+      sync_interval_seconds: 60,
+    }
+  }
+
+  #[test]
+  fn derive_skips_unspecified_listen_addr() {
+    let eps = derive_endpoints(&cfg(vec![]), "0.0.0.0:8088");
+    assert_eq!(eps.len(), 1);
+    assert_eq!(eps[0].kind, "doh");
+    let eps = derive_endpoints(&cfg(vec![]), "198.51.100.4:8088");
+    assert_eq!(eps[1].value, "198.51.100.4");
+  }
+
+  #[test]
+  fn drift_ignores_order() {
+    let advertised = derive_endpoints(&cfg(vec!["198.51.100.4", "2001:db8::1"]), "0.0.0.0:8088");
+    let mut onchain: Vec<Endpoint> = advertised.iter().map(|e| e.endpoint).collect();
+    onchain.reverse();
+    assert!(!endpoints_drifted(&onchain, &advertised));
+    onchain.pop();
+    assert!(endpoints_drifted(&onchain, &advertised));
+  }
+
+  #[test]
+  fn empty_advertised_set_never_clears_onchain_endpoints() {
+    let mut config = cfg(vec![]);
+    config.public_doh_url = None;
+    let advertised = derive_endpoints(&config, "0.0.0.0:8088");
+    assert!(advertised.is_empty());
+    let onchain = vec![Endpoint::ip("198.51.100.4".parse().unwrap(), 7)];
+    assert!(endpoints_drifted(&onchain, &advertised));
+    assert_eq!(endpoints_to_sync(&onchain, &advertised), None);
+    assert_eq!(endpoints_to_sync(&[], &advertised), None);
+  }
+
+  #[test]
+  fn advertised_set_is_capped_at_the_program_limit() {
+    let ips: Vec<String> = (1..=12).map(|i| format!("198.51.100.{i}")).collect();
+    let advertised = derive_endpoints(&cfg(ips.iter().map(String::as_str).collect()), "0.0.0.0:8088");
+    assert_eq!(advertised.len(), MAX_OPERATOR_ENDPOINTS);
+    assert_eq!(advertised[0].kind, "doh");
+    let synced = endpoints_to_sync(&[], &advertised).unwrap();
+    assert_eq!(synced.len(), MAX_OPERATOR_ENDPOINTS);
+  }
+
+  #[test]
+  fn region_lookup_orders_by_stake() {
+    let op = |stake: u64, region: u16| Operator {
+      operator_wallet: Pubkey([stake as u8; 32]),
+      kind: 0,
+      status: OPERATOR_STATUS_ACTIVE,
+      stake_amount_lamports: stake,
+      endpoints: vec![Endpoint::ip("198.51.100.4".parse().unwrap(), region)],
+    };
+    let directory = vec![op(1, 7), op(5, 7), op(9, 2)];
+    let found = operators_in_region(&directory, Some(7));
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0]["stake_amount_lamports"], 5);
+  }
+}
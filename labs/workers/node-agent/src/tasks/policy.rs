@@ -0,0 +1,147 @@
+use crate::cache::Cache;
+use crate::config::QuarantineAction;
+use crate::onchain::{decode_name_policy_state, hash_name, name_policy_address, normalize_name, NamePolicyState, PolicyStatus};
+use crate::solana::{Pubkey, RpcClient};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// Fallback caps matching what `ddns_watchdog_policy` writes for each status.
+const WARN_TTL_CAP_S: u32 = 300;
+const QUARANTINE_TTL_CAP_S: u32 = 60;
+
+/// Latest on-chain policy per normalized name; names without a
+/// `NamePolicyState` account are absent and treated as OK.
+pub type PolicyMap = Arc<RwLock<HashMap<String, NamePolicyState>>>;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AppliedPolicy {
+  pub status: PolicyStatus,
+  pub action: &'static str,
+  pub penalty_bps: u16,
+  pub ttl_cap_s: u32,
+  pub confidence_bps: u16,
+  pub reason_flags: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ServeDecision {
+  Refuse(AppliedPolicy),
+  Serve { max_age_s: u64, policy: Option<AppliedPolicy> },
+}
+
+pub async fn run_policy_loop(
+  cache: Arc<Mutex<Cache>>,
+  rpc: Arc<RpcClient>,
+  policy_program: Pubkey,
+  interval_seconds: u64,
+  policies: PolicyMap,
+) {
+  loop {
+    let names = cache.lock().await.keys();
+    let previous = policies.read().await.clone();
+    let mut next = HashMap::new();
+    for name in names {
+      // Names that fail on-chain normalization never have a policy account.
+      let Ok(normalized) = normalize_name(&name) else {
+        continue;
+      };
+      let address = name_policy_address(&policy_program, &hash_name(&normalized));
+      match rpc.get_account_data(&address).await {
+        Ok(Some(data)) => match decode_name_policy_state(&data) {
+          Ok(state) => {
+            if state.status != PolicyStatus::Ok {
+              info!("policy {:?} for {}", state.status, normalized);
+            }
+            next.insert(normalized, state);
+          }
+          Err(err) => warn!("invalid policy state for {}: {}", normalized, err),
+        },
+        Ok(None) => {}
+        Err(err) => {
+          warn!("policy read failed for {}: {}", normalized, err);
+          if let Some(state) = previous.get(&normalized) {
+            next.insert(normalized, state.clone());
+          }
+        }
+      }
+    }
+    *policies.write().await = next;
+    sleep(Duration::from_secs(interval_seconds)).await;
+  }
+}
+
+/// Decides how a cached answer may be served given the name's policy.
+pub fn decide(policy: Option<&NamePolicyState>, remaining_ttl_s: u64, quarantine_action: QuarantineAction) -> ServeDecision {
+  let Some(policy) = policy else {
+    return ServeDecision::Serve { max_age_s: remaining_ttl_s, policy: None };
+  };
+  let default_cap = match policy.status {
+    PolicyStatus::Ok => return ServeDecision::Serve { max_age_s: remaining_ttl_s, policy: None },
+    PolicyStatus::Warn => WARN_TTL_CAP_S,
+    PolicyStatus::Quarantine => QUARANTINE_TTL_CAP_S,
+  };
+  let ttl_cap_s = if policy.recommended_ttl_cap > 0 { policy.recommended_ttl_cap } else { default_cap };
+  let refuse = policy.status == PolicyStatus::Quarantine && quarantine_action == QuarantineAction::Refuse;
+  let applied = AppliedPolicy {
+    status: policy.status,
+    action: if refuse { "refuse" } else { "downgrade" },
+    penalty_bps: policy.penalty_bps,
+    ttl_cap_s,
+    confidence_bps: policy.confidence_bps,
+    reason_flags: policy.reason_flags,
+  };
+  if refuse {
+    return ServeDecision::Refuse(applied);
+  }
+  ServeDecision::Serve { max_age_s: remaining_ttl_s.min(ttl_cap_s as u64), policy: Some(applied) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn state(status: PolicyStatus, recommended_ttl_cap: u32) -> NamePolicyState {
+    NamePolicyState {
+      status,
+      confidence_bps: 5_000,
+      reason_flags: 0,
+      penalty_bps: 500,
+      recommended_ttl_cap,
+    }
+  }
+
+  #[test]
+  fn no_policy_serves_full_ttl() {
+    assert_eq!(
+      decide(None, 600, QuarantineAction::Refuse),
+      ServeDecision::Serve { max_age_s: 600, policy: None }
+    );
+  }
+
+  #[test]
+  fn warn_caps_ttl() {
+    let decision = decide(Some(&state(PolicyStatus::Warn, 120)), 600, QuarantineAction::Refuse);
+    match decision {
+      ServeDecision::Serve { max_age_s, policy: Some(applied) } => {
+        assert_eq!(max_age_s, 120);
+        assert_eq!(applied.action, "downgrade");
+      }
+      other => panic!("unexpected {:?}", other),
+    }
+  }
+
+  #[test]
+  fn quarantine_follows_action() {
+    let quarantined = state(PolicyStatus::Quarantine, 0);
+    assert!(matches!(decide(Some(&quarantined), 600, QuarantineAction::Refuse), ServeDecision::Refuse(_)));
+    match decide(Some(&quarantined), 600, QuarantineAction::Downgrade) {
+      ServeDecision::Serve { max_age_s, .. } => assert_eq!(max_age_s, QUARANTINE_TTL_CAP_S as u64),
+      other => panic!("unexpected {:?}", other),
+    }
+  }
+}
@@ -1,68 +1,226 @@
 use crate::{cache::Cache, receipts::{hash_response, Receipt, ReceiptRequest, sign_receipt}};
+use crate::config::QuorumConfig;
 use crate::coordinator::CoordinatorClient;
+use crate::upstreams::UpstreamPool;
 use crate::verify::{verify_proof, ProofStep};
 use ed25519_dalek::SigningKey;
 use reqwest::Client;
+use serde_json::json;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 use tokio::time::sleep;
 use tracing::{info, warn};
 
+pub struct PrefetchContext {
+  pub cache: Arc<Mutex<Cache>>,
+  pub client: Client,
+  pub coordinator: Arc<CoordinatorClient>,
+  pub signing_key: Arc<SigningKey>,
+  pub node_id: String,
+  pub upstreams: Arc<Mutex<UpstreamPool>>,
+  pub quorum: QuorumConfig,
+}
+
+/// Result of asking several upstreams for the same name.
+#[derive(Debug)]
+pub struct QuorumOutcome {
+  pub body: Option<String>,
+  pub result_hash: Option<String>,
+  pub details: serde_json::Value,
+}
+
 pub async fn run_prefetch_loop(
-  cache: Arc<Mutex<Cache>>,
-  client: Client,
-  coordinator: Arc<CoordinatorClient>,
-  signing_key: Arc<SigningKey>,
-  node_id: String,
-  resolver_url: String,
+  ctx: PrefetchContext,
   hot_names: Vec<String>,
   interval_seconds: u64,
   ttl_seconds: u64,
 ) {
   loop {
     for name in &hot_names {
-      let url = format!("{}?name={}", resolver_url, name);
-      match client.get(&url).send().await {
-        Ok(resp) if resp.status().is_success() => {
-          if let Ok(body) = resp.text().await {
-            if !should_cache_response(&body) {
-              warn!("skipping unverified response for {}", name);
-              continue;
-            }
-            let result_hash = hash_response(&body);
-            let mut cache_lock = cache.lock().await;
-            cache_lock.set(
-              name.clone(),
-              body.clone(),
-              Duration::from_secs(ttl_seconds),
-              result_hash.clone(),
-            );
-            info!("cached {}", name);
-            let receipt = Receipt {
-              receipt_type: "VERIFY".to_string(),
-              node_id: node_id.clone(),
-              ts: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
-              request: Some(ReceiptRequest { name: name.clone() }),
-              result_hash: Some(result_hash),
-              bytes: Some(body.len() as u64),
-              details: None,
-            };
-            if let Ok(env) = sign_receipt(&signing_key, receipt) {
-              let coordinator_clone = coordinator.clone();
-              tokio::spawn(async move {
-                let _ = coordinator_clone.post_receipt(&env).await;
-              });
-            }
+      if ctx.quorum.enabled {
+        let outcome = fetch_quorum(&ctx, name).await;
+        match (outcome.body, outcome.result_hash) {
+          (Some(body), Some(result_hash)) => {
+            cache_and_report(&ctx, name, body, result_hash, Some(outcome.details), ttl_seconds).await;
+          }
+          _ => {
+            warn!("no upstream majority for {}", name);
+            post_verify_receipt(&ctx, name, None, None, Some(outcome.details));
           }
         }
-        _ => {}
+      } else if let Some(body) = fetch_failover(&ctx, name).await {
+        let result_hash = hash_response(&body);
+        cache_and_report(&ctx, name, body, result_hash, None, ttl_seconds).await;
       }
     }
     sleep(Duration::from_secs(interval_seconds)).await;
   }
 }
 
+async fn cache_and_report(
+  ctx: &PrefetchContext,
+  name: &str,
+  body: String,
+  result_hash: String,
+  details: Option<serde_json::Value>,
+  ttl_seconds: u64,
+) {
+  let bytes = body.len() as u64;
+  {
+    let mut cache_lock = ctx.cache.lock().await;
+    cache_lock.set(name.to_string(), body, Duration::from_secs(ttl_seconds), result_hash.clone());
+  }
+  info!("cached {}", name);
+  post_verify_receipt(ctx, name, Some(result_hash), Some(bytes), details);
+}
+
+fn post_verify_receipt(
+  ctx: &PrefetchContext,
+  name: &str,
+  result_hash: Option<String>,
+  bytes: Option<u64>,
+  details: Option<serde_json::Value>,
+) {
+  let receipt = Receipt {
+    receipt_type: "VERIFY".to_string(),
+    node_id: ctx.node_id.clone(),
+    ts: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+    request: Some(ReceiptRequest { name: name.to_string() }),
+    result_hash,
+    bytes,
+    details,
+  };
+  if let Ok(env) = sign_receipt(&ctx.signing_key, receipt) {
+    let coordinator_clone = ctx.coordinator.clone();
+    tokio::spawn(async move {
+      let _ = coordinator_clone.post_receipt(&env).await;
+    });
+  }
+}
+
+/// Tries upstreams in health order and returns the first verified body.
+async fn fetch_failover(ctx: &PrefetchContext, name: &str) -> Option<String> {
+  let urls = ctx.upstreams.lock().await.ordered();
+  for url in urls {
+    match fetch_verified(&ctx.client, &url, name).await {
+      Ok(body) => {
+        ctx.upstreams.lock().await.record_success(&url);
+        return Some(body);
+      }
+      Err(reason) => {
+        warn!("upstream {} failed for {}: {}", url, name, reason);
+        ctx.upstreams.lock().await.record_failure(&url);
+      }
+    }
+  }
+  None
+}
+
+/// Queries `sample_size` upstreams concurrently and tallies their answers.
+async fn fetch_quorum(ctx: &PrefetchContext, name: &str) -> QuorumOutcome {
+  let urls: Vec<String> = ctx
+    .upstreams
+    .lock()
+    .await
+    .ordered()
+    .into_iter()
+    .take(ctx.quorum.sample_size.max(1))
+    .collect();
+
+  let handles: Vec<_> = urls
+    .iter()
+    .map(|url| {
+      let client = ctx.client.clone();
+      let url = url.clone();
+      let name = name.to_string();
+      tokio::spawn(async move { fetch_verified(&client, &url, &name).await })
+    })
+    .collect();
+
+  let mut results = Vec::with_capacity(urls.len());
+  for (url, handle) in urls.into_iter().zip(handles) {
+    let result = handle.await.unwrap_or_else(|_| Err("join_error".to_string()));
+    {
+      let mut pool = ctx.upstreams.lock().await;
+      if result.is_ok() {
+        pool.record_success(&url);
+      } else {
+        pool.record_failure(&url);
+      }
+    }
+    results.push((url, result));
+  }
+
+  tally_quorum(results)
+}
+
+/// Picks the body whose `hash_response` is shared by a strict majority of the
+/// queried upstreams. Every upstream that disagreed or failed is listed in
+/// the returned details so receipts carry the evidence.
+pub fn tally_quorum(results: Vec<(String, Result<String, String>)>) -> QuorumOutcome {
+  let queried = results.len();
+  let mut groups: BTreeMap<String, (usize, String)> = BTreeMap::new();
+  let mut hashed: Vec<(String, Result<String, String>)> = Vec::with_capacity(queried);
+  for (url, result) in results {
+    match result {
+      Ok(body) => {
+        let hash = hash_response(&body);
+        let entry = groups.entry(hash.clone()).or_insert((0, body));
+        entry.0 += 1;
+        hashed.push((url, Ok(hash)));
+      }
+      Err(reason) => hashed.push((url, Err(reason))),
+    }
+  }
+
+  let winner = groups
+    .into_iter()
+    .max_by_key(|(_, (count, _))| *count)
+    .filter(|(_, (count, _))| count * 2 > queried);
+  let winner_hash = winner.as_ref().map(|(hash, _)| hash.clone());
+  let agreeing = winner.as_ref().map(|(_, (count, _))| *count).unwrap_or(0);
+
+  let disagreements: Vec<serde_json::Value> = hashed
+    .into_iter()
+    .filter_map(|(url, result)| match result {
+      Ok(hash) if Some(&hash) == winner_hash.as_ref() => None,
+      Ok(hash) => Some(json!({"upstream": url, "result_hash": hash})),
+      Err(reason) => Some(json!({"upstream": url, "error": reason})),
+    })
+    .collect();
+
+  let details = json!({
+    "quorum": {
+      "status": if winner_hash.is_some() { "majority" } else { "no_majority" },
+      "queried": queried,
+      "agreeing": agreeing,
+      "result_hash": winner_hash,
+      "disagreements": disagreements,
+    }
+  });
+
+  QuorumOutcome {
+    body: winner.map(|(_, (_, body))| body),
+    result_hash: winner_hash,
+    details,
+  }
+}
+
+async fn fetch_verified(client: &Client, upstream: &str, name: &str) -> Result<String, String> {
+  let url = format!("{}?name={}", upstream, name);
+  let resp = client.get(&url).send().await.map_err(|_| "request_error".to_string())?;
+  if !resp.status().is_success() {
+    return Err(format!("status_{}", resp.status().as_u16()));
+  }
+  let body = resp.text().await.map_err(|_| "body_error".to_string())?;
+  if !should_cache_response(&body) {
+    return Err("unverified".to_string());
+  }
+  Ok(body)
+}
+
 fn should_cache_response(body: &str) -> bool {
   let value: serde_json::Value = match serde_json::from_str(body) {
     Ok(v) => v,
@@ -89,3 +247,36 @@ fn should_cache_response(body: &str) -> bool {
   };
   verify_proof(root.unwrap(), leaf.unwrap(), &steps)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ok(url: &str, body: &str) -> (String, Result<String, String>) {
+    (url.to_string(), Ok(body.to_string()))
+  }
+
+  #[test]
+  fn quorum_majority_records_disagreement() {
+    let outcome = tally_quorum(vec![
+      ok("a", r#"{"ip":"1.1.1.1"}"#),
+      ok("b", r#"{"ip":"1.1.1.1"}"#),
+      ok("c", r#"{"ip":"6.6.6.6"}"#),
+    ]);
+    assert_eq!(outcome.body.as_deref(), Some(r#"{"ip":"1.1.1.1"}"#));
+    let quorum = &outcome.details["quorum"];
+    assert_eq!(quorum["agreeing"], 2);
+    assert_eq!(quorum["disagreements"][0]["upstream"], "c");
+  }
+
+  #[test]
+  fn quorum_requires_strict_majority_of_queried() {
+    let outcome = tally_quorum(vec![
+      ok("a", r#"{"ip":"1.1.1.1"}"#),
+      ("b".to_string(), Err("status_503".to_string())),
+    ]);
+    assert!(outcome.body.is_none());
+    assert_eq!(outcome.details["quorum"]["status"], "no_majority");
+    assert_eq!(outcome.details["quorum"]["disagreements"].as_array().unwrap().len(), 2);
+  }
+}
@@ -0,0 +1,576 @@
+use crate::onchain::{
+  canonical_route_address, decode_canonical_route, decode_policy_config, hash_name, normalize_name,
+  policy_config_address, sha256, submit_attestation_digest_ix, AttestationDigest,
+};
+use crate::solana::{Pubkey, RpcClient};
+use ed25519_dalek::SigningKey;
+use reqwest::Client;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+pub const KIND_RESOLVE: u8 = 1;
+pub const KIND_MISMATCH: u8 = 2;
+
+pub const OUTCOME_OK: u8 = 0;
+pub const OUTCOME_NXDOMAIN: u8 = 1;
+pub const OUTCOME_SERVFAIL: u8 = 2;
+pub const OUTCOME_TIMEOUT: u8 = 3;
+pub const OUTCOME_REFUSED: u8 = 5;
+
+pub const REASON_UPSTREAM_FAILURE: u32 = 1 << 0;
+pub const REASON_UPSTREAM_DISAGREEMENT: u32 = 1 << 1;
+pub const REASON_ONCHAIN_MISMATCH: u32 = 1 << 2;
+pub const REASON_ONCHAIN_MISSING: u32 = 1 << 3;
+pub const REASON_TIMEOUT: u32 = 1 << 4;
+
+const ATTEST_DOMAIN: &[u8] = b"DDNS_WATCHDOG_ATTEST_V1";
+
+pub struct WatchdogContext {
+  pub client: Client,
+  pub rpc: Arc<RpcClient>,
+  pub signing_key: Arc<SigningKey>,
+  pub upstreams: Vec<String>,
+  pub names: Vec<String>,
+  pub registry_program: Pubkey,
+  pub policy_program: Pubkey,
+  pub submit_margin_slots: u64,
+}
+
+/// One resolution of a name through one upstream.
+#[derive(Clone, Debug)]
+pub struct Observation {
+  pub upstream: String,
+  pub outcome: u8,
+  pub rrset_hash: Option<[u8; 32]>,
+  pub ttl_s: u32,
+  pub latency_ms: u32,
+  pub observed_at_unix: i64,
+  pub observed_slot: u64,
+}
+
+/// Everything seen for one name during the current epoch.
+#[derive(Clone, Debug, Default)]
+pub struct NameBatch {
+  pub observations: Vec<Observation>,
+  pub onchain_dest_hash: Option<[u8; 32]>,
+  pub expects_onchain: bool,
+  /// Set once this name's digest for the epoch has been accepted.
+  pub submitted: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct EpochBatch {
+  pub epoch_id: u64,
+  pub names: BTreeMap<String, NameBatch>,
+  pub submitted: bool,
+  /// Root of the leaf set at the epoch's first submit. Once set, no more
+  /// observations are recorded, so every digest of the epoch, including
+  /// retries, commits this same root.
+  pub frozen_root: Option<[u8; 32]>,
+}
+
+impl EpochBatch {
+  pub fn new(epoch_id: u64) -> Self {
+    Self { epoch_id, names: BTreeMap::new(), submitted: false, frozen_root: None }
+  }
+
+  /// Adds a tick's observations of `name`; ignored once the batch is frozen.
+  pub fn record(
+    &mut self,
+    name: &str,
+    observations: Vec<Observation>,
+    expects_onchain: bool,
+    onchain_dest_hash: Option<[u8; 32]>,
+  ) {
+    if self.frozen_root.is_some() {
+      return;
+    }
+    let entry = self.names.entry(name.to_string()).or_default();
+    entry.observations.extend(observations);
+    entry.expects_onchain = expects_onchain;
+    if onchain_dest_hash.is_some() {
+      entry.onchain_dest_hash = onchain_dest_hash;
+    }
+  }
+
+  /// Freezes the leaf set and returns its root; later calls return the same
+  /// root.
+  pub fn freeze(&mut self) -> [u8; 32] {
+    if self.frozen_root.is_none() {
+      self.frozen_root = Some(self.root());
+    }
+    self.frozen_root.unwrap_or_default()
+  }
+
+  /// Merkle root over every observation leaf in the batch, committed as
+  /// `last_root` so the digests can later be tied back to raw observations.
+  pub fn root(&self) -> [u8; 32] {
+    let leaves: Vec<[u8; 32]> = self
+      .names
+      .iter()
+      .flat_map(|(name, batch)| {
+        let hash = hash_name(name);
+        batch.observations.iter().map(move |obs| observation_leaf(&hash, obs))
+      })
+      .collect();
+    merkle_root(leaves)
+  }
+
+  /// Pending digests, against the frozen root once the batch is frozen.
+  pub fn digests(&self) -> Vec<AttestationDigest> {
+    let last_root = self.frozen_root.unwrap_or_else(|| self.root());
+    self
+      .names
+      .iter()
+      .filter(|(_, batch)| !batch.observations.is_empty() && !batch.submitted)
+      .map(|(name, batch)| {
+        let mut digest = classify(batch);
+        digest.epoch_id = self.epoch_id;
+        digest.name_hash = hash_name(name);
+        digest.last_root = last_root;
+        digest
+      })
+      .collect()
+  }
+}
+
+pub async fn run_watchdog_loop(mut ctx: WatchdogContext, interval_seconds: u64) {
+  ctx.names = ctx
+    .names
+    .iter()
+    .filter_map(|name| match normalize_name(name) {
+      Ok(normalized) => Some(normalized),
+      Err(err) => {
+        warn!("watchdog skips {}: {}", name, err);
+        None
+      }
+    })
+    .collect();
+  let mut batch = EpochBatch::default();
+  loop {
+    if let Err(err) = watchdog_tick(&ctx, &mut batch).await {
+      warn!("watchdog tick failed: {}", err);
+    }
+    sleep(Duration::from_secs(interval_seconds)).await;
+  }
+}
+
+async fn watchdog_tick(ctx: &WatchdogContext, batch: &mut EpochBatch) -> anyhow::Result<()> {
+  let config_data = ctx
+    .rpc
+    .get_account_data(&policy_config_address(&ctx.policy_program))
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("watchdog policy config not initialized"))?;
+  let policy = decode_policy_config(&config_data)?;
+  anyhow::ensure!(policy.epoch_len_slots > 0, "invalid epoch length");
+
+  let slot = ctx.rpc.get_slot().await?;
+  let epoch_id = slot / policy.epoch_len_slots;
+  if batch.epoch_id != epoch_id {
+    if !batch.submitted && !batch.names.is_empty() {
+      warn!("dropping unsubmitted watchdog batch for epoch {}", batch.epoch_id);
+    }
+    *batch = EpochBatch::new(epoch_id);
+  }
+
+  if batch.frozen_root.is_none() {
+    for normalized in &ctx.names {
+      let observations = observe_name(ctx, normalized, slot).await;
+      let expects_onchain = normalized.ends_with(".dns");
+      let onchain_dest_hash = if expects_onchain {
+        read_canonical_dest_hash(ctx, normalized).await
+      } else {
+        None
+      };
+      batch.record(normalized, observations, expects_onchain, onchain_dest_hash);
+    }
+  }
+
+  let slots_left = policy.epoch_len_slots - slot % policy.epoch_len_slots;
+  if batch.submitted || slots_left > ctx.submit_margin_slots {
+    return Ok(());
+  }
+
+  let watchdog = Pubkey::from_signing_key(&ctx.signing_key);
+  batch.freeze();
+  let digests = batch.digests();
+  if !policy.allowlisted_watchdogs.contains(&watchdog) {
+    info!("watchdog {} not allowlisted; {} digests for epoch {} not submitted", watchdog, digests.len(), epoch_id);
+    batch.submitted = true;
+    return Ok(());
+  }
+  // Names whose send fails stay pending and are retried on the next tick
+  // that is still inside the epoch, against the same frozen root.
+  for digest in digests {
+    let ix = submit_attestation_digest_ix(&ctx.policy_program, &watchdog, &watchdog, &digest);
+    match ctx.rpc.send_instructions(&ctx.signing_key, &[ix]).await {
+      Ok(sig) => {
+        info!("submitted attestation digest kind={} sig={}", digest.kind, sig);
+        if let Some((_, entry)) = batch.names.iter_mut().find(|(name, _)| hash_name(name) == digest.name_hash) {
+          entry.submitted = true;
+        }
+      }
+      Err(err) => warn!("attestation digest submit failed: {}", err),
+    }
+  }
+  batch.submitted = batch.digests().is_empty();
+  Ok(())
+}
+
+async fn read_canonical_dest_hash(ctx: &WatchdogContext, name: &str) -> Option<[u8; 32]> {
+  let address = canonical_route_address(&ctx.registry_program, &hash_name(name));
+  match ctx.rpc.get_account_data(&address).await {
+    Ok(Some(data)) => decode_canonical_route(&data).ok().map(|route| route.dest_hash),
+    Ok(None) => None,
+    Err(err) => {
+      warn!("canonical route read failed for {}: {}", name, err);
+      None
+    }
+  }
+}
+
+async fn observe_name(ctx: &WatchdogContext, name: &str, slot: u64) -> Vec<Observation> {
+  let handles: Vec<_> = ctx
+    .upstreams
+    .iter()
+    .map(|upstream| {
+      let client = ctx.client.clone();
+      let upstream = upstream.clone();
+      let name = name.to_string();
+      tokio::spawn(async move { observe_upstream(&client, &upstream, &name, slot).await })
+    })
+    .collect();
+  let mut out = Vec::with_capacity(handles.len());
+  for handle in handles {
+    if let Ok(obs) = handle.await {
+      out.push(obs);
+    }
+  }
+  out
+}
+
+async fn observe_upstream(client: &Client, upstream: &str, name: &str, slot: u64) -> Observation {
+  let started = Instant::now();
+  let observed_at_unix = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+  let url = format!("{}?name={}", upstream, name);
+  let (outcome, rrset_hash, ttl_s) = match client.get(&url).send().await {
+    Ok(resp) if resp.status().is_success() => match resp.text().await {
+      Ok(body) => parse_answer(name, &body),
+      Err(_) => (OUTCOME_SERVFAIL, None, 0),
+    },
+    Ok(resp) if resp.status() == reqwest::StatusCode::NOT_FOUND => (OUTCOME_NXDOMAIN, None, 0),
+    Ok(_) => (OUTCOME_SERVFAIL, None, 0),
+    Err(err) if err.is_timeout() => (OUTCOME_TIMEOUT, None, 0),
+    Err(err) if err.is_connect() => (OUTCOME_REFUSED, None, 0),
+    Err(_) => (OUTCOME_SERVFAIL, None, 0),
+  };
+  Observation {
+    upstream: upstream.to_string(),
+    outcome,
+    rrset_hash,
+    ttl_s,
+    latency_ms: started.elapsed().as_millis().min(u32::MAX as u128) as u32,
+    observed_at_unix,
+    observed_slot: slot,
+  }
+}
+
+/// Owner names inside the gateway's rrset hash input: trimmed, no trailing
+/// dot, lowercase. Answers may carry names that are not valid on chain.
+fn answer_name(name: &str) -> String {
+  name.trim().trim_end_matches('.').to_lowercase()
+}
+
+/// Extracts `(outcome, rrset_hash, ttl_s)` from a gateway `/resolve` body.
+///
+/// `.dns` answers carry `destHashHex`; recursive answers carry `rrset_hash`
+/// or raw `answers`, hashed the same way as the gateway's
+/// `computeRrsetHashFromAnswers`.
+pub fn parse_answer(name: &str, body: &str) -> (u8, Option<[u8; 32]>, u32) {
+  let value: serde_json::Value = match serde_json::from_str(body) {
+    Ok(v) => v,
+    Err(_) => return (OUTCOME_SERVFAIL, None, 0),
+  };
+  match value.get("status").and_then(|v| v.as_str()) {
+    Some("NXDOMAIN") => return (OUTCOME_NXDOMAIN, None, 0),
+    Some("ERROR") => return (OUTCOME_SERVFAIL, None, 0),
+    _ => {}
+  }
+  let ttl_s = value
+    .get("ttlS")
+    .or_else(|| value.get("ttl_s"))
+    .and_then(|v| v.as_u64())
+    .unwrap_or(0)
+    .min(u32::MAX as u64) as u32;
+
+  let hex_field = value
+    .get("destHashHex")
+    .or_else(|| value.get("rrset_hash"))
+    .and_then(|v| v.as_str())
+    .and_then(decode_hash_hex);
+  if let Some(hash) = hex_field {
+    return (OUTCOME_OK, Some(hash), ttl_s);
+  }
+
+  if let Some(answers) = value.get("answers").and_then(|v| v.as_array()) {
+    let qtype = value.get("type").and_then(|v| v.as_str()).unwrap_or("A").to_uppercase();
+    let mut norm: Vec<String> = answers
+      .iter()
+      .map(|a| {
+        let a_name = a.get("name").and_then(|v| v.as_str()).filter(|s| !s.is_empty()).unwrap_or(name);
+        let a_type = a.get("type").and_then(|v| v.as_str()).unwrap_or(&qtype).to_uppercase();
+        let a_data = a.get("data").and_then(|v| v.as_str()).unwrap_or("").trim();
+        format!("{}|{}|{}", answer_name(a_name), a_type, a_data)
+      })
+      .filter(|s| !s.ends_with('|'))
+      .collect();
+    norm.sort();
+    let input = format!("{}|{}|{}", qtype, answer_name(name), norm.join(","));
+    return (OUTCOME_OK, Some(sha256(input.as_bytes())), ttl_s);
+  }
+
+  (OUTCOME_OK, Some(sha256(body.as_bytes())), ttl_s)
+}
+
+fn decode_hash_hex(s: &str) -> Option<[u8; 32]> {
+  hex::decode(s.trim_start_matches("0x")).ok()?.try_into().ok()
+}
+
+/// Classifies a name's epoch observations into a digest (epoch, name and
+/// root are filled in by the caller).
+///
+/// The expected answer is the on-chain `dest_hash` when one exists, otherwise
+/// the majority upstream answer. Any answer differing from it makes the
+/// digest a mismatch; no answers at all make it a failure.
+pub fn classify(batch: &NameBatch) -> AttestationDigest {
+  let total = batch.observations.len().max(1) as u64;
+  let observed_at_unix = batch.observations.iter().map(|o| o.observed_at_unix).max().unwrap_or(0);
+  let mut reason_flags = 0u32;
+
+  let failures: Vec<u8> = batch.observations.iter().filter(|o| o.outcome != OUTCOME_OK).map(|o| o.outcome).collect();
+  if !failures.is_empty() {
+    reason_flags |= REASON_UPSTREAM_FAILURE;
+  }
+  if failures.contains(&OUTCOME_TIMEOUT) {
+    reason_flags |= REASON_TIMEOUT;
+  }
+  if batch.expects_onchain && batch.onchain_dest_hash.is_none() {
+    reason_flags |= REASON_ONCHAIN_MISSING;
+  }
+
+  let mut groups: BTreeMap<[u8; 32], u64> = BTreeMap::new();
+  for hash in batch.observations.iter().filter(|o| o.outcome == OUTCOME_OK).filter_map(|o| o.rrset_hash) {
+    *groups.entry(hash).or_default() += 1;
+  }
+
+  let majority = groups.iter().max_by_key(|(_, count)| **count).map(|(hash, _)| *hash);
+  let Some(majority) = majority else {
+    let mut counts: BTreeMap<u8, u64> = BTreeMap::new();
+    for outcome in &failures {
+      *counts.entry(*outcome).or_default() += 1;
+    }
+    let (outcome, count) = counts.into_iter().max_by_key(|(_, c)| *c).unwrap_or((OUTCOME_SERVFAIL, 0));
+    return AttestationDigest {
+      epoch_id: 0,
+      name_hash: [0u8; 32],
+      kind: KIND_RESOLVE,
+      outcome,
+      reason_flags,
+      confidence_bps: bps(count, total),
+      rrset_hash: [0u8; 32],
+      observed_at_unix,
+      last_root: [0u8; 32],
+    };
+  };
+
+  if groups.len() > 1 {
+    reason_flags |= REASON_UPSTREAM_DISAGREEMENT;
+  }
+  let expected = batch.onchain_dest_hash.unwrap_or(majority);
+  if batch.onchain_dest_hash.is_some() && majority != expected {
+    reason_flags |= REASON_ONCHAIN_MISMATCH;
+  }
+  let matching = groups.get(&expected).copied().unwrap_or(0);
+  let mismatching: u64 = groups.values().sum::<u64>() - matching;
+
+  let (kind, confidence_bps) = if mismatching > 0 {
+    (KIND_MISMATCH, bps(mismatching, total))
+  } else {
+    (KIND_RESOLVE, bps(matching, total))
+  };
+  AttestationDigest {
+    epoch_id: 0,
+    name_hash: [0u8; 32],
+    kind,
+    outcome: OUTCOME_OK,
+    reason_flags,
+    confidence_bps,
+    rrset_hash: majority,
+    observed_at_unix,
+    last_root: [0u8; 32],
+  }
+}
+
+fn bps(part: u64, total: u64) -> u16 {
+  (part.saturating_mul(10_000) / total.max(1)).min(10_000) as u16
+}
+
+/// `SHA256("DDNS_WATCHDOG_ATTEST_V1" || ResolveObservationV1)`.
+pub fn observation_leaf(name_hash: &[u8; 32], obs: &Observation) -> [u8; 32] {
+  let mut payload = Vec::with_capacity(2 + 32 + 32 + 4 + 32 + 8 + 8 + 4 + 1 + 2);
+  payload.push(1u8);
+  payload.push(KIND_RESOLVE);
+  payload.extend_from_slice(name_hash);
+  payload.extend_from_slice(&obs.rrset_hash.unwrap_or([0u8; 32]));
+  payload.extend_from_slice(&obs.ttl_s.to_le_bytes());
+  payload.extend_from_slice(&sha256(obs.upstream.as_bytes()));
+  payload.extend_from_slice(&obs.observed_at_unix.to_le_bytes());
+  payload.extend_from_slice(&obs.observed_slot.to_le_bytes());
+  payload.extend_from_slice(&obs.latency_ms.to_le_bytes());
+  payload.push(obs.outcome);
+  payload.extend_from_slice(&0u16.to_le_bytes());
+  let mut msg = ATTEST_DOMAIN.to_vec();
+  msg.extend_from_slice(&payload);
+  sha256(&msg)
+}
+
+fn merkle_root(mut level: Vec<[u8; 32]>) -> [u8; 32] {
+  if level.is_empty() {
+    return [0u8; 32];
+  }
+  while level.len() > 1 {
+    level = level
+      .chunks(2)
+      .map(|pair| {
+        let right = pair.get(1).unwrap_or(&pair[0]);
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(&pair[0]);
+        buf[32..].copy_from_slice(right);
+        sha256(&buf)
+      })
+      .collect();
+  }
+  level[0]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn obs(upstream: &str, outcome: u8, hash: Option<[u8; 32]>) -> Observation {
+    Observation {
+      upstream: upstream.to_string(),
+      outcome,
+      rrset_hash: hash,
+      ttl_s: 60,
+      latency_ms: 10,
+      observed_at_unix: 1_700_000_000,
+      observed_slot: 100,
+    }
+  }
+
+  #[test]
+  fn classify_all_agree_with_chain_is_ok() {
+    let batch = NameBatch {
+      observations: vec![obs("a", OUTCOME_OK, Some([1u8; 32])), obs("b", OUTCOME_OK, Some([1u8; 32]))],
+      onchain_dest_hash: Some([1u8; 32]),
+      expects_onchain: true,
+      submitted: false,
+    };
+    let digest = classify(&batch);
+    assert_eq!(digest.kind, KIND_RESOLVE);
+    assert_eq!(digest.outcome, OUTCOME_OK);
+    assert_eq!(digest.reason_flags, 0);
+    assert_eq!(digest.confidence_bps, 10_000);
+  }
+
+  #[test]
+  fn classify_chain_disagreement_is_mismatch() {
+    let batch = NameBatch {
+      observations: vec![obs("a", OUTCOME_OK, Some([2u8; 32])), obs("b", OUTCOME_TIMEOUT, None)],
+      onchain_dest_hash: Some([1u8; 32]),
+      expects_onchain: true,
+      submitted: false,
+    };
+    let digest = classify(&batch);
+    assert_eq!(digest.kind, KIND_MISMATCH);
+    assert_eq!(digest.rrset_hash, [2u8; 32]);
+    assert_ne!(digest.reason_flags & REASON_ONCHAIN_MISMATCH, 0);
+    assert_ne!(digest.reason_flags & REASON_TIMEOUT, 0);
+    assert_eq!(digest.confidence_bps, 5_000);
+  }
+
+  #[test]
+  fn classify_no_answers_is_failure() {
+    let batch = NameBatch {
+      observations: vec![obs("a", OUTCOME_NXDOMAIN, None), obs("b", OUTCOME_NXDOMAIN, None)],
+      onchain_dest_hash: None,
+      expects_onchain: false,
+      submitted: false,
+    };
+    let digest = classify(&batch);
+    assert_eq!(digest.kind, KIND_RESOLVE);
+    assert_eq!(digest.outcome, OUTCOME_NXDOMAIN);
+  }
+
+  #[test]
+  fn parse_answer_matches_gateway_rrset_hash() {
+    let body = r#"{"name":"example.com","type":"A","answers":[{"name":"example.com","type":"A","data":"2.2.2.2"},{"name":"example.com","type":"A","data":"1.1.1.1"}]}"#;
+    let (outcome, hash, _) = parse_answer("example.com", body);
+    assert_eq!(outcome, OUTCOME_OK);
+    let expected = sha256(b"A|example.com|example.com|A|1.1.1.1,example.com|A|2.2.2.2");
+    assert_eq!(hash, Some(expected));
+  }
+
+  #[test]
+  fn epoch_digests_share_root() {
+    let mut batch = EpochBatch::new(9);
+    batch.names.insert("a.dns".to_string(), NameBatch {
+      observations: vec![obs("a", OUTCOME_OK, Some([1u8; 32]))],
+      onchain_dest_hash: Some([1u8; 32]),
+      expects_onchain: true,
+      submitted: false,
+    });
+    batch.names.insert("b.dns".to_string(), NameBatch {
+      observations: vec![obs("a", OUTCOME_OK, Some([3u8; 32]))],
+      onchain_dest_hash: None,
+      expects_onchain: true,
+      submitted: false,
+    });
+    let digests = batch.digests();
+    assert_eq!(digests.len(), 2);
+    assert_eq!(digests[0].epoch_id, 9);
+    assert_eq!(digests[0].last_root, digests[1].last_root);
+    assert_ne!(digests[0].last_root, [0u8; 32]);
+
+    batch.names.get_mut("a.dns").unwrap().submitted = true;
+    let pending = batch.digests();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].name_hash, hash_name("b.dns"));
+  }
+
+  #[test]
+  fn digests_after_freeze_keep_the_first_root() {
+    let mut batch = EpochBatch::new(9);
+    batch.record("a.dns", vec![obs("a", OUTCOME_OK, Some([1u8; 32]))], true, Some([1u8; 32]));
+    batch.record("b.dns", vec![obs("a", OUTCOME_OK, Some([3u8; 32]))], true, None);
+    let root = batch.freeze();
+    assert_eq!(root, batch.root());
+    batch.names.get_mut("a.dns").unwrap().submitted = true;
+
+    // Observations arriving after the first submit are not recorded.
+    batch.record("b.dns", vec![obs("b", OUTCOME_TIMEOUT, None)], true, None);
+    batch.record("c.dns", vec![obs("a", OUTCOME_OK, Some([4u8; 32]))], true, None);
+    assert_eq!(batch.names.len(), 2);
+    assert_eq!(batch.names["b.dns"].observations.len(), 1);
+    assert_eq!(batch.freeze(), root);
+
+    let retried = batch.digests();
+    assert_eq!(retried.len(), 1);
+    assert_eq!(retried[0].name_hash, hash_name("b.dns"));
+    assert_eq!(retried[0].last_root, root);
+  }
+}
@@ -20,4 +20,18 @@ mod tests {
     let env = sign_receipt(&signing, receipt).unwrap();
     assert!(verify_envelope(&env).unwrap());
   }
+
+  #[test]
+  fn name_hash_matches_shared_name_vectors() {
+    for v in ddns_names_core::vectors::names().filter(|v| v.depth == 0) {
+      match (v.normalized, v.hash) {
+        (Some(name), Some(hash)) => {
+          assert_eq!(crate::onchain::normalize_name(v.input).as_deref(), Ok(name), "{}", v.input);
+          assert_eq!(crate::onchain::hash_name(name), hash, "{}", v.input);
+        }
+        (None, _) => assert!(crate::onchain::normalize_name(v.input).is_err(), "{}", v.input),
+        _ => {}
+      }
+    }
+  }
 }
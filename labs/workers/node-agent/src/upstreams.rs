@@ -0,0 +1,92 @@
+use std::time::{Duration, Instant};
+
+const FAILURES_BEFORE_COOLDOWN: u32 = 3;
+const BASE_COOLDOWN_SECS: u64 = 30;
+const MAX_COOLDOWN_SECS: u64 = 600;
+
+#[derive(Clone, Debug)]
+pub struct Upstream {
+  pub url: String,
+  pub consecutive_failures: u32,
+  pub unhealthy_until: Option<Instant>,
+}
+
+impl Upstream {
+  fn is_healthy(&self, now: Instant) -> bool {
+    self.unhealthy_until.map(|until| until <= now).unwrap_or(true)
+  }
+}
+
+/// Tracks health for a list of upstream gateways so prefetch can fail over
+/// away from gateways that keep erroring.
+pub struct UpstreamPool {
+  upstreams: Vec<Upstream>,
+}
+
+impl UpstreamPool {
+  pub fn new(urls: Vec<String>) -> Self {
+    let upstreams = urls
+      .into_iter()
+      .map(|url| Upstream { url, consecutive_failures: 0, unhealthy_until: None })
+      .collect();
+    Self { upstreams }
+  }
+
+  /// Upstream URLs in the order they should be tried: healthy ones first
+  /// (fewest recent failures first, config order as tie-break), then the
+  /// ones still cooling down so a fully unhealthy pool is still usable.
+  pub fn ordered(&self) -> Vec<String> {
+    let now = Instant::now();
+    let mut indexed: Vec<(usize, &Upstream)> = self.upstreams.iter().enumerate().collect();
+    indexed.sort_by_key(|(idx, up)| (!up.is_healthy(now), up.consecutive_failures, *idx));
+    indexed.into_iter().map(|(_, up)| up.url.clone()).collect()
+  }
+
+  pub fn record_success(&mut self, url: &str) {
+    if let Some(up) = self.upstreams.iter_mut().find(|u| u.url == url) {
+      up.consecutive_failures = 0;
+      up.unhealthy_until = None;
+    }
+  }
+
+  pub fn record_failure(&mut self, url: &str) {
+    if let Some(up) = self.upstreams.iter_mut().find(|u| u.url == url) {
+      up.consecutive_failures = up.consecutive_failures.saturating_add(1);
+      if up.consecutive_failures >= FAILURES_BEFORE_COOLDOWN {
+        let cooldown = cooldown_secs(up.consecutive_failures);
+        up.unhealthy_until = Some(Instant::now() + Duration::from_secs(cooldown));
+      }
+    }
+  }
+}
+
+fn cooldown_secs(consecutive_failures: u32) -> u64 {
+  let over = consecutive_failures.saturating_sub(FAILURES_BEFORE_COOLDOWN).min(6);
+  (BASE_COOLDOWN_SECS * 2u64.pow(over)).min(MAX_COOLDOWN_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pool() -> UpstreamPool {
+    UpstreamPool::new(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+  }
+
+  #[test]
+  fn failing_upstream_moves_to_back() {
+    let mut pool = pool();
+    for _ in 0..FAILURES_BEFORE_COOLDOWN {
+      pool.record_failure("a");
+    }
+    assert_eq!(pool.ordered(), vec!["b", "c", "a"]);
+    pool.record_success("a");
+    assert_eq!(pool.ordered(), vec!["a", "b", "c"]);
+  }
+
+  #[test]
+  fn cooldown_caps() {
+    assert_eq!(cooldown_secs(FAILURES_BEFORE_COOLDOWN), BASE_COOLDOWN_SECS);
+    assert!(cooldown_secs(100) <= MAX_COOLDOWN_SECS);
+  }
+}
@@ -0,0 +1,195 @@
+//! Instructions for the common flows, with every PDA derived here. Callers
+//! pass only wallets and values they read from config accounts (treasury,
+//! reward mint).
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::get_associated_token_address;
+use sha2::{Digest, Sha256};
+
+use crate::programs::{anchor as toll, names, stake};
+use crate::{instruction, name_hash, pda, ClientError};
+
+/// `sha256(name_hash || owner || salt)`, the value `commit_name` stores.
+pub fn name_commitment(name_hash: &[u8; 32], owner: &Pubkey, salt: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(name_hash)
+        .chain_update(owner.as_ref())
+        .chain_update(salt)
+        .finalize()
+        .into()
+}
+
+/// First step of commit-reveal registration. Send it, wait out the config's
+/// `commit_min_slots`, then send [`register_premium`] with the same salt.
+pub fn commit_name(owner: &Pubkey, name: &str, salt: &[u8; 32]) -> Result<Instruction, ClientError> {
+    let (_, hash) = name_hash(name)?;
+    let commitment = name_commitment(&hash, owner, salt);
+    Ok(instruction(
+        names::ID,
+        names::accounts::CommitName {
+            name_commitment: pda::names::commitment(&commitment).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        names::instruction::CommitName { commitment },
+    ))
+}
+
+/// Buys a premium `.dns` name for lamports. With `salt`, the commitment made
+/// by [`commit_name`] is revealed and consumed in the same transaction;
+/// without it the config must not require commit-reveal. `treasury` is
+/// `NamesConfig.treasury`.
+pub fn register_premium(
+    owner: &Pubkey,
+    treasury: &Pubkey,
+    name: &str,
+    salt: Option<&[u8; 32]>,
+) -> Result<Vec<Instruction>, ClientError> {
+    let (normalized, hash) = name_hash(name)?;
+    let mut ixs = Vec::new();
+    let name_commitment = salt.map(|salt| {
+        let commitment = name_commitment(&hash, owner, salt);
+        let address = pda::names::commitment(&commitment).0;
+        ixs.push(instruction(
+            names::ID,
+            names::accounts::RevealName { config: pda::names::config().0, name_commitment: address, owner: *owner },
+            names::instruction::RevealName { _commitment: commitment, name_hash: hash, salt: *salt },
+        ));
+        address
+    });
+    ixs.push(instruction(
+        names::ID,
+        names::accounts::PurchasePremium {
+            config: pda::names::config().0,
+            premium_config: pda::names::premium_config().0,
+            treasury: *treasury,
+            premium_name: pda::names::premium_name(&hash).0,
+            parent_policy: pda::names::parent_policy(&hash).0,
+            primary: pda::names::primary_name(owner).0,
+            name_price: pda::names::name_price(&hash).0,
+            name_commitment,
+            owner: *owner,
+            system_program: system_program::ID,
+            payer_toll_account: None,
+            toll_treasury: None,
+            toll_mint: None,
+            token_program: None,
+        },
+        names::instruction::PurchasePremium { name: normalized, name_hash: hash },
+    ));
+    Ok(ixs)
+}
+
+/// Sets the `ddns_anchor` route for `<label>.dns`, signed by the config admin
+/// on behalf of `owner_wallet`, who must hold the toll pass for the name.
+pub fn set_route(
+    admin: &Pubkey,
+    owner_wallet: &Pubkey,
+    name: &str,
+    dest_hash: [u8; 32],
+    ttl: u32,
+) -> Result<Instruction, ClientError> {
+    let (normalized, hash) = name_hash(name)?;
+    Ok(instruction(
+        toll::ID,
+        toll::accounts::SetRoute {
+            config: pda::anchor::config().0,
+            route_record: pda::anchor::route_record(owner_wallet, &hash).0,
+            name_record: pda::anchor::name_record(&hash).0,
+            toll_pass: pda::anchor::toll_pass(owner_wallet).0,
+            owner_wallet: *owner_wallet,
+            authority: *admin,
+            system_program: system_program::ID,
+        },
+        toll::instruction::SetRoute { name: normalized, name_hash: hash, dest_hash, ttl },
+    ))
+}
+
+/// Stakes lamports into `ddns_stake`, creating the position on first use.
+pub fn stake(owner: &Pubkey, amount_lamports: u64) -> Instruction {
+    instruction(
+        stake::ID,
+        stake::accounts::Stake {
+            owner: *owner,
+            stake_config: pda::stake::config().0,
+            stake_vault: pda::stake::stake_vault().0,
+            stake_position: pda::stake::stake_position(owner).0,
+            system_program: system_program::ID,
+        },
+        stake::instruction::Stake { amount_lamports },
+    )
+}
+
+/// Claims `ddns_stake` rewards to the owner's associated token account for
+/// `reward_mint` (`StakeConfig.reward_mint`), which must already exist.
+pub fn claim_stake_rewards(owner: &Pubkey, reward_mint: &Pubkey) -> Instruction {
+    instruction(
+        stake::ID,
+        stake::accounts::ClaimRewards {
+            owner: *owner,
+            stake_config: pda::stake::config().0,
+            stake_position: pda::stake::stake_position(owner).0,
+            reward_mint: *reward_mint,
+            user_reward_ata: get_associated_token_address(owner, reward_mint),
+            mint_authority: pda::stake::mint_authority().0,
+            token_program: anchor_spl::token::ID,
+        },
+        stake::instruction::ClaimRewards {},
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AnchorDeserialize;
+
+    #[test]
+    fn register_premium_reveals_then_purchases_with_the_commitment() {
+        let owner = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let salt = [9u8; 32];
+        let commit = commit_name(&owner, "Alice.dns", &salt).unwrap();
+        let ixs = register_premium(&owner, &treasury, "Alice.dns", Some(&salt)).unwrap();
+        assert_eq!(ixs.len(), 2);
+
+        let hash = ddns_names_core::hash_name("alice.dns");
+        let commitment_account = pda::names::commitment(&name_commitment(&hash, &owner, &salt)).0;
+        assert_eq!(commit.accounts[0].pubkey, commitment_account);
+        assert_eq!(ixs[0].accounts[1].pubkey, commitment_account);
+        // `name_commitment` follows `name_price` in `PurchasePremium`.
+        assert_eq!(ixs[1].accounts[7].pubkey, commitment_account);
+        assert_eq!(ixs[1].accounts[3].pubkey, pda::names::premium_name(&hash).0);
+
+        let args = names::instruction::PurchasePremium::try_from_slice(&ixs[1].data[8..]).unwrap();
+        assert_eq!((args.name.as_str(), args.name_hash), ("alice.dns", hash));
+    }
+
+    #[test]
+    fn register_premium_without_commit_passes_program_id_for_optional_accounts() {
+        let owner = Pubkey::new_unique();
+        let ixs = register_premium(&owner, &Pubkey::new_unique(), "bob.dns", None).unwrap();
+        assert_eq!(ixs.len(), 1);
+        let optional: Vec<_> = [7, 10, 11, 12, 13].iter().map(|&i| ixs[0].accounts[i].pubkey).collect();
+        assert!(optional.iter().all(|k| *k == names::ID));
+        assert!(register_premium(&owner, &Pubkey::new_unique(), "bad name.dns", None).is_err());
+    }
+
+    #[test]
+    fn set_route_and_stake_flows_sign_with_the_right_wallets() {
+        let admin = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let ix = set_route(&admin, &owner, "shop.dns", [1u8; 32], 60).unwrap();
+        let signers: Vec<_> = ix.accounts.iter().filter(|m| m.is_signer).map(|m| m.pubkey).collect();
+        assert_eq!(signers, vec![admin]);
+        let hash = ddns_names_core::hash_dns_label("shop");
+        assert_eq!(ix.accounts[1].pubkey, pda::anchor::route_record(&owner, &hash).0);
+
+        let stake_ix = stake(&owner, 5);
+        assert_eq!(stake_ix.accounts[3].pubkey, pda::stake::stake_position(&owner).0);
+        let mint = Pubkey::new_unique();
+        let claim = claim_stake_rewards(&owner, &mint);
+        assert_eq!(claim.accounts[4].pubkey, get_associated_token_address(&owner, &mint));
+    }
+}
@@ -0,0 +1,144 @@
+//! Rust client for the ddns Solana programs.
+//!
+//! - [`pda`] derives every program-derived address from its seeds.
+//! - [`programs`] re-exports each program crate (built without its
+//!   entrypoint). `programs::<name>::accounts::*` and
+//!   `programs::<name>::instruction::*` are Anchor's typed account lists and
+//!   argument structs; [`instruction`] turns a pair of them into an
+//!   [`Instruction`].
+//! - [`decode`] reads any program account, checking its discriminator.
+//! - [`flows`] builds the common multi-account calls with every PDA filled
+//!   in: register a name, set a route, stake and claim rewards.
+//!
+//! The crate builds instructions and decodes data; sending transactions and
+//! fetching accounts is left to whichever RPC client the caller uses.
+
+use std::fmt;
+
+pub use anchor_lang::prelude::Pubkey;
+pub use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+
+pub mod flows;
+pub mod pda;
+
+/// The program crates, for their `ID`, account types, `accounts::*` and
+/// `instruction::*` modules.
+pub mod programs {
+    pub use ddns_anchor as anchor;
+    pub use ddns_cache_head as cache_head;
+    pub use ddns_domain_rewards as domain_rewards;
+    pub use ddns_escrow as escrow;
+    pub use ddns_miner_score as miner_score;
+    pub use ddns_names as names;
+    pub use ddns_ns_incentives as ns_incentives;
+    pub use ddns_operators as operators;
+    pub use ddns_quorum as quorum;
+    pub use ddns_registry as registry;
+    pub use ddns_rent_bond as rent_bond;
+    pub use ddns_rep as rep;
+    pub use ddns_rewards as rewards;
+    pub use ddns_stake as stake;
+    pub use ddns_stake_gov as stake_gov;
+    pub use ddns_watchdog_policy as watchdog_policy;
+    pub use ddns_witness_rewards as witness_rewards;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientError {
+    /// Account data is missing, too short or has another account's
+    /// discriminator.
+    InvalidAccount,
+    /// The name fails `ddns_names_core::normalize_name`.
+    InvalidName(ddns_names_core::NameError),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::InvalidAccount => write!(f, "account data does not decode as the requested type"),
+            ClientError::InvalidName(e) => write!(f, "invalid name: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Builds an instruction from Anchor's generated account list and argument
+/// struct, e.g. `programs::registry::accounts::FinalizeRoute` with
+/// `programs::registry::instruction::FinalizeRoute`.
+pub fn instruction(program_id: Pubkey, accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+/// Decodes account data (discriminator included) as a program account type.
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T, ClientError> {
+    let mut slice = data;
+    T::try_deserialize(&mut slice).map_err(|_| ClientError::InvalidAccount)
+}
+
+/// Normalizes `name` and returns it with its `name_hash`.
+pub fn name_hash(name: &str) -> Result<(String, [u8; 32]), ClientError> {
+    let normalized = ddns_names_core::normalize_name(name).map_err(ClientError::InvalidName)?;
+    let hash = ddns_names_core::hash_name(&normalized);
+    Ok((normalized, hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountSerialize, AnchorDeserialize, Discriminator};
+    use programs::{names, registry};
+
+    #[test]
+    fn decodes_accounts_and_rejects_other_discriminators() {
+        let route = registry::CanonicalRoute {
+            name_hash: [1u8; 32],
+            dest_hash: [2u8; 32],
+            ttl_s: 300,
+            version: 4,
+            updated_at_slot: 9,
+            last_aggregate: Pubkey::new_unique(),
+            bump: 255,
+        };
+        let mut data = Vec::new();
+        route.try_serialize(&mut data).unwrap();
+
+        let decoded: registry::CanonicalRoute = decode(&data).unwrap();
+        assert_eq!(decoded.dest_hash, [2u8; 32]);
+        assert_eq!(decoded.version, 4);
+        assert_eq!(decode::<names::PrimaryName>(&data).err(), Some(ClientError::InvalidAccount));
+        assert_eq!(decode::<registry::CanonicalRoute>(&data[..7]).err(), Some(ClientError::InvalidAccount));
+    }
+
+    #[test]
+    fn instruction_data_is_discriminator_then_borsh_args() {
+        let name_hash = [7u8; 32];
+        let ix = instruction(
+            names::ID,
+            names::accounts::CommitName {
+                name_commitment: pda::names::commitment(&name_hash).0,
+                owner: Pubkey::new_unique(),
+                system_program: anchor_lang::system_program::ID,
+            },
+            names::instruction::CommitName { commitment: name_hash },
+        );
+        assert_eq!(&ix.data[..8], names::instruction::CommitName::DISCRIMINATOR);
+        let args = names::instruction::CommitName::try_from_slice(&ix.data[8..]).unwrap();
+        assert_eq!(args.commitment, name_hash);
+        assert_eq!(ix.accounts.len(), 3);
+        assert!(ix.accounts[0].is_writable && ix.accounts[1].is_signer);
+    }
+
+    #[test]
+    fn name_hash_uses_the_shared_normalization() {
+        let (name, hash) = name_hash("Alice.DNS.").unwrap();
+        assert_eq!(name, "alice.dns");
+        assert_eq!(hash, ddns_names_core::hash_name("alice.dns"));
+        assert!(matches!(name_hash("a_b.dns"), Err(ClientError::InvalidName(_))));
+    }
+}
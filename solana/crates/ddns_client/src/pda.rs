@@ -0,0 +1,563 @@
+//! PDA derivation for every seed used by the programs, one module per
+//! program. Each function returns `(address, bump)` under that program's
+//! declared ID; seeds that hold an epoch or record type use little-endian
+//! bytes, as the programs do. Seed constants come from the program crate
+//! where it declares them, and mirror its inline literals otherwise.
+
+use anchor_lang::prelude::Pubkey;
+
+fn find(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, program_id)
+}
+
+pub mod anchor {
+    use super::*;
+    use ddns_anchor::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"config";
+    pub const SEED_TOLL_PASS: &[u8] = b"toll_pass";
+    pub const SEED_NAME: &[u8] = b"name";
+    pub const SEED_RECORD: &[u8] = b"record";
+    pub const SEED_LOCK: &[u8] = b"lock";
+    pub const SEED_VAULT_AUTH: &[u8] = b"vault_auth";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn toll_pass(owner_wallet: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_TOLL_PASS, owner_wallet.as_ref()], &ID)
+    }
+
+    pub fn name_record(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_NAME, name_hash], &ID)
+    }
+
+    pub fn route_record(owner_wallet: &Pubkey, name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_RECORD, owner_wallet.as_ref(), name_hash], &ID)
+    }
+
+    pub fn lock(owner: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_LOCK, owner.as_ref()], &ID)
+    }
+
+    pub fn vault_authority(owner: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_VAULT_AUTH, owner.as_ref()], &ID)
+    }
+}
+
+pub mod cache_head {
+    use super::*;
+    use ddns_cache_head::ID;
+
+    pub use ddns_cache_head::SEED_CACHE_HEAD;
+
+    pub fn cache_head(parent_name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_CACHE_HEAD, parent_name_hash], &ID)
+    }
+}
+
+pub mod domain_rewards {
+    use super::*;
+    use ddns_domain_rewards::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"config";
+    pub const SEED_VAULT_AUTHORITY: &[u8] = b"vault_authority";
+    pub const SEED_DOMAIN_OWNER: &[u8] = b"domain_owner";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn vault_authority() -> (Pubkey, u8) {
+        find(&[SEED_VAULT_AUTHORITY], &ID)
+    }
+
+    pub fn domain_owner(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_DOMAIN_OWNER, name_hash], &ID)
+    }
+}
+
+pub mod escrow {
+    use super::*;
+    use ddns_escrow::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"escrow_config";
+    pub const SEED_ESCROW: &[u8] = b"escrow";
+    pub const SEED_REDEEMED: &[u8] = b"redeemed";
+    pub const SEED_DOMAIN_OWNER: &[u8] = b"domain_owner";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn escrow(user: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_ESCROW, user.as_ref()], &ID)
+    }
+
+    pub fn redeemed(voucher_payer: &Pubkey, redeem_seed: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_REDEEMED, voucher_payer.as_ref(), redeem_seed], &ID)
+    }
+
+    pub fn domain_owner(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_DOMAIN_OWNER, name_hash], &ID)
+    }
+}
+
+pub mod miner_score {
+    use super::*;
+    use ddns_miner_score::ID;
+
+    pub use ddns_miner_score::{
+        SEED_CONFIG, SEED_VAULT_AUTH, SEED_REWARD_VAULT, SEED_MINER_EPOCH, SEED_EPOCH_TOTALS, SEED_CLAIM,
+    };
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn vault_authority() -> (Pubkey, u8) {
+        find(&[SEED_VAULT_AUTH], &ID)
+    }
+
+    pub fn reward_vault() -> (Pubkey, u8) {
+        find(&[SEED_REWARD_VAULT], &ID)
+    }
+
+    pub fn miner_epoch(epoch_id: u64, miner: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_MINER_EPOCH, &epoch_id.to_le_bytes(), miner.as_ref()], &ID)
+    }
+
+    pub fn epoch_totals(epoch_id: u64) -> (Pubkey, u8) {
+        find(&[SEED_EPOCH_TOTALS, &epoch_id.to_le_bytes()], &ID)
+    }
+
+    pub fn claim_receipt(epoch_id: u64, miner: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_CLAIM, &epoch_id.to_le_bytes(), miner.as_ref()], &ID)
+    }
+}
+
+pub mod names {
+    use super::*;
+    use ddns_names::ID;
+
+    pub use ddns_names::{
+        SEED_CONFIG, SEED_PREMIUM, SEED_PREMIUM_CONFIG, SEED_AUCTION, SEED_ESCROW, SEED_ESCROW_VAULT, SEED_SUB,
+        SEED_PRIMARY, SEED_POLICY, SEED_NFT_AUTHORITY, SEED_NFT_CUSTODY_AUTHORITY, SEED_PREMIUM_NFT_MINT,
+        SEED_SUB_NFT_MINT, SEED_RECORDS, SEED_NAME_PRICE, SEED_COMMIT, SEED_SEALED_BID, SEED_LISTING, SEED_SUB_LABEL,
+    };
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn premium_config() -> (Pubkey, u8) {
+        find(&[SEED_PREMIUM_CONFIG], &ID)
+    }
+
+    pub fn premium_name(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_PREMIUM, name_hash], &ID)
+    }
+
+    pub fn auction(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_AUCTION, name_hash], &ID)
+    }
+
+    /// Bid accounts are per auction round: `auction_start_slot` is the
+    /// round's `Auction.start_slot`.
+    pub fn bid_escrow(name_hash: &[u8; 32], bidder: &Pubkey, auction_start_slot: u64) -> (Pubkey, u8) {
+        find(&[SEED_ESCROW, name_hash, bidder.as_ref(), &auction_start_slot.to_le_bytes()], &ID)
+    }
+
+    pub fn bid_escrow_vault(name_hash: &[u8; 32], bidder: &Pubkey, auction_start_slot: u64) -> (Pubkey, u8) {
+        find(&[SEED_ESCROW_VAULT, name_hash, bidder.as_ref(), &auction_start_slot.to_le_bytes()], &ID)
+    }
+
+    pub fn sealed_bid(name_hash: &[u8; 32], bidder: &Pubkey, auction_start_slot: u64) -> (Pubkey, u8) {
+        find(&[SEED_SEALED_BID, name_hash, bidder.as_ref(), &auction_start_slot.to_le_bytes()], &ID)
+    }
+
+    pub fn sub_name(parent_hash: &[u8; 32], label_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_SUB, parent_hash, label_hash], &ID)
+    }
+
+    pub fn sub_label_rule(parent_hash: &[u8; 32], label_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_SUB_LABEL, parent_hash, label_hash], &ID)
+    }
+
+    /// `ParentPolicy` of a premium name or subdomain, keyed by its name hash.
+    pub fn parent_policy(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_POLICY, name_hash], &ID)
+    }
+
+    pub fn primary_name(owner: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_PRIMARY, owner.as_ref()], &ID)
+    }
+
+    pub fn nft_authority() -> (Pubkey, u8) {
+        find(&[SEED_NFT_AUTHORITY], &ID)
+    }
+
+    pub fn nft_custody_authority() -> (Pubkey, u8) {
+        find(&[SEED_NFT_CUSTODY_AUTHORITY], &ID)
+    }
+
+    /// `created_at` is the `PremiumName` registration time the mint belongs to.
+    pub fn premium_nft_mint(name_hash: &[u8; 32], created_at: i64) -> (Pubkey, u8) {
+        find(&[SEED_PREMIUM_NFT_MINT, name_hash, &created_at.to_le_bytes()], &ID)
+    }
+
+    /// `created_at` is the `SubName` claim time the mint belongs to.
+    pub fn sub_nft_mint(parent_hash: &[u8; 32], label_hash: &[u8; 32], created_at: i64) -> (Pubkey, u8) {
+        find(&[SEED_SUB_NFT_MINT, parent_hash, label_hash, &created_at.to_le_bytes()], &ID)
+    }
+
+    pub fn records(name_hash: &[u8; 32], rrtype: u16) -> (Pubkey, u8) {
+        find(&[SEED_RECORDS, name_hash, &rrtype.to_le_bytes()], &ID)
+    }
+
+    pub fn name_price(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_NAME_PRICE, name_hash], &ID)
+    }
+
+    pub fn commitment(commitment: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_COMMIT, commitment], &ID)
+    }
+
+    pub fn listing(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_LISTING, name_hash], &ID)
+    }
+
+    /// Metaplex metadata account of a name NFT mint.
+    pub fn nft_metadata(mint: &Pubkey) -> (Pubkey, u8) {
+        let program = anchor_spl::metadata::ID;
+        find(&[b"metadata", program.as_ref(), mint.as_ref()], &program)
+    }
+
+    /// Metaplex master edition account of a name NFT mint.
+    pub fn nft_master_edition(mint: &Pubkey) -> (Pubkey, u8) {
+        let program = anchor_spl::metadata::ID;
+        find(&[b"metadata", program.as_ref(), mint.as_ref(), b"edition"], &program)
+    }
+}
+
+pub mod ns_incentives {
+    use super::*;
+    use ddns_ns_incentives::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"ns_config";
+    pub const SEED_CLAIM: &[u8] = b"ns_claim";
+    pub const SEED_USAGE: &[u8] = b"ns_usage";
+    pub const SEED_ATTEST: &[u8] = b"ns_attest";
+    pub const SEED_VAULT_AUTHORITY: &[u8] = b"vault_authority";
+    pub const SEED_REWARD_VAULT: &[u8] = b"reward_vault";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn ns_claim(domain_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_CLAIM, domain_hash], &ID)
+    }
+
+    pub fn ns_usage(domain_hash: &[u8; 32], epoch_id: u64) -> (Pubkey, u8) {
+        find(&[SEED_USAGE, domain_hash, &epoch_id.to_le_bytes()], &ID)
+    }
+
+    pub fn ns_attestation(domain_hash: &[u8; 32], epoch_id: u64, attestor: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_ATTEST, domain_hash, &epoch_id.to_le_bytes(), attestor.as_ref()], &ID)
+    }
+
+    pub fn vault_authority() -> (Pubkey, u8) {
+        find(&[SEED_VAULT_AUTHORITY], &ID)
+    }
+
+    pub fn reward_vault() -> (Pubkey, u8) {
+        find(&[SEED_REWARD_VAULT], &ID)
+    }
+}
+
+pub mod operators {
+    use super::*;
+    use ddns_operators::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"operators_config";
+    pub const SEED_OPERATOR: &[u8] = b"operator";
+    pub const SEED_OPERATOR_VAULT: &[u8] = b"operator_vault";
+    pub const SEED_METRICS: &[u8] = b"metrics";
+    pub const SEED_DOMAIN_NS: &[u8] = b"domain_ns";
+    pub const SEED_TREASURY_AUTHORITY: &[u8] = b"treasury_authority";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn operator(operator_wallet: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_OPERATOR, operator_wallet.as_ref()], &ID)
+    }
+
+    pub fn operator_vault(operator_wallet: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_OPERATOR_VAULT, operator_wallet.as_ref()], &ID)
+    }
+
+    pub fn metrics(epoch_id: u64, operator_wallet: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_METRICS, &epoch_id.to_le_bytes(), operator_wallet.as_ref()], &ID)
+    }
+
+    pub fn domain_ns(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_DOMAIN_NS, name_hash], &ID)
+    }
+
+    pub fn treasury_authority() -> (Pubkey, u8) {
+        find(&[SEED_TREASURY_AUTHORITY], &ID)
+    }
+}
+
+pub mod quorum {
+    use super::*;
+    use ddns_quorum::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"quorum_config";
+    pub const SEED_AUTHORITY: &[u8] = b"quorum_authority";
+    pub const SEED_VERIFIER_SET: &[u8] = b"verifierset";
+    pub const SEED_STAKE_SNAPSHOT: &[u8] = b"stake_snapshot";
+    pub const SEED_AGG: &[u8] = b"agg";
+    pub const SEED_DISPUTE: &[u8] = b"dispute";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn quorum_authority() -> (Pubkey, u8) {
+        find(&[SEED_AUTHORITY], &ID)
+    }
+
+    pub fn verifier_set(epoch_id: u64) -> (Pubkey, u8) {
+        find(&[SEED_VERIFIER_SET, &epoch_id.to_le_bytes()], &ID)
+    }
+
+    pub fn stake_snapshot(epoch_id: u64) -> (Pubkey, u8) {
+        find(&[SEED_STAKE_SNAPSHOT, &epoch_id.to_le_bytes()], &ID)
+    }
+
+    pub fn aggregate(epoch_id: u64, name_hash: &[u8; 32], submitter: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_AGG, &epoch_id.to_le_bytes(), name_hash, submitter.as_ref()], &ID)
+    }
+
+    pub fn dispute(name_hash: &[u8; 32], disputed_version: u64, target_version: u64, epoch_id: u64) -> (Pubkey, u8) {
+        find(
+            &[
+                SEED_DISPUTE,
+                name_hash,
+                &disputed_version.to_le_bytes(),
+                &target_version.to_le_bytes(),
+                &epoch_id.to_le_bytes(),
+            ],
+            &ID,
+        )
+    }
+}
+
+pub mod registry {
+    use super::*;
+    use ddns_registry::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"config";
+    pub const SEED_CANONICAL: &[u8] = b"canonical";
+    pub const SEED_ROUTE_HISTORY: &[u8] = b"route_history";
+    pub const SEED_RRSET: &[u8] = b"rrset";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn canonical_route(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_CANONICAL, name_hash], &ID)
+    }
+
+    pub fn route_history(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_ROUTE_HISTORY, name_hash], &ID)
+    }
+
+    pub fn rrset(name_hash: &[u8; 32], rrtype: u16) -> (Pubkey, u8) {
+        find(&[SEED_RRSET, name_hash, &rrtype.to_le_bytes()], &ID)
+    }
+}
+
+pub mod rent_bond {
+    use super::*;
+    use ddns_rent_bond::ID;
+
+    pub use ddns_rent_bond::{SEED_CONFIG, SEED_RESERVE};
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn reserve() -> (Pubkey, u8) {
+        find(&[SEED_RESERVE], &ID)
+    }
+}
+
+pub mod rep {
+    use super::*;
+    use ddns_rep::ID;
+
+    pub use ddns_rep::{SEED_CONFIG, SEED_EPOCH_REP};
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn epoch_rep(epoch_id: u64, contributor: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_EPOCH_REP, &epoch_id.to_le_bytes(), contributor.as_ref()], &ID)
+    }
+}
+
+pub mod rewards {
+    use super::*;
+    use ddns_rewards::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"rewards_config";
+    pub const SEED_DOMAIN: &[u8] = b"domain";
+    pub const SEED_CHALLENGE: &[u8] = b"challenge";
+    pub const SEED_USAGE: &[u8] = b"usage";
+    pub const SEED_TREASURY_AUTHORITY: &[u8] = b"treasury_authority";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn domain_claim(domain_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_DOMAIN, domain_hash], &ID)
+    }
+
+    pub fn challenge(domain_hash: &[u8; 32], nonce: &[u8; 16]) -> (Pubkey, u8) {
+        find(&[SEED_CHALLENGE, domain_hash, nonce], &ID)
+    }
+
+    pub fn usage(domain_hash: &[u8; 32], epoch_id: u64) -> (Pubkey, u8) {
+        find(&[SEED_USAGE, domain_hash, &epoch_id.to_le_bytes()], &ID)
+    }
+
+    pub fn treasury_authority() -> (Pubkey, u8) {
+        find(&[SEED_TREASURY_AUTHORITY], &ID)
+    }
+}
+
+pub mod stake {
+    use super::*;
+    use ddns_stake::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"stake_config";
+    pub const SEED_STAKE: &[u8] = b"stake";
+    pub const SEED_VAULT: &[u8] = b"stake_vault";
+    pub const SEED_MINT_AUTHORITY: &[u8] = b"mint_authority";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn stake_position(owner: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_STAKE, owner.as_ref()], &ID)
+    }
+
+    pub fn stake_vault() -> (Pubkey, u8) {
+        find(&[SEED_VAULT], &ID)
+    }
+
+    pub fn mint_authority() -> (Pubkey, u8) {
+        find(&[SEED_MINT_AUTHORITY], &ID)
+    }
+}
+
+pub mod stake_gov {
+    use super::*;
+    use ddns_stake_gov::ID;
+
+    pub use ddns_stake_gov::{
+        SEED_CONFIG, SEED_VAULT_AUTH, SEED_VERIFIER_REGISTRY, SEED_STAKE, SEED_SNAPSHOT, SEED_SLASH,
+    };
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn vault_authority() -> (Pubkey, u8) {
+        find(&[SEED_VAULT_AUTH], &ID)
+    }
+
+    pub fn verifier_registry() -> (Pubkey, u8) {
+        find(&[SEED_VERIFIER_REGISTRY], &ID)
+    }
+
+    pub fn stake_position(staker: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_STAKE, staker.as_ref()], &ID)
+    }
+
+    pub fn snapshot(epoch_id: u64) -> (Pubkey, u8) {
+        find(&[SEED_SNAPSHOT, &epoch_id.to_le_bytes()], &ID)
+    }
+
+    pub fn slash(epoch_id: u64, offender: &Pubkey, reason_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_SLASH, &epoch_id.to_le_bytes(), offender.as_ref(), reason_hash], &ID)
+    }
+}
+
+pub mod watchdog_policy {
+    use super::*;
+    use ddns_watchdog_policy::ID;
+
+    pub use ddns_watchdog_policy::{SEED_CONFIG, SEED_WATCHDOG, SEED_NAME_POLICY, SEED_ATTEST_LOG, SEED_ATTEST_MARK};
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn watchdog(watchdog: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_WATCHDOG, watchdog.as_ref()], &ID)
+    }
+
+    pub fn name_policy(name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_NAME_POLICY, name_hash], &ID)
+    }
+
+    pub fn attest_log(epoch_id: u64, name_hash: &[u8; 32]) -> (Pubkey, u8) {
+        find(&[SEED_ATTEST_LOG, &epoch_id.to_le_bytes(), name_hash], &ID)
+    }
+
+    pub fn attest_mark(epoch_id: u64, name_hash: &[u8; 32], watchdog: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_ATTEST_MARK, &epoch_id.to_le_bytes(), name_hash, watchdog.as_ref()], &ID)
+    }
+}
+
+pub mod witness_rewards {
+    use super::*;
+    use ddns_witness_rewards::ID;
+
+    pub const SEED_CONFIG: &[u8] = b"witness_rewards_config";
+    pub const SEED_VAULT_AUTHORITY: &[u8] = b"witness_rewards_vault_authority";
+    pub const SEED_BOND: &[u8] = b"bond";
+    pub const SEED_EPOCH_STATE: &[u8] = b"epoch_state";
+    pub const SEED_EPOCH_STATS: &[u8] = b"epoch_stats";
+
+    pub fn config() -> (Pubkey, u8) {
+        find(&[SEED_CONFIG], &ID)
+    }
+
+    pub fn vault_authority() -> (Pubkey, u8) {
+        find(&[SEED_VAULT_AUTHORITY], &ID)
+    }
+
+    pub fn bond(miner: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_BOND, miner.as_ref()], &ID)
+    }
+
+    pub fn epoch_state(epoch_id: u64) -> (Pubkey, u8) {
+        find(&[SEED_EPOCH_STATE, &epoch_id.to_le_bytes()], &ID)
+    }
+
+    pub fn epoch_stats(epoch_id: u64, miner: &Pubkey) -> (Pubkey, u8) {
+        find(&[SEED_EPOCH_STATS, &epoch_id.to_le_bytes(), miner.as_ref()], &ID)
+    }
+}
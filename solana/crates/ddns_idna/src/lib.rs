@@ -0,0 +1,43 @@
+//! Internationalized `.dns` labels.
+//!
+//! Names are stored and hashed in their ASCII form: LDH labels, with
+//! non-ASCII labels as `xn--` A-labels. [`check_ldh_label`] is the on-chain
+//! check (shape plus punycode well-formedness), re-exported from
+//! `ddns_names_core` and free of Unicode tables.
+//! With the `uts46` feature, [`normalize_label`] and [`normalize_name`] map
+//! user input to that form with UTS-46 and reject mixed-script and
+//! Latin-confusable labels. `vectors/labels.tsv` pins both sides to the same
+//! answers.
+
+#[cfg(feature = "uts46")]
+mod uts46;
+
+pub use ddns_names_core::{check_ldh_label, punycode, LabelError, NameError, ACE_PREFIX, MAX_LABEL_LEN, MAX_NAME_LEN};
+#[cfg(feature = "uts46")]
+pub use uts46::{normalize_label, normalize_name, to_unicode};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `vectors/labels.tsv`: `input`, off-chain result (A-label or `!error`)
+    /// and whether the on-chain check accepts `input` as given.
+    pub(crate) fn vectors() -> Vec<(String, String, bool)> {
+        include_str!("../vectors/labels.tsv")
+            .lines()
+            .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let cols: Vec<&str> = l.split('\t').collect();
+                assert_eq!(cols.len(), 3, "bad vector line: {l}");
+                (cols[0].to_string(), cols[1].to_string(), cols[2] == "ok")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn on_chain_check_matches_vectors() {
+        for (input, _, onchain) in vectors() {
+            assert_eq!(check_ldh_label(&input).is_ok(), onchain, "{input}");
+        }
+    }
+}
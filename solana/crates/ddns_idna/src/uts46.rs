@@ -0,0 +1,152 @@
+//! UTS-46 mapping plus the script policy for new registrations.
+
+use idna::uts46::{AsciiDenyList, DnsLength, Hyphens, Uts46};
+use icu_properties::props::Script;
+use icu_properties::script::ScriptWithExtensions;
+
+use crate::{check_ldh_label, LabelError, NameError};
+
+/// UTS-39 "highly restrictive" combinations for labels that mix scripts.
+const ALLOWED_MIXES: [&[Script]; 3] = [
+    &[Script::Latin, Script::Han, Script::Hiragana, Script::Katakana],
+    &[Script::Latin, Script::Han, Script::Bopomofo],
+    &[Script::Latin, Script::Han, Script::Hangul],
+];
+
+/// Cyrillic and Greek letters that render like Latin ones. A label written
+/// only in these (plus digits and hyphens) reads as a Latin label.
+const LATIN_LOOKALIKES: &[char] = &[
+    'а', 'с', 'е', 'һ', 'і', 'ј', 'ԁ', 'о', 'р', 'ԛ', 'ѕ', 'ԝ', 'х', 'у', 'ӏ', 'ο', 'ν', 'ι', 'ρ', 'α', 'γ',
+];
+
+/// Maps a single user-typed label to the form stored on chain: UTS-46
+/// mapped, lowercased and punycode-encoded when non-ASCII.
+pub fn normalize_label(input: &str) -> Result<String, LabelError> {
+    let ascii = to_ascii(input.trim())?;
+    if ascii.contains('.') {
+        return Err(LabelError::InvalidChars);
+    }
+    check_label(&ascii)?;
+    Ok(ascii)
+}
+
+/// Maps a full name (any number of labels, optional trailing dot) the same
+/// way, then applies [`ddns_names_core::normalize_name`] so the result obeys
+/// the on-chain name rules, including the 253-byte limit.
+pub fn normalize_name(input: &str) -> Result<String, NameError> {
+    let ascii = to_ascii(input.trim()).map_err(NameError::Label)?;
+    let name = ddns_names_core::normalize_name(&ascii)?;
+    for label in name.split('.') {
+        check_label(label).map_err(NameError::Label)?;
+    }
+    Ok(name)
+}
+
+/// Renders a stored name for display, decoding A-labels. Labels that fail to
+/// decode are left as stored.
+pub fn to_unicode(name: &str) -> String {
+    let (unicode, _) = Uts46::new().to_unicode(name.as_bytes(), AsciiDenyList::STD3, Hyphens::Allow);
+    unicode.into_owned()
+}
+
+fn to_ascii(input: &str) -> Result<String, LabelError> {
+    Uts46::new()
+        .to_ascii(input.as_bytes(), AsciiDenyList::STD3, Hyphens::Check, DnsLength::Ignore)
+        .map(|ascii| ascii.into_owned())
+        .map_err(|_| LabelError::Disallowed)
+}
+
+fn check_label(ascii: &str) -> Result<(), LabelError> {
+    check_ldh_label(ascii)?;
+    if let Some(encoded) = ascii.strip_prefix(crate::ACE_PREFIX) {
+        let unicode = crate::punycode::decode(encoded).ok_or(LabelError::MalformedPunycode)?;
+        check_scripts(&unicode)?;
+    }
+    Ok(())
+}
+
+fn check_scripts(label: &[char]) -> Result<(), LabelError> {
+    let table = ScriptWithExtensions::new();
+    let sets: Vec<Vec<Script>> = label
+        .iter()
+        .map(|&c| table.get_script_extensions_val(c).iter().collect::<Vec<_>>())
+        .filter(|set| !set.iter().any(|s| *s == Script::Common || *s == Script::Inherited))
+        .collect();
+    let single_script = sets
+        .first()
+        .is_none_or(|first| first.iter().any(|s| sets.iter().all(|set| set.contains(s))));
+    if !single_script {
+        let fits = ALLOWED_MIXES
+            .iter()
+            .any(|mix| sets.iter().all(|set| set.iter().any(|s| mix.contains(s))));
+        return if fits { Ok(()) } else { Err(LabelError::MixedScript) };
+    }
+    let lookalike = label
+        .iter()
+        .all(|c| c.is_ascii_digit() || *c == '-' || LATIN_LOOKALIKES.contains(c));
+    if lookalike {
+        return Err(LabelError::Confusable);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::vectors;
+
+    #[test]
+    fn off_chain_normalizer_matches_vectors() {
+        for (input, expected, _) in vectors() {
+            let got = match normalize_label(&input) {
+                Ok(ascii) => ascii,
+                Err(LabelError::MixedScript) => "!mixed-script".into(),
+                Err(LabelError::Confusable) => "!confusable".into(),
+                Err(_) => "!invalid".into(),
+            };
+            assert_eq!(got, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn normalized_labels_pass_the_on_chain_check() {
+        for (input, _, _) in vectors() {
+            if let Ok(ascii) = normalize_label(&input) {
+                assert_eq!(check_ldh_label(&ascii), Ok(()), "{input}");
+            }
+        }
+    }
+
+    #[test]
+    fn encoder_agrees_with_the_idna_crate() {
+        for word in ["münchen", "日本語", "straße", "ελληνικά", "한국어", "café"] {
+            let chars: Vec<char> = word.chars().collect();
+            assert_eq!(crate::punycode::encode(&chars), idna::punycode::encode_str(word), "{word}");
+        }
+    }
+
+    #[test]
+    fn normalizes_full_names_and_renders_them_back() {
+        assert_eq!(normalize_name("Bücher.Example.dns.").as_deref(), Ok("xn--bcher-kva.example.dns"));
+        assert_eq!(normalize_name("a..dns"), Err(NameError::Label(LabelError::Length)));
+        assert_eq!(to_unicode("xn--bcher-kva.example.dns"), "bücher.example.dns");
+        assert_eq!(normalize_label("a.b"), Err(LabelError::InvalidChars));
+    }
+
+    #[test]
+    fn ascii_names_normalize_like_the_core_vectors() {
+        for v in ddns_names_core::vectors::names().filter(|v| v.input.is_ascii()) {
+            assert_eq!(normalize_name(v.input).ok().as_deref(), v.normalized, "{}", v.input);
+        }
+    }
+
+    #[test]
+    fn name_length_counts_the_encoded_form() {
+        // Each 30-letter label encodes to 36 bytes: four fit, eight are 295.
+        let label = "ü".repeat(30);
+        let name = [label.as_str(); 4].join(".");
+        assert_eq!(normalize_name(&name).map(|n| n.len()), Ok(147));
+        let long = [label.as_str(); 8].join(".");
+        assert_eq!(normalize_name(&long), Err(NameError::Length));
+    }
+}
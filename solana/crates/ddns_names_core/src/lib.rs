@@ -0,0 +1,243 @@
+//! Canonical `.dns` name normalization and hashing, shared by every program
+//! that checks a name against a hash and by off-chain clients.
+//!
+//! A normalized name is trimmed, ASCII-lowercased, has one trailing dot
+//! removed and is at most [`MAX_NAME_LEN`] bytes of LDH labels (Unicode
+//! labels arrive as `xn--` A-labels, see `ddns_idna`). Hashes are plain
+//! SHA-256 over those bytes:
+//!
+//! ```text
+//! hash_name(name)           = sha256(name)
+//! hash_label(label)         = sha256(label)
+//! hash_dns_label(label)     = sha256(label || ".dns") = hash_name(label.dns)
+//! subdomain_name_hash(p, l) = sha256(p || l)
+//! ```
+//!
+//! `vectors/names.tsv` pins these for every implementation, including the
+//! TypeScript clients.
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+#[cfg(all(feature = "std", not(test)))]
+extern crate std;
+
+use alloc::string::String;
+use core::fmt;
+
+use sha2::{Digest, Sha256};
+
+pub mod punycode;
+
+/// DNS limit on a full name, in ASCII bytes without the trailing dot.
+pub const MAX_NAME_LEN: usize = 253;
+/// DNS limit on a single label, in ASCII bytes.
+pub const MAX_LABEL_LEN: usize = 63;
+pub const ACE_PREFIX: &str = "xn--";
+pub const DNS_SUFFIX: &str = ".dns";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelError {
+    /// Empty or longer than [`MAX_LABEL_LEN`].
+    Length,
+    /// Outside `[a-z0-9-]`, or starts or ends with `-`.
+    InvalidChars,
+    /// `--` in the third and fourth positions without the `xn--` prefix.
+    ReservedHyphens,
+    /// An `xn--` label that is not canonical punycode for a non-ASCII label.
+    MalformedPunycode,
+    /// Rejected by UTS-46 mapping or validation (off-chain only).
+    Disallowed,
+    /// Mixes scripts beyond UTS-39 "highly restrictive" (off-chain only).
+    MixedScript,
+    /// Written entirely in look-alikes of Latin letters (off-chain only).
+    Confusable,
+}
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabelError::Length => write!(f, "label is empty or longer than {MAX_LABEL_LEN} bytes"),
+            LabelError::InvalidChars => write!(f, "label has characters outside [a-z0-9-] or an edge hyphen"),
+            LabelError::ReservedHyphens => write!(f, "label has reserved hyphens in positions 3-4"),
+            LabelError::MalformedPunycode => write!(f, "xn-- label is not well-formed punycode"),
+            LabelError::Disallowed => write!(f, "label is disallowed by UTS-46"),
+            LabelError::MixedScript => write!(f, "label mixes scripts"),
+            LabelError::Confusable => write!(f, "label is confusable with a Latin label"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LabelError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameError {
+    /// Empty or longer than [`MAX_NAME_LEN`].
+    Length,
+    /// One of the labels fails [`check_ldh_label`].
+    Label(LabelError),
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::Length => write!(f, "name is empty or longer than {MAX_NAME_LEN} bytes"),
+            NameError::Label(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NameError {}
+
+/// Checks a normalized ASCII label: LDH characters, no edge hyphens, `--` in
+/// positions 3-4 only as the `xn--` prefix, and an `xn--` label must be the
+/// canonical punycode of a label with at least one non-ASCII character.
+/// Programs apply their own, shorter length limits on top.
+pub fn check_ldh_label(label: &str) -> Result<(), LabelError> {
+    let bytes = label.as_bytes();
+    if bytes.is_empty() || bytes.len() > MAX_LABEL_LEN {
+        return Err(LabelError::Length);
+    }
+    let ldh = bytes.iter().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || *b == b'-');
+    if !ldh || bytes[0] == b'-' || bytes[bytes.len() - 1] == b'-' {
+        return Err(LabelError::InvalidChars);
+    }
+    if bytes.len() >= 4 && &bytes[2..4] == b"--" {
+        let encoded = label.strip_prefix(ACE_PREFIX).ok_or(LabelError::ReservedHyphens)?;
+        let decoded = punycode::decode(encoded).ok_or(LabelError::MalformedPunycode)?;
+        let canonical = decoded.iter().any(|c| !c.is_ascii()) && punycode::encode(&decoded).as_deref() == Some(encoded);
+        if !canonical {
+            return Err(LabelError::MalformedPunycode);
+        }
+    }
+    Ok(())
+}
+
+/// Canonical form of a full name: trimmed, one trailing dot removed,
+/// ASCII-lowercased, 1..=[`MAX_NAME_LEN`] bytes, every label passing
+/// [`check_ldh_label`].
+pub fn normalize_name(input: &str) -> Result<String, NameError> {
+    let trimmed = input.trim();
+    let mut name = String::from(trimmed.strip_suffix('.').unwrap_or(trimmed));
+    name.make_ascii_lowercase();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(NameError::Length);
+    }
+    for label in name.split('.') {
+        check_ldh_label(label).map_err(NameError::Label)?;
+    }
+    Ok(name)
+}
+
+/// Hash of a normalized full name.
+pub fn hash_name(normalized: &str) -> [u8; 32] {
+    Sha256::digest(normalized.as_bytes()).into()
+}
+
+/// Hash of a single label, as folded into [`subdomain_name_hash`].
+pub fn hash_label(label: &str) -> [u8; 32] {
+    Sha256::digest(label.as_bytes()).into()
+}
+
+/// Hash of `label.dns` without building the string.
+pub fn hash_dns_label(label: &str) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(label.as_bytes())
+        .chain_update(DNS_SUFFIX.as_bytes())
+        .finalize()
+        .into()
+}
+
+pub fn subdomain_name_hash(parent_hash: [u8; 32], label_hash: [u8; 32]) -> [u8; 32] {
+    Sha256::new().chain_update(parent_hash).chain_update(label_hash).finalize().into()
+}
+
+/// Hash of a name with `depth` subdomain labels above its root: the root is
+/// hashed whole and each label is folded in with [`subdomain_name_hash`].
+/// `None` if the name has fewer than `depth + 1` labels.
+pub fn chained_name_hash(normalized: &str, depth: u8) -> Option<[u8; 32]> {
+    if depth == 0 {
+        return Some(hash_name(normalized));
+    }
+    let (label, parent) = normalized.split_once('.')?;
+    Some(subdomain_name_hash(chained_name_hash(parent, depth - 1)?, hash_label(label)))
+}
+
+pub mod vectors {
+    //! `vectors/names.tsv`, for tests in every crate that hashes names.
+    //! Columns: input, normalized name (or `!invalid`), subdomain depth and
+    //! the hex `chained_name_hash` (or `-`).
+
+    pub const NAMES_TSV: &str = include_str!("../vectors/names.tsv");
+
+    pub struct NameVector {
+        pub input: &'static str,
+        /// `None` when `normalize_name` must reject the input.
+        pub normalized: Option<&'static str>,
+        pub depth: u8,
+        pub hash: Option<[u8; 32]>,
+    }
+
+    pub fn names() -> impl Iterator<Item = NameVector> {
+        NAMES_TSV
+            .lines()
+            .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let mut cols = l.split('\t');
+                let mut next = || cols.next().unwrap_or_else(|| panic!("bad vector line: {l}"));
+                let (input, normalized, depth, hash) = (next(), next(), next(), next());
+                NameVector {
+                    input,
+                    normalized: (normalized != "!invalid").then_some(normalized),
+                    depth: depth.parse().expect("depth"),
+                    hash: (hash != "-").then(|| hex32(hash)),
+                }
+            })
+    }
+
+    fn hex32(s: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).expect("hex");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalization_and_hashes_match_vectors() {
+        for v in vectors::names() {
+            let got = normalize_name(v.input);
+            assert_eq!(got.as_deref().ok(), v.normalized, "{}", v.input);
+            if let (Some(name), Some(hash)) = (v.normalized, v.hash) {
+                assert_eq!(chained_name_hash(name, v.depth), Some(hash), "{}", v.input);
+            }
+        }
+    }
+
+    #[test]
+    fn dns_label_hash_is_the_full_name_hash() {
+        assert_eq!(hash_dns_label("alice"), hash_name("alice.dns"));
+        let www = subdomain_name_hash(hash_name("alice.dns"), hash_label("www"));
+        assert_eq!(chained_name_hash("www.alice.dns", 1), Some(www));
+        assert_eq!(chained_name_hash("alice.dns", 3), None);
+    }
+
+    #[test]
+    fn rejects_malformed_and_non_canonical_a_labels() {
+        assert_eq!(check_ldh_label("xn--mnchen-3ya"), Ok(()));
+        assert_eq!(check_ldh_label("xn--"), Err(LabelError::InvalidChars));
+        assert_eq!(check_ldh_label("xn--mnchen-3y"), Err(LabelError::MalformedPunycode));
+        // An A-label with no deltas would spell plain ASCII; its trailing
+        // hyphen already fails the LDH shape.
+        assert_eq!(check_ldh_label("xn--mnchen-"), Err(LabelError::InvalidChars));
+        assert_eq!(check_ldh_label("ab--cd"), Err(LabelError::ReservedHyphens));
+        assert_eq!(check_ldh_label(&"a".repeat(64)), Err(LabelError::Length));
+    }
+}
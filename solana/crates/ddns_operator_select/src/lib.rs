@@ -0,0 +1,483 @@
+//! Client-side operator selection over the `ddns_operators` registry.
+//!
+//! Callers fetch the program's accounts (e.g. `getProgramAccounts`) and the
+//! `operators_config` PDA, build an [`OperatorDirectory`] from the raw
+//! account data, then rank endpoints with a [`SelectionStrategy`].
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use ddns_operators::{Endpoint, EpochMetrics, Operator, OperatorStatus, OperatorsConfig};
+
+pub const ENDPOINT_DOH_URL_HASH: u8 = 0;
+pub const ENDPOINT_IPV4: u8 = 1;
+pub const ENDPOINT_IPV6: u8 = 2;
+pub const ENDPOINT_DNS_NAME_HASH: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectError {
+    InvalidConfig,
+}
+
+impl fmt::Display for SelectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectError::InvalidConfig => write!(f, "invalid operators_config account"),
+        }
+    }
+}
+
+impl std::error::Error for SelectError {}
+
+/// Latest `EpochMetrics` seen for an operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub epoch_id: u64,
+    pub uptime_score: u16,
+    pub latency_ms_p50: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperatorEntry {
+    pub operator_wallet: Pubkey,
+    pub kind: u8,
+    pub stake_amount_lamports: u64,
+    pub endpoints: Vec<EndpointInfo>,
+    pub metrics: Option<MetricsSnapshot>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EndpointInfo {
+    pub endpoint_kind: u8,
+    pub value: [u8; 32],
+    pub region: u16,
+}
+
+impl From<&Endpoint> for EndpointInfo {
+    fn from(e: &Endpoint) -> Self {
+        Self {
+            endpoint_kind: e.endpoint_kind,
+            value: e.value,
+            region: e.region,
+        }
+    }
+}
+
+/// One rankable endpoint together with its operator's stake and metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    pub operator_wallet: Pubkey,
+    pub operator_kind: u8,
+    pub stake_amount_lamports: u64,
+    pub endpoint: EndpointInfo,
+    pub metrics: Option<MetricsSnapshot>,
+}
+
+impl Candidate {
+    /// p50 latency, or `u32::MAX` when no metrics were submitted.
+    pub fn latency_ms(&self) -> u32 {
+        self.metrics.map(|m| m.latency_ms_p50).unwrap_or(u32::MAX)
+    }
+
+    pub fn uptime_score(&self) -> u16 {
+        self.metrics.map(|m| m.uptime_score).unwrap_or(0)
+    }
+}
+
+/// Client preferences applied before and during ranking.
+#[derive(Debug, Clone, Default)]
+pub struct Preferences {
+    /// Preferred region; 0 on-chain means unknown and never matches.
+    pub region: Option<u16>,
+    /// Endpoint kinds to consider; empty means all.
+    pub endpoint_kinds: Vec<u8>,
+    /// Drop candidates below this uptime (0..10000); 0 keeps operators without metrics.
+    pub min_uptime_score: u16,
+}
+
+impl Preferences {
+    pub fn in_region(&self, c: &Candidate) -> bool {
+        matches!(self.region, Some(r) if r != 0 && c.endpoint.region == r)
+    }
+}
+
+pub trait SelectionStrategy {
+    /// Orders two candidates; `Less` ranks `a` ahead of `b`.
+    fn compare(&self, a: &Candidate, b: &Candidate, prefs: &Preferences) -> Ordering;
+}
+
+/// Preferred region first, then lowest latency, then highest stake.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegionLatencyStake;
+
+impl SelectionStrategy for RegionLatencyStake {
+    fn compare(&self, a: &Candidate, b: &Candidate, prefs: &Preferences) -> Ordering {
+        prefs
+            .in_region(b)
+            .cmp(&prefs.in_region(a))
+            .then(a.latency_ms().cmp(&b.latency_ms()))
+            .then(b.stake_amount_lamports.cmp(&a.stake_amount_lamports))
+    }
+}
+
+/// Lowest latency regardless of region, then highest uptime.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LowestLatency;
+
+impl SelectionStrategy for LowestLatency {
+    fn compare(&self, a: &Candidate, b: &Candidate, _prefs: &Preferences) -> Ordering {
+        a.latency_ms()
+            .cmp(&b.latency_ms())
+            .then(b.uptime_score().cmp(&a.uptime_score()))
+    }
+}
+
+/// Highest stake first, preferred region breaking ties.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StakeWeighted;
+
+impl SelectionStrategy for StakeWeighted {
+    fn compare(&self, a: &Candidate, b: &Candidate, prefs: &Preferences) -> Ordering {
+        b.stake_amount_lamports
+            .cmp(&a.stake_amount_lamports)
+            .then(prefs.in_region(b).cmp(&prefs.in_region(a)))
+            .then(a.latency_ms().cmp(&b.latency_ms()))
+    }
+}
+
+/// Eligible operators: Active with stake ≥ `min_operator_stake_lamports`.
+#[derive(Debug, Clone, Default)]
+pub struct OperatorDirectory {
+    pub min_operator_stake_lamports: u64,
+    pub operators: Vec<OperatorEntry>,
+    /// `Operator` / `EpochMetrics` accounts whose data failed to decode
+    /// (e.g. written under an older layout), left out of the directory.
+    pub skipped: Vec<Pubkey>,
+}
+
+impl OperatorDirectory {
+    /// Builds the directory from the `operators_config` account data and the
+    /// program's accounts. Accounts that are neither `Operator` nor
+    /// `EpochMetrics` are ignored; ones that carry either discriminator but
+    /// fail to decode are listed in `skipped` instead of failing the load.
+    pub fn load<'a, I>(config_data: &[u8], accounts: I) -> Result<Self, SelectError>
+    where
+        I: IntoIterator<Item = (Pubkey, &'a [u8])>,
+    {
+        let mut cfg_slice = config_data;
+        let cfg = OperatorsConfig::try_deserialize(&mut cfg_slice)
+            .map_err(|_| SelectError::InvalidConfig)?;
+
+        let mut operators = Vec::new();
+        let mut metrics: HashMap<Pubkey, MetricsSnapshot> = HashMap::new();
+        let mut skipped = Vec::new();
+        for (key, data) in accounts {
+            if data.starts_with(Operator::DISCRIMINATOR) {
+                let mut slice = data;
+                match Operator::try_deserialize(&mut slice) {
+                    Ok(op) => operators.push(op),
+                    Err(_) => skipped.push(key),
+                }
+            } else if data.starts_with(EpochMetrics::DISCRIMINATOR) {
+                let mut slice = data;
+                let Ok(m) = EpochMetrics::try_deserialize(&mut slice) else {
+                    skipped.push(key);
+                    continue;
+                };
+                let snapshot = MetricsSnapshot {
+                    epoch_id: m.epoch_id,
+                    uptime_score: m.uptime_score,
+                    latency_ms_p50: m.latency_ms_p50,
+                };
+                metrics
+                    .entry(m.operator_wallet)
+                    .and_modify(|cur| {
+                        if snapshot.epoch_id > cur.epoch_id {
+                            *cur = snapshot;
+                        }
+                    })
+                    .or_insert(snapshot);
+            }
+        }
+
+        let mut entries: Vec<OperatorEntry> = operators
+            .into_iter()
+            .filter(|op| op.status == OperatorStatus::Active as u8)
+            .filter(|op| op.stake_amount_lamports >= cfg.min_operator_stake_lamports)
+            .map(|op| {
+                let count = (op.endpoint_count as usize).min(op.endpoints.len());
+                OperatorEntry {
+                    operator_wallet: op.operator_wallet,
+                    kind: op.kind,
+                    stake_amount_lamports: op.stake_amount_lamports,
+                    endpoints: op.endpoints[..count]
+                        .iter()
+                        .map(EndpointInfo::from)
+                        .collect(),
+                    metrics: metrics.get(&op.operator_wallet).copied(),
+                }
+            })
+            .collect();
+        entries.sort_by_key(|e| e.operator_wallet);
+        skipped.sort();
+
+        Ok(Self {
+            min_operator_stake_lamports: cfg.min_operator_stake_lamports,
+            operators: entries,
+            skipped,
+        })
+    }
+
+    /// All endpoints passing `prefs` filters, best first. Ties fall back to
+    /// operator wallet and endpoint bytes so the order is deterministic.
+    pub fn rank(&self, prefs: &Preferences, strategy: &dyn SelectionStrategy) -> Vec<Candidate> {
+        let mut candidates: Vec<Candidate> = self
+            .operators
+            .iter()
+            .filter(|op| {
+                prefs.min_uptime_score == 0
+                    || op
+                        .metrics
+                        .is_some_and(|m| m.uptime_score >= prefs.min_uptime_score)
+            })
+            .flat_map(|op| {
+                op.endpoints.iter().map(move |e| Candidate {
+                    operator_wallet: op.operator_wallet,
+                    operator_kind: op.kind,
+                    stake_amount_lamports: op.stake_amount_lamports,
+                    endpoint: *e,
+                    metrics: op.metrics,
+                })
+            })
+            .filter(|c| {
+                prefs.endpoint_kinds.is_empty()
+                    || prefs.endpoint_kinds.contains(&c.endpoint.endpoint_kind)
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            strategy
+                .compare(a, b, prefs)
+                .then(a.operator_wallet.cmp(&b.operator_wallet))
+                .then(a.endpoint.cmp(&b.endpoint))
+        });
+        candidates
+    }
+
+    /// Best `n` endpoints under `strategy`.
+    pub fn select(
+        &self,
+        prefs: &Preferences,
+        strategy: &dyn SelectionStrategy,
+        n: usize,
+    ) -> Vec<Candidate> {
+        let mut ranked = self.rank(prefs, strategy);
+        ranked.truncate(n);
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+
+    fn wallet(n: u8) -> Pubkey {
+        Pubkey::new_from_array([n; 32])
+    }
+
+    fn config_fixture(min_stake: u64) -> Vec<u8> {
+        let cfg = OperatorsConfig {
+            authority: wallet(200),
+            toll_mint: wallet(201),
+            treasury_vault: wallet(202),
+            treasury_authority_bump: 255,
+            epoch_len_slots: 100,
+            min_operator_stake_lamports: min_stake,
+            max_endpoints_per_operator: 8,
+            reward_per_paid_query: 0,
+            reward_per_verified_receipt: 0,
+            uptime_bonus_per_10k: 0,
+            latency_bonus_threshold_ms: 0,
+            latency_bonus: 0,
+            max_rewards_per_epoch: 0,
+            metrics_submitters: vec![],
+            slashing_authorities: vec![],
+            enabled: true,
+            bump: 254,
+        };
+        let mut data = Vec::new();
+        cfg.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn operator_fixture(n: u8, status: OperatorStatus, stake: u64, regions: &[u16]) -> Vec<u8> {
+        let mut endpoints = [Endpoint::default(); 8];
+        for (i, region) in regions.iter().enumerate() {
+            endpoints[i] = Endpoint {
+                endpoint_kind: ENDPOINT_IPV4,
+                value: {
+                    let mut v = [0u8; 32];
+                    v[..4].copy_from_slice(&[198, 51, 100, n]);
+                    v
+                },
+                region: *region,
+            };
+        }
+        let op = Operator {
+            operator_wallet: wallet(n),
+            kind: 0,
+            status: status as u8,
+            stake_amount_lamports: stake,
+            payout_token_account: wallet(100 + n),
+            endpoint_count: regions.len() as u8,
+            endpoints,
+            last_claimed_epoch: 0,
+            bump: 255,
+            vault_bump: 255,
+        };
+        let mut data = Vec::new();
+        op.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn metrics_fixture(n: u8, epoch_id: u64, uptime_score: u16, latency_ms_p50: u32) -> Vec<u8> {
+        let m = EpochMetrics {
+            epoch_id,
+            operator_wallet: wallet(n),
+            paid_query_count: 0,
+            receipt_count: 0,
+            uptime_score,
+            latency_ms_p50,
+            metrics_root: [0u8; 32],
+            submitted_by: wallet(200),
+            submitted_at_slot: 0,
+            rewarded_amount: 0,
+            rewarded_at_slot: 0,
+            bump: 255,
+        };
+        let mut data = Vec::new();
+        m.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn directory() -> OperatorDirectory {
+        let accounts = vec![
+            (
+                wallet(11),
+                operator_fixture(1, OperatorStatus::Active, 5_000, &[7]),
+            ),
+            (
+                wallet(12),
+                operator_fixture(2, OperatorStatus::Active, 9_000, &[3]),
+            ),
+            (
+                wallet(13),
+                operator_fixture(3, OperatorStatus::Active, 2_000, &[7, 3]),
+            ),
+            (
+                wallet(14),
+                operator_fixture(4, OperatorStatus::Slashed, 50_000, &[7]),
+            ),
+            (
+                wallet(15),
+                operator_fixture(5, OperatorStatus::Active, 100, &[7]),
+            ),
+            (wallet(21), metrics_fixture(1, 1, 9_000, 80)),
+            (wallet(22), metrics_fixture(1, 2, 9_900, 40)),
+            (wallet(23), metrics_fixture(2, 2, 9_500, 20)),
+            (wallet(24), metrics_fixture(3, 2, 7_000, 30)),
+        ];
+        OperatorDirectory::load(
+            &config_fixture(1_000),
+            accounts.iter().map(|(k, d)| (*k, d.as_slice())),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn load_filters_status_and_stake_and_keeps_latest_metrics() {
+        let dir = directory();
+        let wallets: Vec<Pubkey> = dir.operators.iter().map(|o| o.operator_wallet).collect();
+        assert_eq!(wallets, vec![wallet(1), wallet(2), wallet(3)]);
+        assert_eq!(dir.operators[0].metrics.unwrap().latency_ms_p50, 40);
+        assert_eq!(dir.operators[2].endpoints.len(), 2);
+    }
+
+    #[test]
+    fn region_latency_stake_prefers_region_then_latency() {
+        let prefs = Preferences {
+            region: Some(7),
+            ..Preferences::default()
+        };
+        let ranked = directory().rank(&prefs, &RegionLatencyStake);
+        let order: Vec<(Pubkey, u16)> = ranked
+            .iter()
+            .map(|c| (c.operator_wallet, c.endpoint.region))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (wallet(3), 7),
+                (wallet(1), 7),
+                (wallet(2), 3),
+                (wallet(3), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn alternate_strategies_and_filters() {
+        let dir = directory();
+        let prefs = Preferences::default();
+        assert_eq!(
+            dir.select(&prefs, &LowestLatency, 1)[0].operator_wallet,
+            wallet(2)
+        );
+        assert_eq!(
+            dir.select(&prefs, &StakeWeighted, 1)[0].operator_wallet,
+            wallet(2)
+        );
+
+        let strict = Preferences {
+            min_uptime_score: 9_000,
+            endpoint_kinds: vec![ENDPOINT_IPV4],
+            ..Preferences::default()
+        };
+        let wallets: Vec<Pubkey> = dir
+            .rank(&strict, &StakeWeighted)
+            .iter()
+            .map(|c| c.operator_wallet)
+            .collect();
+        assert_eq!(wallets, vec![wallet(2), wallet(1)]);
+    }
+
+    #[test]
+    fn skips_undecodable_accounts() {
+        let good = operator_fixture(1, OperatorStatus::Active, 5_000, &[7]);
+        let truncated = operator_fixture(2, OperatorStatus::Active, 9_000, &[3]);
+        let bad_metrics = metrics_fixture(1, 3, 9_999, 1);
+        let accounts: Vec<(Pubkey, &[u8])> = vec![
+            (wallet(11), &good),
+            (wallet(12), &truncated[..truncated.len() - 4]),
+            (wallet(21), &bad_metrics[..12]),
+        ];
+        let dir = OperatorDirectory::load(&config_fixture(1_000), accounts).unwrap();
+        let wallets: Vec<Pubkey> = dir.operators.iter().map(|o| o.operator_wallet).collect();
+        assert_eq!(wallets, vec![wallet(1)]);
+        assert_eq!(dir.operators[0].metrics, None);
+        assert_eq!(dir.skipped, vec![wallet(12), wallet(21)]);
+        assert!(directory().skipped.is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_config() {
+        let empty: Vec<(Pubkey, &[u8])> = vec![];
+        assert_eq!(
+            OperatorDirectory::load(&[0u8; 16], empty).unwrap_err(),
+            SelectError::InvalidConfig
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! Reverse resolution over `ddns_names`: wallet → verified primary name.
+//!
+//! Callers fetch the wallet's `PrimaryName` PDA and the name account it points
+//! at, then call [`verify_primary`]. A record only resolves while the wallet
+//! still owns an unexpired name whose bytes match the stored hash.
+
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use ddns_names::{PremiumName, PrimaryName, SubName};
+use ddns_names_core::{chained_name_hash, hash_label, hash_name, subdomain_name_hash};
+
+pub const KIND_PREMIUM: u8 = 1;
+pub const KIND_SUBDOMAIN: u8 = 2;
+
+const SEED_PRIMARY: &[u8] = b"primary";
+const SEED_PREMIUM: &[u8] = b"premium";
+const SEED_SUB: &[u8] = b"sub";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReverseError {
+    /// Account data is not a `PrimaryName` / `PremiumName` / `SubName`.
+    InvalidAccount,
+    /// The wallet has no primary name set.
+    NotSet,
+    /// The record belongs to a different wallet.
+    WrongOwner,
+    /// The stored name bytes do not hash to the stored `name_hash`.
+    NameMismatch,
+    /// The name account no longer exists (released or never created).
+    NameMissing,
+    /// The name now belongs to someone else.
+    NotOwned,
+    /// The premium name or subdomain is past `expires_at`.
+    Expired,
+}
+
+impl fmt::Display for ReverseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReverseError::InvalidAccount => write!(f, "invalid ddns_names account"),
+            ReverseError::NotSet => write!(f, "no primary name set"),
+            ReverseError::WrongOwner => write!(f, "primary record belongs to another wallet"),
+            ReverseError::NameMismatch => write!(f, "primary name bytes do not match name_hash"),
+            ReverseError::NameMissing => write!(f, "primary name account not found"),
+            ReverseError::NotOwned => write!(f, "primary name is owned by another wallet"),
+            ReverseError::Expired => write!(f, "primary name has expired"),
+        }
+    }
+}
+
+impl std::error::Error for ReverseError {}
+
+/// A primary name that passed every check in [`verify_primary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedPrimary {
+    pub wallet: Pubkey,
+    pub name: String,
+    pub name_hash: [u8; 32],
+    pub kind: u8,
+    /// `PremiumName.expires_at` or `SubName.expires_at`; `None` for
+    /// subdomains that never expire.
+    pub expires_at: Option<i64>,
+}
+
+/// Decoded `PrimaryName` account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimaryRecord {
+    pub owner: Pubkey,
+    pub name: String,
+    pub name_hash: [u8; 32],
+    pub kind: u8,
+    pub is_set: bool,
+    /// Subdomain labels below the premium name or parent zone; 0 for premium names.
+    pub depth: u8,
+}
+
+impl PrimaryRecord {
+    pub fn load(data: &[u8]) -> Result<Self, ReverseError> {
+        let mut slice = data;
+        let p = PrimaryName::try_deserialize(&mut slice).map_err(|_| ReverseError::InvalidAccount)?;
+        Ok(Self {
+            owner: p.owner,
+            name: p.name(),
+            name_hash: p.name_hash,
+            kind: p.kind,
+            is_set: p.is_set,
+            depth: p.depth,
+        })
+    }
+
+    /// Address of the `PremiumName` or `SubName` this record points at.
+    pub fn name_account(&self, program_id: &Pubkey) -> Result<Pubkey, ReverseError> {
+        match self.kind {
+            KIND_PREMIUM => Ok(Pubkey::find_program_address(&[SEED_PREMIUM, &self.name_hash], program_id).0),
+            KIND_SUBDOMAIN => {
+                let (parent_hash, label_hash) = split_subdomain(&self.name, self.depth)?;
+                Ok(Pubkey::find_program_address(&[SEED_SUB, &parent_hash, &label_hash], program_id).0)
+            }
+            _ => Err(ReverseError::InvalidAccount),
+        }
+    }
+
+    fn computed_hash(&self) -> Result<[u8; 32], ReverseError> {
+        match self.kind {
+            KIND_PREMIUM => Ok(hash_name(&self.name)),
+            KIND_SUBDOMAIN => {
+                let (parent_hash, label_hash) = split_subdomain(&self.name, self.depth)?;
+                Ok(subdomain_name_hash(parent_hash, label_hash))
+            }
+            _ => Err(ReverseError::InvalidAccount),
+        }
+    }
+}
+
+pub fn primary_name_address(program_id: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[SEED_PRIMARY, wallet.as_ref()], program_id).0
+}
+
+/// Resolves `wallet` to its primary name. `name_data` is the account at
+/// [`PrimaryRecord::name_account`], or `None` when it does not exist; `now`
+/// is the cluster's unix time.
+pub fn verify_primary(
+    wallet: &Pubkey,
+    primary_data: &[u8],
+    name_data: Option<&[u8]>,
+    now: i64,
+) -> Result<VerifiedPrimary, ReverseError> {
+    let record = PrimaryRecord::load(primary_data)?;
+    if !record.is_set {
+        return Err(ReverseError::NotSet);
+    }
+    if record.owner != *wallet {
+        return Err(ReverseError::WrongOwner);
+    }
+    if record.computed_hash()? != record.name_hash {
+        return Err(ReverseError::NameMismatch);
+    }
+
+    let mut slice = name_data.ok_or(ReverseError::NameMissing)?;
+    let expires_at = match record.kind {
+        KIND_PREMIUM => {
+            let premium = PremiumName::try_deserialize(&mut slice).map_err(|_| ReverseError::InvalidAccount)?;
+            if premium.name_hash != record.name_hash {
+                return Err(ReverseError::NameMismatch);
+            }
+            if premium.owner != *wallet {
+                return Err(ReverseError::NotOwned);
+            }
+            if now >= premium.expires_at {
+                return Err(ReverseError::Expired);
+            }
+            Some(premium.expires_at)
+        }
+        _ => {
+            let sub = SubName::try_deserialize(&mut slice).map_err(|_| ReverseError::InvalidAccount)?;
+            if sub.depth != record.depth
+                || (sub.parent_hash, sub.label_hash) != split_subdomain(&record.name, record.depth)?
+            {
+                return Err(ReverseError::NameMismatch);
+            }
+            if sub.owner != *wallet {
+                return Err(ReverseError::NotOwned);
+            }
+            if sub.is_expired(now) {
+                return Err(ReverseError::Expired);
+            }
+            (sub.expires_at != 0).then_some(sub.expires_at)
+        }
+    };
+
+    Ok(VerifiedPrimary {
+        wallet: *wallet,
+        name: record.name,
+        name_hash: record.name_hash,
+        kind: record.kind,
+        expires_at,
+    })
+}
+
+/// `(parent_hash, sha256(label))` of a subdomain's normalized name with
+/// `depth` labels below its root, matching `SubName.parent_hash`/`label_hash`.
+fn split_subdomain(name: &str, depth: u8) -> Result<([u8; 32], [u8; 32]), ReverseError> {
+    if depth == 0 {
+        return Err(ReverseError::NameMismatch);
+    }
+    let (label, parent) = name.split_once('.').ok_or(ReverseError::NameMismatch)?;
+    let parent_hash = chained_name_hash(parent, depth - 1).ok_or(ReverseError::NameMismatch)?;
+    Ok((parent_hash, hash_label(label)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    fn sha256(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+    use anchor_lang::AccountSerialize;
+
+    fn wallet(n: u8) -> Pubkey {
+        Pubkey::new_from_array([n; 32])
+    }
+
+    fn primary_fixture(owner: Pubkey, name: &str, name_hash: [u8; 32], kind: u8, depth: u8) -> Vec<u8> {
+        let mut name_bytes = [0u8; 253];
+        name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+        let p = PrimaryName {
+            owner,
+            name_hash,
+            kind,
+            is_set: true,
+            bump: 255,
+            name_len: name.len() as u8,
+            name_bytes,
+            depth,
+        };
+        let mut data = Vec::new();
+        p.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn premium_fixture(name: &str, owner: Pubkey, expires_at: i64) -> Vec<u8> {
+        let p = PremiumName {
+            name_hash: sha256(name.as_bytes()),
+            owner,
+            nft_mint: Pubkey::default(),
+            purchase_lamports: 0,
+            created_at: 0,
+            transferable: true,
+            nft_bound: false,
+            bump: 255,
+            expires_at,
+            listed: false,
+        };
+        let mut data = Vec::new();
+        p.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    fn sub_fixture(label: &str, parent_hash: [u8; 32], owner: Pubkey, depth: u8, expires_at: i64) -> Vec<u8> {
+        let s = SubName {
+            parent_hash,
+            label_hash: sha256(label.as_bytes()),
+            owner,
+            transfer_policy: 0,
+            parent_owner: Pubkey::default(),
+            transfers_enabled: false,
+            created_at: 0,
+            nft_mint: Pubkey::default(),
+            nft_custody_account: Pubkey::default(),
+            bump: 255,
+            depth,
+            expires_at,
+        };
+        let mut data = Vec::new();
+        s.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn resolves_owned_unexpired_premium_name() {
+        let w = wallet(1);
+        let primary = primary_fixture(w, "alice.dns", sha256(b"alice.dns"), KIND_PREMIUM, 0);
+        let premium = premium_fixture("alice.dns", w, 1_000);
+
+        let v = verify_primary(&w, &primary, Some(&premium), 999).unwrap();
+        assert_eq!(v.name, "alice.dns");
+        assert_eq!(v.expires_at, Some(1_000));
+        assert_eq!(verify_primary(&w, &primary, Some(&premium), 1_000), Err(ReverseError::Expired));
+    }
+
+    #[test]
+    fn rejects_transferred_released_or_forged_records() {
+        let w = wallet(1);
+        let primary = primary_fixture(w, "alice.dns", sha256(b"alice.dns"), KIND_PREMIUM, 0);
+        let sold = premium_fixture("alice.dns", wallet(2), 1_000);
+
+        assert_eq!(verify_primary(&w, &primary, Some(&sold), 0), Err(ReverseError::NotOwned));
+        assert_eq!(verify_primary(&w, &primary, None, 0), Err(ReverseError::NameMissing));
+        assert_eq!(verify_primary(&wallet(3), &primary, Some(&sold), 0), Err(ReverseError::WrongOwner));
+
+        let forged = primary_fixture(w, "bob.dns", sha256(b"alice.dns"), KIND_PREMIUM, 0);
+        let premium = premium_fixture("alice.dns", w, 1_000);
+        assert_eq!(verify_primary(&w, &forged, Some(&premium), 0), Err(ReverseError::NameMismatch));
+    }
+
+    #[test]
+    fn resolves_subdomains_and_derives_their_account() {
+        let w = wallet(1);
+        let hash = sha256(&[sha256(b"user.dns"), sha256(b"www")].concat());
+        let primary = primary_fixture(w, "www.user.dns", hash, KIND_SUBDOMAIN, 1);
+        let sub = sub_fixture("www", sha256(b"user.dns"), w, 1, 0);
+
+        let v = verify_primary(&w, &primary, Some(&sub), 0).unwrap();
+        assert_eq!(v.name, "www.user.dns");
+        assert_eq!(v.expires_at, None);
+
+        let program_id = wallet(9);
+        let record = PrimaryRecord::load(&primary).unwrap();
+        let expected = Pubkey::find_program_address(
+            &[SEED_SUB, &sha256(b"user.dns"), &sha256(b"www")],
+            &program_id,
+        )
+        .0;
+        assert_eq!(record.name_account(&program_id).unwrap(), expected);
+
+        let moved = sub_fixture("www", sha256(b"user.dns"), wallet(2), 1, 0);
+        assert_eq!(verify_primary(&w, &primary, Some(&moved), 0), Err(ReverseError::NotOwned));
+
+        let sold = sub_fixture("www", sha256(b"user.dns"), w, 1, 500);
+        assert_eq!(verify_primary(&w, &primary, Some(&sold), 499).unwrap().expires_at, Some(500));
+        assert_eq!(verify_primary(&w, &primary, Some(&sold), 500), Err(ReverseError::Expired));
+    }
+
+    #[test]
+    fn resolves_nested_subdomains_by_chained_hash() {
+        let w = wallet(1);
+        let www = sha256(&[sha256(b"alice.dns"), sha256(b"www")].concat());
+        let hash = sha256(&[www, sha256(b"api")].concat());
+        let primary = primary_fixture(w, "api.www.alice.dns", hash, KIND_SUBDOMAIN, 2);
+        let sub = sub_fixture("api", www, w, 2, 0);
+
+        assert_eq!(verify_primary(&w, &primary, Some(&sub), 0).unwrap().name, "api.www.alice.dns");
+        let program_id = wallet(9);
+        let expected = Pubkey::find_program_address(&[SEED_SUB, &www, &sha256(b"api")], &program_id).0;
+        assert_eq!(PrimaryRecord::load(&primary).unwrap().name_account(&program_id).unwrap(), expected);
+
+        // Read as a first-level name under "www.alice.dns" the bytes hash differently.
+        let flat = primary_fixture(w, "api.www.alice.dns", hash, KIND_SUBDOMAIN, 1);
+        assert_eq!(verify_primary(&w, &flat, Some(&sub), 0), Err(ReverseError::NameMismatch));
+    }
+}
@@ -0,0 +1,729 @@
+//! Canonical encoding of typed DNS record sets (RRsets).
+//!
+//! One encoded RRset holds every record of a single type for a name. It is
+//! what `ddns_registry::RecordSet` stores on-chain, what verifiers hash into
+//! aggregates, and what the node agent decodes when serving records:
+//!
+//! ```text
+//! version u8 | rrtype u16 LE | count u8 | count * (rdata_len u16 LE | rdata)
+//! ```
+//!
+//! `rdata` is the RFC 1035 wire form of each record (big-endian fields,
+//! uncompressed lowercase names). Records are sorted by `rdata` and
+//! deduplicated, so equal sets always encode, and hash, identically.
+
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use sha2::{Digest, Sha256};
+
+pub const ENCODING_VERSION: u8 = 1;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_CNAME: u16 = 5;
+pub const TYPE_MX: u16 = 15;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+pub const TYPE_HTTPS: u16 = 65;
+pub const TYPE_CAA: u16 = 257;
+
+pub const SUPPORTED_TYPES: [u16; 8] = [
+    TYPE_A, TYPE_CNAME, TYPE_MX, TYPE_TXT, TYPE_AAAA, TYPE_SRV, TYPE_HTTPS, TYPE_CAA,
+];
+
+/// Records per set.
+pub const MAX_RECORDS: usize = 16;
+/// Encoded set size, header included; bounds the on-chain account.
+pub const MAX_RRSET_BYTES: usize = 512;
+pub const MAX_NAME_WIRE_LEN: usize = 255;
+const MAX_LABEL_LEN: usize = 63;
+const MAX_CAA_TAG_LEN: usize = 15;
+const HEADER_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RrsetError {
+    UnsupportedVersion(u8),
+    UnsupportedType(u16),
+    TypeMismatch { expected: u16, found: u16 },
+    Empty,
+    TooManyRecords(usize),
+    TooLarge(usize),
+    Truncated,
+    TrailingBytes,
+    BadName,
+    BadRdata(u16),
+    NotCanonical,
+}
+
+impl fmt::Display for RrsetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RrsetError::UnsupportedVersion(v) => {
+                write!(f, "unsupported rrset encoding version {v}")
+            }
+            RrsetError::UnsupportedType(t) => write!(f, "unsupported record type {t}"),
+            RrsetError::TypeMismatch { expected, found } => {
+                write!(f, "record type {found} in a set of type {expected}")
+            }
+            RrsetError::Empty => write!(f, "record set is empty"),
+            RrsetError::TooManyRecords(n) => write!(f, "too many records in set ({n})"),
+            RrsetError::TooLarge(n) => write!(f, "encoded record set too large ({n} bytes)"),
+            RrsetError::Truncated => write!(f, "record set data truncated"),
+            RrsetError::TrailingBytes => write!(f, "trailing bytes after record data"),
+            RrsetError::BadName => write!(f, "invalid domain name"),
+            RrsetError::BadRdata(t) => write!(f, "invalid rdata for record type {t}"),
+            RrsetError::NotCanonical => write!(f, "record set is not canonically encoded"),
+        }
+    }
+}
+
+impl std::error::Error for RrsetError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    Cname(String),
+    /// One or more character-strings of at most 255 bytes each.
+    Txt(Vec<Vec<u8>>),
+    Mx {
+        preference: u16,
+        exchange: String,
+    },
+    Srv {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+    },
+    Caa {
+        flags: u8,
+        tag: String,
+        value: Vec<u8>,
+    },
+    /// `params` is the SvcParams wire form (RFC 9460), keys ascending.
+    Https {
+        priority: u16,
+        target: String,
+        params: Vec<u8>,
+    },
+}
+
+impl Record {
+    pub fn rrtype(&self) -> u16 {
+        match self {
+            Record::A(_) => TYPE_A,
+            Record::Aaaa(_) => TYPE_AAAA,
+            Record::Cname(_) => TYPE_CNAME,
+            Record::Txt(_) => TYPE_TXT,
+            Record::Mx { .. } => TYPE_MX,
+            Record::Srv { .. } => TYPE_SRV,
+            Record::Caa { .. } => TYPE_CAA,
+            Record::Https { .. } => TYPE_HTTPS,
+        }
+    }
+
+    /// RFC 1035 wire-form rdata with uncompressed, lowercased names.
+    pub fn to_rdata(&self) -> Result<Vec<u8>, RrsetError> {
+        let mut out = Vec::new();
+        match self {
+            Record::A(ip) => out.extend_from_slice(&ip.octets()),
+            Record::Aaaa(ip) => out.extend_from_slice(&ip.octets()),
+            Record::Cname(name) => out.extend(encode_name(name)?),
+            Record::Txt(strings) => {
+                if strings.is_empty() {
+                    return Err(RrsetError::BadRdata(TYPE_TXT));
+                }
+                for s in strings {
+                    let len = u8::try_from(s.len()).map_err(|_| RrsetError::BadRdata(TYPE_TXT))?;
+                    out.push(len);
+                    out.extend_from_slice(s);
+                }
+            }
+            Record::Mx {
+                preference,
+                exchange,
+            } => {
+                out.extend_from_slice(&preference.to_be_bytes());
+                out.extend(encode_name(exchange)?);
+            }
+            Record::Srv {
+                priority,
+                weight,
+                port,
+                target,
+            } => {
+                out.extend_from_slice(&priority.to_be_bytes());
+                out.extend_from_slice(&weight.to_be_bytes());
+                out.extend_from_slice(&port.to_be_bytes());
+                out.extend(encode_name(target)?);
+            }
+            Record::Caa { flags, tag, value } => {
+                let tag = tag.to_ascii_lowercase();
+                if !valid_caa_tag(tag.as_bytes()) {
+                    return Err(RrsetError::BadRdata(TYPE_CAA));
+                }
+                out.push(*flags);
+                out.push(tag.len() as u8);
+                out.extend_from_slice(tag.as_bytes());
+                out.extend_from_slice(value);
+            }
+            Record::Https {
+                priority,
+                target,
+                params,
+            } => {
+                if !valid_svc_params(params) {
+                    return Err(RrsetError::BadRdata(TYPE_HTTPS));
+                }
+                out.extend_from_slice(&priority.to_be_bytes());
+                out.extend(encode_name(target)?);
+                out.extend_from_slice(params);
+            }
+        }
+        if out.len() > u16::MAX as usize {
+            return Err(RrsetError::BadRdata(self.rrtype()));
+        }
+        Ok(out)
+    }
+
+    pub fn from_rdata(rrtype: u16, rdata: &[u8]) -> Result<Self, RrsetError> {
+        let bad = RrsetError::BadRdata(rrtype);
+        let record = match rrtype {
+            TYPE_A => {
+                let octets: [u8; 4] = rdata.try_into().map_err(|_| bad)?;
+                Record::A(Ipv4Addr::from(octets))
+            }
+            TYPE_AAAA => {
+                let octets: [u8; 16] = rdata.try_into().map_err(|_| bad)?;
+                Record::Aaaa(Ipv6Addr::from(octets))
+            }
+            TYPE_CNAME => {
+                let (name, used) = decode_name(rdata)?;
+                if used != rdata.len() {
+                    return Err(bad);
+                }
+                Record::Cname(name)
+            }
+            TYPE_TXT => {
+                if rdata.is_empty() {
+                    return Err(bad);
+                }
+                let mut strings = Vec::new();
+                let mut pos = 0;
+                while pos < rdata.len() {
+                    let len = rdata[pos] as usize;
+                    let s = rdata.get(pos + 1..pos + 1 + len).ok_or(bad.clone())?;
+                    strings.push(s.to_vec());
+                    pos += 1 + len;
+                }
+                Record::Txt(strings)
+            }
+            TYPE_MX => {
+                let preference = read_u16_be(rdata, 0).ok_or(bad.clone())?;
+                let (exchange, used) = decode_name(&rdata[2..])?;
+                if 2 + used != rdata.len() {
+                    return Err(bad);
+                }
+                Record::Mx {
+                    preference,
+                    exchange,
+                }
+            }
+            TYPE_SRV => {
+                let priority = read_u16_be(rdata, 0).ok_or(bad.clone())?;
+                let weight = read_u16_be(rdata, 2).ok_or(bad.clone())?;
+                let port = read_u16_be(rdata, 4).ok_or(bad.clone())?;
+                let (target, used) = decode_name(&rdata[6..])?;
+                if 6 + used != rdata.len() {
+                    return Err(bad);
+                }
+                Record::Srv {
+                    priority,
+                    weight,
+                    port,
+                    target,
+                }
+            }
+            TYPE_CAA => {
+                let flags = *rdata.first().ok_or(bad.clone())?;
+                let tag_len = *rdata.get(1).ok_or(bad.clone())? as usize;
+                let tag = rdata.get(2..2 + tag_len).ok_or(bad.clone())?;
+                if !valid_caa_tag(tag) {
+                    return Err(bad);
+                }
+                Record::Caa {
+                    flags,
+                    tag: String::from_utf8(tag.to_vec()).map_err(|_| bad)?,
+                    value: rdata[2 + tag_len..].to_vec(),
+                }
+            }
+            TYPE_HTTPS => {
+                let priority = read_u16_be(rdata, 0).ok_or(bad.clone())?;
+                let (target, used) = decode_name(&rdata[2..])?;
+                let params = rdata[2 + used..].to_vec();
+                if !valid_svc_params(&params) {
+                    return Err(bad);
+                }
+                Record::Https {
+                    priority,
+                    target,
+                    params,
+                }
+            }
+            other => return Err(RrsetError::UnsupportedType(other)),
+        };
+        Ok(record)
+    }
+}
+
+/// Zone-file presentation form, e.g. `10 mail.example.dns.` for MX.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Record::A(ip) => write!(f, "{ip}"),
+            Record::Aaaa(ip) => write!(f, "{ip}"),
+            Record::Cname(name) => write!(f, "{}", fqdn(name)),
+            Record::Txt(strings) => {
+                for (i, s) in strings.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write_quoted(f, s)?;
+                }
+                Ok(())
+            }
+            Record::Mx {
+                preference,
+                exchange,
+            } => write!(f, "{preference} {}", fqdn(exchange)),
+            Record::Srv {
+                priority,
+                weight,
+                port,
+                target,
+            } => write!(f, "{priority} {weight} {port} {}", fqdn(target)),
+            Record::Caa { flags, tag, value } => {
+                write!(f, "{flags} {tag} ")?;
+                write_quoted(f, value)
+            }
+            Record::Https {
+                priority,
+                target,
+                params,
+            } => {
+                write!(f, "{priority} {}", fqdn(target))?;
+                if !params.is_empty() {
+                    f.write_str(" ")?;
+                    for b in params {
+                        write!(f, "{b:02x}")?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A decoded record set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rrset {
+    pub rrtype: u16,
+    pub records: Vec<Record>,
+}
+
+pub fn is_supported_type(rrtype: u16) -> bool {
+    SUPPORTED_TYPES.contains(&rrtype)
+}
+
+pub fn type_name(rrtype: u16) -> Option<&'static str> {
+    Some(match rrtype {
+        TYPE_A => "A",
+        TYPE_AAAA => "AAAA",
+        TYPE_CNAME => "CNAME",
+        TYPE_TXT => "TXT",
+        TYPE_MX => "MX",
+        TYPE_SRV => "SRV",
+        TYPE_CAA => "CAA",
+        TYPE_HTTPS => "HTTPS",
+        _ => return None,
+    })
+}
+
+pub fn type_from_name(name: &str) -> Option<u16> {
+    SUPPORTED_TYPES
+        .iter()
+        .copied()
+        .find(|t| type_name(*t).is_some_and(|n| n.eq_ignore_ascii_case(name)))
+}
+
+/// Encodes `records` as one canonical set of type `rrtype`, sorting and
+/// deduplicating by rdata. A CNAME set holds exactly one record.
+pub fn encode_rrset(rrtype: u16, records: &[Record]) -> Result<Vec<u8>, RrsetError> {
+    if !is_supported_type(rrtype) {
+        return Err(RrsetError::UnsupportedType(rrtype));
+    }
+    let mut rdatas = Vec::with_capacity(records.len());
+    for record in records {
+        if record.rrtype() != rrtype {
+            return Err(RrsetError::TypeMismatch {
+                expected: rrtype,
+                found: record.rrtype(),
+            });
+        }
+        rdatas.push(record.to_rdata()?);
+    }
+    rdatas.sort();
+    rdatas.dedup();
+
+    if rdatas.is_empty() {
+        return Err(RrsetError::Empty);
+    }
+    if rdatas.len() > MAX_RECORDS || (rrtype == TYPE_CNAME && rdatas.len() > 1) {
+        return Err(RrsetError::TooManyRecords(rdatas.len()));
+    }
+
+    let mut out = Vec::with_capacity(MAX_RRSET_BYTES);
+    out.push(ENCODING_VERSION);
+    out.extend_from_slice(&rrtype.to_le_bytes());
+    out.push(rdatas.len() as u8);
+    for rdata in &rdatas {
+        out.extend_from_slice(&(rdata.len() as u16).to_le_bytes());
+        out.extend_from_slice(rdata);
+    }
+    if out.len() > MAX_RRSET_BYTES {
+        return Err(RrsetError::TooLarge(out.len()));
+    }
+    Ok(out)
+}
+
+/// Decodes an encoded set, rejecting anything [`encode_rrset`] would not
+/// have produced byte for byte.
+pub fn decode_rrset(data: &[u8]) -> Result<Rrset, RrsetError> {
+    if data.len() > MAX_RRSET_BYTES {
+        return Err(RrsetError::TooLarge(data.len()));
+    }
+    if data.len() < HEADER_LEN {
+        return Err(RrsetError::Truncated);
+    }
+    if data[0] != ENCODING_VERSION {
+        return Err(RrsetError::UnsupportedVersion(data[0]));
+    }
+    let rrtype = u16::from_le_bytes([data[1], data[2]]);
+    if !is_supported_type(rrtype) {
+        return Err(RrsetError::UnsupportedType(rrtype));
+    }
+    let count = data[3] as usize;
+
+    let mut records = Vec::with_capacity(count);
+    let mut pos = HEADER_LEN;
+    for _ in 0..count {
+        let len_bytes = data.get(pos..pos + 2).ok_or(RrsetError::Truncated)?;
+        let len = u16::from_le_bytes([len_bytes[0], len_bytes[1]]) as usize;
+        pos += 2;
+        let rdata = data.get(pos..pos + len).ok_or(RrsetError::Truncated)?;
+        records.push(Record::from_rdata(rrtype, rdata)?);
+        pos += len;
+    }
+    if pos != data.len() {
+        return Err(RrsetError::TrailingBytes);
+    }
+    if encode_rrset(rrtype, &records)? != data {
+        return Err(RrsetError::NotCanonical);
+    }
+    Ok(Rrset { rrtype, records })
+}
+
+/// `sha256(encoded)`: the `dest_hash` verifiers put in their aggregates and
+/// the `rrset_hash` stored on-chain.
+pub fn rrset_hash(encoded: &[u8]) -> [u8; 32] {
+    Sha256::digest(encoded).into()
+}
+
+/// Aggregate key for a record set: `sha256("rrset" || name_hash || rrtype LE)`.
+/// Used in place of `name_hash` so one verifier can attest every record type
+/// of a name in the same epoch.
+pub fn rrset_key(name_hash: &[u8; 32], rrtype: u16) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"rrset");
+    hasher.update(name_hash);
+    hasher.update(rrtype.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Uncompressed wire form of `name`; a trailing dot is optional and `"."` or
+/// `""` is the root. Labels are lowercased.
+pub fn encode_name(name: &str) -> Result<Vec<u8>, RrsetError> {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    let mut out = Vec::with_capacity(name.len() + 2);
+    if !name.is_empty() {
+        for label in name.split('.') {
+            if label.is_empty()
+                || label.len() > MAX_LABEL_LEN
+                || !label.bytes().all(valid_label_byte)
+            {
+                return Err(RrsetError::BadName);
+            }
+            out.push(label.len() as u8);
+            out.extend(label.bytes().map(|b| b.to_ascii_lowercase()));
+        }
+    }
+    out.push(0);
+    if out.len() > MAX_NAME_WIRE_LEN {
+        return Err(RrsetError::BadName);
+    }
+    Ok(out)
+}
+
+/// Decodes an uncompressed wire name from the start of `data`, returning the
+/// dotted name (no trailing dot, `"."` for the root) and bytes consumed.
+pub fn decode_name(data: &[u8]) -> Result<(String, usize), RrsetError> {
+    let mut labels: Vec<&str> = Vec::new();
+    let mut pos = 0;
+    loop {
+        let len = *data.get(pos).ok_or(RrsetError::BadName)? as usize;
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        if len > MAX_LABEL_LEN {
+            return Err(RrsetError::BadName);
+        }
+        let label = data.get(pos..pos + len).ok_or(RrsetError::BadName)?;
+        if !label.iter().copied().all(valid_label_byte) {
+            return Err(RrsetError::BadName);
+        }
+        labels.push(std::str::from_utf8(label).map_err(|_| RrsetError::BadName)?);
+        pos += len;
+    }
+    if pos > MAX_NAME_WIRE_LEN {
+        return Err(RrsetError::BadName);
+    }
+    if labels.is_empty() {
+        return Ok((".".to_string(), pos));
+    }
+    Ok((labels.join("."), pos))
+}
+
+fn valid_label_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'*'
+}
+
+fn valid_caa_tag(tag: &[u8]) -> bool {
+    !tag.is_empty() && tag.len() <= MAX_CAA_TAG_LEN && tag.iter().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// SvcParams are `key u16 | len u16 | value` entries with strictly ascending keys.
+fn valid_svc_params(params: &[u8]) -> bool {
+    let mut pos = 0;
+    let mut last_key: Option<u16> = None;
+    while pos < params.len() {
+        let (Some(key), Some(len)) = (read_u16_be(params, pos), read_u16_be(params, pos + 2))
+        else {
+            return false;
+        };
+        if last_key.is_some_and(|last| key <= last) {
+            return false;
+        }
+        last_key = Some(key);
+        pos += 4 + len as usize;
+        if pos > params.len() {
+            return false;
+        }
+    }
+    true
+}
+
+fn read_u16_be(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn fqdn(name: &str) -> String {
+    if name == "." {
+        return ".".to_string();
+    }
+    format!("{name}.")
+}
+
+fn write_quoted(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    f.write_str("\"")?;
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' => write!(f, "\\{}", b as char)?,
+            0x20..=0x7e => write!(f, "{}", b as char)?,
+            _ => write!(f, "\\{b:03}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_sets() -> Vec<(u16, Vec<Record>)> {
+        vec![
+            (
+                TYPE_A,
+                vec![
+                    Record::A(Ipv4Addr::new(203, 0, 113, 7)),
+                    Record::A(Ipv4Addr::new(198, 51, 100, 1)),
+                ],
+            ),
+            (
+                TYPE_AAAA,
+                vec![Record::Aaaa("2001:db8::1".parse().unwrap())],
+            ),
+            (
+                TYPE_CNAME,
+                vec![Record::Cname("edge.example.dns".to_string())],
+            ),
+            (
+                TYPE_TXT,
+                vec![Record::Txt(vec![b"v=spf1 -all".to_vec(), b"x".to_vec()])],
+            ),
+            (
+                TYPE_MX,
+                vec![Record::Mx {
+                    preference: 10,
+                    exchange: "mail.example.dns".to_string(),
+                }],
+            ),
+            (
+                TYPE_SRV,
+                vec![Record::Srv {
+                    priority: 1,
+                    weight: 5,
+                    port: 443,
+                    target: "svc.example.dns".to_string(),
+                }],
+            ),
+            (
+                TYPE_CAA,
+                vec![Record::Caa {
+                    flags: 0,
+                    tag: "issue".to_string(),
+                    value: b"letsencrypt.org".to_vec(),
+                }],
+            ),
+            (
+                TYPE_HTTPS,
+                vec![Record::Https {
+                    priority: 1,
+                    target: ".".to_string(),
+                    // alpn="h2"
+                    params: vec![0, 1, 0, 3, 2, b'h', b'2'],
+                }],
+            ),
+        ]
+    }
+
+    #[test]
+    fn every_type_round_trips() {
+        for (rrtype, records) in sample_sets() {
+            let encoded = encode_rrset(rrtype, &records).unwrap();
+            let decoded = decode_rrset(&encoded).unwrap();
+            assert_eq!(decoded.rrtype, rrtype);
+            assert_eq!(encode_rrset(rrtype, &decoded.records).unwrap(), encoded);
+        }
+    }
+
+    #[test]
+    fn encoding_is_order_and_case_independent() {
+        let a = vec![
+            Record::Mx {
+                preference: 20,
+                exchange: "B.Example.dns.".to_string(),
+            },
+            Record::Mx {
+                preference: 10,
+                exchange: "a.example.dns".to_string(),
+            },
+        ];
+        let mut b = a.clone();
+        b.reverse();
+        b.push(a[0].clone());
+        let ea = encode_rrset(TYPE_MX, &a).unwrap();
+        assert_eq!(ea, encode_rrset(TYPE_MX, &b).unwrap());
+        assert_eq!(
+            rrset_hash(&ea),
+            rrset_hash(&encode_rrset(TYPE_MX, &b).unwrap())
+        );
+        assert_eq!(decode_rrset(&ea).unwrap().records.len(), 2);
+    }
+
+    #[test]
+    fn rejects_non_canonical_input() {
+        let records = vec![
+            Record::A(Ipv4Addr::new(10, 0, 0, 2)),
+            Record::A(Ipv4Addr::new(10, 0, 0, 1)),
+        ];
+        let mut encoded = encode_rrset(TYPE_A, &records).unwrap();
+        // Swap the two 4-byte rdatas so they are no longer sorted.
+        let (first, second) = (encoded[6..10].to_vec(), encoded[12..16].to_vec());
+        encoded[6..10].copy_from_slice(&second);
+        encoded[12..16].copy_from_slice(&first);
+        assert_eq!(decode_rrset(&encoded), Err(RrsetError::NotCanonical));
+
+        let mut upper = encode_rrset(TYPE_CNAME, &[Record::Cname("a.dns".to_string())]).unwrap();
+        upper[7] = b'A';
+        assert_eq!(decode_rrset(&upper), Err(RrsetError::NotCanonical));
+
+        encoded.push(0);
+        assert!(decode_rrset(&encoded).is_err());
+    }
+
+    #[test]
+    fn enforces_type_and_size_limits() {
+        assert_eq!(
+            encode_rrset(TYPE_A, &[Record::Aaaa(Ipv6Addr::LOCALHOST)]),
+            Err(RrsetError::TypeMismatch {
+                expected: TYPE_A,
+                found: TYPE_AAAA
+            })
+        );
+        assert_eq!(encode_rrset(TYPE_A, &[]), Err(RrsetError::Empty));
+        assert_eq!(
+            encode_rrset(
+                TYPE_CNAME,
+                &[
+                    Record::Cname("a.dns".to_string()),
+                    Record::Cname("b.dns".to_string())
+                ]
+            ),
+            Err(RrsetError::TooManyRecords(2))
+        );
+        let many: Vec<Record> = (0..=MAX_RECORDS as u8)
+            .map(|i| Record::A(Ipv4Addr::new(10, 0, 0, i)))
+            .collect();
+        assert_eq!(
+            encode_rrset(TYPE_A, &many),
+            Err(RrsetError::TooManyRecords(MAX_RECORDS + 1))
+        );
+        let big = vec![Record::Txt(vec![vec![b'x'; 255], vec![b'y'; 255]])];
+        assert!(matches!(
+            encode_rrset(TYPE_TXT, &big),
+            Err(RrsetError::TooLarge(_))
+        ));
+        assert_eq!(
+            encode_rrset(TYPE_A, &[Record::A(Ipv4Addr::LOCALHOST)]).map(|_| ()),
+            Ok(())
+        );
+        assert!(encode_name(&"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn presentation_format() {
+        let mx = Record::Mx {
+            preference: 10,
+            exchange: "mail.example.dns".to_string(),
+        };
+        assert_eq!(mx.to_string(), "10 mail.example.dns.");
+        let txt = Record::Txt(vec![b"say \"hi\"".to_vec()]);
+        assert_eq!(txt.to_string(), "\"say \\\"hi\\\"\"");
+        assert_eq!(type_from_name("https"), Some(TYPE_HTTPS));
+        assert_eq!(type_name(TYPE_CAA), Some("CAA"));
+    }
+}
@@ -3,7 +3,7 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program_option::COption;
 use anchor_lang::solana_program::program_pack::Pack;
-use sha2::{Digest, Sha256};
+use ddns_names_core::{hash_dns_label, hash_name};
 use anchor_spl::token::{self, Token, Transfer};
 use anchor_spl::token::spl_token::state::{Account as SplTokenAccount, Mint as SplMint};
 
@@ -40,7 +40,7 @@ pub mod ddns_anchor {
         metadata_hash: [u8; 32],
     ) -> Result<()> {
         validate_label(&label)?;
-        let computed = hash_label_dns(&label);
+        let computed = hash_dns_label(&label);
         require!(computed == name_hash, ErrorCode::InvalidNameHash);
 
         // Centralized MVP authority: all mints must be signed by config.admin (tollbooth).
@@ -221,16 +221,19 @@ pub mod ddns_anchor {
         dest_hash: [u8; 32],
         ttl: u32,
     ) -> Result<()> {
-        // Deterministic name hashing: sha256(lowercase(name)).
+        // Deterministic name hashing: sha256(normalized name), see `ddns_names_core`.
         // For MVP, require `.dns` and validate the label portion against [a-z0-9-]{3,32}.
-        let normalized = name.trim().to_ascii_lowercase();
+        let normalized = match ddns_names_core::normalize_name(&name) {
+            Ok(v) => v,
+            Err(_) => return err!(ErrorCode::InvalidNameChars),
+        };
         require!(normalized.ends_with(".dns"), ErrorCode::InvalidNameChars);
         let label = match normalized.strip_suffix(".dns") {
             Some(v) => v,
             None => return err!(ErrorCode::InvalidNameChars),
         };
         validate_label(label)?;
-        let computed = hash_full_name(&normalized);
+        let computed = hash_name(&normalized);
         require!(computed == name_hash, ErrorCode::InvalidNameHash);
 
         // Centralized MVP authority: all writes must be signed by config.admin (tollbooth).
@@ -515,34 +518,16 @@ impl RouteRecord {
     pub const SIZE: usize = 32 + 32 + 32 + 4 + 8 + 1;
 }
 
-fn hash_label_dns(label: &str) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    hasher.update(label.as_bytes());
-    hasher.update(b".dns");
-    hasher.finalize().into()
-}
-
-fn hash_full_name(name: &str) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    hasher.update(name.as_bytes());
-    hasher.finalize().into()
-}
-
 fn validate_label(label: &str) -> Result<()> {
     let bytes = label.as_bytes();
     if bytes.len() < 3 || bytes.len() > 32 {
         return err!(ErrorCode::InvalidNameLength);
     }
-    if bytes[0] == b'-' || bytes[bytes.len() - 1] == b'-' {
+    // LDH only, lowercase, and `--` in positions 3-4 only as a well-formed
+    // `xn--` A-label for internationalized names.
+    if ddns_names_core::check_ldh_label(label).is_err() {
         return err!(ErrorCode::InvalidNameChars);
     }
-    for b in bytes {
-        let ok = (b'a'..=b'z').contains(b) || (b'0'..=b'9').contains(b) || *b == b'-';
-        if !ok {
-            return err!(ErrorCode::InvalidNameChars);
-        }
-    }
-    // bytes validation above enforces lowercase-only.
     if is_reserved(label) {
         return err!(ErrorCode::ReservedName);
     }
@@ -564,6 +549,7 @@ fn is_reserved(name: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sha2::{Digest, Sha256};
 
     #[test]
     fn validate_label_accepts_valid_labels() {
@@ -594,9 +580,36 @@ mod tests {
         assert!(validate_label("abc_def").is_err());
     }
 
+    #[test]
+    fn validate_label_follows_shared_idna_vectors() {
+        let vectors = include_str!("../../../crates/ddns_idna/vectors/labels.tsv");
+        for line in vectors.lines().filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let cols: Vec<&str> = line.split('\t').collect();
+            let (input, onchain) = (cols[0], cols[2] == "ok");
+            let fits = (3..=32).contains(&input.len());
+            assert_eq!(validate_label(input).is_ok(), onchain && fits, "{input}");
+        }
+        assert!(validate_label("ab--cd").is_err());
+        assert!(validate_label("xn--mnchen-3y").is_err());
+    }
+
+    #[test]
+    fn route_names_hash_like_shared_vectors() {
+        for v in ddns_names_core::vectors::names().filter(|v| v.depth == 0) {
+            let normalized = ddns_names_core::normalize_name(v.input).ok();
+            assert_eq!(normalized.as_deref(), v.normalized, "{}", v.input);
+            if let (Some(name), Some(hash)) = (normalized, v.hash) {
+                assert_eq!(hash_name(&name), hash, "{}", v.input);
+                if let Some(label) = name.strip_suffix(".dns") {
+                    assert_eq!(hash_dns_label(label), hash, "{}", v.input);
+                }
+            }
+        }
+    }
+
     #[test]
     fn hash_label_dns_matches_sha256_label_plus_suffix() {
-        let actual = hash_label_dns("netflix");
+        let actual = hash_dns_label("netflix");
         let mut hasher = Sha256::new();
         hasher.update(b"netflix");
         hasher.update(b".dns");
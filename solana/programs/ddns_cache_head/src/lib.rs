@@ -4,7 +4,7 @@ use anchor_lang::prelude::*;
 
 declare_id!("APczyvaMLpZaxh3hTRw5xKTkzwKcxEScxKn7zReR7QKg");
 
-const SEED_CACHE_HEAD: &[u8] = b"cache_head";
+pub const SEED_CACHE_HEAD: &[u8] = b"cache_head";
 
 #[program]
 pub mod ddns_cache_head {
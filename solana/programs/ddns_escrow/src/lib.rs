@@ -16,6 +16,7 @@ const VOUCHER_DOMAIN_SEP: &[u8] = b"DDNS_VOUCHER_V1";
 pub mod ddns_escrow {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn init_config(
         ctx: Context<InitConfig>,
         authority: Pubkey,
@@ -680,7 +681,7 @@ fn parse_ed25519_ix(data: &[u8]) -> Result<(Pubkey, [u8; 64], Vec<u8>)> {
         u16::from_le_bytes([data[idx], data[idx + 1]])
     };
 
-    let signature_offset = read_u16(off_start + 0) as usize;
+    let signature_offset = read_u16(off_start) as usize;
     let signature_ix_idx = read_u16(off_start + 2);
     let pubkey_offset = read_u16(off_start + 4) as usize;
     let pubkey_ix_idx = read_u16(off_start + 6);
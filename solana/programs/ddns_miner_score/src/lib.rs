@@ -18,12 +18,12 @@ const SCORE_DENOM: u128 = 10_000; // scores in 0..10000
 const MAX_SUBMITTERS: usize = 16;
 const MAX_MINERS: usize = 64;
 
-const SEED_CONFIG: &[u8] = b"miner_score_config";
-const SEED_VAULT_AUTH: &[u8] = b"miner_score_vault_authority";
-const SEED_REWARD_VAULT: &[u8] = b"reward_vault";
-const SEED_MINER_EPOCH: &[u8] = b"miner_epoch";
-const SEED_EPOCH_TOTALS: &[u8] = b"epoch_totals";
-const SEED_CLAIM: &[u8] = b"claim";
+pub const SEED_CONFIG: &[u8] = b"miner_score_config";
+pub const SEED_VAULT_AUTH: &[u8] = b"miner_score_vault_authority";
+pub const SEED_REWARD_VAULT: &[u8] = b"reward_vault";
+pub const SEED_MINER_EPOCH: &[u8] = b"miner_epoch";
+pub const SEED_EPOCH_TOTALS: &[u8] = b"epoch_totals";
+pub const SEED_CLAIM: &[u8] = b"claim";
 
 const PREMIUM_NAME_ACCOUNT: &str = "PremiumName";
 
@@ -3,31 +3,72 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::system_instruction;
 use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::mpl_token_metadata::{self, types::DataV2};
+use anchor_spl::metadata::{
+    self as token_metadata, CreateMasterEditionV3, CreateMetadataAccountsV3, Metadata, MetadataAccount,
+    UpdateMetadataAccountsV2,
+};
 use anchor_spl::token::{self, Mint as SplMint, MintTo, Token, TokenAccount as SplTokenAccount};
-use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::token_interface::{self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked};
+use ddns_names_core::{hash_label, hash_name, subdomain_name_hash};
 use sha2::{Digest, Sha256};
 
 const MAX_PARENT_ZONE: usize = 64;
+// Normalized name bytes kept in reverse (`PrimaryName`) records and auctions.
+const MAX_NAME_BYTES: usize = 253;
+const MAX_PREMIUM_NAME: usize = 32 + ".dns".len();
+const MAX_NFT_SYMBOL: usize = mpl_token_metadata::MAX_SYMBOL_LENGTH;
+const MAX_NFT_URI: usize = mpl_token_metadata::MAX_URI_LENGTH;
+const DEFAULT_NFT_SYMBOL: &[u8] = b"DNS";
 
 const KIND_PREMIUM: u8 = 1;
 const KIND_SUBDOMAIN: u8 = 2;
 
+// Premium registration term defaults; `set_premium_terms` overrides them.
+const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+const DEFAULT_GRACE_PERIOD_SECS: i64 = 30 * 24 * 60 * 60;
+const MAX_RENEWAL_YEARS: u8 = 10;
+
+// Premium label price tiers: index `len - 1`, with the last tier covering
+// every longer label. A zero tier falls back to the flat config price.
+const PRICE_TIERS: usize = 8;
+const LAMPORTS_PER_SOL: u128 = 1_000_000_000;
+
+const AUCTION_OPEN: u8 = 0;
+const AUCTION_SEALED: u8 = 1;
+
+const PREMIUM_ACTIVE: u8 = 0;
+const PREMIUM_GRACE: u8 = 1;
+const PREMIUM_RELEASABLE: u8 = 2;
+
+// Owner-published record TTL bounds (seconds).
+const RECORDS_TTL_MIN_S: u32 = 30;
+const RECORDS_TTL_MAX_S: u32 = 86_400;
+
 const TRANSFER_NON_TRANSFERABLE: u8 = 0;
 const TRANSFER_PARENT_CONTROLLED: u8 = 1;
 
-const SEED_CONFIG: &[u8] = b"names_config";
-const SEED_PREMIUM: &[u8] = b"premium";
-const SEED_PREMIUM_CONFIG: &[u8] = b"premium_config";
-const SEED_AUCTION: &[u8] = b"auction";
-const SEED_ESCROW: &[u8] = b"escrow";
-const SEED_ESCROW_VAULT: &[u8] = b"escrow_vault";
-const SEED_SUB: &[u8] = b"sub";
-const SEED_PRIMARY: &[u8] = b"primary";
-const SEED_POLICY: &[u8] = b"parent_policy";
-const SEED_NFT_AUTHORITY: &[u8] = b"nft_authority";
-const SEED_NFT_CUSTODY_AUTHORITY: &[u8] = b"nft_custody_authority";
-const SEED_PREMIUM_NFT_MINT: &[u8] = b"premium_nft_mint";
-const SEED_SUB_NFT_MINT: &[u8] = b"sub_nft_mint";
+pub const SEED_CONFIG: &[u8] = b"names_config";
+pub const SEED_PREMIUM: &[u8] = b"premium";
+pub const SEED_PREMIUM_CONFIG: &[u8] = b"premium_config";
+pub const SEED_AUCTION: &[u8] = b"auction";
+pub const SEED_ESCROW: &[u8] = b"escrow";
+pub const SEED_ESCROW_VAULT: &[u8] = b"escrow_vault";
+pub const SEED_SUB: &[u8] = b"sub";
+pub const SEED_PRIMARY: &[u8] = b"primary";
+pub const SEED_POLICY: &[u8] = b"parent_policy";
+pub const SEED_NFT_AUTHORITY: &[u8] = b"nft_authority";
+pub const SEED_NFT_CUSTODY_AUTHORITY: &[u8] = b"nft_custody_authority";
+pub const SEED_PREMIUM_NFT_MINT: &[u8] = b"premium_nft_mint";
+pub const SEED_SUB_NFT_MINT: &[u8] = b"sub_nft_mint";
+pub const SEED_RECORDS: &[u8] = b"records";
+pub const SEED_NAME_PRICE: &[u8] = b"name_price";
+pub const SEED_COMMIT: &[u8] = b"commit";
+pub const SEED_SEALED_BID: &[u8] = b"sealed_bid";
+pub const SEED_LISTING: &[u8] = b"listing";
+pub const SEED_SUB_LABEL: &[u8] = b"sub_label";
+
+const MAX_MARKET_FEE_BPS: u16 = 10_000;
 
 // `solana-keygen pubkey solana/target/deploy/ddns_names-keypair.json`
 declare_id!("4V5WcPvxJTkRQv2ps8ueBkqPiNcy8HUMz4FS9i4hePA8");
@@ -62,6 +103,177 @@ pub mod ddns_names {
         cfg.enable_subdomains = enable_subdomains;
         cfg.enable_premium = enable_premium;
         cfg.bump = ctx.bumps.config;
+        cfg.commit_min_slots = 0;
+        cfg.commit_max_slots = 0;
+        cfg.market_fee_bps = 0;
+        cfg.nft_symbol_len = DEFAULT_NFT_SYMBOL.len() as u8;
+        cfg.nft_symbol_bytes = [0u8; MAX_NFT_SYMBOL];
+        cfg.nft_symbol_bytes[..DEFAULT_NFT_SYMBOL.len()].copy_from_slice(DEFAULT_NFT_SYMBOL);
+        cfg.nft_uri_len = 0;
+        cfg.nft_uri_template = [0u8; MAX_NFT_URI];
+        Ok(())
+    }
+
+    /// Token metadata applied to name NFTs. `uri_template` may contain
+    /// `{mint}`, `{owner}` and `{expires}`, filled in when the NFT is issued
+    /// and whenever its metadata is refreshed.
+    pub fn set_nft_metadata(ctx: Context<SetNftMetadata>, symbol: String, uri_template: String) -> Result<()> {
+        require!(symbol.len() <= MAX_NFT_SYMBOL, NamesError::InvalidNftMetadata);
+        require!(uri_template.len() <= MAX_NFT_URI, NamesError::InvalidNftMetadata);
+        let cfg = &mut ctx.accounts.config;
+        cfg.nft_symbol_len = symbol.len() as u8;
+        cfg.nft_symbol_bytes = [0u8; MAX_NFT_SYMBOL];
+        cfg.nft_symbol_bytes[..symbol.len()].copy_from_slice(symbol.as_bytes());
+        cfg.nft_uri_len = uri_template.len() as u8;
+        cfg.nft_uri_template = [0u8; MAX_NFT_URI];
+        cfg.nft_uri_template[..uri_template.len()].copy_from_slice(uri_template.as_bytes());
+        Ok(())
+    }
+
+    /// Protocol fee, in basis points of the sale price, that `buy_listed_premium`
+    /// sends to `NamesConfig.treasury`.
+    pub fn set_market_fee(ctx: Context<SetMarketFee>, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_MARKET_FEE_BPS, NamesError::InvalidListing);
+        ctx.accounts.config.market_fee_bps = fee_bps;
+        Ok(())
+    }
+
+    /// Requires commit-reveal for `purchase_premium` and `claim_subdomain`
+    /// when `min_slots > 0`: a commitment must be at least `min_slots` old
+    /// and, if `max_slots > 0`, at most `max_slots` old when it is used.
+    pub fn set_commit_window(ctx: Context<SetCommitWindow>, min_slots: u64, max_slots: u64) -> Result<()> {
+        require!(max_slots == 0 || max_slots > min_slots, NamesError::InvalidCommitWindow);
+        let cfg = &mut ctx.accounts.config;
+        cfg.commit_min_slots = min_slots;
+        cfg.commit_max_slots = max_slots;
+        Ok(())
+    }
+
+    /// Authority-only: grows an account created before fields were appended
+    /// to its type to the current size, with the authority paying the extra
+    /// rent. The type comes from the discriminator. New fields start zeroed
+    /// unless zero is not a valid value for an existing account:
+    /// - `NamesConfig` gets the default NFT symbol.
+    /// - `PremiumConfig` gets the default term and grace period.
+    /// - `PremiumName` (needs `premium_config`, migrated first) expires one
+    ///   period from now.
+    /// - `SubName` and subdomain `PrimaryName` records get depth 1.
+    /// - An unsettled `Auction` drops its leading bid, since escrows at the
+    ///   old seeds can only be withdrawn, so it can be cancelled.
+    ///
+    /// `config` is read raw so the config itself can be migrated. Accounts
+    /// already at the current size are left as they are.
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        let authority = stored_authority(&ctx.accounts.config.to_account_info())?;
+        require_keys_eq!(authority, ctx.accounts.authority.key(), NamesError::Unauthorized);
+
+        let target = ctx.accounts.target.to_account_info();
+        require_keys_eq!(*target.owner, crate::ID, NamesError::InvalidMigration);
+        let disc = target
+            .try_borrow_data()?
+            .get(..8)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| error!(NamesError::InvalidMigration))?;
+        let size = migrated_size(&disc)?;
+        let old_len = target.data_len();
+        let new_len = 8 + size;
+        if old_len >= new_len {
+            return Ok(());
+        }
+
+        let rent = Rent::get()?.minimum_balance(new_len).saturating_sub(target.lamports());
+        if rent > 0 {
+            anchor_lang::solana_program::program::invoke(
+                &system_instruction::transfer(&ctx.accounts.authority.key(), &target.key(), rent),
+                &[
+                    ctx.accounts.authority.to_account_info(),
+                    target.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        target.resize(new_len)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        if disc == NamesConfig::DISCRIMINATOR {
+            rewrite_account(&target, |cfg: &mut NamesConfig| {
+                cfg.nft_symbol_len = DEFAULT_NFT_SYMBOL.len() as u8;
+                cfg.nft_symbol_bytes[..DEFAULT_NFT_SYMBOL.len()].copy_from_slice(DEFAULT_NFT_SYMBOL);
+            })?;
+        } else if disc == PremiumConfig::DISCRIMINATOR {
+            rewrite_account(&target, |pcfg: &mut PremiumConfig| {
+                pcfg.period_secs = SECONDS_PER_YEAR;
+                pcfg.grace_period_secs = DEFAULT_GRACE_PERIOD_SECS;
+            })?;
+        } else if disc == PremiumName::DISCRIMINATOR {
+            let period_secs = ctx
+                .accounts
+                .premium_config
+                .as_ref()
+                .ok_or_else(|| error!(NamesError::InvalidMigration))?
+                .period_secs;
+            let expires_at = now
+                .checked_add(period_secs)
+                .ok_or_else(|| error!(NamesError::MathOverflow))?;
+            rewrite_account(&target, |premium: &mut PremiumName| premium.expires_at = expires_at)?;
+        } else if disc == SubName::DISCRIMINATOR {
+            rewrite_account(&target, |sub: &mut SubName| sub.depth = 1)?;
+        } else if disc == PrimaryName::DISCRIMINATOR {
+            rewrite_account(&target, |primary: &mut PrimaryName| {
+                primary.depth = if primary.kind == KIND_SUBDOMAIN { 1 } else { 0 };
+            })?;
+        } else if disc == Auction::DISCRIMINATOR {
+            rewrite_account(&target, |auction: &mut Auction| {
+                if !auction.settled {
+                    auction.highest_bidder = Pubkey::default();
+                    auction.highest_bid_lamports = 0;
+                }
+            })?;
+        }
+
+        emit!(AccountMigrated {
+            account: target.key(),
+            old_len: old_len as u32,
+            new_len: new_len as u32,
+        });
+        Ok(())
+    }
+
+    /// Step one of registration: records `commitment =
+    /// sha256(name_hash || owner || salt)` without revealing the name.
+    pub fn commit_name(ctx: Context<CommitName>, commitment: [u8; 32]) -> Result<()> {
+        let c = &mut ctx.accounts.name_commitment;
+        c.owner = ctx.accounts.owner.key();
+        c.commitment = commitment;
+        c.name_hash = [0u8; 32];
+        c.committed_slot = Clock::get()?.slot;
+        c.revealed = false;
+        c.bump = ctx.bumps.name_commitment;
+        Ok(())
+    }
+
+    /// Step two: opens the commitment for `name_hash` once it is old enough.
+    /// Send it in the same transaction as `purchase_premium` or
+    /// `claim_subdomain`, which consume and close the commitment.
+    pub fn reveal_name(
+        ctx: Context<RevealName>,
+        _commitment: [u8; 32],
+        name_hash: [u8; 32],
+        salt: [u8; 32],
+    ) -> Result<()> {
+        let c = &mut ctx.accounts.name_commitment;
+        require!(
+            commitment_hash(name_hash, c.owner, salt) == c.commitment,
+            NamesError::CommitmentMismatch
+        );
+        require_commit_window(&ctx.accounts.config, c, Clock::get()?.slot)?;
+        c.name_hash = name_hash;
+        c.revealed = true;
+        Ok(())
+    }
+
+    /// Closes an unused commitment and refunds its rent to the owner.
+    pub fn cancel_commitment(_ctx: Context<CancelCommitment>, _commitment: [u8; 32]) -> Result<()> {
         Ok(())
     }
 
@@ -74,6 +286,11 @@ pub mod ddns_names {
     ) -> Result<()> {
         let cfg = &ctx.accounts.config;
         require!(cfg.enable_subdomains, NamesError::Disabled);
+        consume_commitment(
+            cfg,
+            ctx.accounts.name_commitment.as_deref(),
+            subdomain_name_hash(parent_hash, label_hash),
+        )?;
 
         let normalized_parent = normalize_full_name(&parent)?;
         require!(normalized_parent == cfg.parent_zone(), NamesError::InvalidParent);
@@ -110,10 +327,13 @@ pub mod ddns_names {
         sub.nft_mint = Pubkey::default();
         sub.nft_custody_account = Pubkey::default();
         sub.bump = ctx.bumps.sub_name;
+        sub.depth = 1;
+        sub.expires_at = 0;
 
         upsert_primary_if_empty(
             &mut ctx.accounts.primary,
             ctx.accounts.owner.key(),
+            &format!("{normalized_label}.{normalized_parent}"),
             subdomain_name_hash(parent_hash, label_hash),
             KIND_SUBDOMAIN,
             ctx.bumps.primary,
@@ -135,6 +355,7 @@ pub mod ddns_names {
         validate_premium_label(label)?;
         let computed = hash_name(&normalized);
         require!(computed == name_hash, NamesError::InvalidHash);
+        consume_commitment(cfg, ctx.accounts.name_commitment.as_deref(), name_hash)?;
         let label_len = label.len() as u8;
 
         if label_len <= pcfg.reserved_max_len {
@@ -143,9 +364,47 @@ pub mod ddns_names {
             return err!(NamesError::AuctionRequired);
         }
 
-        if cfg.premium_price_lamports > 0 {
+        let listed = listed_price(&ctx.accounts.name_price, name_hash)?;
+        let price = premium_price(cfg.premium_price_lamports, &pcfg.length_prices_lamports, label.len(), listed);
+
+        if let Some(payer_toll) = ctx.accounts.payer_toll_account.as_ref() {
+            let toll_treasury = ctx
+                .accounts
+                .toll_treasury
+                .as_ref()
+                .ok_or_else(|| error!(NamesError::MissingRequiredAccount))?;
+            let toll_mint = ctx
+                .accounts
+                .toll_mint
+                .as_ref()
+                .ok_or_else(|| error!(NamesError::MissingRequiredAccount))?;
+            let token_program = ctx
+                .accounts
+                .token_program
+                .as_ref()
+                .ok_or_else(|| error!(NamesError::MissingRequiredAccount))?;
+            require!(pcfg.toll_units_per_sol > 0, NamesError::TollPaymentDisabled);
+            require_keys_eq!(toll_mint.key(), pcfg.toll_mint, NamesError::InvalidTollAccount);
+            require_keys_eq!(toll_treasury.key(), pcfg.toll_treasury, NamesError::InvalidTollAccount);
+            let amount = toll_amount(price, pcfg.toll_units_per_sol)?;
+            if amount > 0 {
+                token_interface::transfer_checked(
+                    CpiContext::new(
+                        token_program.to_account_info(),
+                        TransferChecked {
+                            from: payer_toll.to_account_info(),
+                            mint: toll_mint.to_account_info(),
+                            to: toll_treasury.to_account_info(),
+                            authority: ctx.accounts.owner.to_account_info(),
+                        },
+                    ),
+                    amount,
+                    toll_mint.decimals,
+                )?;
+            }
+        } else if price > 0 {
             anchor_lang::solana_program::program::invoke(
-                &system_instruction::transfer(&ctx.accounts.owner.key(), &cfg.treasury, cfg.premium_price_lamports),
+                &system_instruction::transfer(&ctx.accounts.owner.key(), &cfg.treasury, price),
                 &[
                     ctx.accounts.owner.to_account_info(),
                     ctx.accounts.treasury.to_account_info(),
@@ -154,25 +413,25 @@ pub mod ddns_names {
             )?;
         }
 
-        let premium = &mut ctx.accounts.premium_name;
-        premium.name_hash = name_hash;
-        premium.owner = ctx.accounts.owner.key();
-        premium.nft_mint = Pubkey::default();
-        premium.purchase_lamports = cfg.premium_price_lamports;
-        premium.created_at = Clock::get()?.unix_timestamp;
-        premium.transferable = true;
-        premium.nft_bound = false;
-        premium.bump = ctx.bumps.premium_name;
+        ctx.accounts.premium_name.grant(
+            name_hash,
+            ctx.accounts.owner.key(),
+            price,
+            pcfg.period_secs,
+            ctx.bumps.premium_name,
+        )?;
 
         let policy = &mut ctx.accounts.parent_policy;
         policy.parent_hash = name_hash;
         policy.parent_owner = ctx.accounts.owner.key();
         policy.transfers_enabled = false;
         policy.bump = ctx.bumps.parent_policy;
+        policy.reset_sale();
 
         upsert_primary_if_empty(
             &mut ctx.accounts.primary,
             ctx.accounts.owner.key(),
+            &normalized,
             name_hash,
             KIND_PREMIUM,
             ctx.bumps.primary,
@@ -202,15 +461,216 @@ pub mod ddns_names {
         pcfg.anti_sniping_extension_slots = anti_sniping_extension_slots;
         pcfg.enabled = enabled;
         pcfg.bump = ctx.bumps.premium_config;
+        pcfg.period_secs = SECONDS_PER_YEAR;
+        pcfg.renewal_lamports_per_year = 0;
+        pcfg.grace_period_secs = DEFAULT_GRACE_PERIOD_SECS;
+        pcfg.length_prices_lamports = [0; PRICE_TIERS];
+        pcfg.toll_mint = Pubkey::default();
+        pcfg.toll_treasury = Pubkey::default();
+        pcfg.toll_units_per_sol = 0;
+        Ok(())
+    }
+
+    /// Sets the premium price table. `length_prices_lamports[i]` prices labels
+    /// of `i + 1` characters (the last entry covers longer labels); a zero
+    /// entry keeps the flat `NamesConfig.premium_price_lamports`. When
+    /// `toll_units_per_sol` is non-zero, buyers may instead pay the same price
+    /// in `toll_mint` base units into `toll_treasury`.
+    pub fn set_premium_pricing(
+        ctx: Context<SetPremiumPricing>,
+        length_prices_lamports: [u64; PRICE_TIERS],
+        toll_mint: Pubkey,
+        toll_treasury: Pubkey,
+        toll_units_per_sol: u64,
+    ) -> Result<()> {
+        let pcfg = &mut ctx.accounts.premium_config;
+        pcfg.length_prices_lamports = length_prices_lamports;
+        pcfg.toll_mint = toll_mint;
+        pcfg.toll_treasury = toll_treasury;
+        pcfg.toll_units_per_sol = toll_units_per_sol;
+        Ok(())
+    }
+
+    /// Lists `name_hash` at a fixed premium price that overrides the length tier.
+    pub fn set_name_price(ctx: Context<SetNamePrice>, name_hash: [u8; 32], price_lamports: u64) -> Result<()> {
+        let listed = &mut ctx.accounts.name_price;
+        listed.name_hash = name_hash;
+        listed.price_lamports = price_lamports;
+        listed.bump = ctx.bumps.name_price;
+        Ok(())
+    }
+
+    pub fn clear_name_price(_ctx: Context<ClearNamePrice>, _name_hash: [u8; 32]) -> Result<()> {
+        Ok(())
+    }
+
+    /// View: what `purchase_premium` would charge for `name` right now.
+    /// Simulate it (Anchor `.view()`) to read the returned `PremiumQuote`.
+    pub fn quote_premium(ctx: Context<QuotePremium>, name: String, name_hash: [u8; 32]) -> Result<PremiumQuote> {
+        let cfg = &ctx.accounts.config;
+        let pcfg = &ctx.accounts.premium_config;
+        let normalized = normalize_full_name(&name)?;
+        let label = premium_label(&normalized)?;
+        validate_premium_label(label)?;
+        require!(hash_name(&normalized) == name_hash, NamesError::InvalidHash);
+        let label_len = label.len() as u8;
+
+        let listed = listed_price(&ctx.accounts.name_price, name_hash)?;
+        let price_lamports = premium_price(cfg.premium_price_lamports, &pcfg.length_prices_lamports, label.len(), listed);
+        let toll_units = if pcfg.toll_units_per_sol > 0 {
+            toll_amount(price_lamports, pcfg.toll_units_per_sol)?
+        } else {
+            0
+        };
+        Ok(PremiumQuote {
+            label_len,
+            price_lamports,
+            toll_mint: pcfg.toll_mint,
+            toll_units,
+            listed: listed.is_some(),
+            reserved: label_len <= pcfg.reserved_max_len,
+            auction_only: (pcfg.premium_min_len..=pcfg.premium_max_len).contains(&label_len),
+            available: ctx.accounts.premium_name.data_is_empty(),
+        })
+    }
+
+    /// Sets the registration term for premium names: each purchase, auction
+    /// win or renewed year lasts `period_secs` (one year by default), renewals
+    /// cost `renewal_lamports_per_year`, and expired names stay renewable by
+    /// their owner for `grace_period_secs` before they can be released.
+    pub fn set_premium_terms(
+        ctx: Context<SetPremiumTerms>,
+        period_secs: i64,
+        renewal_lamports_per_year: u64,
+        grace_period_secs: i64,
+    ) -> Result<()> {
+        require!(period_secs > 0 && grace_period_secs >= 0, NamesError::InvalidPremiumTerms);
+        let pcfg = &mut ctx.accounts.premium_config;
+        pcfg.period_secs = period_secs;
+        pcfg.renewal_lamports_per_year = renewal_lamports_per_year;
+        pcfg.grace_period_secs = grace_period_secs;
+        Ok(())
+    }
+
+    /// Extends a premium name by `years` periods from its current expiry.
+    /// Anyone may pay for an active name; during the grace period only the
+    /// owner may renew.
+    pub fn renew_premium(ctx: Context<RenewPremium>, name_hash: [u8; 32], years: u8) -> Result<()> {
+        require!((1..=MAX_RENEWAL_YEARS).contains(&years), NamesError::InvalidRenewal);
+        let pcfg = &ctx.accounts.premium_config;
+        let premium = &mut ctx.accounts.premium_name;
+        require!(premium.name_hash == name_hash, NamesError::InvalidHash);
+
+        let now = Clock::get()?.unix_timestamp;
+        match premium_state(now, premium.expires_at, pcfg.grace_period_secs) {
+            PREMIUM_ACTIVE => {}
+            PREMIUM_GRACE => {
+                require_keys_eq!(premium.owner, ctx.accounts.payer.key(), NamesError::GraceOwnerOnly);
+            }
+            _ => return err!(NamesError::NameExpired),
+        }
+
+        let cost = renewal_cost(pcfg.renewal_lamports_per_year, years)?;
+        if cost > 0 {
+            anchor_lang::solana_program::program::invoke(
+                &system_instruction::transfer(&ctx.accounts.payer.key(), &ctx.accounts.treasury.key(), cost),
+                &[
+                    ctx.accounts.payer.to_account_info(),
+                    ctx.accounts.treasury.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        premium.expires_at = (years as i64)
+            .checked_mul(pcfg.period_secs)
+            .and_then(|extension| premium.expires_at.checked_add(extension))
+            .ok_or_else(|| error!(NamesError::MathOverflow))?;
+        require!(
+            ctx.accounts.nft_metadata.is_some() || !premium.has_issued_nft(),
+            NamesError::NftMetadataRequired
+        );
+        if let Some(metadata) = ctx.accounts.nft_metadata.as_ref() {
+            refresh_name_nft_metadata(
+                &ctx.accounts.config,
+                metadata,
+                required(ctx.accounts.nft_authority.as_ref())?,
+                required(ctx.accounts.token_metadata_program.as_ref())?,
+                premium.nft_mint,
+                premium.owner,
+                premium.expires_at,
+            )?;
+        }
+
+        emit!(PremiumRenewed {
+            name_hash,
+            owner: premium.owner,
+            payer: ctx.accounts.payer.key(),
+            years,
+            lamports: cost,
+            expires_at: premium.expires_at,
+        });
+        Ok(())
+    }
+
+    /// Permissionless: once a name's grace period has passed, closes its
+    /// `PremiumName` and `ParentPolicy` (rent returns to the lapsed owner) so
+    /// the name can be bought again, or re-auctioned with `create_auction`.
+    /// A listed name's listing is closed too and any escrowed NFT returned to
+    /// the seller, who is still the name's owner.
+    pub fn release_premium(ctx: Context<ReleasePremium>, name_hash: [u8; 32]) -> Result<()> {
+        let premium = &ctx.accounts.premium_name;
+        require!(premium.name_hash == name_hash, NamesError::InvalidHash);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            premium_state(now, premium.expires_at, ctx.accounts.premium_config.grace_period_secs)
+                == PREMIUM_RELEASABLE,
+            NamesError::NameNotReleasable
+        );
+        let previous_owner = ctx.accounts.previous_owner.key();
+        // A listed name's listing must be passed so it closes with the name
+        // instead of outliving it.
+        require!(
+            ctx.accounts.listing.is_some() == premium.listed,
+            NamesError::InvalidListing
+        );
+        if let Some(listing) = ctx.accounts.listing.as_ref() {
+            if listing.nft_escrowed {
+                let seller_nft = required(ctx.accounts.seller_nft_account.as_ref())?;
+                require_keys_eq!(seller_nft.owner, previous_owner, NamesError::InvalidNftTokenAccount);
+                release_escrowed_nft(
+                    listing,
+                    required(ctx.accounts.nft_mint.as_ref())?,
+                    required(ctx.accounts.escrow_nft_account.as_ref())?,
+                    seller_nft,
+                    &ctx.accounts.previous_owner.to_account_info(),
+                    required(ctx.accounts.token_program.as_ref())?,
+                )?;
+            }
+            emit!(ListingCancelled { name_hash, seller: previous_owner });
+        }
+        clear_primary_if_points_to(&ctx.accounts.previous_owner_primary, previous_owner, name_hash)?;
+
+        emit!(PremiumReleased {
+            name_hash,
+            previous_owner,
+            expires_at: premium.expires_at,
+        });
         Ok(())
     }
 
+    /// Lists a short premium name. `mode` is `AUCTION_OPEN` (English auction
+    /// via `place_bid`) or `AUCTION_SEALED` (second-price sealed bids: commit
+    /// for `duration_slots`, then reveal for `reveal_slots`, defaulting to the
+    /// same length).
     pub fn create_auction(
         ctx: Context<CreateAuction>,
         name: String,
         name_hash: [u8; 32],
         min_bid_lamports: Option<u64>,
         duration_slots: Option<u64>,
+        mode: u8,
+        reveal_slots: Option<u64>,
     ) -> Result<()> {
         let pcfg = &ctx.accounts.premium_config;
         require!(pcfg.enabled, NamesError::Disabled);
@@ -226,14 +686,27 @@ pub mod ddns_names {
             (pcfg.premium_min_len..=pcfg.premium_max_len).contains(&label_len),
             NamesError::InvalidAuctionDomain
         );
+        // A released name keeps its settled auction account; only re-auction
+        // once the previous round is settled and nobody holds the name.
+        require!(ctx.accounts.premium_name.data_is_empty(), NamesError::NameTaken);
+        require!(
+            ctx.accounts.auction.name_hash == [0u8; 32] || ctx.accounts.auction.settled,
+            NamesError::AuctionInProgress
+        );
 
         let now_slot = Clock::get()?.slot;
         let min_bid = min_bid_lamports.unwrap_or(pcfg.min_bid_lamports_default);
         let duration = duration_slots.unwrap_or(pcfg.auction_duration_slots_default);
         require!(duration > 0, NamesError::InvalidAuctionConfig);
+        require!(mode == AUCTION_OPEN || mode == AUCTION_SEALED, NamesError::InvalidAuctionConfig);
+        let reveal = if mode == AUCTION_SEALED { reveal_slots.unwrap_or(duration) } else { 0 };
+        require!(mode == AUCTION_OPEN || reveal > 0, NamesError::InvalidAuctionConfig);
 
         let auction = &mut ctx.accounts.auction;
         auction.name_hash = name_hash;
+        auction.name_len = normalized.len() as u8;
+        auction.name_bytes = [0u8; MAX_PREMIUM_NAME];
+        auction.name_bytes[..normalized.len()].copy_from_slice(normalized.as_bytes());
         auction.start_slot = now_slot;
         auction.end_slot = now_slot
             .checked_add(duration)
@@ -243,6 +716,13 @@ pub mod ddns_names {
         auction.highest_bid_lamports = 0;
         auction.settled = false;
         auction.bump = ctx.bumps.auction;
+        auction.mode = mode;
+        auction.reveal_end_slot = auction
+            .end_slot
+            .checked_add(reveal)
+            .ok_or_else(|| error!(NamesError::MathOverflow))?;
+        auction.second_bid_lamports = 0;
+        auction.sealed_bids = 0;
         Ok(())
     }
 
@@ -256,6 +736,7 @@ pub mod ddns_names {
         let auction = &mut ctx.accounts.auction;
         require!(pcfg.enabled && !auction.settled, NamesError::Disabled);
         require!(auction.name_hash == name_hash, NamesError::InvalidHash);
+        require!(auction.mode == AUCTION_OPEN, NamesError::WrongAuctionMode);
 
         let now_slot = Clock::get()?.slot;
         require!(now_slot < auction.end_slot, NamesError::AuctionClosed);
@@ -269,6 +750,7 @@ pub mod ddns_names {
                 escrow.active = true;
                 escrow.refunded = false;
                 escrow.bump = ctx.bumps.bid_escrow;
+                escrow.auction_start_slot = auction.start_slot;
             } else {
                 require!(escrow.name_hash == name_hash, NamesError::InvalidHash);
                 require_keys_eq!(escrow.bidder, ctx.accounts.bidder.key(), NamesError::Unauthorized);
@@ -314,31 +796,101 @@ pub mod ddns_names {
         Ok(())
     }
 
+    /// Refunds an outbid (or prior-round) bid: the whole vault, bid plus
+    /// rent, goes back to the bidder and the escrow account is closed.
     pub fn withdraw_losing_bid(
         ctx: Context<WithdrawLosingBid>,
         name_hash: [u8; 32],
     ) -> Result<()> {
         let auction = &ctx.accounts.auction;
         require!(auction.name_hash == name_hash, NamesError::InvalidHash);
-        require!(!auction.settled || auction.highest_bidder != ctx.accounts.bidder.key(), NamesError::WinnerCannotWithdraw);
-        require!(auction.highest_bidder != ctx.accounts.bidder.key(), NamesError::WinnerCannotWithdraw);
-
-        let escrow = &ctx.accounts.bid_escrow;
+        let escrow = &mut ctx.accounts.bid_escrow;
+        // Only the current round has a leader; an earlier round's winning
+        // escrow was closed when it settled.
+        require!(
+            escrow.auction_start_slot != auction.start_slot || auction.highest_bidder != ctx.accounts.bidder.key(),
+            NamesError::WinnerCannotWithdraw
+        );
         require!(escrow.name_hash == name_hash, NamesError::InvalidHash);
         require_keys_eq!(escrow.bidder, ctx.accounts.bidder.key(), NamesError::Unauthorized);
         require!(escrow.active && !escrow.refunded, NamesError::EscrowInactive);
         require!(escrow.amount_lamports > 0, NamesError::NothingToWithdraw);
         let amount = escrow.amount_lamports;
         let vault_info = ctx.accounts.escrow_vault.to_account_info();
-        let bidder_info = ctx.accounts.bidder.to_account_info();
-        let vault_balance = **vault_info.lamports.borrow();
-        require!(vault_balance >= amount, NamesError::EscrowInsufficient);
-        **vault_info.try_borrow_mut_lamports()? = vault_balance
-            .checked_sub(amount)
-            .ok_or_else(|| error!(NamesError::MathOverflow))?;
-        **bidder_info.try_borrow_mut_lamports()? = (**bidder_info.lamports.borrow())
-            .checked_add(amount)
-            .ok_or_else(|| error!(NamesError::MathOverflow))?;
+        require!(vault_info.lamports() >= amount, NamesError::EscrowInsufficient);
+        let refunded = drain_vault(&vault_info, &ctx.accounts.bidder.to_account_info())?;
+
+        escrow.amount_lamports = 0;
+        escrow.active = false;
+        escrow.refunded = true;
+        emit!(BidRefunded {
+            name_hash,
+            bidder: ctx.accounts.bidder.key(),
+            lamports: refunded,
+        });
+        Ok(())
+    }
+
+    /// Refunds a bid escrowed before escrows were keyed by auction round, at
+    /// `[SEED_ESCROW | SEED_ESCROW_VAULT, name_hash, bidder]`; nothing else
+    /// reads those accounts any more. The vault goes to the bidder and the
+    /// escrow account is closed. The one exception is the winning bid of an
+    /// auction settled under the old seeds, which was never collected: it
+    /// goes to the treasury. A legacy `auction` must be migrated first.
+    pub fn withdraw_legacy_bid(ctx: Context<WithdrawLegacyBid>, name_hash: [u8; 32]) -> Result<()> {
+        let bidder = ctx.accounts.bidder.to_account_info();
+        let escrow = ctx.accounts.bid_escrow.to_account_info();
+        let vault = ctx.accounts.escrow_vault.to_account_info();
+
+        // Old layout: name_hash(32) bidder(32) amount_lamports(8) ...
+        let escrowed = if *escrow.owner == crate::ID {
+            let data = escrow.try_borrow_data()?;
+            require!(data.starts_with(BidEscrow::DISCRIMINATOR), NamesError::EscrowInactive);
+            data.get(72..80)
+                .and_then(|bytes| <[u8; 8]>::try_from(bytes).ok())
+                .map(u64::from_le_bytes)
+                .ok_or_else(|| error!(NamesError::EscrowInactive))?
+        } else {
+            0
+        };
+        let auction = ctx.accounts.auction.to_account_info();
+        if escrowed > 0 && !auction.data_is_empty() {
+            let auction = Auction::try_deserialize(&mut &auction.try_borrow_data()?[..])?;
+            require!(auction.name_hash == name_hash, NamesError::InvalidHash);
+            if auction.settled && auction.highest_bidder == bidder.key() && auction.highest_bid_lamports == escrowed {
+                let owed = escrowed.min(vault.lamports());
+                move_lamports(&vault, &ctx.accounts.treasury.to_account_info(), owed)?;
+            }
+        }
+
+        let refunded = drain_vault(&vault, &bidder)?;
+        let mut closed = 0;
+        if *escrow.owner == crate::ID {
+            closed = escrow.lamports();
+            move_lamports(&escrow, &bidder, closed)?;
+            escrow.assign(&system_program::ID);
+            escrow.resize(0)?;
+        }
+        require!(refunded + closed > 0, NamesError::NothingToWithdraw);
+        emit!(BidRefunded {
+            name_hash,
+            bidder: bidder.key(),
+            lamports: refunded,
+        });
+        Ok(())
+    }
+
+    /// Authority-only: closes an auction that drew no bids so the name can
+    /// be listed again with `create_auction`.
+    pub fn cancel_auction(ctx: Context<CancelAuction>, name_hash: [u8; 32]) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        require!(auction.name_hash == name_hash, NamesError::InvalidHash);
+        require!(!auction.settled, NamesError::AlreadySettled);
+        require!(
+            auction.highest_bid_lamports == 0 && auction.sealed_bids == 0,
+            NamesError::AuctionHasBids
+        );
+        emit!(AuctionCancelled { name_hash });
         Ok(())
     }
 
@@ -349,49 +901,254 @@ pub mod ddns_names {
         let pcfg = &ctx.accounts.premium_config;
         let auction = &mut ctx.accounts.auction;
         require!(auction.name_hash == name_hash, NamesError::InvalidHash);
+        require!(auction.mode == AUCTION_OPEN, NamesError::WrongAuctionMode);
         require!(!auction.settled, NamesError::AlreadySettled);
         require!(Clock::get()?.slot >= auction.end_slot, NamesError::AuctionNotEnded);
         require!(auction.highest_bid_lamports > 0, NamesError::NoWinningBid);
         require_keys_eq!(auction.highest_bidder, ctx.accounts.winner.key(), NamesError::Unauthorized);
 
         require!(ctx.accounts.treasury.key() == pcfg.treasury_vault, NamesError::InvalidTreasury);
-        let premium = &mut ctx.accounts.premium_name;
-        premium.name_hash = name_hash;
-        premium.owner = ctx.accounts.winner.key();
-        premium.nft_mint = Pubkey::default();
-        premium.purchase_lamports = auction.highest_bid_lamports;
-        premium.created_at = Clock::get()?.unix_timestamp;
-        premium.transferable = true;
-        premium.nft_bound = false;
-        premium.bump = ctx.bumps.premium_name;
+        let escrow = &mut ctx.accounts.winner_escrow;
+        require!(escrow.name_hash == name_hash, NamesError::InvalidHash);
+        require!(escrow.active && !escrow.refunded, NamesError::EscrowInactive);
+        require!(escrow.amount_lamports == auction.highest_bid_lamports, NamesError::EscrowInsufficient);
+
+        // Winning bid to the treasury; the vault's leftover rent back to the winner.
+        let vault_info = ctx.accounts.winner_escrow_vault.to_account_info();
+        let (to_treasury, to_winner) = split_winner_vault(vault_info.lamports(), auction.highest_bid_lamports)?;
+        move_lamports(&vault_info, &ctx.accounts.treasury.to_account_info(), to_treasury)?;
+        move_lamports(&vault_info, &ctx.accounts.winner.to_account_info(), to_winner)?;
+        escrow.amount_lamports = 0;
+        escrow.active = false;
+
+        ctx.accounts.premium_name.grant(
+            name_hash,
+            ctx.accounts.winner.key(),
+            auction.highest_bid_lamports,
+            pcfg.period_secs,
+            ctx.bumps.premium_name,
+        )?;
+
+        let policy = &mut ctx.accounts.parent_policy;
+        policy.parent_hash = name_hash;
+        policy.parent_owner = ctx.accounts.winner.key();
+        policy.transfers_enabled = false;
+        policy.bump = ctx.bumps.parent_policy;
+        policy.reset_sale();
+
+        upsert_primary_if_empty(
+            &mut ctx.accounts.primary,
+            ctx.accounts.winner.key(),
+            &auction.name(),
+            name_hash,
+            KIND_PREMIUM,
+            ctx.bumps.primary,
+        );
+
+        auction.settled = true;
+        emit!(AuctionSettled {
+            name_hash,
+            winner: ctx.accounts.winner.key(),
+            lamports: to_treasury,
+        });
+        Ok(())
+    }
+
+    /// Sealed mode, bid phase: locks `deposit_lamports` (at least the minimum
+    /// bid, and at least the bid it hides) behind
+    /// `commitment = sha256(name_hash || bidder || bid_lamports_le || salt)`.
+    pub fn commit_sealed_bid(
+        ctx: Context<CommitSealedBid>,
+        name_hash: [u8; 32],
+        commitment: [u8; 32],
+        deposit_lamports: u64,
+    ) -> Result<()> {
+        let pcfg = &ctx.accounts.premium_config;
+        let auction = &mut ctx.accounts.auction;
+        require!(pcfg.enabled && !auction.settled, NamesError::Disabled);
+        require!(auction.name_hash == name_hash, NamesError::InvalidHash);
+        require!(auction.mode == AUCTION_SEALED, NamesError::WrongAuctionMode);
+        require!(Clock::get()?.slot < auction.end_slot, NamesError::AuctionClosed);
+        require!(deposit_lamports >= auction.min_bid_lamports && deposit_lamports > 0, NamesError::BidTooLow);
+
+        anchor_lang::solana_program::program::invoke(
+            &system_instruction::transfer(&ctx.accounts.bidder.key(), &ctx.accounts.sealed_bid.key(), deposit_lamports),
+            &[
+                ctx.accounts.bidder.to_account_info(),
+                ctx.accounts.sealed_bid.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let bid = &mut ctx.accounts.sealed_bid;
+        bid.name_hash = name_hash;
+        bid.bidder = ctx.accounts.bidder.key();
+        bid.commitment = commitment;
+        bid.deposit_lamports = deposit_lamports;
+        bid.bid_lamports = 0;
+        bid.revealed = false;
+        bid.bump = ctx.bumps.sealed_bid;
+        bid.auction_start_slot = auction.start_slot;
+        auction.sealed_bids = auction
+            .sealed_bids
+            .checked_add(1)
+            .ok_or_else(|| error!(NamesError::MathOverflow))?;
+        Ok(())
+    }
+
+    /// Sealed mode, reveal phase: opens a committed bid. A bid above its
+    /// deposit or below the minimum cannot be revealed and is forfeited.
+    pub fn reveal_sealed_bid(
+        ctx: Context<RevealSealedBid>,
+        name_hash: [u8; 32],
+        bid_lamports: u64,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        let auction = &mut ctx.accounts.auction;
+        require!(auction.name_hash == name_hash, NamesError::InvalidHash);
+        require!(auction.mode == AUCTION_SEALED, NamesError::WrongAuctionMode);
+        let now_slot = Clock::get()?.slot;
+        require!(
+            now_slot >= auction.end_slot && now_slot < auction.reveal_end_slot,
+            NamesError::NotRevealPhase
+        );
+
+        let bid = &mut ctx.accounts.sealed_bid;
+        require!(!bid.revealed, NamesError::AlreadyRevealed);
+        require!(
+            sealed_bid_hash(name_hash, bid.bidder, bid_lamports, salt) == bid.commitment,
+            NamesError::CommitmentMismatch
+        );
+        require!(
+            bid_lamports >= auction.min_bid_lamports && bid_lamports <= bid.deposit_lamports,
+            NamesError::InvalidBid
+        );
+        bid.revealed = true;
+        bid.bid_lamports = bid_lamports;
+
+        let (leads, highest, second) =
+            rank_sealed_bid(auction.highest_bid_lamports, auction.second_bid_lamports, bid_lamports);
+        if leads {
+            auction.highest_bidder = bid.bidder;
+        }
+        auction.highest_bid_lamports = highest;
+        auction.second_bid_lamports = second;
+        Ok(())
+    }
+
+    /// Sealed mode: after the reveal phase the highest revealed bidder takes
+    /// the name at the second-highest revealed bid (or the minimum bid when
+    /// unopposed); the rest of their deposit and the bid account's rent are
+    /// returned.
+    pub fn settle_sealed_auction(ctx: Context<SettleSealedAuction>, name_hash: [u8; 32]) -> Result<()> {
+        let pcfg = &ctx.accounts.premium_config;
+        let auction = &mut ctx.accounts.auction;
+        require!(auction.name_hash == name_hash, NamesError::InvalidHash);
+        require!(auction.mode == AUCTION_SEALED, NamesError::WrongAuctionMode);
+        require!(!auction.settled, NamesError::AlreadySettled);
+        require!(Clock::get()?.slot >= auction.reveal_end_slot, NamesError::AuctionNotEnded);
+        require!(auction.highest_bid_lamports > 0, NamesError::NoWinningBid);
+        require_keys_eq!(auction.highest_bidder, ctx.accounts.winner.key(), NamesError::Unauthorized);
+
+        let price = vickrey_price(auction.second_bid_lamports, auction.min_bid_lamports);
+        move_lamports(
+            &ctx.accounts.winner_bid.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            price,
+        )?;
+
+        ctx.accounts.premium_name.grant(
+            name_hash,
+            ctx.accounts.winner.key(),
+            price,
+            pcfg.period_secs,
+            ctx.bumps.premium_name,
+        )?;
 
         let policy = &mut ctx.accounts.parent_policy;
         policy.parent_hash = name_hash;
         policy.parent_owner = ctx.accounts.winner.key();
         policy.transfers_enabled = false;
         policy.bump = ctx.bumps.parent_policy;
+        policy.reset_sale();
 
         upsert_primary_if_empty(
             &mut ctx.accounts.primary,
             ctx.accounts.winner.key(),
+            &auction.name(),
             name_hash,
             KIND_PREMIUM,
             ctx.bumps.primary,
         );
 
         auction.settled = true;
+        auction.sealed_bids = auction.sealed_bids.saturating_sub(1);
+        emit!(AuctionSettled {
+            name_hash,
+            winner: ctx.accounts.winner.key(),
+            lamports: price,
+        });
+        Ok(())
+    }
+
+    /// Permissionless cleanup of a sealed bid that did not win. A revealed
+    /// losing bid is refunded in full; a bid still unrevealed after the
+    /// reveal phase forfeits its deposit to the treasury (rent still goes
+    /// back to the bidder). Bids from an earlier round, whose reveal phase
+    /// is over, close the same way.
+    pub fn close_sealed_bid(ctx: Context<CloseSealedBid>, name_hash: [u8; 32]) -> Result<()> {
+        // No mode check: a bid left over from an earlier sealed round must
+        // stay closable after the name is re-listed.
+        let auction = &mut ctx.accounts.auction;
+        require!(auction.name_hash == name_hash, NamesError::InvalidHash);
+        let bid = &ctx.accounts.sealed_bid;
+        let current_round = bid.auction_start_slot == auction.start_slot;
+        if current_round {
+            require!(auction.highest_bidder != bid.bidder, NamesError::WinnerCannotWithdraw);
+        }
+
+        let lamports = if bid.revealed {
+            bid.deposit_lamports
+        } else {
+            require!(
+                !current_round || Clock::get()?.slot >= auction.reveal_end_slot,
+                NamesError::NotRevealPhase
+            );
+            move_lamports(
+                &bid.to_account_info(),
+                &ctx.accounts.treasury.to_account_info(),
+                bid.deposit_lamports,
+            )?;
+            0
+        };
+        if current_round {
+            auction.sealed_bids = auction.sealed_bids.saturating_sub(1);
+        }
+        emit!(BidRefunded {
+            name_hash,
+            bidder: bid.bidder,
+            lamports,
+        });
         Ok(())
     }
 
     pub fn transfer_premium(ctx: Context<TransferPremium>) -> Result<()> {
         let premium = &mut ctx.accounts.premium_name;
         require_keys_eq!(premium.owner, ctx.accounts.current_owner.key(), NamesError::Unauthorized);
+        require_not_expired(premium)?;
+        require!(!premium.listed, NamesError::NameListed);
         require!(!premium.nft_bound, NamesError::NftBoundTransferRequiresSync);
         premium.owner = ctx.accounts.new_owner.key();
+        clear_primary_if_points_to(
+            &ctx.accounts.current_owner_primary,
+            ctx.accounts.current_owner.key(),
+            premium.name_hash,
+        )?;
 
         if ctx.accounts.parent_policy.parent_owner == ctx.accounts.current_owner.key() {
             ctx.accounts.parent_policy.parent_owner = ctx.accounts.new_owner.key();
         }
+        ctx.accounts.parent_policy.reset_sale();
         Ok(())
     }
 
@@ -399,6 +1156,7 @@ pub mod ddns_names {
         let premium = &mut ctx.accounts.premium_name;
         require_keys_eq!(premium.owner, ctx.accounts.current_owner.key(), NamesError::Unauthorized);
         require!(premium.transferable, NamesError::NonTransferable);
+        require!(!premium.listed, NamesError::NameListed);
         require!(
             ctx.accounts.owner_nft_account.amount == 1,
             NamesError::InvalidNftTokenAccount
@@ -420,11 +1178,16 @@ pub mod ddns_names {
         Ok(())
     }
 
-    pub fn issue_premium_nft(ctx: Context<IssuePremiumNft>, name_hash: [u8; 32]) -> Result<()> {
+    /// Mints the 1/1 NFT for a premium name to its owner, with token metadata
+    /// named after the name and a master edition capping supply at one.
+    pub fn issue_premium_nft(ctx: Context<IssuePremiumNft>, name: String, name_hash: [u8; 32]) -> Result<()> {
+        let normalized = normalize_full_name(&name)?;
+        require!(hash_name(&normalized) == name_hash, NamesError::InvalidHash);
         let premium = &mut ctx.accounts.premium_name;
         require!(premium.name_hash == name_hash, NamesError::InvalidHash);
         require_keys_eq!(premium.owner, ctx.accounts.owner.key(), NamesError::Unauthorized);
         require!(premium.transferable, NamesError::NonTransferable);
+        require!(!premium.listed, NamesError::NameListed);
         require!(premium.nft_mint == Pubkey::default(), NamesError::NftAlreadyIssued);
 
         let signer_seeds: &[&[u8]] = &[SEED_NFT_AUTHORITY, &[ctx.bumps.nft_authority]];
@@ -440,6 +1203,28 @@ pub mod ddns_names {
             ),
             1,
         )?;
+        let data = name_nft_data(
+            &ctx.accounts.config,
+            &normalized,
+            ctx.accounts.nft_mint.key(),
+            premium.owner,
+            premium.expires_at,
+        )?;
+        create_name_nft_metadata(
+            NameNftMetadataAccounts {
+                metadata: ctx.accounts.nft_metadata.to_account_info(),
+                edition: ctx.accounts.nft_edition.to_account_info(),
+                mint: ctx.accounts.nft_mint.to_account_info(),
+                nft_authority: ctx.accounts.nft_authority.to_account_info(),
+                payer: ctx.accounts.owner.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+            },
+            data,
+            signer_seeds,
+        )?;
 
         premium.nft_mint = ctx.accounts.nft_mint.key();
         premium.nft_bound = true;
@@ -449,6 +1234,8 @@ pub mod ddns_names {
     pub fn sync_premium_owner_from_nft(ctx: Context<SyncPremiumOwnerFromNft>) -> Result<()> {
         let premium = &mut ctx.accounts.premium_name;
         require!(premium.nft_bound, NamesError::NftBindingRequired);
+        // The listing holds the NFT in escrow; the seller stays owner.
+        require!(!premium.listed, NamesError::NameListed);
         require_keys_eq!(premium.nft_mint, ctx.accounts.nft_mint.key(), NamesError::InvalidNftMint);
         require!(
             ctx.accounts.holder_nft_account.amount == 1,
@@ -457,6 +1244,10 @@ pub mod ddns_names {
         let previous_owner = premium.owner;
         let new_owner = ctx.accounts.holder_nft_account.owner;
         premium.owner = new_owner;
+        if previous_owner != new_owner {
+            clear_primary_if_points_to(&ctx.accounts.previous_owner_primary, previous_owner, premium.name_hash)?;
+            ctx.accounts.parent_policy.reset_sale();
+        }
 
         if ctx.accounts.parent_policy.parent_owner == Pubkey::default()
             || ctx.accounts.parent_policy.parent_owner == previous_owner
@@ -464,17 +1255,44 @@ pub mod ddns_names {
         {
             ctx.accounts.parent_policy.parent_owner = new_owner;
         }
+        require!(
+            ctx.accounts.nft_metadata.is_some() || !premium.has_issued_nft(),
+            NamesError::NftMetadataRequired
+        );
+        if let Some(metadata) = ctx.accounts.nft_metadata.as_ref() {
+            refresh_name_nft_metadata(
+                required(ctx.accounts.config.as_ref())?,
+                metadata,
+                required(ctx.accounts.nft_authority.as_ref())?,
+                required(ctx.accounts.token_metadata_program.as_ref())?,
+                premium.nft_mint,
+                new_owner,
+                premium.expires_at,
+            )?;
+        }
         Ok(())
     }
 
+    /// Mints a subdomain's 1/1 NFT into program custody, with token metadata
+    /// named after the full subdomain and a master edition.
     pub fn issue_subdomain_nft(
         ctx: Context<IssueSubdomainNft>,
+        parent: String,
+        label: String,
         parent_hash: [u8; 32],
         label_hash: [u8; 32],
     ) -> Result<()> {
+        let normalized_parent = normalize_full_name(&parent)?;
+        let normalized_label = normalize_label(&label)?;
         let sub = &mut ctx.accounts.sub_name;
+        require!(
+            chained_name_hash(&normalized_parent, sub.depth.saturating_sub(1))? == parent_hash,
+            NamesError::InvalidHash
+        );
+        require!(hash_label(&normalized_label) == label_hash, NamesError::InvalidHash);
         require!(sub.parent_hash == parent_hash && sub.label_hash == label_hash, NamesError::InvalidHash);
         require_keys_eq!(sub.owner, ctx.accounts.owner.key(), NamesError::Unauthorized);
+        require_sub_active(sub, ctx.remaining_accounts)?;
         require!(sub.nft_mint == Pubkey::default(), NamesError::NftAlreadyIssued);
 
         let signer_seeds: &[&[u8]] = &[SEED_NFT_AUTHORITY, &[ctx.bumps.nft_authority]];
@@ -490,6 +1308,28 @@ pub mod ddns_names {
             ),
             1,
         )?;
+        let data = name_nft_data(
+            &ctx.accounts.config,
+            &format!("{normalized_label}.{normalized_parent}"),
+            ctx.accounts.nft_mint.key(),
+            sub.owner,
+            0,
+        )?;
+        create_name_nft_metadata(
+            NameNftMetadataAccounts {
+                metadata: ctx.accounts.nft_metadata.to_account_info(),
+                edition: ctx.accounts.nft_edition.to_account_info(),
+                mint: ctx.accounts.nft_mint.to_account_info(),
+                nft_authority: ctx.accounts.nft_authority.to_account_info(),
+                payer: ctx.accounts.owner.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+            },
+            data,
+            signer_seeds,
+        )?;
 
         sub.nft_mint = ctx.accounts.nft_mint.key();
         sub.nft_custody_account = ctx.accounts.custody_nft_account.key();
@@ -514,6 +1354,7 @@ pub mod ddns_names {
         let premium = &ctx.accounts.premium_parent;
         require!(premium.name_hash == parent_hash, NamesError::InvalidParent);
         require_keys_eq!(premium.owner, ctx.accounts.parent_owner.key(), NamesError::Unauthorized);
+        require_not_expired(premium)?;
 
         let policy = &mut ctx.accounts.parent_policy;
         if policy.parent_hash == [0u8; 32] {
@@ -537,6 +1378,8 @@ pub mod ddns_names {
         sub.nft_mint = Pubkey::default();
         sub.nft_custody_account = Pubkey::default();
         sub.bump = ctx.bumps.sub_name;
+        sub.depth = 1;
+        sub.expires_at = 0;
 
         Ok(())
     }
@@ -552,6 +1395,7 @@ pub mod ddns_names {
         require!(computed_parent == parent_hash, NamesError::InvalidHash);
         require!(ctx.accounts.premium_parent.name_hash == parent_hash, NamesError::InvalidParent);
         require_keys_eq!(ctx.accounts.premium_parent.owner, ctx.accounts.parent_owner.key(), NamesError::Unauthorized);
+        require_not_expired(&ctx.accounts.premium_parent)?;
 
         let policy = &mut ctx.accounts.parent_policy;
         if policy.parent_hash == [0u8; 32] {
@@ -566,21 +1410,376 @@ pub mod ddns_names {
         Ok(())
     }
 
-    pub fn transfer_subdomain(
-        ctx: Context<TransferSubdomain>,
-        parent: String,
-        label: String,
+    /// Premium-parent-owner-only: sets the terms on which anyone may claim a
+    /// subdomain with `claim_subdomain_under_parent`.
+    pub fn set_subdomain_sale_terms(
+        ctx: Context<SetSubdomainSaleTerms>,
         parent_hash: [u8; 32],
-        label_hash: [u8; 32],
+        terms: SubdomainSaleTerms,
     ) -> Result<()> {
-        let normalized_parent = normalize_full_name(&parent)?;
-        let normalized_label = normalize_label(&label)?;
+        require!(terms.period_secs >= 0, NamesError::InvalidPremiumTerms);
+        let premium = &ctx.accounts.premium_parent;
+        require_keys_eq!(premium.owner, ctx.accounts.parent_owner.key(), NamesError::Unauthorized);
+        require_not_expired(premium)?;
+
+        let policy = &mut ctx.accounts.parent_policy;
+        if policy.parent_hash == [0u8; 32] {
+            policy.parent_hash = parent_hash;
+            policy.parent_owner = ctx.accounts.parent_owner.key();
+            policy.bump = ctx.bumps.parent_policy;
+        } else {
+            require_keys_eq!(policy.parent_owner, ctx.accounts.parent_owner.key(), NamesError::Unauthorized);
+        }
+        policy.sale = terms;
+        Ok(())
+    }
+
+    /// Premium-parent-owner-only: reserves a label or overrides its price.
+    pub fn set_sub_label_rule(
+        ctx: Context<SetSubLabelRule>,
+        parent_hash: [u8; 32],
+        label_hash: [u8; 32],
+        reserved: bool,
+        price: u64,
+    ) -> Result<()> {
+        let premium = &ctx.accounts.premium_parent;
+        require_keys_eq!(premium.owner, ctx.accounts.parent_owner.key(), NamesError::Unauthorized);
+        require_not_expired(premium)?;
+
+        let rule = &mut ctx.accounts.label_rule;
+        rule.parent_hash = parent_hash;
+        rule.label_hash = label_hash;
+        rule.reserved = reserved;
+        rule.price = price;
+        rule.bump = ctx.bumps.label_rule;
+        Ok(())
+    }
+
+    pub fn clear_sub_label_rule(ctx: Context<ClearSubLabelRule>, _parent_hash: [u8; 32], _label_hash: [u8; 32]) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.premium_parent.owner,
+            ctx.accounts.parent_owner.key(),
+            NamesError::Unauthorized
+        );
+        Ok(())
+    }
+
+    /// Permissionless: buys `label.parent` on the premium parent's sale terms.
+    /// `allowlist_proof` is the claimer's Merkle proof when the parent keeps
+    /// an allowlist. The price goes to the parent owner, in SOL or (passing
+    /// the token accounts) the terms' mint. The current holder renews by
+    /// claiming again; once expired, anyone may claim it.
+    pub fn claim_subdomain_under_parent(
+        ctx: Context<ClaimSubdomainUnderParent>,
+        parent: String,
+        label: String,
+        parent_hash: [u8; 32],
+        label_hash: [u8; 32],
+        allowlist_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let normalized_parent = normalize_full_name(&parent)?;
+        let normalized_label = normalize_label(&label)?;
         require!(hash_name(&normalized_parent) == parent_hash, NamesError::InvalidHash);
         require!(hash_label(&normalized_label) == label_hash, NamesError::InvalidHash);
 
+        consume_commitment(
+            &ctx.accounts.config,
+            ctx.accounts.name_commitment.as_deref(),
+            subdomain_name_hash(parent_hash, label_hash),
+        )?;
+
+        let premium = &ctx.accounts.premium_parent;
+        require_not_expired(premium)?;
+        // Subdomains sold while the parent is listed would change what its
+        // buyer gets.
+        require!(!premium.listed, NamesError::NameListed);
+        let claimer = ctx.accounts.claimer.key();
+        let policy = &ctx.accounts.parent_policy;
+        let terms = policy.sale.clone();
+        require!(terms.enabled, NamesError::Disabled);
+        if terms.allowlist_root != [0u8; 32] {
+            require!(
+                merkle_proof_valid(terms.allowlist_root, Sha256::digest(claimer.as_ref()).into(), &allowlist_proof),
+                NamesError::NotAllowlisted
+            );
+        }
+        let rule = sub_label_rule(&ctx.accounts.label_rule, parent_hash, label_hash)?;
+        if rule.as_ref().is_some_and(|r| r.reserved) {
+            return err!(NamesError::LabelReserved);
+        }
+        let price = match rule.as_ref().map(|r| r.price) {
+            Some(price) if price > 0 => price,
+            _ => terms.price,
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+        let sub = &ctx.accounts.sub_name;
+        let is_new = sub.parent_hash == [0u8; 32];
+        let previous_owner = sub.owner;
+        if is_new {
+            require!(
+                terms.max_sales == 0 || policy.sold_count < terms.max_sales,
+                NamesError::SubdomainLimitReached
+            );
+        } else {
+            require!(previous_owner == claimer || sub.is_expired(now), NamesError::SubdomainTaken);
+        }
+
+        if price > 0 {
+            if terms.price_mint == Pubkey::default() {
+                anchor_lang::solana_program::program::invoke(
+                    &system_instruction::transfer(&claimer, &premium.owner, price),
+                    &[
+                        ctx.accounts.claimer.to_account_info(),
+                        ctx.accounts.parent_wallet.to_account_info(),
+                        ctx.accounts.system_program.to_account_info(),
+                    ],
+                )?;
+            } else {
+                let payer_tokens = required(ctx.accounts.payer_token_account.as_ref())?;
+                let parent_tokens = required(ctx.accounts.parent_token_account.as_ref())?;
+                let mint = required(ctx.accounts.price_mint.as_ref())?;
+                let token_program = required(ctx.accounts.token_program.as_ref())?;
+                require_keys_eq!(mint.key(), terms.price_mint, NamesError::InvalidPaymentAccount);
+                require!(
+                    parent_tokens.owner == premium.owner && parent_tokens.mint == terms.price_mint,
+                    NamesError::InvalidPaymentAccount
+                );
+                token_interface::transfer_checked(
+                    CpiContext::new(
+                        token_program.to_account_info(),
+                        TransferChecked {
+                            from: payer_tokens.to_account_info(),
+                            mint: mint.to_account_info(),
+                            to: parent_tokens.to_account_info(),
+                            authority: ctx.accounts.claimer.to_account_info(),
+                        },
+                    ),
+                    price,
+                    mint.decimals,
+                )?;
+            }
+        }
+
+        let name_hash = subdomain_name_hash(parent_hash, label_hash);
+        if !is_new && previous_owner != claimer {
+            clear_primary_if_points_to(&ctx.accounts.previous_owner_primary, previous_owner, name_hash)?;
+        }
         let sub = &mut ctx.accounts.sub_name;
+        if is_new || previous_owner != claimer {
+            sub.parent_hash = parent_hash;
+            sub.label_hash = label_hash;
+            sub.owner = claimer;
+            sub.transfer_policy = TRANSFER_PARENT_CONTROLLED;
+            sub.parent_owner = premium.owner;
+            sub.transfers_enabled = policy.transfers_enabled;
+            sub.created_at = now;
+            sub.bump = ctx.bumps.sub_name;
+            sub.depth = 1;
+            sub.expires_at = 0;
+        }
+        if terms.period_secs > 0 {
+            sub.expires_at = sub
+                .expires_at
+                .max(now)
+                .checked_add(terms.period_secs)
+                .ok_or_else(|| error!(NamesError::MathOverflow))?;
+        }
+        if is_new {
+            let policy = &mut ctx.accounts.parent_policy;
+            policy.sold_count = policy
+                .sold_count
+                .checked_add(1)
+                .ok_or_else(|| error!(NamesError::MathOverflow))?;
+        }
+
+        upsert_primary_if_empty(
+            &mut ctx.accounts.primary,
+            claimer,
+            &format!("{normalized_label}.{normalized_parent}"),
+            name_hash,
+            KIND_SUBDOMAIN,
+            ctx.bumps.primary,
+        );
+        emit!(SubdomainSold {
+            parent_hash,
+            name_hash,
+            owner: claimer,
+            price,
+            price_mint: terms.price_mint,
+            expires_at: sub.expires_at,
+        });
+        Ok(())
+    }
+
+    /// The owner of any subdomain delegates `label.parent` under it, paying
+    /// rent. The child's hash chains from the parent's
+    /// (`sha256(parent_hash || sha256(label))`) and it inherits the parent's
+    /// transfer rules: children of a non-transferable subdomain are
+    /// non-transferable, all others are parent-controlled by whoever owns the
+    /// parent. The child has no expiry of its own: it is active while every
+    /// ancestor is. `remaining_accounts` are the parent's ancestor `SubName`s.
+    pub fn claim_nested_subdomain(
+        ctx: Context<ClaimNestedSubdomain>,
+        parent: String,
+        label: String,
+        parent_hash: [u8; 32],
+        label_hash: [u8; 32],
+        initial_owner: Pubkey,
+    ) -> Result<()> {
+        let normalized_parent = normalize_full_name(&parent)?;
+        let normalized_label = normalize_label(&label)?;
+        require!(
+            normalized_label.len() + 1 + normalized_parent.len() <= MAX_NAME_BYTES,
+            NamesError::InvalidName
+        );
+        let parent_sub = &ctx.accounts.parent_sub;
+        let parent_owner = ctx.accounts.parent_owner.key();
+        require!(
+            subdomain_name_hash(parent_sub.parent_hash, parent_sub.label_hash) == parent_hash,
+            NamesError::InvalidParent
+        );
+        require!(
+            chained_name_hash(&normalized_parent, parent_sub.depth)? == parent_hash,
+            NamesError::InvalidHash
+        );
+        require!(hash_label(&normalized_label) == label_hash, NamesError::InvalidHash);
+        require_keys_eq!(parent_sub.owner, parent_owner, NamesError::Unauthorized);
+        require_sub_active(parent_sub, ctx.remaining_accounts)?;
+
+        // The parent `SubName` is the source of truth for who may delegate, so
+        // the policy follows it across transfers.
+        let policy = &mut ctx.accounts.parent_policy;
+        if policy.parent_hash == [0u8; 32] {
+            policy.parent_hash = parent_hash;
+            policy.transfers_enabled = false;
+            policy.bump = ctx.bumps.parent_policy;
+        }
+        policy.parent_owner = parent_owner;
+
+        let sub = &mut ctx.accounts.sub_name;
+        sub.parent_hash = parent_hash;
+        sub.label_hash = label_hash;
+        sub.owner = initial_owner;
+        sub.transfer_policy = if parent_sub.transfer_policy == TRANSFER_NON_TRANSFERABLE {
+            TRANSFER_NON_TRANSFERABLE
+        } else {
+            TRANSFER_PARENT_CONTROLLED
+        };
+        sub.parent_owner = parent_owner;
+        sub.transfers_enabled = policy.transfers_enabled;
+        sub.created_at = Clock::get()?.unix_timestamp;
+        sub.nft_mint = Pubkey::default();
+        sub.nft_custody_account = Pubkey::default();
+        sub.bump = ctx.bumps.sub_name;
+        sub.depth = parent_sub
+            .depth
+            .checked_add(1)
+            .ok_or_else(|| error!(NamesError::MathOverflow))?;
+        sub.expires_at = 0;
+        Ok(())
+    }
+
+    /// Subdomain-owner-only: toggles free transfers for the subdomain's
+    /// children. A non-transferable subdomain cannot open transfers below it.
+    pub fn set_nested_subdomain_policy(
+        ctx: Context<SetNestedSubdomainPolicy>,
+        parent: String,
+        parent_hash: [u8; 32],
+        transfers_enabled: bool,
+    ) -> Result<()> {
+        let normalized_parent = normalize_full_name(&parent)?;
+        let parent_sub = &ctx.accounts.parent_sub;
+        require!(
+            subdomain_name_hash(parent_sub.parent_hash, parent_sub.label_hash) == parent_hash,
+            NamesError::InvalidParent
+        );
+        require!(
+            chained_name_hash(&normalized_parent, parent_sub.depth)? == parent_hash,
+            NamesError::InvalidHash
+        );
+        require_keys_eq!(parent_sub.owner, ctx.accounts.parent_owner.key(), NamesError::Unauthorized);
+        require!(
+            !transfers_enabled || parent_sub.transfer_policy != TRANSFER_NON_TRANSFERABLE,
+            NamesError::NonTransferable
+        );
+
+        let policy = &mut ctx.accounts.parent_policy;
+        if policy.parent_hash == [0u8; 32] {
+            policy.parent_hash = parent_hash;
+            policy.bump = ctx.bumps.parent_policy;
+        }
+        policy.parent_owner = ctx.accounts.parent_owner.key();
+        policy.transfers_enabled = transfers_enabled;
+        Ok(())
+    }
+
+    /// Closes a descendant `SubName` on behalf of any ancestor's owner: a
+    /// premium name (not the config's parent zone) or a subdomain.
+    /// `intermediate_label_hashes` are the labels between the ancestor and the
+    /// revoked name's parent, top-down (empty for a direct child). Rent goes
+    /// to the ancestor's owner and the holder's primary name is cleared if it
+    /// pointed here. Descendants of the revoked name stay until revoked too.
+    pub fn revoke_subdomain(
+        ctx: Context<RevokeSubdomain>,
+        ancestor_hash: [u8; 32],
+        intermediate_label_hashes: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        match (ctx.accounts.ancestor_premium.as_ref(), ctx.accounts.ancestor_sub.as_ref()) {
+            (Some(premium), None) => {
+                require!(premium.name_hash == ancestor_hash, NamesError::InvalidParent);
+                require!(ancestor_hash != ctx.accounts.config.parent_zone_hash, NamesError::InvalidParent);
+                require_keys_eq!(premium.owner, authority, NamesError::Unauthorized);
+                require_not_expired(premium)?;
+            }
+            (None, Some(sub)) => {
+                require!(
+                    subdomain_name_hash(sub.parent_hash, sub.label_hash) == ancestor_hash,
+                    NamesError::InvalidParent
+                );
+                require_keys_eq!(sub.owner, authority, NamesError::Unauthorized);
+            }
+            _ => return err!(NamesError::MissingRequiredAccount),
+        }
+
+        let sub = &ctx.accounts.sub_name;
+        let chained = intermediate_label_hashes
+            .iter()
+            .fold(ancestor_hash, |hash, label_hash| subdomain_name_hash(hash, *label_hash));
+        require!(chained == sub.parent_hash, NamesError::InvalidParent);
+
+        let name_hash = subdomain_name_hash(sub.parent_hash, sub.label_hash);
+        clear_primary_if_points_to(&ctx.accounts.owner_primary, sub.owner, name_hash)?;
+        emit!(SubdomainRevoked {
+            name_hash,
+            owner: sub.owner,
+            revoked_by: authority,
+        });
+        Ok(())
+    }
+
+    /// Parent-controlled transfers are approved by the parent's current owner.
+    /// A nested name passes its ancestor `SubName`s, parent first, as
+    /// `remaining_accounts`.
+    pub fn transfer_subdomain(
+        ctx: Context<TransferSubdomain>,
+        parent: String,
+        label: String,
+        parent_hash: [u8; 32],
+        label_hash: [u8; 32],
+    ) -> Result<()> {
+        let normalized_parent = normalize_full_name(&parent)?;
+        let normalized_label = normalize_label(&label)?;
+        let sub = &mut ctx.accounts.sub_name;
+        require!(
+            chained_name_hash(&normalized_parent, sub.depth.saturating_sub(1))? == parent_hash,
+            NamesError::InvalidHash
+        );
+        require!(hash_label(&normalized_label) == label_hash, NamesError::InvalidHash);
         require!(sub.parent_hash == parent_hash && sub.label_hash == label_hash, NamesError::InvalidHash);
         require_keys_eq!(sub.owner, ctx.accounts.current_owner.key(), NamesError::Unauthorized);
+        let live_parent = require_sub_active(sub, ctx.remaining_accounts)?;
 
         match sub.transfer_policy {
             TRANSFER_NON_TRANSFERABLE => return err!(NamesError::NonTransferable),
@@ -597,48 +1796,327 @@ pub mod ddns_names {
                         .parent_owner
                         .as_ref()
                         .ok_or_else(|| error!(NamesError::ParentOwnerRequired))?;
-                    require_keys_eq!(sub.parent_owner, parent_owner.key(), NamesError::ParentOwnerRequired);
+                    let approver = live_parent.as_ref().map(|p| p.owner).unwrap_or(sub.parent_owner);
+                    require_keys_eq!(approver, parent_owner.key(), NamesError::ParentOwnerRequired);
                 }
             }
             _ => return err!(NamesError::InvalidTransferPolicy),
         }
 
         sub.owner = ctx.accounts.new_owner.key();
+        clear_primary_if_points_to(
+            &ctx.accounts.current_owner_primary,
+            ctx.accounts.current_owner.key(),
+            subdomain_name_hash(parent_hash, label_hash),
+        )?;
+        require!(
+            ctx.accounts.nft_metadata.is_some() || !sub.has_issued_nft(),
+            NamesError::NftMetadataRequired
+        );
+        if let Some(metadata) = ctx.accounts.nft_metadata.as_ref() {
+            refresh_name_nft_metadata(
+                required(ctx.accounts.config.as_ref())?,
+                metadata,
+                required(ctx.accounts.nft_authority.as_ref())?,
+                required(ctx.accounts.token_metadata_program.as_ref())?,
+                sub.nft_mint,
+                sub.owner,
+                0,
+            )?;
+        }
         Ok(())
     }
 
-    pub fn set_primary_name(ctx: Context<SetPrimaryName>, name_hash: [u8; 32], kind: u8) -> Result<()> {
-        match kind {
-            KIND_PREMIUM => {
-                let premium = ctx
-                    .accounts
-                    .premium_name
-                    .as_ref()
-                    .ok_or_else(|| error!(NamesError::MissingRequiredAccount))?;
-                require!(premium.name_hash == name_hash, NamesError::InvalidHash);
-                require_keys_eq!(premium.owner, ctx.accounts.owner.key(), NamesError::Unauthorized);
-            }
-            KIND_SUBDOMAIN => {
-                let sub = ctx
-                    .accounts
-                    .sub_name
-                    .as_ref()
-                    .ok_or_else(|| error!(NamesError::MissingRequiredAccount))?;
-                require_keys_eq!(sub.owner, ctx.accounts.owner.key(), NamesError::Unauthorized);
-                let computed = subdomain_name_hash(sub.parent_hash, sub.label_hash);
-                require!(computed == name_hash, NamesError::InvalidHash);
+    /// Lists a premium name for sale. The seller stays `PremiumName.owner`
+    /// and the name is locked (`listed`) until the listing closes; a bound NFT
+    /// moves into `escrow_nft_account`, a token account owned by the listing. The price
+    /// falls linearly from `start_price_lamports` to `end_price_lamports` over
+    /// `duration_secs` (a fixed price when they are equal or the duration is 0).
+    pub fn list_premium(
+        ctx: Context<ListPremium>,
+        name_hash: [u8; 32],
+        start_price_lamports: u64,
+        end_price_lamports: u64,
+        duration_secs: i64,
+    ) -> Result<()> {
+        require!(
+            start_price_lamports > 0 && end_price_lamports <= start_price_lamports && duration_secs >= 0,
+            NamesError::InvalidListing
+        );
+        let seller = ctx.accounts.seller.key();
+        let listing_key = ctx.accounts.listing.key();
+        let premium = &mut ctx.accounts.premium_name;
+        require_keys_eq!(premium.owner, seller, NamesError::Unauthorized);
+        require_not_expired(premium)?;
+        require!(premium.transferable, NamesError::NonTransferable);
+        require!(!premium.listed, NamesError::NameListed);
+
+        if premium.nft_bound {
+            let mint = required(ctx.accounts.nft_mint.as_ref())?;
+            let seller_nft = required(ctx.accounts.seller_nft_account.as_ref())?;
+            let escrow_nft = required(ctx.accounts.escrow_nft_account.as_ref())?;
+            let token_program = required(ctx.accounts.token_program.as_ref())?;
+            require_keys_eq!(mint.key(), premium.nft_mint, NamesError::InvalidNftTokenAccount);
+            require!(
+                seller_nft.owner == seller && seller_nft.mint == premium.nft_mint && seller_nft.amount == 1,
+                NamesError::InvalidNftTokenAccount
+            );
+            require!(
+                escrow_nft.owner == listing_key && escrow_nft.mint == premium.nft_mint,
+                NamesError::InvalidNftTokenAccount
+            );
+            transfer_name_nft(
+                token_program,
+                mint,
+                seller_nft.to_account_info(),
+                escrow_nft.to_account_info(),
+                ctx.accounts.seller.to_account_info(),
+                &[],
+            )?;
+        }
+        premium.listed = true;
+
+        let now = Clock::get()?.unix_timestamp;
+        let listing = &mut ctx.accounts.listing;
+        listing.name_hash = name_hash;
+        listing.seller = seller;
+        listing.start_price_lamports = start_price_lamports;
+        listing.end_price_lamports = end_price_lamports;
+        listing.start_ts = now;
+        listing.end_ts = now
+            .checked_add(duration_secs)
+            .ok_or_else(|| error!(NamesError::MathOverflow))?;
+        listing.nft_escrowed = premium.nft_bound;
+        listing.bump = ctx.bumps.listing;
+
+        emit!(NameListed {
+            name_hash,
+            seller,
+            start_price_lamports,
+            end_price_lamports,
+            end_ts: listing.end_ts,
+        });
+        Ok(())
+    }
+
+    /// Seller-only: ends a listing and returns the name (and NFT) to the seller.
+    pub fn delist_premium(ctx: Context<DelistPremium>, name_hash: [u8; 32]) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        let seller = ctx.accounts.seller.key();
+        if listing.nft_escrowed {
+            release_escrowed_nft(
+                listing,
+                required(ctx.accounts.nft_mint.as_ref())?,
+                required(ctx.accounts.escrow_nft_account.as_ref())?,
+                required(ctx.accounts.seller_nft_account.as_ref())?,
+                &ctx.accounts.seller.to_account_info(),
+                required(ctx.accounts.token_program.as_ref())?,
+            )?;
+        }
+        ctx.accounts.premium_name.listed = false;
+        emit!(ListingCancelled { name_hash, seller });
+        Ok(())
+    }
+
+    /// Buys a listed name at the current listing price (at most
+    /// `max_price_lamports`). The seller receives the price minus the market
+    /// fee, the buyer becomes owner (and NFT holder), the seller's primary
+    /// name is cleared if it pointed here, and the buyer's is set if empty.
+    pub fn buy_listed_premium(
+        ctx: Context<BuyListedPremium>,
+        name: String,
+        name_hash: [u8; 32],
+        max_price_lamports: u64,
+    ) -> Result<()> {
+        let normalized = normalize_full_name(&name)?;
+        require!(hash_name(&normalized) == name_hash, NamesError::InvalidHash);
+        let buyer = ctx.accounts.buyer.key();
+        let listing = &ctx.accounts.listing;
+        require_keys_neq!(buyer, listing.seller, NamesError::InvalidListing);
+        require_not_expired(&ctx.accounts.premium_name)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let price = listing_price(
+            listing.start_price_lamports,
+            listing.end_price_lamports,
+            listing.start_ts,
+            listing.end_ts,
+            now,
+        );
+        require!(price <= max_price_lamports, NamesError::PriceAboveMax);
+        let fee = market_fee(price, ctx.accounts.config.market_fee_bps);
+        for (to, lamports) in [
+            (ctx.accounts.treasury.to_account_info(), fee),
+            (ctx.accounts.seller.to_account_info(), price - fee),
+        ] {
+            if lamports > 0 {
+                anchor_lang::solana_program::program::invoke(
+                    &system_instruction::transfer(&buyer, to.key, lamports),
+                    &[
+                        ctx.accounts.buyer.to_account_info(),
+                        to,
+                        ctx.accounts.system_program.to_account_info(),
+                    ],
+                )?;
             }
-            _ => return err!(NamesError::InvalidKind),
         }
 
+        if listing.nft_escrowed {
+            let buyer_nft = required(ctx.accounts.buyer_nft_account.as_ref())?;
+            require_keys_eq!(buyer_nft.owner, buyer, NamesError::InvalidNftTokenAccount);
+            release_escrowed_nft(
+                listing,
+                required(ctx.accounts.nft_mint.as_ref())?,
+                required(ctx.accounts.escrow_nft_account.as_ref())?,
+                buyer_nft,
+                &ctx.accounts.seller.to_account_info(),
+                required(ctx.accounts.token_program.as_ref())?,
+            )?;
+        }
+
+        ctx.accounts.premium_name.owner = buyer;
+        ctx.accounts.premium_name.listed = false;
+        if ctx.accounts.parent_policy.parent_owner == listing.seller {
+            ctx.accounts.parent_policy.parent_owner = buyer;
+        }
+        ctx.accounts.parent_policy.reset_sale();
+        require!(
+            ctx.accounts.nft_metadata.is_some() || !ctx.accounts.premium_name.has_issued_nft(),
+            NamesError::NftMetadataRequired
+        );
+        if let Some(metadata) = ctx.accounts.nft_metadata.as_ref() {
+            refresh_name_nft_metadata(
+                &ctx.accounts.config,
+                metadata,
+                required(ctx.accounts.nft_authority.as_ref())?,
+                required(ctx.accounts.token_metadata_program.as_ref())?,
+                ctx.accounts.premium_name.nft_mint,
+                buyer,
+                ctx.accounts.premium_name.expires_at,
+            )?;
+        }
+        clear_primary_if_points_to(&ctx.accounts.seller_primary, listing.seller, name_hash)?;
+        upsert_primary_if_empty(
+            &mut ctx.accounts.buyer_primary,
+            buyer,
+            &normalized,
+            name_hash,
+            KIND_PREMIUM,
+            ctx.bumps.buyer_primary,
+        );
+
+        emit!(NameSold {
+            name_hash,
+            seller: listing.seller,
+            buyer,
+            price_lamports: price,
+            fee_lamports: fee,
+        });
+        Ok(())
+    }
+
+    /// Points the owner's reverse record at a name they own, storing its
+    /// normalized bytes so wallets can display it without a lookup.
+    pub fn set_primary_name(ctx: Context<SetPrimaryName>, name: String, name_hash: [u8; 32], kind: u8) -> Result<()> {
+        let normalized = normalize_full_name(&name)?;
+        let depth = match kind {
+            KIND_SUBDOMAIN => required(ctx.accounts.sub_name.as_ref())?.depth,
+            _ => 0,
+        };
+        require!(name_hash_for(kind, &normalized, depth)? == name_hash, NamesError::InvalidHash);
+        require_name_owner(
+            kind,
+            name_hash,
+            ctx.accounts.premium_name.as_ref(),
+            ctx.accounts.sub_name.as_ref(),
+            ctx.remaining_accounts,
+            ctx.accounts.owner.key(),
+        )?;
+
         let primary = &mut ctx.accounts.primary;
-        primary.owner = ctx.accounts.owner.key();
-        primary.name_hash = name_hash;
-        primary.kind = kind;
-        primary.is_set = true;
+        primary.assign(ctx.accounts.owner.key(), &normalized, name_hash, kind, depth);
         if primary.bump == 0 {
             primary.bump = ctx.bumps.primary;
         }
+        emit!(PrimaryNameSet {
+            owner: primary.owner,
+            name_hash,
+            kind,
+        });
+        Ok(())
+    }
+
+    /// Publishes the owner's record set of type `rrtype` for a premium name or
+    /// subdomain. `data` is a canonical `ddns_rrset` encoding; no admin
+    /// co-signer is needed.
+    pub fn set_records(
+        ctx: Context<SetRecords>,
+        name_hash: [u8; 32],
+        kind: u8,
+        rrtype: u16,
+        ttl_s: u32,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        require_name_owner(
+            kind,
+            name_hash,
+            ctx.accounts.premium_name.as_ref(),
+            ctx.accounts.sub_name.as_ref(),
+            ctx.remaining_accounts,
+            owner,
+        )?;
+        require!(
+            (RECORDS_TTL_MIN_S..=RECORDS_TTL_MAX_S).contains(&ttl_s),
+            NamesError::InvalidRecords
+        );
+        let decoded = ddns_rrset::decode_rrset(&data).map_err(|_| error!(NamesError::InvalidRecords))?;
+        require!(decoded.rrtype == rrtype, NamesError::InvalidRecords);
+
+        let records = &mut ctx.accounts.name_records;
+        if records.version == 0 {
+            records.name_hash = name_hash;
+            records.kind = kind;
+            records.rrtype = rrtype;
+            records.bump = ctx.bumps.name_records;
+        }
+        records.owner = owner;
+        records.version = records.version.checked_add(1).ok_or(NamesError::MathOverflow)?;
+        records.ttl_s = ttl_s;
+        records.rrset_hash = ddns_rrset::rrset_hash(&data);
+        records.data = data.clone();
+        records.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(RecordsSet {
+            name_hash,
+            kind,
+            rrtype,
+            owner,
+            version: records.version,
+            ttl_s,
+            rrset_hash: records.rrset_hash,
+            data,
+        });
+        Ok(())
+    }
+
+    /// Deletes an owner record set and returns its rent to the owner.
+    pub fn clear_records(ctx: Context<ClearRecords>, name_hash: [u8; 32], kind: u8, rrtype: u16) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        require_name_owner(
+            kind,
+            name_hash,
+            ctx.accounts.premium_name.as_ref(),
+            ctx.accounts.sub_name.as_ref(),
+            ctx.remaining_accounts,
+            owner,
+        )?;
+        emit!(RecordsCleared {
+            name_hash,
+            kind,
+            rrtype,
+            owner,
+        });
         Ok(())
     }
 }
@@ -659,6 +2137,214 @@ pub struct InitNamesConfig<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct SetNftMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = authority @ NamesError::Unauthorized
+    )]
+    pub config: Account<'info, NamesConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMarketFee<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = authority @ NamesError::Unauthorized
+    )]
+    pub config: Account<'info, NamesConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct ListPremium<'info> {
+    #[account(mut, seeds = [SEED_PREMIUM, name_hash.as_ref()], bump = premium_name.bump)]
+    pub premium_name: Account<'info, PremiumName>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + Listing::SIZE,
+        seeds = [SEED_LISTING, name_hash.as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // NFT-bound names only.
+    pub nft_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub seller_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub escrow_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct DelistPremium<'info> {
+    #[account(mut, seeds = [SEED_PREMIUM, name_hash.as_ref()], bump = premium_name.bump)]
+    pub premium_name: Account<'info, PremiumName>,
+    #[account(
+        mut,
+        close = seller,
+        seeds = [SEED_LISTING, name_hash.as_ref()],
+        bump = listing.bump,
+        has_one = seller @ NamesError::Unauthorized,
+        constraint = premium_name.listed && premium_name.owner == listing.seller @ NamesError::InvalidListing
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    // NFT-bound names only.
+    pub nft_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub escrow_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub seller_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, name_hash: [u8; 32])]
+pub struct BuyListedPremium<'info> {
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, NamesConfig>,
+    #[account(mut, seeds = [SEED_PREMIUM, name_hash.as_ref()], bump = premium_name.bump)]
+    pub premium_name: Account<'info, PremiumName>,
+    #[account(mut, seeds = [SEED_POLICY, name_hash.as_ref()], bump = parent_policy.bump)]
+    pub parent_policy: Account<'info, ParentPolicy>,
+    #[account(
+        mut,
+        close = seller,
+        seeds = [SEED_LISTING, name_hash.as_ref()],
+        bump = listing.bump,
+        has_one = seller @ NamesError::Unauthorized,
+        constraint = premium_name.listed && premium_name.owner == listing.seller @ NamesError::InvalidListing
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(mut)]
+    /// CHECK: sale proceeds destination, bound to the listing by `has_one`.
+    pub seller: UncheckedAccount<'info>,
+    #[account(mut, seeds = [SEED_PRIMARY, seller.key().as_ref()], bump)]
+    /// CHECK: seller's `PrimaryName` when it exists; cleared if it points at this name.
+    pub seller_primary: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + PrimaryName::SIZE,
+        seeds = [SEED_PRIMARY, buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_primary: Account<'info, PrimaryName>,
+    #[account(mut, address = config.treasury)]
+    /// CHECK: validated by address constraint.
+    pub treasury: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // NFT-bound names only.
+    pub nft_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub escrow_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub buyer_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    #[account(mut)]
+    pub nft_metadata: Option<Account<'info, MetadataAccount>>,
+    /// CHECK: metadata update authority PDA, checked when refreshing.
+    pub nft_authority: Option<UncheckedAccount<'info>>,
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+}
+
+#[derive(Accounts)]
+pub struct SetCommitWindow<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_CONFIG],
+        bump = config.bump,
+        has_one = authority @ NamesError::Unauthorized
+    )]
+    pub config: Account<'info, NamesConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: `NamesConfig` PDA, read raw so an old layout still decodes.
+    #[account(seeds = [SEED_CONFIG], bump)]
+    pub config: UncheckedAccount<'info>,
+    /// Required when `target` is a `PremiumName`.
+    #[account(seeds = [SEED_PREMIUM_CONFIG], bump = premium_config.bump)]
+    pub premium_config: Option<Account<'info, PremiumConfig>>,
+    /// CHECK: any program account; its type is taken from the discriminator.
+    #[account(mut)]
+    pub target: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(commitment: [u8; 32])]
+pub struct CommitName<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + NameCommitment::SIZE,
+        seeds = [SEED_COMMIT, commitment.as_ref()],
+        bump
+    )]
+    pub name_commitment: Account<'info, NameCommitment>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(commitment: [u8; 32])]
+pub struct RevealName<'info> {
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, NamesConfig>,
+
+    #[account(
+        mut,
+        seeds = [SEED_COMMIT, commitment.as_ref()],
+        bump = name_commitment.bump,
+        has_one = owner @ NamesError::Unauthorized
+    )]
+    pub name_commitment: Account<'info, NameCommitment>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(commitment: [u8; 32])]
+pub struct CancelCommitment<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [SEED_COMMIT, commitment.as_ref()],
+        bump = name_commitment.bump,
+        has_one = owner @ NamesError::Unauthorized
+    )]
+    pub name_commitment: Account<'info, NameCommitment>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
 #[derive(Accounts)]
 #[instruction(parent: String, label: String, parent_hash: [u8; 32], label_hash: [u8; 32])]
 pub struct ClaimSubdomain<'info> {
@@ -687,6 +2373,14 @@ pub struct ClaimSubdomain<'info> {
     )]
     pub primary: Account<'info, PrimaryName>,
 
+    // Revealed commitment, required while commit-reveal is enabled.
+    #[account(
+        mut,
+        close = owner,
+        constraint = name_commitment.owner == owner.key() @ NamesError::Unauthorized
+    )]
+    pub name_commitment: Option<Account<'info, NameCommitment>>,
+
     #[account(mut)]
     pub owner: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -732,9 +2426,29 @@ pub struct PurchasePremium<'info> {
     )]
     pub primary: Account<'info, PrimaryName>,
 
+    #[account(seeds = [SEED_NAME_PRICE, name_hash.as_ref()], bump)]
+    /// CHECK: `NamePrice` listing when initialized; empty for unlisted names.
+    pub name_price: UncheckedAccount<'info>,
+
+    // Revealed commitment, required while commit-reveal is enabled.
+    #[account(
+        mut,
+        close = owner,
+        constraint = name_commitment.owner == owner.key() @ NamesError::Unauthorized
+    )]
+    pub name_commitment: Option<Account<'info, NameCommitment>>,
+
     #[account(mut)]
     pub owner: Signer<'info>,
     pub system_program: Program<'info, System>,
+
+    // Pass all four to pay in the toll mint instead of SOL.
+    #[account(mut, token::authority = owner)]
+    pub payer_toll_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub toll_treasury: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub toll_mint: Option<InterfaceAccount<'info, Mint>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
 #[derive(Accounts)]
@@ -755,6 +2469,10 @@ pub struct TransferPremium<'info> {
 
     /// CHECK: new owner pubkey only.
     pub new_owner: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [SEED_PRIMARY, current_owner.key().as_ref()], bump)]
+    /// CHECK: current owner's `PrimaryName` when it exists; cleared if it points at this name.
+    pub current_owner_primary: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
@@ -804,11 +2522,27 @@ pub struct SyncPremiumOwnerFromNft<'info> {
     pub holder_nft_account: InterfaceAccount<'info, TokenAccount>,
 
     pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(mut, seeds = [SEED_PRIMARY, premium_name.owner.as_ref()], bump)]
+    /// CHECK: previous owner's `PrimaryName` when it exists; cleared if it points at this name.
+    pub previous_owner_primary: UncheckedAccount<'info>,
+
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, NamesConfig>>,
+    // Program-issued NFTs only: refreshes the token metadata URI.
+    #[account(mut)]
+    pub nft_metadata: Option<Account<'info, MetadataAccount>>,
+    /// CHECK: metadata update authority PDA, checked when refreshing.
+    pub nft_authority: Option<UncheckedAccount<'info>>,
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
 }
 
 #[derive(Accounts)]
-#[instruction(name_hash: [u8; 32])]
+#[instruction(name: String, name_hash: [u8; 32])]
 pub struct IssuePremiumNft<'info> {
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, NamesConfig>,
+
     #[account(
         mut,
         seeds = [SEED_PREMIUM, name_hash.as_ref()],
@@ -820,7 +2554,7 @@ pub struct IssuePremiumNft<'info> {
     #[account(
         init,
         payer = owner,
-        seeds = [SEED_PREMIUM_NFT_MINT, name_hash.as_ref()],
+        seeds = [SEED_PREMIUM_NFT_MINT, name_hash.as_ref(), premium_name.created_at.to_le_bytes().as_ref()],
         bump,
         mint::decimals = 0,
         mint::authority = nft_authority,
@@ -828,6 +2562,24 @@ pub struct IssuePremiumNft<'info> {
     )]
     pub nft_mint: Account<'info, SplMint>,
 
+    /// CHECK: created by the token metadata program.
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub nft_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: master edition, created by the token metadata program.
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), nft_mint.key().as_ref(), b"edition"],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub nft_edition: UncheckedAccount<'info>,
+
     #[account(
         init_if_needed,
         payer = owner,
@@ -846,11 +2598,16 @@ pub struct IssuePremiumNft<'info> {
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(parent_hash: [u8; 32], label_hash: [u8; 32])]
+#[instruction(parent: String, label: String, parent_hash: [u8; 32], label_hash: [u8; 32])]
 pub struct IssueSubdomainNft<'info> {
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, NamesConfig>,
+
     #[account(
         mut,
         seeds = [SEED_SUB, parent_hash.as_ref(), label_hash.as_ref()],
@@ -861,13 +2618,31 @@ pub struct IssueSubdomainNft<'info> {
     #[account(
         init,
         payer = owner,
-        seeds = [SEED_SUB_NFT_MINT, parent_hash.as_ref(), label_hash.as_ref()],
+        seeds = [SEED_SUB_NFT_MINT, parent_hash.as_ref(), label_hash.as_ref(), sub_name.created_at.to_le_bytes().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = nft_authority,
+        mint::freeze_authority = nft_authority
+    )]
+    pub nft_mint: Account<'info, SplMint>,
+
+    /// CHECK: created by the token metadata program.
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub nft_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: master edition, created by the token metadata program.
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), nft_mint.key().as_ref(), b"edition"],
         bump,
-        mint::decimals = 0,
-        mint::authority = nft_authority,
-        mint::freeze_authority = nft_authority
+        seeds::program = token_metadata_program.key()
     )]
-    pub nft_mint: Account<'info, SplMint>,
+    pub nft_edition: UncheckedAccount<'info>,
 
     /// CHECK: PDA signer authority for minting name NFTs.
     #[account(seeds = [SEED_NFT_AUTHORITY], bump)]
@@ -891,6 +2666,8 @@ pub struct IssueSubdomainNft<'info> {
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
@@ -954,6 +2731,193 @@ pub struct SetParentSubdomainPolicy<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(parent_hash: [u8; 32])]
+pub struct SetSubdomainSaleTerms<'info> {
+    #[account(seeds = [SEED_PREMIUM, parent_hash.as_ref()], bump = premium_parent.bump)]
+    pub premium_parent: Account<'info, PremiumName>,
+
+    #[account(
+        init_if_needed,
+        payer = parent_owner,
+        space = 8 + ParentPolicy::SIZE,
+        seeds = [SEED_POLICY, parent_hash.as_ref()],
+        bump
+    )]
+    pub parent_policy: Account<'info, ParentPolicy>,
+
+    #[account(mut)]
+    pub parent_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(parent_hash: [u8; 32], label_hash: [u8; 32])]
+pub struct SetSubLabelRule<'info> {
+    #[account(seeds = [SEED_PREMIUM, parent_hash.as_ref()], bump = premium_parent.bump)]
+    pub premium_parent: Account<'info, PremiumName>,
+
+    #[account(
+        init_if_needed,
+        payer = parent_owner,
+        space = 8 + SubLabelRule::SIZE,
+        seeds = [SEED_SUB_LABEL, parent_hash.as_ref(), label_hash.as_ref()],
+        bump
+    )]
+    pub label_rule: Account<'info, SubLabelRule>,
+
+    #[account(mut)]
+    pub parent_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(parent_hash: [u8; 32], label_hash: [u8; 32])]
+pub struct ClearSubLabelRule<'info> {
+    #[account(seeds = [SEED_PREMIUM, parent_hash.as_ref()], bump = premium_parent.bump)]
+    pub premium_parent: Account<'info, PremiumName>,
+
+    #[account(
+        mut,
+        close = parent_owner,
+        seeds = [SEED_SUB_LABEL, parent_hash.as_ref(), label_hash.as_ref()],
+        bump = label_rule.bump
+    )]
+    pub label_rule: Account<'info, SubLabelRule>,
+
+    #[account(mut)]
+    pub parent_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(parent: String, label: String, parent_hash: [u8; 32], label_hash: [u8; 32])]
+pub struct ClaimSubdomainUnderParent<'info> {
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, NamesConfig>,
+
+    #[account(seeds = [SEED_PREMIUM, parent_hash.as_ref()], bump = premium_parent.bump)]
+    pub premium_parent: Account<'info, PremiumName>,
+
+    #[account(mut, seeds = [SEED_POLICY, parent_hash.as_ref()], bump = parent_policy.bump)]
+    pub parent_policy: Account<'info, ParentPolicy>,
+
+    #[account(seeds = [SEED_SUB_LABEL, parent_hash.as_ref(), label_hash.as_ref()], bump)]
+    /// CHECK: `SubLabelRule` when the parent set one; empty otherwise.
+    pub label_rule: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = claimer,
+        space = 8 + SubName::SIZE,
+        seeds = [SEED_SUB, parent_hash.as_ref(), label_hash.as_ref()],
+        bump
+    )]
+    pub sub_name: Account<'info, SubName>,
+
+    #[account(mut, seeds = [SEED_PRIMARY, sub_name.owner.as_ref()], bump)]
+    /// CHECK: an expired holder's `PrimaryName` when it exists; cleared if it points at this name.
+    pub previous_owner_primary: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = claimer,
+        space = 8 + PrimaryName::SIZE,
+        seeds = [SEED_PRIMARY, claimer.key().as_ref()],
+        bump
+    )]
+    pub primary: Account<'info, PrimaryName>,
+
+    #[account(mut, address = premium_parent.owner @ NamesError::Unauthorized)]
+    /// CHECK: receives SOL payments; must be the parent's owner.
+    pub parent_wallet: UncheckedAccount<'info>,
+
+    // Revealed commitment, required while commit-reveal is enabled.
+    #[account(
+        mut,
+        close = claimer,
+        constraint = name_commitment.owner == claimer.key() @ NamesError::Unauthorized
+    )]
+    pub name_commitment: Option<Account<'info, NameCommitment>>,
+
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Pass all four to pay a sale priced in an SPL mint.
+    #[account(mut, token::authority = claimer)]
+    pub payer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub parent_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub price_mint: Option<InterfaceAccount<'info, Mint>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(parent: String, label: String, parent_hash: [u8; 32], label_hash: [u8; 32], initial_owner: Pubkey)]
+pub struct ClaimNestedSubdomain<'info> {
+    pub parent_sub: Account<'info, SubName>,
+
+    #[account(
+        init_if_needed,
+        payer = parent_owner,
+        space = 8 + ParentPolicy::SIZE,
+        seeds = [SEED_POLICY, parent_hash.as_ref()],
+        bump
+    )]
+    pub parent_policy: Account<'info, ParentPolicy>,
+
+    #[account(
+        init,
+        payer = parent_owner,
+        space = 8 + SubName::SIZE,
+        seeds = [SEED_SUB, parent_hash.as_ref(), label_hash.as_ref()],
+        bump
+    )]
+    pub sub_name: Account<'info, SubName>,
+
+    #[account(mut)]
+    pub parent_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(parent: String, parent_hash: [u8; 32], transfers_enabled: bool)]
+pub struct SetNestedSubdomainPolicy<'info> {
+    pub parent_sub: Account<'info, SubName>,
+
+    #[account(
+        init_if_needed,
+        payer = parent_owner,
+        space = 8 + ParentPolicy::SIZE,
+        seeds = [SEED_POLICY, parent_hash.as_ref()],
+        bump
+    )]
+    pub parent_policy: Account<'info, ParentPolicy>,
+
+    #[account(mut)]
+    pub parent_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSubdomain<'info> {
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, NamesConfig>,
+
+    #[account(mut, close = authority)]
+    pub sub_name: Account<'info, SubName>,
+
+    pub ancestor_premium: Option<Account<'info, PremiumName>>,
+    pub ancestor_sub: Option<Account<'info, SubName>>,
+
+    #[account(mut, seeds = [SEED_PRIMARY, sub_name.owner.as_ref()], bump)]
+    /// CHECK: holder's `PrimaryName` when it exists; cleared if it points at this name.
+    pub owner_primary: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 #[instruction(parent: String, label: String, parent_hash: [u8; 32], label_hash: [u8; 32])]
 pub struct TransferSubdomain<'info> {
@@ -970,6 +2934,10 @@ pub struct TransferSubdomain<'info> {
     /// CHECK: destination wallet pubkey only.
     pub new_owner: UncheckedAccount<'info>,
 
+    #[account(mut, seeds = [SEED_PRIMARY, current_owner.key().as_ref()], bump)]
+    /// CHECK: current owner's `PrimaryName` when it exists; cleared if it points at this name.
+    pub current_owner_primary: UncheckedAccount<'info>,
+
     pub parent_owner: Option<Signer<'info>>,
 
     #[account(
@@ -977,6 +2945,15 @@ pub struct TransferSubdomain<'info> {
         bump = parent_policy.bump
     )]
     pub parent_policy: Option<Account<'info, ParentPolicy>>,
+
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Option<Account<'info, NamesConfig>>,
+    // Program-issued NFTs only: refreshes the token metadata URI.
+    #[account(mut)]
+    pub nft_metadata: Option<Account<'info, MetadataAccount>>,
+    /// CHECK: metadata update authority PDA, checked when refreshing.
+    pub nft_authority: Option<UncheckedAccount<'info>>,
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
 }
 
 #[derive(Accounts)]
@@ -1001,16 +2978,176 @@ pub struct SetPrimaryName<'info> {
 #[derive(Accounts)]
 pub struct InitPremiumConfig<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + PremiumConfig::SIZE,
+        init,
+        payer = authority,
+        space = 8 + PremiumConfig::SIZE,
+        seeds = [SEED_PREMIUM_CONFIG],
+        bump
+    )]
+    pub premium_config: Account<'info, PremiumConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct CommitSealedBid<'info> {
+    #[account(seeds = [SEED_PREMIUM_CONFIG], bump = premium_config.bump)]
+    pub premium_config: Account<'info, PremiumConfig>,
+    #[account(
+        mut,
+        seeds = [SEED_AUCTION, name_hash.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = bidder,
+        space = 8 + SealedBid::SIZE,
+        seeds = [
+            SEED_SEALED_BID,
+            name_hash.as_ref(),
+            bidder.key().as_ref(),
+            auction.start_slot.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub sealed_bid: Account<'info, SealedBid>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct RevealSealedBid<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_AUCTION, name_hash.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        seeds = [
+            SEED_SEALED_BID,
+            name_hash.as_ref(),
+            bidder.key().as_ref(),
+            auction.start_slot.to_le_bytes().as_ref()
+        ],
+        bump = sealed_bid.bump,
+        has_one = bidder @ NamesError::Unauthorized
+    )]
+    pub sealed_bid: Account<'info, SealedBid>,
+    pub bidder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct SettleSealedAuction<'info> {
+    #[account(seeds = [SEED_PREMIUM_CONFIG], bump = premium_config.bump)]
+    pub premium_config: Account<'info, PremiumConfig>,
+    #[account(
+        mut,
+        seeds = [SEED_AUCTION, name_hash.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        close = winner,
+        seeds = [
+            SEED_SEALED_BID,
+            name_hash.as_ref(),
+            winner.key().as_ref(),
+            auction.start_slot.to_le_bytes().as_ref()
+        ],
+        bump = winner_bid.bump
+    )]
+    pub winner_bid: Account<'info, SealedBid>,
+    #[account(mut, address = premium_config.treasury_vault)]
+    /// CHECK: validated by address constraint.
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = winner,
+        space = 8 + PremiumName::SIZE,
+        seeds = [SEED_PREMIUM, name_hash.as_ref()],
+        bump
+    )]
+    pub premium_name: Account<'info, PremiumName>,
+    #[account(
+        init_if_needed,
+        payer = winner,
+        space = 8 + ParentPolicy::SIZE,
+        seeds = [SEED_POLICY, name_hash.as_ref()],
+        bump
+    )]
+    pub parent_policy: Account<'info, ParentPolicy>,
+    #[account(
+        init_if_needed,
+        payer = winner,
+        space = 8 + PrimaryName::SIZE,
+        seeds = [SEED_PRIMARY, winner.key().as_ref()],
+        bump
+    )]
+    pub primary: Account<'info, PrimaryName>,
+    #[account(mut)]
+    pub winner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct CloseSealedBid<'info> {
+    #[account(seeds = [SEED_PREMIUM_CONFIG], bump = premium_config.bump)]
+    pub premium_config: Account<'info, PremiumConfig>,
+    #[account(
+        mut,
+        seeds = [SEED_AUCTION, name_hash.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        close = bidder,
+        seeds = [
+            SEED_SEALED_BID,
+            name_hash.as_ref(),
+            bidder.key().as_ref(),
+            sealed_bid.auction_start_slot.to_le_bytes().as_ref()
+        ],
+        bump = sealed_bid.bump,
+        has_one = bidder @ NamesError::Unauthorized
+    )]
+    pub sealed_bid: Account<'info, SealedBid>,
+    #[account(mut)]
+    /// CHECK: refund destination, bound to the bid by `has_one`.
+    pub bidder: UncheckedAccount<'info>,
+    #[account(mut, address = premium_config.treasury_vault)]
+    /// CHECK: validated by address constraint.
+    pub treasury: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct CancelAuction<'info> {
+    #[account(
         seeds = [SEED_PREMIUM_CONFIG],
-        bump
+        bump = premium_config.bump,
+        has_one = authority @ NamesError::Unauthorized
     )]
     pub premium_config: Account<'info, PremiumConfig>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [SEED_AUCTION, name_hash.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
     #[account(mut)]
     pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -1018,8 +3155,11 @@ pub struct InitPremiumConfig<'info> {
 pub struct CreateAuction<'info> {
     #[account(seeds = [SEED_PREMIUM_CONFIG], bump = premium_config.bump)]
     pub premium_config: Account<'info, PremiumConfig>,
+    #[account(seeds = [SEED_PREMIUM, name_hash.as_ref()], bump)]
+    /// CHECK: must be empty (name unowned or released).
+    pub premium_name: UncheckedAccount<'info>,
     #[account(
-        init,
+        init_if_needed,
         payer = authority,
         space = 8 + Auction::SIZE,
         seeds = [SEED_AUCTION, name_hash.as_ref()],
@@ -1046,7 +3186,12 @@ pub struct PlaceBid<'info> {
         init_if_needed,
         payer = bidder,
         space = 8 + BidEscrow::SIZE,
-        seeds = [SEED_ESCROW, name_hash.as_ref(), bidder.key().as_ref()],
+        seeds = [
+            SEED_ESCROW,
+            name_hash.as_ref(),
+            bidder.key().as_ref(),
+            auction.start_slot.to_le_bytes().as_ref()
+        ],
         bump
     )]
     pub bid_escrow: Account<'info, BidEscrow>,
@@ -1054,7 +3199,12 @@ pub struct PlaceBid<'info> {
         init_if_needed,
         payer = bidder,
         space = 0,
-        seeds = [SEED_ESCROW_VAULT, name_hash.as_ref(), bidder.key().as_ref()],
+        seeds = [
+            SEED_ESCROW_VAULT,
+            name_hash.as_ref(),
+            bidder.key().as_ref(),
+            auction.start_slot.to_le_bytes().as_ref()
+        ],
         bump
     )]
     /// CHECK: lamport vault PDA with zero data.
@@ -1072,11 +3222,26 @@ pub struct WithdrawLosingBid<'info> {
         bump = auction.bump
     )]
     pub auction: Account<'info, Auction>,
-    #[account(seeds = [SEED_ESCROW, name_hash.as_ref(), bidder.key().as_ref()], bump = bid_escrow.bump)]
+    #[account(
+        mut,
+        close = bidder,
+        seeds = [
+            SEED_ESCROW,
+            name_hash.as_ref(),
+            bidder.key().as_ref(),
+            bid_escrow.auction_start_slot.to_le_bytes().as_ref()
+        ],
+        bump = bid_escrow.bump
+    )]
     pub bid_escrow: Account<'info, BidEscrow>,
     #[account(
         mut,
-        seeds = [SEED_ESCROW_VAULT, name_hash.as_ref(), bidder.key().as_ref()],
+        seeds = [
+            SEED_ESCROW_VAULT,
+            name_hash.as_ref(),
+            bidder.key().as_ref(),
+            bid_escrow.auction_start_slot.to_le_bytes().as_ref()
+        ],
         bump
     )]
     /// CHECK: lamport vault PDA with zero data.
@@ -1086,6 +3251,27 @@ pub struct WithdrawLosingBid<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct WithdrawLegacyBid<'info> {
+    #[account(seeds = [SEED_PREMIUM_CONFIG], bump = premium_config.bump)]
+    pub premium_config: Account<'info, PremiumConfig>,
+    /// CHECK: the name's auction, if it still exists; decoded in the handler.
+    #[account(seeds = [SEED_AUCTION, name_hash.as_ref()], bump)]
+    pub auction: UncheckedAccount<'info>,
+    /// CHECK: escrow at the seeds used before auction rounds; may be closed.
+    #[account(mut, seeds = [SEED_ESCROW, name_hash.as_ref(), bidder.key().as_ref()], bump)]
+    pub bid_escrow: UncheckedAccount<'info>,
+    /// CHECK: lamport vault PDA with zero data, at the pre-round seeds.
+    #[account(mut, seeds = [SEED_ESCROW_VAULT, name_hash.as_ref(), bidder.key().as_ref()], bump)]
+    pub escrow_vault: UncheckedAccount<'info>,
+    /// CHECK: validated by address constraint.
+    #[account(mut, address = premium_config.treasury_vault @ NamesError::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+}
+
 #[derive(Accounts)]
 #[instruction(name_hash: [u8; 32])]
 pub struct SettleAuction<'info> {
@@ -1099,10 +3285,28 @@ pub struct SettleAuction<'info> {
         bump = auction.bump
     )]
     pub auction: Account<'info, Auction>,
-    #[account(mut, seeds = [SEED_ESCROW, name_hash.as_ref(), winner.key().as_ref()], bump)]
-    /// CHECK: escrow checked off-chain in MVP settle path.
-    pub winner_escrow: UncheckedAccount<'info>,
-    #[account(mut, seeds = [SEED_ESCROW_VAULT, name_hash.as_ref(), winner.key().as_ref()], bump)]
+    #[account(
+        mut,
+        close = winner,
+        seeds = [
+            SEED_ESCROW,
+            name_hash.as_ref(),
+            winner.key().as_ref(),
+            auction.start_slot.to_le_bytes().as_ref()
+        ],
+        bump = winner_escrow.bump
+    )]
+    pub winner_escrow: Account<'info, BidEscrow>,
+    #[account(
+        mut,
+        seeds = [
+            SEED_ESCROW_VAULT,
+            name_hash.as_ref(),
+            winner.key().as_ref(),
+            auction.start_slot.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
     /// CHECK: lamport vault PDA with zero data.
     pub winner_escrow_vault: UncheckedAccount<'info>,
     #[account(mut, address = premium_config.treasury_vault)]
@@ -1137,6 +3341,214 @@ pub struct SettleAuction<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32], kind: u8, rrtype: u16)]
+pub struct SetRecords<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + NameRecords::SIZE,
+        seeds = [SEED_RECORDS, name_hash.as_ref(), rrtype.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub name_records: Account<'info, NameRecords>,
+
+    pub premium_name: Option<Account<'info, PremiumName>>,
+    pub sub_name: Option<Account<'info, SubName>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32], kind: u8, rrtype: u16)]
+pub struct ClearRecords<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [SEED_RECORDS, name_hash.as_ref(), rrtype.to_le_bytes().as_ref()],
+        bump = name_records.bump
+    )]
+    pub name_records: Account<'info, NameRecords>,
+
+    pub premium_name: Option<Account<'info, PremiumName>>,
+    pub sub_name: Option<Account<'info, SubName>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPremiumPricing<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_PREMIUM_CONFIG],
+        bump = premium_config.bump,
+        has_one = authority @ NamesError::Unauthorized
+    )]
+    pub premium_config: Account<'info, PremiumConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct SetNamePrice<'info> {
+    #[account(
+        seeds = [SEED_PREMIUM_CONFIG],
+        bump = premium_config.bump,
+        has_one = authority @ NamesError::Unauthorized
+    )]
+    pub premium_config: Account<'info, PremiumConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + NamePrice::SIZE,
+        seeds = [SEED_NAME_PRICE, name_hash.as_ref()],
+        bump
+    )]
+    pub name_price: Account<'info, NamePrice>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct ClearNamePrice<'info> {
+    #[account(
+        seeds = [SEED_PREMIUM_CONFIG],
+        bump = premium_config.bump,
+        has_one = authority @ NamesError::Unauthorized
+    )]
+    pub premium_config: Account<'info, PremiumConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [SEED_NAME_PRICE, name_hash.as_ref()],
+        bump = name_price.bump
+    )]
+    pub name_price: Account<'info, NamePrice>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, name_hash: [u8; 32])]
+pub struct QuotePremium<'info> {
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, NamesConfig>,
+
+    #[account(seeds = [SEED_PREMIUM_CONFIG], bump = premium_config.bump)]
+    pub premium_config: Account<'info, PremiumConfig>,
+
+    #[account(seeds = [SEED_PREMIUM, name_hash.as_ref()], bump)]
+    /// CHECK: only checked for existence.
+    pub premium_name: UncheckedAccount<'info>,
+
+    #[account(seeds = [SEED_NAME_PRICE, name_hash.as_ref()], bump)]
+    /// CHECK: `NamePrice` listing when initialized; empty for unlisted names.
+    pub name_price: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPremiumTerms<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_PREMIUM_CONFIG],
+        bump = premium_config.bump,
+        has_one = authority @ NamesError::Unauthorized
+    )]
+    pub premium_config: Account<'info, PremiumConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct RenewPremium<'info> {
+    #[account(seeds = [SEED_CONFIG], bump = config.bump)]
+    pub config: Account<'info, NamesConfig>,
+
+    #[account(seeds = [SEED_PREMIUM_CONFIG], bump = premium_config.bump)]
+    pub premium_config: Account<'info, PremiumConfig>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PREMIUM, name_hash.as_ref()],
+        bump = premium_name.bump
+    )]
+    pub premium_name: Account<'info, PremiumName>,
+
+    #[account(mut, address = config.treasury)]
+    /// CHECK: validated by address constraint.
+    pub treasury: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Program-issued NFTs only: refreshes the token metadata URI.
+    #[account(mut)]
+    pub nft_metadata: Option<Account<'info, MetadataAccount>>,
+    /// CHECK: metadata update authority PDA, checked when refreshing.
+    pub nft_authority: Option<UncheckedAccount<'info>>,
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8; 32])]
+pub struct ReleasePremium<'info> {
+    #[account(seeds = [SEED_PREMIUM_CONFIG], bump = premium_config.bump)]
+    pub premium_config: Account<'info, PremiumConfig>,
+
+    #[account(
+        mut,
+        close = previous_owner,
+        seeds = [SEED_PREMIUM, name_hash.as_ref()],
+        bump = premium_name.bump
+    )]
+    pub premium_name: Account<'info, PremiumName>,
+
+    #[account(
+        mut,
+        close = previous_owner,
+        seeds = [SEED_POLICY, name_hash.as_ref()],
+        bump = parent_policy.bump
+    )]
+    pub parent_policy: Account<'info, ParentPolicy>,
+
+    /// The name's `Listing`, required while it is listed; closed to the seller.
+    #[account(
+        mut,
+        close = previous_owner,
+        seeds = [SEED_LISTING, name_hash.as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Option<Account<'info, Listing>>,
+
+    #[account(mut, address = premium_name.owner)]
+    /// CHECK: rent refund destination, validated by address constraint.
+    pub previous_owner: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [SEED_PRIMARY, previous_owner.key().as_ref()], bump)]
+    /// CHECK: previous owner's `PrimaryName` when it exists; cleared if it points at this name.
+    pub previous_owner_primary: UncheckedAccount<'info>,
+
+    pub caller: Signer<'info>,
+
+    // Listed NFT-bound names only: the escrowed NFT goes back to the seller.
+    pub nft_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub escrow_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub seller_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
 #[account]
 pub struct NamesConfig {
     pub authority: Pubkey,
@@ -1149,15 +3561,37 @@ pub struct NamesConfig {
     pub enable_subdomains: bool,
     pub enable_premium: bool,
     pub bump: u8,
+    pub commit_min_slots: u64,
+    pub commit_max_slots: u64,
+    pub market_fee_bps: u16,
+    pub nft_symbol_len: u8,
+    pub nft_symbol_bytes: [u8; MAX_NFT_SYMBOL],
+    pub nft_uri_len: u8,
+    pub nft_uri_template: [u8; MAX_NFT_URI],
 }
 
 impl NamesConfig {
-    pub const SIZE: usize = 32 + 32 + 32 + 1 + MAX_PARENT_ZONE + 8 + 8 + 1 + 1 + 1;
+    pub const SIZE: usize =
+        32 + 32 + 32 + 1 + MAX_PARENT_ZONE + 8 + 8 + 1 + 1 + 1 + 8 + 8 + 2 + 1 + MAX_NFT_SYMBOL + 1 + MAX_NFT_URI;
+
+    pub fn commit_required(&self) -> bool {
+        self.commit_min_slots > 0
+    }
 
     pub fn parent_zone(&self) -> String {
         let len = self.parent_zone_len as usize;
         String::from_utf8_lossy(&self.parent_zone_bytes[..len]).to_string()
     }
+
+    pub fn nft_symbol(&self) -> String {
+        let len = self.nft_symbol_len as usize;
+        String::from_utf8_lossy(&self.nft_symbol_bytes[..len]).to_string()
+    }
+
+    pub fn nft_uri_template(&self) -> String {
+        let len = self.nft_uri_len as usize;
+        String::from_utf8_lossy(&self.nft_uri_template[..len]).to_string()
+    }
 }
 
 #[account]
@@ -1166,14 +3600,47 @@ pub struct PremiumName {
     pub owner: Pubkey,
     pub nft_mint: Pubkey,
     pub purchase_lamports: u64,
+    /// Registration time; also seeds the NFT mint so each registration of the
+    /// name gets its own mint.
     pub created_at: i64,
     pub transferable: bool,
     pub nft_bound: bool,
     pub bump: u8,
+    pub expires_at: i64,
+    /// Set while a `Listing` is open; `owner` stays the seller, but the name
+    /// cannot be transferred, bound or listed again until it closes.
+    pub listed: bool,
 }
 
 impl PremiumName {
-    pub const SIZE: usize = 32 + 32 + 32 + 8 + 8 + 1 + 1 + 1;
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 8 + 1 + 1 + 1 + 8 + 1;
+
+    /// Whether `nft_mint` is the NFT `issue_premium_nft` minted for this
+    /// registration, rather than an external one bound with `bind_premium_nft`.
+    /// Its metadata shows the owner and expiry, so changes must refresh it.
+    pub fn has_issued_nft(&self) -> bool {
+        let generation = self.created_at.to_le_bytes();
+        let seeds: &[&[u8]] = &[SEED_PREMIUM_NFT_MINT, &self.name_hash, &generation];
+        self.nft_mint != Pubkey::default() && Pubkey::find_program_address(seeds, &crate::ID).0 == self.nft_mint
+    }
+
+    /// Fills a freshly created name for its first registration period.
+    fn grant(&mut self, name_hash: [u8; 32], owner: Pubkey, price_lamports: u64, period_secs: i64, bump: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.name_hash = name_hash;
+        self.owner = owner;
+        self.nft_mint = Pubkey::default();
+        self.purchase_lamports = price_lamports;
+        self.created_at = now;
+        self.transferable = true;
+        self.nft_bound = false;
+        self.bump = bump;
+        self.listed = false;
+        self.expires_at = now
+            .checked_add(period_secs)
+            .ok_or_else(|| error!(NamesError::MathOverflow))?;
+        Ok(())
+    }
 }
 
 #[account]
@@ -1182,41 +3649,207 @@ pub struct ParentPolicy {
     pub parent_owner: Pubkey,
     pub transfers_enabled: bool,
     pub bump: u8,
+    /// Terms for `claim_subdomain_under_parent`; sales are off by default.
+    pub sale: SubdomainSaleTerms,
+    pub sold_count: u32,
+}
+
+impl ParentPolicy {
+    pub const SIZE: usize = 32 + 32 + 1 + 1 + SubdomainSaleTerms::SIZE + 4;
+
+    /// Sale terms belong to the owner who set them: a new owner starts with
+    /// sales off and a fresh count.
+    pub fn reset_sale(&mut self) {
+        self.sale = SubdomainSaleTerms::default();
+        self.sold_count = 0;
+    }
+}
+
+/// How a premium parent sells first-level subdomains itself.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubdomainSaleTerms {
+    pub enabled: bool,
+    /// Price per label, paid to the parent owner: lamports when `price_mint`
+    /// is the default pubkey, otherwise base units of `price_mint`.
+    pub price: u64,
+    pub price_mint: Pubkey,
+    /// Root of a sorted-pair sha256 Merkle tree over `sha256(claimer)`
+    /// leaves; all zeroes lets anyone claim.
+    pub allowlist_root: [u8; 32],
+    /// Most subdomains this policy may sell; 0 for no limit.
+    pub max_sales: u32,
+    /// Lifetime of a sold subdomain; 0 for subdomains that never expire.
+    pub period_secs: i64,
+}
+
+impl SubdomainSaleTerms {
+    pub const SIZE: usize = 1 + 8 + 32 + 32 + 4 + 8;
+}
+
+/// Per-label override under a premium parent: a reserved label is never
+/// sold (the parent can still delegate it); a non-zero price replaces the
+/// policy price.
+#[account]
+pub struct SubLabelRule {
+    pub parent_hash: [u8; 32],
+    pub label_hash: [u8; 32],
+    pub reserved: bool,
+    pub price: u64,
+    pub bump: u8,
+}
+
+impl SubLabelRule {
+    pub const SIZE: usize = 32 + 32 + 1 + 8 + 1;
+}
+
+#[account]
+pub struct SubName {
+    pub parent_hash: [u8; 32],
+    pub label_hash: [u8; 32],
+    pub owner: Pubkey,
+    pub transfer_policy: u8,
+    pub parent_owner: Pubkey,
+    pub transfers_enabled: bool,
+    /// Claim time; also seeds the NFT mint, like `PremiumName::created_at`.
+    pub created_at: i64,
+    pub nft_mint: Pubkey,
+    pub nft_custody_account: Pubkey,
+    pub bump: u8,
+    /// Labels below the premium name or parent zone: 1 for `label.parent.dns`.
+    pub depth: u8,
+    /// Set for subdomains sold with a period; 0 never expires.
+    pub expires_at: i64,
+}
+
+impl SubName {
+    pub const SIZE: usize = 32 + 32 + 32 + 1 + 32 + 1 + 8 + 32 + 32 + 1 + 1 + 8;
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+
+    /// Only `issue_subdomain_nft` sets `nft_mint`, so any mint is program-issued.
+    pub fn has_issued_nft(&self) -> bool {
+        self.nft_mint != Pubkey::default()
+    }
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub old_len: u32,
+    pub new_len: u32,
+}
+
+#[event]
+pub struct SubdomainSold {
+    pub parent_hash: [u8; 32],
+    pub name_hash: [u8; 32],
+    pub owner: Pubkey,
+    pub price: u64,
+    pub price_mint: Pubkey,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct SubdomainRevoked {
+    pub name_hash: [u8; 32],
+    pub owner: Pubkey,
+    pub revoked_by: Pubkey,
+}
+
+#[account]
+pub struct PrimaryName {
+    pub owner: Pubkey,
+    pub name_hash: [u8; 32],
+    pub kind: u8,
+    pub is_set: bool,
+    pub bump: u8,
+    pub name_len: u8,
+    pub name_bytes: [u8; MAX_NAME_BYTES],
+    /// `SubName.depth` of a subdomain; 0 for premium names.
+    pub depth: u8,
+}
+
+impl PrimaryName {
+    pub const SIZE: usize = 32 + 32 + 1 + 1 + 1 + 1 + MAX_NAME_BYTES + 1;
+
+    pub fn name(&self) -> String {
+        let len = self.name_len as usize;
+        String::from_utf8_lossy(&self.name_bytes[..len]).to_string()
+    }
+
+    fn assign(&mut self, owner: Pubkey, name: &str, name_hash: [u8; 32], kind: u8, depth: u8) {
+        self.owner = owner;
+        self.name_hash = name_hash;
+        self.kind = kind;
+        self.depth = depth;
+        self.is_set = true;
+        self.name_len = name.len() as u8;
+        self.name_bytes = [0u8; MAX_NAME_BYTES];
+        self.name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+    }
+
+    fn clear(&mut self) {
+        self.is_set = false;
+        self.name_hash = [0u8; 32];
+        self.name_len = 0;
+        self.name_bytes = [0u8; MAX_NAME_BYTES];
+        self.depth = 0;
+    }
 }
 
-impl ParentPolicy {
-    pub const SIZE: usize = 32 + 32 + 1 + 1;
+#[event]
+pub struct PrimaryNameSet {
+    pub owner: Pubkey,
+    pub name_hash: [u8; 32],
+    pub kind: u8,
+}
+
+#[event]
+pub struct PrimaryNameCleared {
+    pub owner: Pubkey,
+    pub name_hash: [u8; 32],
 }
 
+/// Owner-published records of one type for a name. `owner` is the signer at
+/// the last write; resolvers ignore the set once the name changes hands.
 #[account]
-pub struct SubName {
-    pub parent_hash: [u8; 32],
-    pub label_hash: [u8; 32],
+pub struct NameRecords {
+    pub name_hash: [u8; 32],
+    pub kind: u8,
+    pub rrtype: u16,
     pub owner: Pubkey,
-    pub transfer_policy: u8,
-    pub parent_owner: Pubkey,
-    pub transfers_enabled: bool,
-    pub created_at: i64,
-    pub nft_mint: Pubkey,
-    pub nft_custody_account: Pubkey,
+    pub version: u64,
+    pub ttl_s: u32,
+    pub rrset_hash: [u8; 32],
+    pub data: Vec<u8>,
+    pub updated_at: i64,
     pub bump: u8,
 }
 
-impl SubName {
-    pub const SIZE: usize = 32 + 32 + 32 + 1 + 32 + 1 + 8 + 32 + 32 + 1;
+impl NameRecords {
+    pub const SIZE: usize = 32 + 1 + 2 + 32 + 8 + 4 + 32 + 4 + ddns_rrset::MAX_RRSET_BYTES + 8 + 1;
 }
 
-#[account]
-pub struct PrimaryName {
-    pub owner: Pubkey,
+#[event]
+pub struct RecordsSet {
     pub name_hash: [u8; 32],
     pub kind: u8,
-    pub is_set: bool,
-    pub bump: u8,
+    pub rrtype: u16,
+    pub owner: Pubkey,
+    pub version: u64,
+    pub ttl_s: u32,
+    pub rrset_hash: [u8; 32],
+    pub data: Vec<u8>,
 }
 
-impl PrimaryName {
-    pub const SIZE: usize = 32 + 32 + 1 + 1 + 1;
+#[event]
+pub struct RecordsCleared {
+    pub name_hash: [u8; 32],
+    pub kind: u8,
+    pub rrtype: u16,
+    pub owner: Pubkey,
 }
 
 #[account]
@@ -1232,10 +3865,73 @@ pub struct PremiumConfig {
     pub anti_sniping_extension_slots: u64,
     pub enabled: bool,
     pub bump: u8,
+    pub period_secs: i64,
+    pub renewal_lamports_per_year: u64,
+    pub grace_period_secs: i64,
+    pub length_prices_lamports: [u64; PRICE_TIERS],
+    pub toll_mint: Pubkey,
+    pub toll_treasury: Pubkey,
+    pub toll_units_per_sol: u64,
 }
 
 impl PremiumConfig {
-    pub const SIZE: usize = 32 + 32 + 32 + 1 + 1 + 1 + 8 + 8 + 8 + 1 + 1;
+    pub const SIZE: usize =
+        32 + 32 + 32 + 1 + 1 + 1 + 8 + 8 + 8 + 1 + 1 + 8 + 8 + 8 + 8 * PRICE_TIERS + 32 + 32 + 8;
+}
+
+#[account]
+pub struct NameCommitment {
+    pub owner: Pubkey,
+    pub commitment: [u8; 32],
+    pub name_hash: [u8; 32],
+    pub committed_slot: u64,
+    pub revealed: bool,
+    pub bump: u8,
+}
+
+impl NameCommitment {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 1 + 1;
+}
+
+#[account]
+pub struct NamePrice {
+    pub name_hash: [u8; 32],
+    pub price_lamports: u64,
+    pub bump: u8,
+}
+
+impl NamePrice {
+    pub const SIZE: usize = 32 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PremiumQuote {
+    pub label_len: u8,
+    pub price_lamports: u64,
+    pub toll_mint: Pubkey,
+    /// Price in `toll_mint` base units; zero when toll payment is disabled.
+    pub toll_units: u64,
+    pub listed: bool,
+    pub reserved: bool,
+    pub auction_only: bool,
+    pub available: bool,
+}
+
+#[event]
+pub struct PremiumRenewed {
+    pub name_hash: [u8; 32],
+    pub owner: Pubkey,
+    pub payer: Pubkey,
+    pub years: u8,
+    pub lamports: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct PremiumReleased {
+    pub name_hash: [u8; 32],
+    pub previous_owner: Pubkey,
+    pub expires_at: i64,
 }
 
 #[account]
@@ -1248,10 +3944,78 @@ pub struct Auction {
     pub highest_bid_lamports: u64,
     pub settled: bool,
     pub bump: u8,
+    pub mode: u8,
+    pub reveal_end_slot: u64,
+    pub second_bid_lamports: u64,
+    pub sealed_bids: u32,
+    pub name_len: u8,
+    pub name_bytes: [u8; MAX_PREMIUM_NAME],
 }
 
 impl Auction {
-    pub const SIZE: usize = 32 + 8 + 8 + 8 + 32 + 8 + 1 + 1;
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 32 + 8 + 1 + 1 + 1 + 8 + 8 + 4 + 1 + MAX_PREMIUM_NAME;
+
+    pub fn name(&self) -> String {
+        let len = self.name_len as usize;
+        String::from_utf8_lossy(&self.name_bytes[..len]).to_string()
+    }
+}
+
+#[account]
+pub struct Listing {
+    pub name_hash: [u8; 32],
+    pub seller: Pubkey,
+    pub start_price_lamports: u64,
+    pub end_price_lamports: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub nft_escrowed: bool,
+    pub bump: u8,
+}
+
+impl Listing {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 8 + 8 + 1 + 1;
+}
+
+#[event]
+pub struct NameListed {
+    pub name_hash: [u8; 32],
+    pub seller: Pubkey,
+    pub start_price_lamports: u64,
+    pub end_price_lamports: u64,
+    pub end_ts: i64,
+}
+
+#[event]
+pub struct ListingCancelled {
+    pub name_hash: [u8; 32],
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct NameSold {
+    pub name_hash: [u8; 32],
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price_lamports: u64,
+    pub fee_lamports: u64,
+}
+
+#[account]
+pub struct SealedBid {
+    pub name_hash: [u8; 32],
+    pub bidder: Pubkey,
+    pub commitment: [u8; 32],
+    pub deposit_lamports: u64,
+    pub bid_lamports: u64,
+    pub revealed: bool,
+    pub bump: u8,
+    /// `Auction.start_slot` of the round the bid was placed in (a seed).
+    pub auction_start_slot: u64,
+}
+
+impl SealedBid {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 8 + 1 + 1 + 8;
 }
 
 #[account]
@@ -1262,28 +4026,550 @@ pub struct BidEscrow {
     pub active: bool,
     pub refunded: bool,
     pub bump: u8,
+    /// `Auction.start_slot` of the round the bid was placed in (a seed).
+    pub auction_start_slot: u64,
 }
 
 impl BidEscrow {
-    pub const SIZE: usize = 32 + 32 + 8 + 1 + 1 + 1;
+    pub const SIZE: usize = 32 + 32 + 8 + 1 + 1 + 1 + 8;
+}
+
+#[event]
+pub struct AuctionSettled {
+    pub name_hash: [u8; 32],
+    pub winner: Pubkey,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct BidRefunded {
+    pub name_hash: [u8; 32],
+    pub bidder: Pubkey,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct AuctionCancelled {
+    pub name_hash: [u8; 32],
+}
+
+/// Listing price at `now`: `start` until `start_ts`, falling linearly to
+/// `end` at `end_ts` and staying there.
+fn listing_price(start: u64, end: u64, start_ts: i64, end_ts: i64, now: i64) -> u64 {
+    if now <= start_ts || end_ts <= start_ts {
+        return start;
+    }
+    if now >= end_ts {
+        return end;
+    }
+    let elapsed = (now - start_ts) as u128;
+    let span = (end_ts - start_ts) as u128;
+    let drop = (start.saturating_sub(end) as u128) * elapsed / span;
+    start - drop as u64
+}
+
+fn market_fee(price: u64, fee_bps: u16) -> u64 {
+    (price as u128 * fee_bps as u128 / 10_000) as u64
+}
+
+fn required<T>(account: Option<&T>) -> Result<&T> {
+    account.ok_or_else(|| error!(NamesError::MissingRequiredAccount))
+}
+
+fn transfer_name_nft<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from,
+                mint: mint.to_account_info(),
+                to,
+                authority,
+            },
+            signer_seeds,
+        ),
+        1,
+        mint.decimals,
+    )
+}
+
+/// Fills a name NFT URI template's `{mint}`, `{owner}` and `{expires}`.
+fn render_nft_uri(template: &str, mint: &Pubkey, owner: &Pubkey, expires_at: i64) -> String {
+    template
+        .replace("{mint}", &mint.to_string())
+        .replace("{owner}", &owner.to_string())
+        .replace("{expires}", &expires_at.to_string())
+}
+
+/// Token metadata name for a `.dns` name. Names over the metadata program's
+/// 32-byte limit are rejected rather than cut, so an NFT never shows a
+/// different name than the one it stands for.
+fn nft_display_name(name: &str) -> Result<String> {
+    require!(name.len() <= mpl_token_metadata::MAX_NAME_LENGTH, NamesError::NftNameTooLong);
+    Ok(name.to_string())
+}
+
+fn name_nft_data(cfg: &NamesConfig, name: &str, mint: Pubkey, owner: Pubkey, expires_at: i64) -> Result<DataV2> {
+    let uri = render_nft_uri(&cfg.nft_uri_template(), &mint, &owner, expires_at);
+    require!(uri.len() <= MAX_NFT_URI, NamesError::InvalidNftMetadata);
+    Ok(DataV2 {
+        name: nft_display_name(name)?,
+        symbol: cfg.nft_symbol(),
+        uri,
+        seller_fee_basis_points: 0,
+        creators: None,
+        collection: None,
+        uses: None,
+    })
+}
+
+struct NameNftMetadataAccounts<'info> {
+    metadata: AccountInfo<'info>,
+    edition: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    nft_authority: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    rent: AccountInfo<'info>,
+    token_metadata_program: AccountInfo<'info>,
+}
+
+/// Creates the metadata and a zero-supply master edition for a freshly
+/// minted name NFT; the edition takes over the mint and freeze authorities.
+fn create_name_nft_metadata(accounts: NameNftMetadataAccounts, data: DataV2, signer_seeds: &[&[u8]]) -> Result<()> {
+    token_metadata::create_metadata_accounts_v3(
+        CpiContext::new_with_signer(
+            accounts.token_metadata_program.clone(),
+            CreateMetadataAccountsV3 {
+                metadata: accounts.metadata.clone(),
+                mint: accounts.mint.clone(),
+                mint_authority: accounts.nft_authority.clone(),
+                payer: accounts.payer.clone(),
+                update_authority: accounts.nft_authority.clone(),
+                system_program: accounts.system_program.clone(),
+                rent: accounts.rent.clone(),
+            },
+            &[signer_seeds],
+        ),
+        data,
+        true,
+        true,
+        None,
+    )?;
+    token_metadata::create_master_edition_v3(
+        CpiContext::new_with_signer(
+            accounts.token_metadata_program,
+            CreateMasterEditionV3 {
+                edition: accounts.edition,
+                mint: accounts.mint,
+                update_authority: accounts.nft_authority.clone(),
+                mint_authority: accounts.nft_authority,
+                payer: accounts.payer,
+                metadata: accounts.metadata,
+                token_program: accounts.token_program,
+                system_program: accounts.system_program,
+                rent: accounts.rent,
+            },
+            &[signer_seeds],
+        ),
+        Some(0),
+    )
+}
+
+/// Re-renders a program-issued name NFT's URI after a transfer or renewal.
+fn refresh_name_nft_metadata<'info>(
+    cfg: &NamesConfig,
+    metadata: &Account<'info, MetadataAccount>,
+    nft_authority: &UncheckedAccount<'info>,
+    token_metadata_program: &Program<'info, Metadata>,
+    mint: Pubkey,
+    owner: Pubkey,
+    expires_at: i64,
+) -> Result<()> {
+    let (authority_key, authority_bump) = Pubkey::find_program_address(&[SEED_NFT_AUTHORITY], &crate::ID);
+    require_keys_eq!(nft_authority.key(), authority_key, NamesError::InvalidNftMetadata);
+    require_keys_eq!(metadata.mint, mint, NamesError::InvalidNftMint);
+    require_keys_eq!(metadata.update_authority, authority_key, NamesError::InvalidNftMetadata);
+
+    let data = name_nft_data(cfg, metadata.name.trim_end_matches('\0'), mint, owner, expires_at)?;
+    let signer_seeds: &[&[u8]] = &[SEED_NFT_AUTHORITY, &[authority_bump]];
+    token_metadata::update_metadata_accounts_v2(
+        CpiContext::new_with_signer(
+            token_metadata_program.to_account_info(),
+            UpdateMetadataAccountsV2 {
+                metadata: metadata.to_account_info(),
+                update_authority: nft_authority.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        None,
+        Some(data),
+        None,
+        None,
+    )
+}
+
+/// Sends a listing's escrowed NFT to `to` and closes the escrow token
+/// account, refunding its rent to `rent_to` (the seller).
+fn release_escrowed_nft<'info>(
+    listing: &Account<'info, Listing>,
+    mint: &InterfaceAccount<'info, Mint>,
+    escrow: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    rent_to: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    require!(
+        escrow.owner == listing.key() && escrow.mint == mint.key() && to.mint == mint.key(),
+        NamesError::InvalidNftTokenAccount
+    );
+    let seeds: &[&[u8]] = &[SEED_LISTING, listing.name_hash.as_ref(), &[listing.bump]];
+    transfer_name_nft(
+        token_program,
+        mint,
+        escrow.to_account_info(),
+        to.to_account_info(),
+        listing.to_account_info(),
+        &[seeds],
+    )?;
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: escrow.to_account_info(),
+            destination: rent_to.clone(),
+            authority: listing.to_account_info(),
+        },
+        &[seeds],
+    ))
+}
+
+/// Clears `owner`'s reverse record when it points at `name_hash`; called
+/// wherever a name leaves its owner so stale primaries do not linger. A
+/// record that does not decode (an old layout not yet migrated) is left
+/// alone rather than blocking the transfer; readers cannot decode it either.
+fn clear_primary_if_points_to(primary: &AccountInfo, owner: Pubkey, name_hash: [u8; 32]) -> Result<()> {
+    if primary.data_is_empty() {
+        return Ok(());
+    }
+    let mut data = primary.try_borrow_mut_data()?;
+    let Ok(mut record) = PrimaryName::try_deserialize(&mut &data[..]) else {
+        return Ok(());
+    };
+    if record.is_set && record.owner == owner && record.name_hash == name_hash {
+        record.clear();
+        record.try_serialize(&mut &mut data[..])?;
+        emit!(PrimaryNameCleared { owner, name_hash });
+    }
+    Ok(())
+}
+
+fn sealed_bid_hash(name_hash: [u8; 32], bidder: Pubkey, bid_lamports: u64, salt: [u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(name_hash);
+    hasher.update(bidder.as_ref());
+    hasher.update(bid_lamports.to_le_bytes());
+    hasher.update(salt);
+    hasher.finalize().into()
+}
+
+/// Folds a revealed bid into the top two. Returns whether it now leads
+/// (ties keep the earlier revealer ahead) and the new highest and second.
+fn rank_sealed_bid(highest: u64, second: u64, bid: u64) -> (bool, u64, u64) {
+    if bid > highest {
+        (true, bid, highest)
+    } else {
+        (false, highest, second.max(bid))
+    }
+}
+
+fn vickrey_price(second_bid: u64, min_bid: u64) -> u64 {
+    second_bid.max(min_bid)
+}
+
+/// Splits a winner's vault balance into the bid (to the treasury) and the
+/// remainder, the vault's own rent (back to the winner).
+fn split_winner_vault(vault_lamports: u64, bid_lamports: u64) -> Result<(u64, u64)> {
+    let rest = vault_lamports
+        .checked_sub(bid_lamports)
+        .ok_or_else(|| error!(NamesError::EscrowInsufficient))?;
+    Ok((bid_lamports, rest))
 }
 
-fn upsert_primary_if_empty(primary: &mut Account<PrimaryName>, owner: Pubkey, name_hash: [u8; 32], kind: u8, bump: u8) {
+// Escrow vaults are zero-data PDAs owned by this program, so their lamports
+// can be debited directly.
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> Result<()> {
+    if lamports == 0 {
+        return Ok(());
+    }
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or_else(|| error!(NamesError::EscrowInsufficient))?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(lamports)
+        .ok_or_else(|| error!(NamesError::MathOverflow))?;
+    Ok(())
+}
+
+/// `authority` is the first field of `NamesConfig` in every layout.
+fn stored_authority(config: &AccountInfo) -> Result<Pubkey> {
+    let data = config.try_borrow_data()?;
+    data.get(8..40)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(Pubkey::new_from_array)
+        .ok_or_else(|| error!(NamesError::InvalidMigration))
+}
+
+/// Decodes a program account, applies `update` and writes it back.
+fn rewrite_account<T: AccountSerialize + AccountDeserialize>(info: &AccountInfo, update: impl FnOnce(&mut T)) -> Result<()> {
+    let mut account = T::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    update(&mut account);
+    account.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])
+}
+
+/// Current size of each account type that has grown since it was first
+/// deployed.
+fn migrated_size(discriminator: &[u8]) -> Result<usize> {
+    let sizes: [(&[u8], usize); 7] = [
+        (NamesConfig::DISCRIMINATOR, NamesConfig::SIZE),
+        (PremiumConfig::DISCRIMINATOR, PremiumConfig::SIZE),
+        (PremiumName::DISCRIMINATOR, PremiumName::SIZE),
+        (ParentPolicy::DISCRIMINATOR, ParentPolicy::SIZE),
+        (SubName::DISCRIMINATOR, SubName::SIZE),
+        (PrimaryName::DISCRIMINATOR, PrimaryName::SIZE),
+        (Auction::DISCRIMINATOR, Auction::SIZE),
+    ];
+    sizes
+        .iter()
+        .find(|(disc, _)| *disc == discriminator)
+        .map(|(_, size)| *size)
+        .ok_or_else(|| error!(NamesError::InvalidMigration))
+}
+
+fn drain_vault(vault: &AccountInfo, to: &AccountInfo) -> Result<u64> {
+    let lamports = vault.lamports();
+    move_lamports(vault, to, lamports)?;
+    Ok(lamports)
+}
+
+/// Only premium names and first-level subdomains are upserted, so a
+/// subdomain's depth is always 1 here.
+fn upsert_primary_if_empty(
+    primary: &mut Account<PrimaryName>,
+    owner: Pubkey,
+    name: &str,
+    name_hash: [u8; 32],
+    kind: u8,
+    bump: u8,
+) {
     if !primary.is_set {
-        primary.owner = owner;
-        primary.name_hash = name_hash;
-        primary.kind = kind;
-        primary.is_set = true;
+        let depth = if kind == KIND_SUBDOMAIN { 1 } else { 0 };
+        primary.assign(owner, name, name_hash, kind, depth);
         primary.bump = bump;
     }
 }
 
+/// `name_hash` of a normalized name: `sha256(name)` for premium names,
+/// [`chained_name_hash`] at `depth` for subdomains.
+fn name_hash_for(kind: u8, normalized: &str, depth: u8) -> Result<[u8; 32]> {
+    match kind {
+        KIND_PREMIUM => Ok(hash_name(normalized)),
+        KIND_SUBDOMAIN => {
+            require!(depth > 0, NamesError::InvalidName);
+            chained_name_hash(normalized, depth)
+        }
+        _ => err!(NamesError::InvalidKind),
+    }
+}
+
+/// Hash of a name with `depth` subdomain labels: the remaining root (premium
+/// name or parent zone) is `sha256(root)`, then each label to the left folds
+/// in as `sha256(parent_hash || sha256(label))`.
+fn chained_name_hash(normalized: &str, depth: u8) -> Result<[u8; 32]> {
+    ddns_names_core::chained_name_hash(normalized, depth).ok_or_else(|| error!(NamesError::InvalidName))
+}
+
+/// `PREMIUM_ACTIVE` before `expires_at`, `PREMIUM_GRACE` for `grace_secs`
+/// after it, then `PREMIUM_RELEASABLE`.
+fn premium_state(now: i64, expires_at: i64, grace_secs: i64) -> u8 {
+    if now < expires_at {
+        PREMIUM_ACTIVE
+    } else if now < expires_at.saturating_add(grace_secs) {
+        PREMIUM_GRACE
+    } else {
+        PREMIUM_RELEASABLE
+    }
+}
+
+/// Lamport price of a premium label: a listed price wins, then the label's
+/// length tier, then the flat `NamesConfig.premium_price_lamports`.
+fn premium_price(flat: u64, tiers: &[u64; PRICE_TIERS], label_len: usize, listed: Option<u64>) -> u64 {
+    if let Some(price) = listed {
+        return price;
+    }
+    match tiers[label_len.clamp(1, PRICE_TIERS) - 1] {
+        0 => flat,
+        tier => tier,
+    }
+}
+
+/// `price_lamports` converted at `toll_units_per_sol`, rounded up.
+fn toll_amount(price_lamports: u64, toll_units_per_sol: u64) -> Result<u64> {
+    let units = (price_lamports as u128 * toll_units_per_sol as u128).div_ceil(LAMPORTS_PER_SOL);
+    u64::try_from(units).map_err(|_| error!(NamesError::MathOverflow))
+}
+
+fn listed_price(name_price: &UncheckedAccount, name_hash: [u8; 32]) -> Result<Option<u64>> {
+    if name_price.data_is_empty() {
+        return Ok(None);
+    }
+    let data = name_price.try_borrow_data()?;
+    let listed = NamePrice::try_deserialize(&mut &data[..])?;
+    require!(listed.name_hash == name_hash, NamesError::InvalidHash);
+    Ok(Some(listed.price_lamports))
+}
+
+/// A `SubLabelRule` for the label, if the parent set one.
+fn sub_label_rule(rule: &UncheckedAccount, parent_hash: [u8; 32], label_hash: [u8; 32]) -> Result<Option<SubLabelRule>> {
+    if rule.data_is_empty() {
+        return Ok(None);
+    }
+    let data = rule.try_borrow_data()?;
+    let rule = SubLabelRule::try_deserialize(&mut &data[..])?;
+    require!(rule.parent_hash == parent_hash && rule.label_hash == label_hash, NamesError::InvalidHash);
+    Ok(Some(rule))
+}
+
+/// Checks `leaf` against a Merkle `root` whose nodes are
+/// `sha256(min(a, b) || max(a, b))`, so proofs need no left/right flags.
+fn merkle_proof_valid(root: [u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let (a, b) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        let mut h = Sha256::new();
+        h.update(a);
+        h.update(b);
+        h.finalize().into()
+    });
+    computed == root
+}
+
+/// A subdomain is active while it and every subdomain above it are
+/// unexpired. `ancestors` are the `SubName`s from the parent up to depth 1,
+/// matched by hash, so a revoked ancestor (closed account) fails the check.
+/// Returns the live parent of a nested name.
+fn require_sub_active(sub: &SubName, ancestors: &[AccountInfo]) -> Result<Option<SubName>> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(!sub.is_expired(now), NamesError::SubdomainExpired);
+    let above = usize::from(sub.depth.saturating_sub(1));
+    require!(ancestors.len() >= above, NamesError::MissingRequiredAccount);
+    let mut parent = None;
+    let mut expected_hash = sub.parent_hash;
+    for info in &ancestors[..above] {
+        require_keys_eq!(*info.owner, crate::ID, NamesError::InvalidParent);
+        let ancestor = SubName::try_deserialize(&mut &info.try_borrow_data()?[..])
+            .map_err(|_| error!(NamesError::InvalidParent))?;
+        require!(
+            subdomain_name_hash(ancestor.parent_hash, ancestor.label_hash) == expected_hash,
+            NamesError::InvalidParent
+        );
+        require!(!ancestor.is_expired(now), NamesError::SubdomainExpired);
+        expected_hash = ancestor.parent_hash;
+        if parent.is_none() {
+            parent = Some(ancestor);
+        }
+    }
+    Ok(parent)
+}
+
+fn commitment_hash(name_hash: [u8; 32], owner: Pubkey, salt: [u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(name_hash);
+    hasher.update(owner.as_ref());
+    hasher.update(salt);
+    hasher.finalize().into()
+}
+
+fn commit_window_open(min_slots: u64, max_slots: u64, committed_slot: u64, now_slot: u64) -> bool {
+    let age = now_slot.saturating_sub(committed_slot);
+    age >= min_slots && (max_slots == 0 || age <= max_slots)
+}
+
+fn require_commit_window(cfg: &NamesConfig, c: &NameCommitment, now_slot: u64) -> Result<()> {
+    require!(
+        commit_window_open(cfg.commit_min_slots, cfg.commit_max_slots, c.committed_slot, now_slot),
+        NamesError::CommitWindowClosed
+    );
+    Ok(())
+}
+
+/// With a commitment, it must be revealed for `name_hash` and still inside
+/// the window (the account constraint closes it). Without one, commit-reveal
+/// must be disabled.
+fn consume_commitment(cfg: &NamesConfig, commitment: Option<&NameCommitment>, name_hash: [u8; 32]) -> Result<()> {
+    match commitment {
+        Some(c) => {
+            require!(c.revealed && c.name_hash == name_hash, NamesError::CommitmentMismatch);
+            require_commit_window(cfg, c, Clock::get()?.slot)
+        }
+        None => {
+            require!(!cfg.commit_required(), NamesError::CommitRequired);
+            Ok(())
+        }
+    }
+}
+
+fn renewal_cost(lamports_per_year: u64, years: u8) -> Result<u64> {
+    lamports_per_year
+        .checked_mul(years as u64)
+        .ok_or_else(|| error!(NamesError::MathOverflow))
+}
+
+fn require_not_expired(premium: &PremiumName) -> Result<()> {
+    require!(Clock::get()?.unix_timestamp < premium.expires_at, NamesError::NameExpired);
+    Ok(())
+}
+
+/// `owner` must own `name_hash`, given as a premium name or a subdomain.
+fn require_name_owner(
+    kind: u8,
+    name_hash: [u8; 32],
+    premium_name: Option<&Account<PremiumName>>,
+    sub_name: Option<&Account<SubName>>,
+    ancestors: &[AccountInfo],
+    owner: Pubkey,
+) -> Result<()> {
+    match kind {
+        KIND_PREMIUM => {
+            let premium = premium_name.ok_or_else(|| error!(NamesError::MissingRequiredAccount))?;
+            require!(premium.name_hash == name_hash, NamesError::InvalidHash);
+            require_keys_eq!(premium.owner, owner, NamesError::Unauthorized);
+            require_not_expired(premium)?;
+        }
+        KIND_SUBDOMAIN => {
+            let sub = sub_name.ok_or_else(|| error!(NamesError::MissingRequiredAccount))?;
+            require_keys_eq!(sub.owner, owner, NamesError::Unauthorized);
+            let computed = subdomain_name_hash(sub.parent_hash, sub.label_hash);
+            require!(computed == name_hash, NamesError::InvalidHash);
+            require_sub_active(sub, ancestors)?;
+        }
+        _ => return err!(NamesError::InvalidKind),
+    }
+    Ok(())
+}
+
+/// Unicode names arrive already UTS-46 mapped; each label must be LDH or a
+/// well-formed `xn--` A-label.
 fn normalize_full_name(input: &str) -> Result<String> {
-    let lower = input.trim().to_ascii_lowercase();
-    let normalized = lower.trim_end_matches('.').to_string();
-    require!(!normalized.is_empty() && normalized.len() <= 253, NamesError::InvalidName);
-    require!(!normalized.contains(".."), NamesError::InvalidName);
-    Ok(normalized)
+    ddns_names_core::normalize_name(input).map_err(|_| error!(NamesError::InvalidName))
 }
 
 fn normalize_label(input: &str) -> Result<String> {
@@ -1294,23 +4580,13 @@ fn normalize_label(input: &str) -> Result<String> {
 
 fn validate_label(label: &str) -> Result<()> {
     require!((3..=32).contains(&label.len()), NamesError::InvalidLabel);
-    let bytes = label.as_bytes();
-    require!(bytes[0] != b'-' && bytes[bytes.len() - 1] != b'-', NamesError::InvalidLabel);
-    for &b in bytes {
-        let ok = b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-';
-        require!(ok, NamesError::InvalidLabel);
-    }
+    require!(ddns_names_core::check_ldh_label(label).is_ok(), NamesError::InvalidLabel);
     Ok(())
 }
 
 fn validate_premium_label(label: &str) -> Result<()> {
     require!((1..=32).contains(&label.len()), NamesError::InvalidLabel);
-    let bytes = label.as_bytes();
-    require!(bytes[0] != b'-' && bytes[bytes.len() - 1] != b'-', NamesError::InvalidLabel);
-    for &b in bytes {
-        let ok = b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-';
-        require!(ok, NamesError::InvalidLabel);
-    }
+    require!(ddns_names_core::check_ldh_label(label).is_ok(), NamesError::InvalidLabel);
     Ok(())
 }
 
@@ -1321,23 +4597,6 @@ fn premium_label(normalized: &str) -> Result<&str> {
     Ok(parts[0])
 }
 
-fn hash_name(value: &str) -> [u8; 32] {
-    let digest = Sha256::digest(value.as_bytes());
-    digest.into()
-}
-
-fn hash_label(value: &str) -> [u8; 32] {
-    let digest = Sha256::digest(value.as_bytes());
-    digest.into()
-}
-
-fn subdomain_name_hash(parent_hash: [u8; 32], label_hash: [u8; 32]) -> [u8; 32] {
-    let mut h = Sha256::new();
-    h.update(parent_hash);
-    h.update(label_hash);
-    h.finalize().into()
-}
-
 #[error_code]
 pub enum NamesError {
     #[msg("Unauthorized")]
@@ -1404,4 +4663,337 @@ pub enum NamesError {
     InvalidTreasury,
     #[msg("Math overflow")]
     MathOverflow,
+    #[msg("Invalid record set or TTL")]
+    InvalidRecords,
+    #[msg("Invalid premium registration terms")]
+    InvalidPremiumTerms,
+    #[msg("Invalid renewal length")]
+    InvalidRenewal,
+    #[msg("Name has expired")]
+    NameExpired,
+    #[msg("Only the owner can renew during the grace period")]
+    GraceOwnerOnly,
+    #[msg("Name is still active or in its grace period")]
+    NameNotReleasable,
+    #[msg("Name is already registered")]
+    NameTaken,
+    #[msg("Auction for this name has not settled")]
+    AuctionInProgress,
+    #[msg("Toll token payment is not enabled")]
+    TollPaymentDisabled,
+    #[msg("Toll mint or treasury account does not match premium config")]
+    InvalidTollAccount,
+    #[msg("Invalid commit-reveal window")]
+    InvalidCommitWindow,
+    #[msg("Registration requires a revealed commitment")]
+    CommitRequired,
+    #[msg("Commitment does not match this name and owner")]
+    CommitmentMismatch,
+    #[msg("Commitment is too new or has expired")]
+    CommitWindowClosed,
+    #[msg("Auction already has bids")]
+    AuctionHasBids,
+    #[msg("Instruction does not match the auction mode")]
+    WrongAuctionMode,
+    #[msg("Sealed auction is not in its reveal phase")]
+    NotRevealPhase,
+    #[msg("Sealed bid already revealed")]
+    AlreadyRevealed,
+    #[msg("Invalid listing or market fee")]
+    InvalidListing,
+    #[msg("Listing price is above the buyer's maximum")]
+    PriceAboveMax,
+    #[msg("Invalid NFT metadata settings or accounts")]
+    InvalidNftMetadata,
+    #[msg("Label is reserved by the parent owner")]
+    LabelReserved,
+    #[msg("Claimer is not on the parent's allowlist")]
+    NotAllowlisted,
+    #[msg("Parent has sold its maximum number of subdomains")]
+    SubdomainLimitReached,
+    #[msg("Subdomain is held by another owner")]
+    SubdomainTaken,
+    #[msg("Subdomain has expired")]
+    SubdomainExpired,
+    #[msg("Payment mint or token accounts do not match the sale terms")]
+    InvalidPaymentAccount,
+    #[msg("Name is listed for sale")]
+    NameListed,
+    #[msg("Account has no layout to migrate to")]
+    InvalidMigration,
+    #[msg("Metadata accounts are required to update a program-issued name NFT")]
+    NftMetadataRequired,
+    #[msg("Name is longer than an NFT name can hold (32 bytes)")]
+    NftNameTooLong,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn premium_state_walks_active_grace_releasable() {
+        let expires_at = 1_000;
+        let grace = 100;
+        assert_eq!(premium_state(999, expires_at, grace), PREMIUM_ACTIVE);
+        assert_eq!(premium_state(1_000, expires_at, grace), PREMIUM_GRACE);
+        assert_eq!(premium_state(1_099, expires_at, grace), PREMIUM_GRACE);
+        assert_eq!(premium_state(1_100, expires_at, grace), PREMIUM_RELEASABLE);
+        // No grace: releasable as soon as it expires.
+        assert_eq!(premium_state(1_000, expires_at, 0), PREMIUM_RELEASABLE);
+    }
+
+    #[test]
+    fn premium_price_prefers_listing_then_tier_then_flat() {
+        let mut tiers = [0u64; PRICE_TIERS];
+        tiers[4] = 500; // 5-char labels
+        tiers[PRICE_TIERS - 1] = 80; // 8+ chars
+        assert_eq!(premium_price(100, &tiers, 5, None), 500);
+        assert_eq!(premium_price(100, &tiers, 6, None), 100);
+        assert_eq!(premium_price(100, &tiers, 8, None), 80);
+        assert_eq!(premium_price(100, &tiers, 63, None), 80);
+        assert_eq!(premium_price(100, &tiers, 5, Some(9_000)), 9_000);
+        assert_eq!(premium_price(100, &tiers, 5, Some(0)), 0);
+    }
+
+    #[test]
+    fn toll_amount_rounds_up() {
+        assert_eq!(toll_amount(1_000_000_000, 1_000_000).unwrap(), 1_000_000);
+        assert_eq!(toll_amount(1, 1_000_000).unwrap(), 1);
+        assert_eq!(toll_amount(0, 1_000_000).unwrap(), 0);
+        assert!(toll_amount(u64::MAX, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn commitment_binds_name_owner_and_salt() {
+        let owner = Pubkey::new_unique();
+        let c = commitment_hash([1; 32], owner, [9; 32]);
+        assert_eq!(c, commitment_hash([1; 32], owner, [9; 32]));
+        assert_ne!(c, commitment_hash([2; 32], owner, [9; 32]));
+        assert_ne!(c, commitment_hash([1; 32], Pubkey::new_unique(), [9; 32]));
+        assert_ne!(c, commitment_hash([1; 32], owner, [8; 32]));
+    }
+
+    #[test]
+    fn commit_window_bounds() {
+        assert!(!commit_window_open(5, 100, 10, 14));
+        assert!(commit_window_open(5, 100, 10, 15));
+        assert!(commit_window_open(5, 100, 10, 110));
+        assert!(!commit_window_open(5, 100, 10, 111));
+        assert!(commit_window_open(5, 0, 10, u64::MAX));
+    }
+
+    #[test]
+    fn winner_vault_pays_bid_and_returns_rent() {
+        assert_eq!(split_winner_vault(12_890_880, 12_000_000).unwrap(), (12_000_000, 890_880));
+        assert_eq!(split_winner_vault(5, 5).unwrap(), (5, 0));
+        assert!(split_winner_vault(4, 5).is_err());
+    }
+
+    #[test]
+    fn clear_primary_skips_records_it_cannot_decode() {
+        let (key, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let name_hash = [7u8; 32];
+        let mut record = PrimaryName {
+            owner,
+            name_hash,
+            kind: KIND_PREMIUM,
+            is_set: true,
+            bump: 255,
+            name_len: 0,
+            name_bytes: [0u8; MAX_NAME_BYTES],
+            depth: 0,
+        };
+        let mut current = Vec::new();
+        record.try_serialize(&mut current).unwrap();
+        // The layout before name bytes and depth were appended.
+        let mut legacy = current[..8 + 32 + 32 + 1 + 1 + 1].to_vec();
+        let legacy_before = legacy.clone();
+
+        let mut lamports = 1;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut legacy, &crate::ID, false, 0);
+        clear_primary_if_points_to(&info, owner, name_hash).unwrap();
+        drop(info);
+        assert_eq!(legacy, legacy_before);
+
+        let mut lamports = 1;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut current, &crate::ID, false, 0);
+        clear_primary_if_points_to(&info, owner, name_hash).unwrap();
+        drop(info);
+        record = PrimaryName::try_deserialize(&mut &current[..]).unwrap();
+        assert!(!record.is_set);
+    }
+
+    #[test]
+    fn move_lamports_debits_and_credits() {
+        let (k1, k2, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut l1, mut l2) = (100u64, 7u64);
+        let (mut d1, mut d2) = ([0u8; 0], [0u8; 0]);
+        let from = AccountInfo::new(&k1, false, true, &mut l1, &mut d1, &owner, false, 0);
+        let to = AccountInfo::new(&k2, false, true, &mut l2, &mut d2, &owner, false, 0);
+        move_lamports(&from, &to, 30).unwrap();
+        assert_eq!((from.lamports(), to.lamports()), (70, 37));
+        assert!(move_lamports(&from, &to, 71).is_err());
+        assert_eq!(drain_vault(&from, &to).unwrap(), 70);
+        assert_eq!((from.lamports(), to.lamports()), (0, 107));
+    }
+
+    #[test]
+    fn sealed_bids_rank_and_price_at_second_highest() {
+        let (mut highest, mut second) = (0, 0);
+        for (bid, leads) in [(30, true), (50, true), (40, false), (50, false), (10, false)] {
+            let (l, h, s) = rank_sealed_bid(highest, second, bid);
+            assert_eq!(l, leads, "bid {bid}");
+            (highest, second) = (h, s);
+        }
+        // A tie at the top sets the price to the full winning bid.
+        assert_eq!((highest, second), (50, 50));
+        assert_eq!(vickrey_price(second, 20), 50);
+        // Unopposed winner pays the minimum bid.
+        assert_eq!(rank_sealed_bid(0, 0, 70), (true, 70, 0));
+        assert_eq!(vickrey_price(0, 20), 20);
+    }
+
+    #[test]
+    fn sealed_bid_hash_binds_amount() {
+        let bidder = Pubkey::new_unique();
+        let h = sealed_bid_hash([3; 32], bidder, 1_000, [7; 32]);
+        assert_eq!(h, sealed_bid_hash([3; 32], bidder, 1_000, [7; 32]));
+        assert_ne!(h, sealed_bid_hash([3; 32], bidder, 1_001, [7; 32]));
+        assert_ne!(h, sealed_bid_hash([3; 32], Pubkey::new_unique(), 1_000, [7; 32]));
+    }
+
+    #[test]
+    fn listing_price_declines_linearly_then_floors() {
+        // Fixed price.
+        assert_eq!(listing_price(500, 500, 100, 100, 10_000), 500);
+        assert_eq!(listing_price(500, 100, 100, 100, 10_000), 500);
+        // Dutch: 1_000 -> 200 over 100s.
+        assert_eq!(listing_price(1_000, 200, 100, 200, 100), 1_000);
+        assert_eq!(listing_price(1_000, 200, 100, 200, 150), 600);
+        assert_eq!(listing_price(1_000, 200, 100, 200, 199), 208);
+        assert_eq!(listing_price(1_000, 200, 100, 200, 200), 200);
+        assert_eq!(listing_price(1_000, 200, 100, 200, 9_999), 200);
+    }
+
+    #[test]
+    fn market_fee_rounds_down() {
+        assert_eq!(market_fee(1_000_000, 250), 25_000);
+        assert_eq!(market_fee(399, 250), 9);
+        assert_eq!(market_fee(u64::MAX, MAX_MARKET_FEE_BPS), u64::MAX);
+        assert_eq!(market_fee(1_000, 0), 0);
+    }
+
+    #[test]
+    fn nft_uri_template_fills_placeholders() {
+        let mint = Pubkey::new_from_array([1u8; 32]);
+        let owner = Pubkey::new_from_array([2u8; 32]);
+        assert_eq!(
+            render_nft_uri("https://names.example/{mint}.json?owner={owner}&exp={expires}", &mint, &owner, 42),
+            format!("https://names.example/{mint}.json?owner={owner}&exp=42")
+        );
+        assert_eq!(render_nft_uri("", &mint, &owner, 42), "");
+    }
+
+    #[test]
+    fn nft_display_name_rejects_names_over_the_metadata_limit() {
+        assert_eq!(nft_display_name("alice.dns").unwrap(), "alice.dns");
+        let longest = format!("{}.dns", "a".repeat(28));
+        assert_eq!(nft_display_name(&longest).unwrap(), longest);
+        assert!(nft_display_name(&format!("{}.dns", "a".repeat(29))).is_err());
+    }
+
+    #[test]
+    fn name_hash_for_matches_premium_and_subdomain_hashing() {
+        assert_eq!(name_hash_for(KIND_PREMIUM, "alice.dns", 0).unwrap(), hash_name("alice.dns"));
+        assert_eq!(
+            name_hash_for(KIND_SUBDOMAIN, "www.alice.dns", 1).unwrap(),
+            subdomain_name_hash(hash_name("alice.dns"), hash_label("www"))
+        );
+        assert!(name_hash_for(KIND_SUBDOMAIN, "alice", 1).is_err());
+        assert!(name_hash_for(KIND_SUBDOMAIN, "www.alice.dns", 0).is_err());
+        assert!(name_hash_for(9, "alice.dns", 0).is_err());
+    }
+
+    #[test]
+    fn chained_name_hash_folds_one_label_per_level() {
+        let www = subdomain_name_hash(hash_name("alice.dns"), hash_label("www"));
+        let api = subdomain_name_hash(www, hash_label("api"));
+        assert_eq!(chained_name_hash("alice.dns", 0).unwrap(), hash_name("alice.dns"));
+        assert_eq!(chained_name_hash("api.www.alice.dns", 2).unwrap(), api);
+        // The split point matters: a two-label root hashes differently.
+        assert_ne!(chained_name_hash("api.www.alice.dns", 1).unwrap(), api);
+        assert!(chained_name_hash("alice.dns", 3).is_err());
+    }
+
+    #[test]
+    fn merkle_proof_valid_accepts_sorted_pair_proofs() {
+        let leaf = |n: u8| -> [u8; 32] { Sha256::digest(Pubkey::new_from_array([n; 32]).as_ref()).into() };
+        let node = |a: [u8; 32], b: [u8; 32]| -> [u8; 32] {
+            let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+            Sha256::new().chain_update(lo).chain_update(hi).finalize().into()
+        };
+        let (l1, l2, l3) = (leaf(1), leaf(2), leaf(3));
+        let root = node(node(l1, l2), l3);
+
+        assert!(merkle_proof_valid(root, l1, &[l2, l3]));
+        assert!(merkle_proof_valid(root, l2, &[l1, l3]));
+        assert!(merkle_proof_valid(root, l3, &[node(l1, l2)]));
+        assert!(!merkle_proof_valid(root, leaf(4), &[l2, l3]));
+        assert!(!merkle_proof_valid(root, l1, &[l3, l2]));
+    }
+
+    #[test]
+    fn sub_name_expiry_is_optional() {
+        let mut sub = SubName {
+            parent_hash: [0u8; 32],
+            label_hash: [0u8; 32],
+            owner: Pubkey::default(),
+            transfer_policy: TRANSFER_PARENT_CONTROLLED,
+            parent_owner: Pubkey::default(),
+            transfers_enabled: false,
+            created_at: 0,
+            nft_mint: Pubkey::default(),
+            nft_custody_account: Pubkey::default(),
+            bump: 0,
+            depth: 1,
+            expires_at: 0,
+        };
+        assert!(!sub.is_expired(i64::MAX));
+        sub.expires_at = 100;
+        assert!(!sub.is_expired(99));
+        assert!(sub.is_expired(100));
+    }
+
+    #[test]
+    fn names_normalize_and_hash_like_shared_vectors() {
+        for v in ddns_names_core::vectors::names() {
+            assert_eq!(normalize_full_name(v.input).ok().as_deref(), v.normalized, "{}", v.input);
+            if let (Some(name), Some(hash)) = (v.normalized, v.hash) {
+                assert_eq!(chained_name_hash(name, v.depth).unwrap(), hash, "{}", v.input);
+            }
+        }
+    }
+
+    #[test]
+    fn label_validation_follows_shared_idna_vectors() {
+        let vectors = include_str!("../../../crates/ddns_idna/vectors/labels.tsv");
+        for line in vectors.lines().filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let cols: Vec<&str> = line.split('\t').collect();
+            let (input, onchain) = (cols[0], cols[2] == "ok");
+            assert_eq!(validate_premium_label(input).is_ok(), onchain && input.len() <= 32, "{input}");
+            if input == input.to_ascii_lowercase() {
+                assert_eq!(normalize_full_name(&format!("www.{input}.dns")).is_ok(), onchain, "{input}");
+            }
+        }
+        assert!(validate_label("xn--mnchen-3ya").is_ok());
+        assert!(validate_label("ab--cd").is_err());
+    }
+
+    #[test]
+    fn renewal_cost_scales_by_years() {
+        assert_eq!(renewal_cost(0, 5).unwrap(), 0);
+        assert_eq!(renewal_cost(1_000, MAX_RENEWAL_YEARS).unwrap(), 10_000);
+        assert!(renewal_cost(u64::MAX, 2).is_err());
+    }
 }
@@ -2,7 +2,7 @@
 
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program_pack::Pack;
-use sha2::{Digest, Sha256};
+use ddns_names_core::hash_name;
 use spl_token::state::{Account as SplAccount, Mint as SplMint};
 
 declare_id!("FRoAXq829PMjtANWdnT16iKb7Unhq9NaViX3Mv9JNoeC");
@@ -15,6 +15,7 @@ const MAX_ALLOWLIST: usize = 64;
 pub mod ddns_ns_incentives {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_ns_config(
         ctx: Context<InitializeNsConfig>,
         epoch_len_slots: u64,
@@ -104,6 +105,7 @@ pub mod ddns_ns_incentives {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_ns_config(
         ctx: Context<UpdateNsConfig>,
         epoch_len_slots: u64,
@@ -170,14 +172,14 @@ pub mod ddns_ns_incentives {
         domain_hash: [u8; 32],
     ) -> Result<()> {
         let normalized = normalize_domain(&domain)?;
-        let expected = sha256_32(&normalized);
+        let expected = hash_name(&normalized);
         require!(expected == domain_hash, NsError::DomainHashMismatch);
 
         let claim = &mut ctx.accounts.ns_claim;
         claim.domain_hash = domain_hash;
         claim.domain_len = normalized.len() as u8;
         claim.domain_bytes = [0u8; MAX_DOMAIN_LEN];
-        claim.domain_bytes[..normalized.len()].copy_from_slice(&normalized);
+        claim.domain_bytes[..normalized.len()].copy_from_slice(normalized.as_bytes());
         claim.owner_wallet = ctx.accounts.owner_wallet.key();
         claim.created_at_slot = Clock::get()?.slot;
         claim.status = ClaimStatus::Active as u8;
@@ -269,7 +271,7 @@ pub mod ddns_ns_incentives {
         require!(from_epoch <= to_epoch, NsError::BadEpochRange);
         require!(from_epoch > claim.last_claimed_epoch, NsError::EpochAlreadyClaimed);
 
-        let epochs = (to_epoch - from_epoch + 1) as u64;
+        let epochs = to_epoch - from_epoch + 1;
         require!(
             epochs <= cfg.max_epochs_claim_range as u64,
             NsError::EpochRangeTooLarge
@@ -336,7 +338,7 @@ pub mod ddns_ns_incentives {
                 .0;
                 require_keys_eq!(att_info.key(), expected_att_key, NsError::BadAttestationPda);
 
-                if !unique_attestors.iter().any(|k| *k == att.attestor) {
+                if !unique_attestors.contains(&att.attestor) {
                     unique_attestors.push(att.attestor);
                 }
             }
@@ -390,27 +392,10 @@ fn require_is_allowlisted(cfg: &NsConfig, key: &Pubkey) -> Result<()> {
     Ok(())
 }
 
-fn normalize_domain(domain: &str) -> Result<Vec<u8>> {
-    let mut d = domain.trim().to_ascii_lowercase();
-    if d.ends_with('.') {
-        d.pop();
-    }
-    require!(!d.is_empty(), NsError::BadDomain);
+fn normalize_domain(domain: &str) -> Result<String> {
+    let d = ddns_names_core::normalize_name(domain).map_err(|_| error!(NsError::BadDomain))?;
     require!(d.len() <= MAX_DOMAIN_LEN, NsError::BadDomain);
-    for b in d.bytes() {
-        let ok = (b'a'..=b'z').contains(&b)
-            || (b'0'..=b'9').contains(&b)
-            || b == b'-'
-            || b == b'.';
-        require!(ok, NsError::BadDomain);
-    }
-    Ok(d.into_bytes())
-}
-
-fn sha256_32(bytes: &[u8]) -> [u8; 32] {
-    let mut h = Sha256::new();
-    h.update(bytes);
-    h.finalize().into()
+    Ok(d)
 }
 
 fn create_pda_account<'info>(
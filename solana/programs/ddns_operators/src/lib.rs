@@ -15,6 +15,7 @@ const MAX_ENDPOINTS: usize = 8;
 pub mod ddns_operators {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn init_operators_config(
         ctx: Context<InitOperatorsConfig>,
         epoch_len_slots: u64,
@@ -133,6 +134,7 @@ pub mod ddns_operators {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_operators_config(
         ctx: Context<UpdateOperatorsConfig>,
         epoch_len_slots: u64,
@@ -535,7 +537,7 @@ pub mod ddns_operators {
 
         let signer = ctx.accounts.signer.key();
         let is_authority = signer == cfg.authority;
-        let is_allowlisted = cfg.slashing_authorities.iter().any(|k| *k == signer);
+        let is_allowlisted = cfg.slashing_authorities.contains(&signer);
         require!(is_authority || is_allowlisted, OperatorsError::Unauthorized);
 
         let op = &mut ctx.accounts.operator;
@@ -5,44 +5,123 @@ use anchor_lang::prelude::*;
 declare_id!("7ru9XCvrBDys1te7MK6WLmMjNnQvhC5GZf2jPqAffucL");
 
 const MAX_VERIFIERS: usize = 64;
+const BPS_DENOM: u64 = 10_000;
 
 #[program]
 pub mod ddns_quorum {
     use super::*;
 
+    pub fn init_quorum_config(
+        ctx: Context<InitQuorumConfig>,
+        authority: Pubkey,
+        handover_slots: u64,
+    ) -> Result<()> {
+        let cfg = &mut ctx.accounts.quorum_config;
+        cfg.authority = authority;
+        cfg.handover_slots = handover_slots;
+        cfg.bump = ctx.bumps.quorum_config;
+        emit!(QuorumConfigUpdated {
+            authority,
+            handover_slots,
+            slot: Clock::get()?.slot,
+        });
+        Ok(())
+    }
+
+    pub fn update_quorum_config(
+        ctx: Context<UpdateQuorumConfig>,
+        authority: Pubkey,
+        handover_slots: u64,
+    ) -> Result<()> {
+        let cfg = &mut ctx.accounts.quorum_config;
+        cfg.authority = authority;
+        cfg.handover_slots = handover_slots;
+        emit!(QuorumConfigUpdated {
+            authority,
+            handover_slots,
+            slot: Clock::get()?.slot,
+        });
+        Ok(())
+    }
+
     pub fn init_quorum_authority(ctx: Context<InitQuorumAuthority>) -> Result<()> {
         let qa = &mut ctx.accounts.quorum_authority;
         qa.bump = ctx.bumps.quorum_authority;
         Ok(())
     }
 
-    pub fn init_verifier_set(
-        ctx: Context<InitVerifierSet>,
+    /// Builds the verifier set for the current or a future epoch from
+    /// `ddns_stake_gov`: active, unjailed verifiers weighted by the stake
+    /// positions (passed as remaining accounts) that delegate to them or belong
+    /// to them undelegated.
+    pub fn init_verifier_set<'info>(
+        ctx: Context<'_, '_, 'info, 'info, InitVerifierSet<'info>>,
         epoch_id: u64,
-        threshold_stake_weight: u64,
-        members: Vec<Pubkey>,
+        threshold_bps: u16,
     ) -> Result<()> {
-        require!(members.len() <= MAX_VERIFIERS, QuorumError::TooManyVerifiers);
+        require!(threshold_bps as u64 <= BPS_DENOM, QuorumError::BadThreshold);
+        let current_epoch =
+            epoch_id_from_slot(Clock::get()?.slot, ctx.accounts.registry_config.epoch_len_slots)?;
+        require!(epoch_id >= current_epoch, QuorumError::EpochEnded);
 
         let vs = &mut ctx.accounts.verifier_set;
         vs.epoch_id = epoch_id;
-        vs.admin = ctx.accounts.admin.key();
-        vs.threshold_stake_weight = threshold_stake_weight;
-        vs.members = members;
         vs.bump = ctx.bumps.verifier_set;
-        Ok(())
+        rebuild_members(
+            vs,
+            &ctx.accounts.stake_gov_config,
+            &ctx.accounts.verifier_registry,
+            ctx.remaining_accounts,
+            threshold_bps,
+        )
     }
 
-    pub fn update_verifier_set(
-        ctx: Context<UpdateVerifierSet>,
-        threshold_stake_weight: u64,
-        members: Vec<Pubkey>,
+    /// Rebuilds a scheduled or active set; sets are frozen once their epoch ends.
+    pub fn update_verifier_set<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateVerifierSet<'info>>,
+        epoch_id: u64,
+        threshold_bps: u16,
     ) -> Result<()> {
-        require!(members.len() <= MAX_VERIFIERS, QuorumError::TooManyVerifiers);
+        require!(threshold_bps as u64 <= BPS_DENOM, QuorumError::BadThreshold);
+        let current_epoch =
+            epoch_id_from_slot(Clock::get()?.slot, ctx.accounts.registry_config.epoch_len_slots)?;
+        require!(epoch_id >= current_epoch, QuorumError::EpochEnded);
+        rebuild_members(
+            &mut ctx.accounts.verifier_set,
+            &ctx.accounts.stake_gov_config,
+            &ctx.accounts.verifier_registry,
+            ctx.remaining_accounts,
+            threshold_bps,
+        )
+    }
+
+    /// Permissionless epoch-boundary crank: creates the current epoch's set
+    /// from `ddns_stake_gov` with the previous set's `threshold_bps`, unless the
+    /// authority already scheduled one. Weights are recomputed from the stake
+    /// positions passed as remaining accounts, never copied from the previous
+    /// set; the authority can `update_verifier_set` while the epoch runs.
+    pub fn roll_verifier_set<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RollVerifierSet<'info>>,
+        epoch_id: u64,
+    ) -> Result<()> {
+        let current_epoch =
+            epoch_id_from_slot(Clock::get()?.slot, ctx.accounts.registry_config.epoch_len_slots)?;
+        require!(current_epoch == epoch_id, QuorumError::WrongEpoch);
+
+        require!(!ctx.remaining_accounts.is_empty(), QuorumError::NoStakePositions);
+
+        let threshold_bps = ctx.accounts.previous_verifier_set.threshold_bps;
+
         let vs = &mut ctx.accounts.verifier_set;
-        vs.threshold_stake_weight = threshold_stake_weight;
-        vs.members = members;
-        Ok(())
+        vs.epoch_id = epoch_id;
+        vs.bump = ctx.bumps.verifier_set;
+        rebuild_members(
+            vs,
+            &ctx.accounts.stake_gov_config,
+            &ctx.accounts.verifier_registry,
+            ctx.remaining_accounts,
+            threshold_bps,
+        )
     }
 
     pub fn submit_stake_snapshot(
@@ -63,9 +142,17 @@ pub mod ddns_quorum {
         snap.user_stake_root = user_stake_root;
         snap.created_at_slot = Clock::get()?.slot;
         snap.bump = ctx.bumps.stake_snapshot;
+        emit!(StakeSnapshotSubmitted {
+            epoch_id,
+            submitter: ctx.accounts.submitter.key(),
+            user_stake_root,
+            total_stake,
+            slot: snap.created_at_slot,
+        });
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn submit_aggregate(
         ctx: Context<SubmitAggregate>,
         epoch_id: u64,
@@ -82,6 +169,7 @@ pub mod ddns_quorum {
             epoch_id_from_slot(Clock::get()?.slot, ctx.accounts.registry_config.epoch_len_slots)?;
         require!(current_epoch == epoch_id, QuorumError::WrongEpoch);
 
+        let aggregate = ctx.accounts.aggregate.key();
         let agg = &mut ctx.accounts.aggregate;
         agg.epoch_id = epoch_id;
         agg.name_hash = name_hash;
@@ -93,9 +181,24 @@ pub mod ddns_quorum {
         agg.submitter = ctx.accounts.submitter.key();
         agg.submitted_at_slot = Clock::get()?.slot;
         agg.bump = ctx.bumps.aggregate;
+        emit!(AggregateSubmitted {
+            epoch_id,
+            name_hash,
+            dest_hash,
+            ttl_s,
+            receipt_count,
+            receipts_root,
+            submitter: agg.submitter,
+            aggregate,
+            slot: agg.submitted_at_slot,
+        });
         Ok(())
     }
 
+    /// Finalizes a route once matching aggregates from distinct verifier-set
+    /// members carry enough stake. Remaining accounts are the aggregates, one
+    /// per submitter; stake is each member's weight recorded in the verifier
+    /// set, not the aggregate's `stake_weight`.
     pub fn finalize_if_quorum(
         ctx: Context<FinalizeIfQuorum>,
         epoch_id: u64,
@@ -105,28 +208,33 @@ pub mod ddns_quorum {
     ) -> Result<()> {
         let cfg = &ctx.accounts.registry_config;
         let vs = &ctx.accounts.verifier_set;
-        let agg = &ctx.accounts.aggregate;
 
         require!(vs.epoch_id == epoch_id, QuorumError::WrongEpoch);
-        require!(agg.epoch_id == epoch_id, QuorumError::WrongEpoch);
-        require!(agg.name_hash == name_hash, QuorumError::AggregateMismatch);
-        require!(agg.dest_hash == dest_hash, QuorumError::AggregateMismatch);
-        require!(agg.ttl_s == ttl_s, QuorumError::AggregateMismatch);
-
-        // Ensure the aggregate is from the current epoch and not stale.
-        let current_epoch = epoch_id_from_slot(Clock::get()?.slot, cfg.epoch_len_slots)?;
-        require!(current_epoch == epoch_id, QuorumError::WrongEpoch);
 
+        // Aggregates finalize during their epoch or the handover window after it.
         require!(
-            agg.receipt_count >= cfg.min_receipts,
-            QuorumError::NotEnoughReceipts
+            within_finalize_window(
+                Clock::get()?.slot,
+                cfg.epoch_len_slots,
+                epoch_id,
+                ctx.accounts.quorum_config.handover_slots,
+            )?,
+            QuorumError::WrongEpoch
         );
 
-        let required_weight = cfg.min_stake_weight.max(vs.threshold_stake_weight);
-        require!(
-            agg.stake_weight >= required_weight,
-            QuorumError::NotEnoughStakeWeight
-        );
+        let remaining = ctx.remaining_accounts;
+        let tally = tally_aggregates(remaining, cfg, vs, epoch_id, &name_hash, &dest_hash, ttl_s)?;
+
+        emit!(QuorumReached {
+            epoch_id,
+            name_hash,
+            dest_hash,
+            ttl_s,
+            stake_weight: tally.total_stake,
+            required_weight: tally.required_weight,
+            submitters: tally.stakes.iter().map(|(submitter, _)| *submitter).collect(),
+            slot: Clock::get()?.slot,
+        });
 
         // CPI to ddns_registry.finalize_route using the ddns_quorum PDA as signer.
         let qa_seeds: &[&[u8]] = &[b"quorum_authority", &[ctx.accounts.quorum_authority.bump]];
@@ -138,6 +246,7 @@ pub mod ddns_quorum {
             finalize_authority: ctx.accounts.quorum_authority.to_account_info(),
             config: ctx.accounts.registry_config.to_account_info(),
             canonical_route: ctx.accounts.canonical_route.to_account_info(),
+            route_history: ctx.accounts.route_history.to_account_info(),
             system_program: ctx.accounts.system_program.to_account_info(),
         };
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
@@ -146,11 +255,227 @@ pub mod ddns_quorum {
             name_hash,
             dest_hash,
             ttl_s,
-            ctx.accounts.aggregate.key(),
+            tally.aggregate,
         )?;
 
         Ok(())
     }
+
+    /// Finalizes the record set `(name_hash, rrtype)` from `data`, a canonical
+    /// `ddns_rrset` encoding. Aggregates are submitted under
+    /// `ddns_rrset::rrset_key(name_hash, rrtype)` with `dest_hash =
+    /// ddns_rrset::rrset_hash(data)`; ddns_registry validates the encoding.
+    pub fn finalize_rrset_if_quorum(
+        ctx: Context<FinalizeRrsetIfQuorum>,
+        epoch_id: u64,
+        name_hash: [u8; 32],
+        rrtype: u16,
+        ttl_s: u32,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.registry_config;
+        let vs = &ctx.accounts.verifier_set;
+
+        require!(vs.epoch_id == epoch_id, QuorumError::WrongEpoch);
+        require!(
+            within_finalize_window(
+                Clock::get()?.slot,
+                cfg.epoch_len_slots,
+                epoch_id,
+                ctx.accounts.quorum_config.handover_slots,
+            )?,
+            QuorumError::WrongEpoch
+        );
+
+        let key = ddns_rrset::rrset_key(&name_hash, rrtype);
+        let rrset_hash = ddns_rrset::rrset_hash(&data);
+        let remaining = ctx.remaining_accounts;
+        let tally = tally_aggregates(remaining, cfg, vs, epoch_id, &key, &rrset_hash, ttl_s)?;
+
+        emit!(QuorumReached {
+            epoch_id,
+            name_hash: key,
+            dest_hash: rrset_hash,
+            ttl_s,
+            stake_weight: tally.total_stake,
+            required_weight: tally.required_weight,
+            submitters: tally.stakes.iter().map(|(submitter, _)| *submitter).collect(),
+            slot: Clock::get()?.slot,
+        });
+
+        let qa_seeds: &[&[u8]] = &[b"quorum_authority", &[ctx.accounts.quorum_authority.bump]];
+        let signer_seeds: &[&[&[u8]]] = &[qa_seeds];
+        let cpi_accounts = ddns_registry::cpi::accounts::FinalizeRrset {
+            payer: ctx.accounts.payer.to_account_info(),
+            finalize_authority: ctx.accounts.quorum_authority.to_account_info(),
+            config: ctx.accounts.registry_config.to_account_info(),
+            record_set: ctx.accounts.record_set.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.ddns_registry_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        ddns_registry::cpi::finalize_rrset(
+            cpi_ctx,
+            name_hash,
+            rrtype,
+            ttl_s,
+            data,
+            tally.aggregate,
+        )
+    }
+
+    /// Records a verifier's vote to roll `name_hash` back from
+    /// `disputed_version` to `target_version`. Votes are tallied per target
+    /// and epoch, so a vote for another target or in another epoch opens its
+    /// own dispute instead of blocking this one. Once the votes' combined weight
+    /// in the current verifier set reaches its threshold, the rollback is sent
+    /// to ddns_registry, which enforces the dispute window.
+    pub fn vote_route_dispute(
+        ctx: Context<VoteRouteDispute>,
+        epoch_id: u64,
+        name_hash: [u8; 32],
+        disputed_version: u64,
+        target_version: u64,
+    ) -> Result<()> {
+        let current_epoch =
+            epoch_id_from_slot(Clock::get()?.slot, ctx.accounts.registry_config.epoch_len_slots)?;
+        require!(current_epoch == epoch_id, QuorumError::WrongEpoch);
+        require!(
+            ctx.accounts.canonical_route.version == disputed_version,
+            QuorumError::DisputeStale
+        );
+
+        let voter = ctx.accounts.voter.key();
+        let weight = member_weight(&ctx.accounts.verifier_set, &voter)?;
+        let threshold = ctx.accounts.verifier_set.threshold_stake_weight;
+
+        let dispute = &mut ctx.accounts.route_dispute;
+        if dispute.voters.is_empty() {
+            dispute.name_hash = name_hash;
+            dispute.disputed_version = disputed_version;
+            dispute.target_version = target_version;
+            dispute.epoch_id = epoch_id;
+            dispute.bump = ctx.bumps.route_dispute;
+        }
+        require!(!dispute.executed, QuorumError::DisputeExecuted);
+        require!(!dispute.voters.contains(&voter), QuorumError::DuplicateSubmitter);
+        require!(dispute.voters.len() < MAX_VERIFIERS, QuorumError::TooManyVerifiers);
+        dispute.voters.push(voter);
+        dispute.stake_weight = dispute
+            .stake_weight
+            .checked_add(weight)
+            .ok_or(QuorumError::Overflow)?;
+        let executed = dispute.stake_weight >= threshold.max(1);
+        dispute.executed = executed;
+
+        emit!(RouteDisputeVoted {
+            name_hash,
+            disputed_version,
+            target_version,
+            epoch_id,
+            voter,
+            voter_weight: weight,
+            stake_weight: dispute.stake_weight,
+            threshold_stake_weight: threshold,
+            executed,
+            slot: Clock::get()?.slot,
+        });
+        if !executed {
+            return Ok(());
+        }
+
+        let qa_seeds: &[&[u8]] = &[b"quorum_authority", &[ctx.accounts.quorum_authority.bump]];
+        let signer_seeds: &[&[&[u8]]] = &[qa_seeds];
+        let cpi_accounts = ddns_registry::cpi::accounts::RollbackRoute {
+            dispute_authority: ctx.accounts.quorum_authority.to_account_info(),
+            config: ctx.accounts.registry_config.to_account_info(),
+            dispute_config: ctx.accounts.dispute_config.to_account_info(),
+            canonical_route: ctx.accounts.canonical_route.to_account_info(),
+            route_history: ctx.accounts.route_history.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.ddns_registry_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        ddns_registry::cpi::rollback_route(cpi_ctx, name_hash, target_version)
+    }
+}
+
+/// Verifier-set weight behind a set of matching aggregates.
+struct Tally {
+    /// First counted aggregate, recorded as the route's `last_aggregate`.
+    aggregate: Pubkey,
+    stakes: Vec<(Pubkey, u64)>,
+    total_stake: u64,
+    required_weight: u64,
+}
+
+/// Checks each aggregate in `remaining` (PDA, epoch, key, dest, TTL, receipt
+/// count) and sums the verifier-set weight of their distinct submitters.
+/// Members without weight are skipped, and at least 1 weight is required
+/// even when the set's threshold is 0.
+fn tally_aggregates(
+    remaining: &[AccountInfo],
+    cfg: &ddns_registry::Config,
+    vs: &VerifierSet,
+    epoch_id: u64,
+    name_hash: &[u8; 32],
+    dest_hash: &[u8; 32],
+    ttl_s: u32,
+) -> Result<Tally> {
+    require!(!remaining.is_empty(), QuorumError::BadRemainingAccounts);
+
+    let mut aggregate = None;
+    let mut stakes: Vec<(Pubkey, u64)> = Vec::with_capacity(remaining.len());
+    for agg_info in remaining {
+        let agg: AggregateSubmission = load_account(agg_info, &crate::ID)?;
+        let expected_agg = Pubkey::create_program_address(
+            &[
+                b"agg",
+                epoch_id.to_le_bytes().as_ref(),
+                name_hash.as_ref(),
+                agg.submitter.as_ref(),
+                &[agg.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| error!(QuorumError::BadAggregateAccount))?;
+        require_keys_eq!(agg_info.key(), expected_agg, QuorumError::BadAggregateAccount);
+
+        require!(agg.epoch_id == epoch_id, QuorumError::WrongEpoch);
+        require!(agg.name_hash == *name_hash, QuorumError::AggregateMismatch);
+        require!(agg.dest_hash == *dest_hash, QuorumError::AggregateMismatch);
+        require!(agg.ttl_s == ttl_s, QuorumError::AggregateMismatch);
+        require!(
+            agg.receipt_count >= cfg.min_receipts,
+            QuorumError::NotEnoughReceipts
+        );
+
+        let weight = member_weight(vs, &agg.submitter)?;
+        if weight == 0 {
+            continue;
+        }
+        aggregate.get_or_insert(agg_info.key());
+        stakes.push((agg.submitter, weight));
+    }
+
+    let total_stake = sum_distinct_stake(&stakes)?;
+    let required_weight = cfg.min_stake_weight.max(vs.threshold_stake_weight).max(1);
+    require!(
+        total_stake >= required_weight,
+        QuorumError::NotEnoughStakeWeight
+    );
+
+    Ok(Tally {
+        aggregate: aggregate.ok_or(QuorumError::NotEnoughStakeWeight)?,
+        stakes,
+        total_stake,
+        required_weight,
+    })
 }
 
 fn epoch_id_from_slot(slot: u64, epoch_len_slots: u64) -> Result<u64> {
@@ -158,16 +483,205 @@ fn epoch_id_from_slot(slot: u64, epoch_len_slots: u64) -> Result<u64> {
     Ok(slot / epoch_len_slots)
 }
 
+/// True while `slot` is in `epoch_id` or within the first `handover_slots` of
+/// the next epoch, so routes aggregated at an epoch's end are not stranded
+/// when the next verifier set takes over.
+fn within_finalize_window(
+    slot: u64,
+    epoch_len_slots: u64,
+    epoch_id: u64,
+    handover_slots: u64,
+) -> Result<bool> {
+    let current_epoch = epoch_id_from_slot(slot, epoch_len_slots)?;
+    if current_epoch == epoch_id {
+        return Ok(true);
+    }
+    if current_epoch != epoch_id.saturating_add(1) {
+        return Ok(false);
+    }
+    Ok(slot % epoch_len_slots < handover_slots)
+}
+
 fn require_is_member(vs: &VerifierSet, key: &Pubkey) -> Result<()> {
-    require!(vs.members.iter().any(|k| k == key), QuorumError::NotVerifier);
+    member_weight(vs, key).map(|_| ())
+}
+
+fn member_weight(vs: &VerifierSet, key: &Pubkey) -> Result<u64> {
+    vs.members
+        .iter()
+        .find(|m| m.verifier == *key)
+        .map(|m| m.stake_weight)
+        .ok_or_else(|| error!(QuorumError::NotVerifier))
+}
+
+/// Verifiers the stake_gov registry lists as active and not jailed.
+fn eligible_verifiers(
+    gov_config: &ddns_stake_gov::StakeGovConfig,
+    registry: &ddns_stake_gov::VerifierRegistry,
+) -> Result<Vec<Pubkey>> {
+    let gov_epoch = epoch_id_from_slot(Clock::get()?.slot, gov_config.epoch_len_slots)?;
+    Ok(registry
+        .verifiers
+        .iter()
+        .filter(|v| v.active && v.jailed_until_epoch <= gov_epoch)
+        .map(|v| v.verifier)
+        .collect())
+}
+
+/// Recomputes `members`, totals and the absolute threshold from the stake_gov
+/// registry and the given stake positions.
+fn rebuild_members(
+    vs: &mut VerifierSet,
+    gov_config: &ddns_stake_gov::StakeGovConfig,
+    registry: &ddns_stake_gov::VerifierRegistry,
+    positions: &[AccountInfo],
+    threshold_bps: u16,
+) -> Result<()> {
+    let eligible = eligible_verifiers(gov_config, registry)?;
+
+    let mut stakes: Vec<(Pubkey, Pubkey, u64)> = Vec::with_capacity(positions.len());
+    for info in positions {
+        let pos: ddns_stake_gov::StakePosition = load_account(info, &ddns_stake_gov::ID)?;
+        let expected = Pubkey::create_program_address(
+            &[b"stake", pos.owner.as_ref(), &[pos.bump]],
+            &ddns_stake_gov::ID,
+        )
+        .map_err(|_| error!(QuorumError::BadStakePosition))?;
+        require_keys_eq!(info.key(), expected, QuorumError::BadStakePosition);
+        stakes.push((
+            pos.owner,
+            pos.delegated_to,
+            pos.staked_amount.saturating_sub(pos.pending_withdraw_amount),
+        ));
+    }
+
+    let (members, total) = compute_member_weights(&eligible, &stakes)?;
+    set_members(vs, members, total, threshold_bps)
+}
+
+fn set_members(vs: &mut VerifierSet, members: Vec<VerifierMember>, total: u64, threshold_bps: u16) -> Result<()> {
+    vs.members = members;
+    vs.total_stake_weight = total;
+    vs.threshold_bps = threshold_bps;
+    vs.threshold_stake_weight = threshold_from_bps(total, threshold_bps);
+    emit!(VerifierSetUpdated {
+        epoch_id: vs.epoch_id,
+        threshold_bps,
+        threshold_stake_weight: vs.threshold_stake_weight,
+        total_stake_weight: total,
+        members: vs.members.clone(),
+        slot: Clock::get()?.slot,
+    });
     Ok(())
 }
 
+/// Weights each eligible verifier by the stake attributed to it: a position
+/// counts toward `delegated_to`, or toward its owner when undelegated.
+/// Positions attributed to non-members are ignored; a position listed twice is
+/// rejected.
+fn compute_member_weights(
+    eligible: &[Pubkey],
+    stakes: &[(Pubkey, Pubkey, u64)],
+) -> Result<(Vec<VerifierMember>, u64)> {
+    let mut members: Vec<VerifierMember> = eligible
+        .iter()
+        .map(|v| VerifierMember {
+            verifier: *v,
+            stake_weight: 0,
+        })
+        .collect();
+    let mut total: u64 = 0;
+    for (i, (owner, delegated_to, amount)) in stakes.iter().enumerate() {
+        require!(
+            !stakes[..i].iter().any(|(o, _, _)| o == owner),
+            QuorumError::DuplicateStakePosition
+        );
+        let target = if *delegated_to == Pubkey::default() {
+            owner
+        } else {
+            delegated_to
+        };
+        if let Some(m) = members.iter_mut().find(|m| m.verifier == *target) {
+            m.stake_weight = m.stake_weight.checked_add(*amount).ok_or(QuorumError::Overflow)?;
+            total = total.checked_add(*amount).ok_or(QuorumError::Overflow)?;
+        }
+    }
+    Ok((members, total))
+}
+
+/// `ceil(total * bps / 10_000)`, so a non-zero fraction never rounds to 0.
+fn threshold_from_bps(total: u64, bps: u16) -> u64 {
+    let num = (total as u128) * (bps as u128);
+    num.div_ceil(BPS_DENOM as u128) as u64
+}
+
+fn load_account<T: AccountDeserialize>(info: &AccountInfo, owner: &Pubkey) -> Result<T> {
+    require_keys_eq!(*info.owner, *owner, QuorumError::BadAccountOwner);
+    let data = info.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Sums per-submitter stake, rejecting a submitter counted twice.
+fn sum_distinct_stake(stakes: &[(Pubkey, u64)]) -> Result<u64> {
+    let mut total: u64 = 0;
+    for (i, (submitter, stake)) in stakes.iter().enumerate() {
+        require!(
+            !stakes[..i].iter().any(|(k, _)| k == submitter),
+            QuorumError::DuplicateSubmitter
+        );
+        total = total.checked_add(*stake).ok_or(QuorumError::Overflow)?;
+    }
+    Ok(total)
+}
+
+/// Only the program's upgrade authority may create the config, so the first
+/// caller after deploy cannot claim the quorum authority.
+#[derive(Accounts)]
+pub struct InitQuorumConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ QuorumError::NotUpgradeAuthority)]
+    pub program: Program<'info, crate::program::DdnsQuorum>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()) @ QuorumError::NotUpgradeAuthority)]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + QuorumConfig::SIZE,
+        seeds = [b"quorum_config"],
+        bump
+    )]
+    pub quorum_config: Account<'info, QuorumConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateQuorumConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"quorum_config"],
+        bump = quorum_config.bump,
+        has_one = authority
+    )]
+    pub quorum_config: Account<'info, QuorumConfig>,
+}
+
 #[derive(Accounts)]
 pub struct InitQuorumAuthority<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"quorum_config"], bump = quorum_config.bump, has_one = authority)]
+    pub quorum_config: Account<'info, QuorumConfig>,
+
     #[account(
         init,
         payer = payer,
@@ -184,32 +698,126 @@ pub struct InitQuorumAuthority<'info> {
 #[instruction(epoch_id: u64)]
 pub struct InitVerifierSet<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub payer: Signer<'info>,
+
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"quorum_config"], bump = quorum_config.bump, has_one = authority)]
+    pub quorum_config: Account<'info, QuorumConfig>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = registry_config.bump,
+        seeds::program = ddns_registry::ID
+    )]
+    pub registry_config: Account<'info, ddns_registry::Config>,
 
     #[account(
         init,
-        payer = admin,
+        payer = payer,
         space = 8 + VerifierSet::SIZE,
         seeds = [b"verifierset", epoch_id.to_le_bytes().as_ref()],
         bump
     )]
     pub verifier_set: Account<'info, VerifierSet>,
 
+    #[account(
+        seeds = [b"stake_gov_config"],
+        bump = stake_gov_config.bump,
+        seeds::program = ddns_stake_gov::ID
+    )]
+    pub stake_gov_config: Account<'info, ddns_stake_gov::StakeGovConfig>,
+
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = verifier_registry.bump,
+        seeds::program = ddns_stake_gov::ID
+    )]
+    pub verifier_registry: Account<'info, ddns_stake_gov::VerifierRegistry>,
+
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(epoch_id: u64)]
 pub struct UpdateVerifierSet<'info> {
-    pub admin: Signer<'info>,
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"quorum_config"], bump = quorum_config.bump, has_one = authority)]
+    pub quorum_config: Account<'info, QuorumConfig>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = registry_config.bump,
+        seeds::program = ddns_registry::ID
+    )]
+    pub registry_config: Account<'info, ddns_registry::Config>,
 
     #[account(
         mut,
         seeds = [b"verifierset", epoch_id.to_le_bytes().as_ref()],
-        bump = verifier_set.bump,
-        has_one = admin
+        bump = verifier_set.bump
     )]
     pub verifier_set: Account<'info, VerifierSet>,
+
+    #[account(
+        seeds = [b"stake_gov_config"],
+        bump = stake_gov_config.bump,
+        seeds::program = ddns_stake_gov::ID
+    )]
+    pub stake_gov_config: Account<'info, ddns_stake_gov::StakeGovConfig>,
+
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = verifier_registry.bump,
+        seeds::program = ddns_stake_gov::ID
+    )]
+    pub verifier_registry: Account<'info, ddns_stake_gov::VerifierRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
+pub struct RollVerifierSet<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = registry_config.bump,
+        seeds::program = ddns_registry::ID
+    )]
+    pub registry_config: Account<'info, ddns_registry::Config>,
+
+    #[account(
+        seeds = [b"verifierset", epoch_id.checked_sub(1).ok_or(QuorumError::WrongEpoch)?.to_le_bytes().as_ref()],
+        bump = previous_verifier_set.bump
+    )]
+    pub previous_verifier_set: Account<'info, VerifierSet>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + VerifierSet::SIZE,
+        seeds = [b"verifierset", epoch_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub verifier_set: Account<'info, VerifierSet>,
+
+    #[account(
+        seeds = [b"stake_gov_config"],
+        bump = stake_gov_config.bump,
+        seeds::program = ddns_stake_gov::ID
+    )]
+    pub stake_gov_config: Account<'info, ddns_stake_gov::StakeGovConfig>,
+
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = verifier_registry.bump,
+        seeds::program = ddns_stake_gov::ID
+    )]
+    pub verifier_registry: Account<'info, ddns_stake_gov::VerifierRegistry>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -279,7 +887,7 @@ pub struct SubmitAggregate<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(epoch_id: u64, name_hash: [u8;32])]
+#[instruction(epoch_id: u64)]
 pub struct FinalizeIfQuorum<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -297,11 +905,8 @@ pub struct FinalizeIfQuorum<'info> {
     )]
     pub verifier_set: Account<'info, VerifierSet>,
 
-    #[account(
-        seeds = [b"agg", epoch_id.to_le_bytes().as_ref(), name_hash.as_ref(), aggregate.submitter.as_ref()],
-        bump = aggregate.bump
-    )]
-    pub aggregate: Account<'info, AggregateSubmission>,
+    #[account(seeds = [b"quorum_config"], bump = quorum_config.bump)]
+    pub quorum_config: Account<'info, QuorumConfig>,
 
     // This PDA is the signer that ddns_registry trusts for finalization.
     #[account(seeds = [b"quorum_authority"], bump = quorum_authority.bump)]
@@ -312,11 +917,119 @@ pub struct FinalizeIfQuorum<'info> {
     #[account(mut)]
     pub canonical_route: UncheckedAccount<'info>,
 
+    /// CHECK: seeds/owner enforced by ddns_registry during CPI
+    #[account(mut)]
+    pub route_history: UncheckedAccount<'info>,
+
     pub ddns_registry_program: Program<'info, ddns_registry::program::DdnsRegistry>,
 
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
+pub struct FinalizeRrsetIfQuorum<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = registry_config.bump,
+        seeds::program = ddns_registry_program.key()
+    )]
+    pub registry_config: Account<'info, ddns_registry::Config>,
+
+    #[account(
+        seeds = [b"verifierset", epoch_id.to_le_bytes().as_ref()],
+        bump = verifier_set.bump
+    )]
+    pub verifier_set: Account<'info, VerifierSet>,
+
+    #[account(seeds = [b"quorum_config"], bump = quorum_config.bump)]
+    pub quorum_config: Account<'info, QuorumConfig>,
+
+    #[account(seeds = [b"quorum_authority"], bump = quorum_authority.bump)]
+    pub quorum_authority: Account<'info, QuorumAuthority>,
+
+    /// CHECK: seeds/owner enforced by ddns_registry during CPI
+    #[account(mut)]
+    pub record_set: UncheckedAccount<'info>,
+
+    pub ddns_registry_program: Program<'info, ddns_registry::program::DdnsRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64, name_hash: [u8;32], disputed_version: u64, target_version: u64)]
+pub struct VoteRouteDispute<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = registry_config.bump,
+        seeds::program = ddns_registry_program.key()
+    )]
+    pub registry_config: Account<'info, ddns_registry::Config>,
+
+    /// CHECK: seeds/owner enforced by ddns_registry during CPI
+    pub dispute_config: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"verifierset", epoch_id.to_le_bytes().as_ref()],
+        bump = verifier_set.bump
+    )]
+    pub verifier_set: Account<'info, VerifierSet>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + RouteDispute::SIZE,
+        seeds = [
+            b"dispute",
+            name_hash.as_ref(),
+            disputed_version.to_le_bytes().as_ref(),
+            target_version.to_le_bytes().as_ref(),
+            epoch_id.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub route_dispute: Account<'info, RouteDispute>,
+
+    #[account(seeds = [b"quorum_authority"], bump = quorum_authority.bump)]
+    pub quorum_authority: Account<'info, QuorumAuthority>,
+
+    #[account(
+        mut,
+        seeds = [b"canonical", name_hash.as_ref()],
+        bump = canonical_route.bump,
+        seeds::program = ddns_registry_program.key()
+    )]
+    pub canonical_route: Account<'info, ddns_registry::CanonicalRoute>,
+
+    /// CHECK: seeds/owner enforced by ddns_registry during CPI
+    #[account(mut)]
+    pub route_history: UncheckedAccount<'info>,
+
+    pub ddns_registry_program: Program<'info, ddns_registry::program::DdnsRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct QuorumConfig {
+    // Governance authority for verifier sets and the quorum authority PDA.
+    pub authority: Pubkey,
+    // Slots into epoch N+1 during which epoch N aggregates may still finalize.
+    pub handover_slots: u64,
+    pub bump: u8,
+}
+
+impl QuorumConfig {
+    pub const SIZE: usize = 32 + 8 + 1;
+}
+
 #[account]
 pub struct QuorumAuthority {
     pub bump: u8,
@@ -329,14 +1042,26 @@ impl QuorumAuthority {
 #[account]
 pub struct VerifierSet {
     pub epoch_id: u64,
-    pub admin: Pubkey,
+    pub threshold_bps: u16,
+    // Derived: ceil(total_stake_weight * threshold_bps / 10_000).
     pub threshold_stake_weight: u64,
-    pub members: Vec<Pubkey>,
+    pub total_stake_weight: u64,
+    pub members: Vec<VerifierMember>,
     pub bump: u8,
 }
 
 impl VerifierSet {
-    pub const SIZE: usize = 8 + 32 + 8 + 4 + (MAX_VERIFIERS * 32) + 1;
+    pub const SIZE: usize = 8 + 2 + 8 + 8 + 4 + (MAX_VERIFIERS * VerifierMember::SIZE) + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct VerifierMember {
+    pub verifier: Pubkey,
+    pub stake_weight: u64,
+}
+
+impl VerifierMember {
+    pub const SIZE: usize = 32 + 8;
 }
 
 #[account]
@@ -359,6 +1084,8 @@ pub struct AggregateSubmission {
     pub dest_hash: [u8; 32],
     pub ttl_s: u32,
     pub receipt_count: u32,
+    // Self-reported; finalization uses the submitter's ddns_stake_gov weight
+    // recorded in the verifier set instead.
     pub stake_weight: u64,
     pub receipts_root: [u8; 32],
     pub submitter: Pubkey,
@@ -370,6 +1097,90 @@ impl AggregateSubmission {
     pub const SIZE: usize = 8 + 32 + 32 + 4 + 4 + 8 + 32 + 32 + 8 + 1;
 }
 
+#[account]
+pub struct RouteDispute {
+    pub name_hash: [u8; 32],
+    pub disputed_version: u64,
+    pub target_version: u64,
+    pub epoch_id: u64,
+    pub voters: Vec<Pubkey>,
+    pub stake_weight: u64,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+impl RouteDispute {
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 4 + (MAX_VERIFIERS * 32) + 8 + 1 + 1;
+}
+
+#[event]
+pub struct QuorumConfigUpdated {
+    pub authority: Pubkey,
+    pub handover_slots: u64,
+    pub slot: u64,
+}
+
+/// Emitted whenever a verifier set is created, rebuilt or rolled forward.
+#[event]
+pub struct VerifierSetUpdated {
+    pub epoch_id: u64,
+    pub threshold_bps: u16,
+    pub threshold_stake_weight: u64,
+    pub total_stake_weight: u64,
+    pub members: Vec<VerifierMember>,
+    pub slot: u64,
+}
+
+#[event]
+pub struct StakeSnapshotSubmitted {
+    pub epoch_id: u64,
+    pub submitter: Pubkey,
+    pub user_stake_root: [u8; 32],
+    pub total_stake: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct AggregateSubmitted {
+    pub epoch_id: u64,
+    pub name_hash: [u8; 32],
+    pub dest_hash: [u8; 32],
+    pub ttl_s: u32,
+    pub receipt_count: u32,
+    pub receipts_root: [u8; 32],
+    pub submitter: Pubkey,
+    pub aggregate: Pubkey,
+    pub slot: u64,
+}
+
+/// Emitted before the `finalize_route` CPI; ddns_registry follows with
+/// `RouteFinalized` carrying the resulting version.
+#[event]
+pub struct QuorumReached {
+    pub epoch_id: u64,
+    pub name_hash: [u8; 32],
+    pub dest_hash: [u8; 32],
+    pub ttl_s: u32,
+    pub stake_weight: u64,
+    pub required_weight: u64,
+    pub submitters: Vec<Pubkey>,
+    pub slot: u64,
+}
+
+#[event]
+pub struct RouteDisputeVoted {
+    pub name_hash: [u8; 32],
+    pub disputed_version: u64,
+    pub target_version: u64,
+    pub epoch_id: u64,
+    pub voter: Pubkey,
+    pub voter_weight: u64,
+    pub stake_weight: u64,
+    pub threshold_stake_weight: u64,
+    pub executed: bool,
+    pub slot: u64,
+}
+
 #[error_code]
 pub enum QuorumError {
     #[msg("Too many verifiers.")]
@@ -386,4 +1197,92 @@ pub enum QuorumError {
     NotEnoughStakeWeight,
     #[msg("Bad epoch length.")]
     BadEpochLen,
+    #[msg("Remaining accounts must list at least one aggregate.")]
+    BadRemainingAccounts,
+    #[msg("Account has the wrong owner.")]
+    BadAccountOwner,
+    #[msg("Aggregate account does not match its PDA.")]
+    BadAggregateAccount,
+    #[msg("Stake position does not match its PDA.")]
+    BadStakePosition,
+    #[msg("Duplicate stake position.")]
+    DuplicateStakePosition,
+    #[msg("Threshold must be at most 10000 bps.")]
+    BadThreshold,
+    #[msg("Verifier set epoch has already ended.")]
+    EpochEnded,
+    #[msg("Route version changed since the dispute was opened.")]
+    DisputeStale,
+    #[msg("Dispute targets a different version.")]
+    DisputeMismatch,
+    #[msg("Dispute already executed.")]
+    DisputeExecuted,
+    #[msg("Duplicate aggregate submitter.")]
+    DuplicateSubmitter,
+    #[msg("Overflow.")]
+    Overflow,
+    #[msg("Remaining accounts must list at least one stake position.")]
+    NoStakePositions,
+    #[msg("Signer is not the program upgrade authority.")]
+    NotUpgradeAuthority,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distinct_stake_sums() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        assert_eq!(sum_distinct_stake(&[(a, 5), (b, 7)]).unwrap(), 12);
+    }
+
+    #[test]
+    fn member_weights_follow_delegation() {
+        let v1 = Pubkey::new_unique();
+        let v2 = Pubkey::new_unique();
+        let outsider = Pubkey::new_unique();
+        let staker = Pubkey::new_unique();
+        let stakes = [
+            (v1, Pubkey::default(), 100),
+            (staker, v2, 300),
+            (outsider, Pubkey::default(), 1_000),
+        ];
+        let (members, total) = compute_member_weights(&[v1, v2], &stakes).unwrap();
+        assert_eq!(members[0].stake_weight, 100);
+        assert_eq!(members[1].stake_weight, 300);
+        assert_eq!(total, 400);
+
+        let dup = [(staker, v2, 300), (staker, v2, 300)];
+        assert_eq!(
+            compute_member_weights(&[v2], &dup).unwrap_err(),
+            error!(QuorumError::DuplicateStakePosition)
+        );
+    }
+
+    #[test]
+    fn finalize_window_covers_handover() {
+        // epoch 2 spans slots 200..300
+        assert!(within_finalize_window(250, 100, 2, 10).unwrap());
+        assert!(within_finalize_window(309, 100, 2, 10).unwrap());
+        assert!(!within_finalize_window(310, 100, 2, 10).unwrap());
+        assert!(!within_finalize_window(305, 100, 2, 0).unwrap());
+        assert!(!within_finalize_window(199, 100, 2, 10).unwrap());
+        assert!(!within_finalize_window(405, 100, 2, 10).unwrap());
+    }
+
+    #[test]
+    fn threshold_rounds_up() {
+        assert_eq!(threshold_from_bps(400, 6_667), 267);
+        assert_eq!(threshold_from_bps(1, 1), 1);
+        assert_eq!(threshold_from_bps(0, 6_667), 0);
+    }
+
+    #[test]
+    fn duplicate_submitter_rejected() {
+        let a = Pubkey::new_unique();
+        let err = sum_distinct_stake(&[(a, 5), (a, 5)]).unwrap_err();
+        assert_eq!(err, error!(QuorumError::DuplicateSubmitter));
+    }
 }
@@ -4,6 +4,8 @@ use anchor_lang::prelude::*;
 
 declare_id!("GDVsLPGxcZLqxj45UujJfEWpXiMwJid8B5a428VZ12QL");
 
+pub const ROUTE_HISTORY_LEN: usize = 8;
+
 #[program]
 pub mod ddns_registry {
     use super::*;
@@ -28,7 +30,7 @@ pub mod ddns_registry {
         cfg.ttl_max_s = ttl_max_s;
         cfg.finalize_authority = finalize_authority;
         cfg.bump = ctx.bumps.config;
-        Ok(())
+        emit_config_updated(cfg)
     }
 
     pub fn update_config(
@@ -49,6 +51,28 @@ pub mod ddns_registry {
         cfg.ttl_min_s = ttl_min_s;
         cfg.ttl_max_s = ttl_max_s;
         cfg.finalize_authority = finalize_authority;
+        emit_config_updated(cfg)
+    }
+
+    /// Creates the `DisputeConfig` PDA holding the rollback window. Kept apart
+    /// from `Config` so existing config accounts keep their layout.
+    pub fn init_dispute_config(ctx: Context<InitDisputeConfig>, dispute_window_slots: u64) -> Result<()> {
+        let dc = &mut ctx.accounts.dispute_config;
+        dc.dispute_window_slots = dispute_window_slots;
+        dc.bump = ctx.bumps.dispute_config;
+        emit!(DisputeWindowUpdated {
+            dispute_window_slots,
+            slot: Clock::get()?.slot,
+        });
+        Ok(())
+    }
+
+    pub fn set_dispute_window(ctx: Context<SetDisputeWindow>, dispute_window_slots: u64) -> Result<()> {
+        ctx.accounts.dispute_config.dispute_window_slots = dispute_window_slots;
+        emit!(DisputeWindowUpdated {
+            dispute_window_slots,
+            slot: Clock::get()?.slot,
+        });
         Ok(())
     }
 
@@ -74,29 +98,186 @@ pub mod ddns_registry {
 
         let slot = Clock::get()?.slot;
         let route = &mut ctx.accounts.canonical_route;
+        let history = &mut ctx.accounts.route_history;
+        if history.len == 0 {
+            history.name_hash = name_hash;
+            history.bump = ctx.bumps.route_history;
+        }
 
         let is_new = route.version == 0;
+        let changed = is_new || route.dest_hash != dest_hash || route.ttl_s != ttl_s;
         if is_new {
             route.name_hash = name_hash;
-            route.dest_hash = dest_hash;
-            route.ttl_s = ttl_s;
-            route.version = 1;
-            route.updated_at_slot = slot;
-            route.last_aggregate = aggregate_ref;
             route.bump = ctx.bumps.canonical_route;
-            return Ok(());
         }
-
-        if route.dest_hash != dest_hash || route.ttl_s != ttl_s {
+        if changed {
             route.version = route.version.checked_add(1).ok_or(RegistryError::Overflow)?;
-            route.dest_hash = dest_hash;
-            route.ttl_s = ttl_s;
         }
-
+        route.dest_hash = dest_hash;
+        route.ttl_s = ttl_s;
         route.updated_at_slot = slot;
         route.last_aggregate = aggregate_ref;
+
+        if changed {
+            history.push(RouteVersion {
+                version: route.version,
+                dest_hash,
+                ttl_s,
+                aggregate: aggregate_ref,
+                finalized_at_slot: slot,
+            });
+        }
+        emit!(RouteFinalized {
+            name_hash,
+            version: route.version,
+            dest_hash,
+            ttl_s,
+            aggregate: aggregate_ref,
+            changed,
+            slot,
+        });
+        Ok(())
+    }
+
+    /// Finalizes the typed record set `(name_hash, rrtype)`. `data` is a
+    /// canonical `ddns_rrset` encoding; called by ddns_quorum via CPI once
+    /// verifiers agree on its hash.
+    pub fn finalize_rrset(
+        ctx: Context<FinalizeRrset>,
+        name_hash: [u8; 32],
+        rrtype: u16,
+        ttl_s: u32,
+        data: Vec<u8>,
+        aggregate_ref: Pubkey,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config;
+        require_keys_eq!(
+            ctx.accounts.finalize_authority.key(),
+            cfg.finalize_authority,
+            RegistryError::UnauthorizedFinalize
+        );
+        require!(
+            ttl_s >= cfg.ttl_min_s && ttl_s <= cfg.ttl_max_s,
+            RegistryError::TtlOutOfRange
+        );
+
+        let decoded = ddns_rrset::decode_rrset(&data).map_err(|_| error!(RegistryError::BadRecordSet))?;
+        require!(decoded.rrtype == rrtype, RegistryError::RecordTypeMismatch);
+        let rrset_hash = ddns_rrset::rrset_hash(&data);
+
+        let slot = Clock::get()?.slot;
+        let set = &mut ctx.accounts.record_set;
+        let is_new = set.version == 0;
+        let changed = is_new || set.rrset_hash != rrset_hash || set.ttl_s != ttl_s;
+        if is_new {
+            set.name_hash = name_hash;
+            set.rrtype = rrtype;
+            set.bump = ctx.bumps.record_set;
+        }
+        if changed {
+            set.version = set.version.checked_add(1).ok_or(RegistryError::Overflow)?;
+        }
+        set.ttl_s = ttl_s;
+        set.rrset_hash = rrset_hash;
+        set.data = data.clone();
+        set.updated_at_slot = slot;
+        set.last_aggregate = aggregate_ref;
+
+        emit!(RecordSetFinalized {
+            name_hash,
+            rrtype,
+            version: set.version,
+            rrset_hash,
+            ttl_s,
+            data,
+            aggregate: aggregate_ref,
+            changed,
+            slot,
+        });
         Ok(())
     }
+
+    /// Reverts a route to a prior version kept in its history, as a new
+    /// version. Allowed for the config authority (governance) or the finalize
+    /// authority (ddns_quorum after a stake-weighted dispute), and only within
+    /// `DisputeConfig.dispute_window_slots` of the current version's
+    /// finalization.
+    pub fn rollback_route(
+        ctx: Context<RollbackRoute>,
+        name_hash: [u8; 32],
+        target_version: u64,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.config;
+        let signer = ctx.accounts.dispute_authority.key();
+        require!(
+            signer == cfg.authority || signer == cfg.finalize_authority,
+            RegistryError::UnauthorizedDispute
+        );
+
+        let slot = Clock::get()?.slot;
+        let route = &mut ctx.accounts.canonical_route;
+        let history = &mut ctx.accounts.route_history;
+
+        let current = history
+            .find(route.version)
+            .ok_or(RegistryError::VersionNotInHistory)?;
+        require!(
+            within_dispute_window(
+                current.finalized_at_slot,
+                slot,
+                ctx.accounts.dispute_config.dispute_window_slots
+            ),
+            RegistryError::DisputeWindowClosed
+        );
+        require!(target_version < route.version, RegistryError::BadRollbackTarget);
+        let target = history
+            .find(target_version)
+            .ok_or(RegistryError::VersionNotInHistory)?;
+
+        let from_version = route.version;
+        route.version = route.version.checked_add(1).ok_or(RegistryError::Overflow)?;
+        route.dest_hash = target.dest_hash;
+        route.ttl_s = target.ttl_s;
+        route.updated_at_slot = slot;
+        route.last_aggregate = target.aggregate;
+
+        history.push(RouteVersion {
+            version: route.version,
+            dest_hash: target.dest_hash,
+            ttl_s: target.ttl_s,
+            aggregate: target.aggregate,
+            finalized_at_slot: slot,
+        });
+        emit!(RouteRolledBack {
+            name_hash,
+            from_version,
+            restored_version: target_version,
+            version: route.version,
+            dest_hash: target.dest_hash,
+            ttl_s: target.ttl_s,
+            authority: signer,
+            slot,
+        });
+        Ok(())
+    }
+}
+
+fn within_dispute_window(finalized_at_slot: u64, slot: u64, window_slots: u64) -> bool {
+    slot <= finalized_at_slot.saturating_add(window_slots)
+}
+
+fn emit_config_updated(cfg: &Config) -> Result<()> {
+    emit!(ConfigUpdated {
+        authority: cfg.authority,
+        epoch_len_slots: cfg.epoch_len_slots,
+        min_receipts: cfg.min_receipts,
+        min_stake_weight: cfg.min_stake_weight,
+        ttl_min_s: cfg.ttl_min_s,
+        ttl_max_s: cfg.ttl_max_s,
+        finalize_authority: cfg.finalize_authority,
+        slot: Clock::get()?.slot,
+    });
+    Ok(())
 }
 
 #[derive(Accounts)]
@@ -129,6 +310,37 @@ pub struct UpdateConfig<'info> {
     pub config: Account<'info, Config>,
 }
 
+#[derive(Accounts)]
+pub struct InitDisputeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, has_one = authority)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + DisputeConfig::SIZE,
+        seeds = [b"dispute_config"],
+        bump
+    )]
+    pub dispute_config: Account<'info, DisputeConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDisputeWindow<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, has_one = authority)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"dispute_config"], bump = dispute_config.bump)]
+    pub dispute_config: Account<'info, DisputeConfig>,
+}
+
 #[derive(Accounts)]
 #[instruction(name_hash: [u8;32])]
 pub struct FinalizeRoute<'info> {
@@ -150,9 +362,69 @@ pub struct FinalizeRoute<'info> {
     )]
     pub canonical_route: Account<'info, CanonicalRoute>,
 
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RouteHistory::SIZE,
+        seeds = [b"route_history", name_hash.as_ref()],
+        bump
+    )]
+    pub route_history: Account<'info, RouteHistory>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name_hash: [u8;32], rrtype: u16)]
+pub struct FinalizeRrset<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    // Must match config.finalize_authority. In MVP, this is a ddns_quorum PDA signer.
+    pub finalize_authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RecordSet::SIZE,
+        seeds = [b"rrset", name_hash.as_ref(), rrtype.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub record_set: Account<'info, RecordSet>,
+
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(name_hash: [u8;32])]
+pub struct RollbackRoute<'info> {
+    // Must be config.authority or config.finalize_authority.
+    pub dispute_authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(seeds = [b"dispute_config"], bump = dispute_config.bump)]
+    pub dispute_config: Account<'info, DisputeConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"canonical", name_hash.as_ref()],
+        bump = canonical_route.bump
+    )]
+    pub canonical_route: Account<'info, CanonicalRoute>,
+
+    #[account(
+        mut,
+        seeds = [b"route_history", name_hash.as_ref()],
+        bump = route_history.bump
+    )]
+    pub route_history: Account<'info, RouteHistory>,
+}
+
 #[account]
 pub struct Config {
     pub authority: Pubkey,
@@ -169,6 +441,17 @@ impl Config {
     pub const SIZE: usize = 32 + 32 + 8 + 4 + 8 + 4 + 4 + 1;
 }
 
+/// Rollback window for `rollback_route`, in slots after a version finalizes.
+#[account]
+pub struct DisputeConfig {
+    pub dispute_window_slots: u64,
+    pub bump: u8,
+}
+
+impl DisputeConfig {
+    pub const SIZE: usize = 8 + 1;
+}
+
 #[account]
 pub struct CanonicalRoute {
     pub name_hash: [u8; 32],
@@ -184,6 +467,124 @@ impl CanonicalRoute {
     pub const SIZE: usize = 32 + 32 + 4 + 8 + 8 + 32 + 1;
 }
 
+/// All records of one type for a name, as a canonical `ddns_rrset` encoding.
+#[account]
+pub struct RecordSet {
+    pub name_hash: [u8; 32],
+    pub rrtype: u16,
+    pub ttl_s: u32,
+    pub version: u64,
+    pub rrset_hash: [u8; 32],
+    pub data: Vec<u8>,
+    pub updated_at_slot: u64,
+    pub last_aggregate: Pubkey,
+    pub bump: u8,
+}
+
+impl RecordSet {
+    pub const SIZE: usize = 32 + 2 + 4 + 8 + 32 + 4 + ddns_rrset::MAX_RRSET_BYTES + 8 + 32 + 1;
+}
+
+/// Ring of the last `ROUTE_HISTORY_LEN` versions of a route.
+#[account]
+pub struct RouteHistory {
+    pub name_hash: [u8; 32],
+    // Index the next entry is written to.
+    pub head: u8,
+    pub len: u8,
+    pub entries: [RouteVersion; ROUTE_HISTORY_LEN],
+    pub bump: u8,
+}
+
+impl RouteHistory {
+    pub const SIZE: usize = 32 + 1 + 1 + (RouteVersion::SIZE * ROUTE_HISTORY_LEN) + 1;
+
+    pub fn push(&mut self, entry: RouteVersion) {
+        self.entries[self.head as usize] = entry;
+        self.head = ((self.head as usize + 1) % ROUTE_HISTORY_LEN) as u8;
+        if (self.len as usize) < ROUTE_HISTORY_LEN {
+            self.len += 1;
+        }
+    }
+
+    pub fn find(&self, version: u64) -> Option<RouteVersion> {
+        self.entries[..self.len as usize]
+            .iter()
+            .find(|e| e.version == version)
+            .copied()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct RouteVersion {
+    pub version: u64,
+    pub dest_hash: [u8; 32],
+    pub ttl_s: u32,
+    pub aggregate: Pubkey,
+    pub finalized_at_slot: u64,
+}
+
+impl RouteVersion {
+    pub const SIZE: usize = 8 + 32 + 4 + 32 + 8;
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub authority: Pubkey,
+    pub epoch_len_slots: u64,
+    pub min_receipts: u32,
+    pub min_stake_weight: u64,
+    pub ttl_min_s: u32,
+    pub ttl_max_s: u32,
+    pub finalize_authority: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct DisputeWindowUpdated {
+    pub dispute_window_slots: u64,
+    pub slot: u64,
+}
+
+/// Emitted on every finalization; `changed` is false when quorum re-confirmed
+/// the current dest/TTL and the version did not move.
+#[event]
+pub struct RouteFinalized {
+    pub name_hash: [u8; 32],
+    pub version: u64,
+    pub dest_hash: [u8; 32],
+    pub ttl_s: u32,
+    pub aggregate: Pubkey,
+    pub changed: bool,
+    pub slot: u64,
+}
+
+#[event]
+pub struct RouteRolledBack {
+    pub name_hash: [u8; 32],
+    pub from_version: u64,
+    pub restored_version: u64,
+    pub version: u64,
+    pub dest_hash: [u8; 32],
+    pub ttl_s: u32,
+    pub authority: Pubkey,
+    pub slot: u64,
+}
+
+/// Carries the encoded set so resolvers can follow records from logs alone.
+#[event]
+pub struct RecordSetFinalized {
+    pub name_hash: [u8; 32],
+    pub rrtype: u16,
+    pub version: u64,
+    pub rrset_hash: [u8; 32],
+    pub ttl_s: u32,
+    pub data: Vec<u8>,
+    pub aggregate: Pubkey,
+    pub changed: bool,
+    pub slot: u64,
+}
+
 #[error_code]
 pub enum RegistryError {
     #[msg("Unauthorized finalize authority.")]
@@ -194,4 +595,55 @@ pub enum RegistryError {
     TtlOutOfRange,
     #[msg("Arithmetic overflow.")]
     Overflow,
+    #[msg("Signer may not roll back routes.")]
+    UnauthorizedDispute,
+    #[msg("Dispute window has closed.")]
+    DisputeWindowClosed,
+    #[msg("Version is not in route history.")]
+    VersionNotInHistory,
+    #[msg("Rollback target must be an earlier version.")]
+    BadRollbackTarget,
+    #[msg("Record set is not a valid canonical encoding.")]
+    BadRecordSet,
+    #[msg("Record set type does not match its account.")]
+    RecordTypeMismatch,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(v: u64) -> RouteVersion {
+        RouteVersion {
+            version: v,
+            dest_hash: [v as u8; 32],
+            ..RouteVersion::default()
+        }
+    }
+
+    #[test]
+    fn history_ring_keeps_last_versions() {
+        let mut h = RouteHistory {
+            name_hash: [0u8; 32],
+            head: 0,
+            len: 0,
+            entries: [RouteVersion::default(); ROUTE_HISTORY_LEN],
+            bump: 0,
+        };
+        for v in 1..=(ROUTE_HISTORY_LEN as u64 + 2) {
+            h.push(version(v));
+        }
+        assert_eq!(h.len as usize, ROUTE_HISTORY_LEN);
+        assert!(h.find(1).is_none());
+        assert!(h.find(2).is_none());
+        assert_eq!(h.find(3).unwrap().dest_hash, [3u8; 32]);
+        assert!(h.find(ROUTE_HISTORY_LEN as u64 + 2).is_some());
+    }
+
+    #[test]
+    fn dispute_window_is_inclusive() {
+        assert!(within_dispute_window(100, 150, 50));
+        assert!(!within_dispute_window(100, 151, 50));
+        assert!(!within_dispute_window(100, 101, 0));
+    }
 }
@@ -5,8 +5,8 @@ use anchor_lang::solana_program::{program::invoke, system_instruction};
 
 declare_id!("BUEaJaRhXkcgnxNtSNRA7p1hbmtQgKwHHHngxKfjRHM3");
 
-const SEED_CONFIG: &[u8] = b"rent_bond_config";
-const SEED_RESERVE: &[u8] = b"program_reserve";
+pub const SEED_CONFIG: &[u8] = b"rent_bond_config";
+pub const SEED_RESERVE: &[u8] = b"program_reserve";
 const MIN_RESERVE_LAMPORTS: u64 = 5_000_000_000;
 const BUFFER_LAMPORTS: u64 = 1_000_000_000;
 
@@ -4,13 +4,14 @@ use anchor_lang::prelude::*;
 
 declare_id!("9J4EysJnFrZ92yTG8MystX9Kwn9eibqPtP6kd8b5mQw5");
 
-const SEED_CONFIG: &[u8] = b"rep_config";
-const SEED_EPOCH_REP: &[u8] = b"rep_epoch";
+pub const SEED_CONFIG: &[u8] = b"rep_config";
+pub const SEED_EPOCH_REP: &[u8] = b"rep_epoch";
 
 #[program]
 pub mod ddns_rep {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn init_rep_config(
         ctx: Context<InitRepConfig>,
         epoch_len_slots: u64,
@@ -3,7 +3,7 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, Transfer};
 use anchor_lang::solana_program::program_pack::Pack;
-use sha2::{Digest, Sha256};
+use ddns_names_core::hash_name;
 use spl_token::state::Account as SplTokenAccount;
 
 declare_id!("DFezrM8E82VpAHo6eCzbfA6QEnmtZhyCYbmpTo5Bm2ak");
@@ -14,6 +14,7 @@ const MAX_VERIFIERS: usize = 64;
 pub mod ddns_rewards {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn init_rewards_config(
         ctx: Context<InitRewardsConfig>,
         domain_share_bps: u16,
@@ -119,6 +120,7 @@ pub mod ddns_rewards {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_rewards_config(
         ctx: Context<UpdateRewardsConfig>,
         domain_share_bps: u16,
@@ -559,24 +561,13 @@ fn require_is_verifier(cfg: &RewardsConfig, key: &Pubkey) -> Result<()> {
 
 fn compute_domain_hash(fqdn: &str) -> Result<[u8; 32]> {
     let normalized = normalize_fqdn(fqdn)?;
-    let mut hasher = Sha256::new();
-    hasher.update(normalized.as_bytes());
-    Ok(hasher.finalize().into())
+    Ok(hash_name(&normalized))
 }
 
+/// Canonical `ddns_names_core` form: one trailing dot stripped, ASCII
+/// lowercase, LDH labels (Unicode as `xn--` A-labels), at most 253 bytes.
 fn normalize_fqdn(fqdn: &str) -> Result<String> {
-    let mut s = fqdn.trim().to_string();
-    // Strip one trailing dot to support fully-qualified input.
-    if s.ends_with('.') {
-        s.pop();
-    }
-
-    // MVP: ASCII lowercase only (punycode handled off-chain in later phases).
-    // This keeps on-chain normalization deterministic and cheap.
-    s.make_ascii_lowercase();
-    require!(!s.is_empty(), RewardsError::BadFqdn);
-    require!(s.len() <= 253, RewardsError::BadFqdn);
-    Ok(s)
+    ddns_names_core::normalize_name(fqdn).map_err(|_| error!(RewardsError::BadFqdn))
 }
 
 fn write_fqdn_bytes(fqdn: &str, out: &mut [u8; 253], out_len: &mut u8) -> Result<()> {
@@ -12,12 +12,12 @@ const MAX_ALLOWLIST: usize = 16;
 const MAX_LOCK_TIERS: usize = 6;
 const MAX_VERIFIERS: usize = 64;
 
-const SEED_CONFIG: &[u8] = b"stake_gov_config";
-const SEED_VAULT_AUTH: &[u8] = b"stake_gov_vault_authority";
-const SEED_VERIFIER_REGISTRY: &[u8] = b"verifier_registry";
-const SEED_STAKE: &[u8] = b"stake";
-const SEED_SNAPSHOT: &[u8] = b"stake_snapshot";
-const SEED_SLASH: &[u8] = b"slash";
+pub const SEED_CONFIG: &[u8] = b"stake_gov_config";
+pub const SEED_VAULT_AUTH: &[u8] = b"stake_gov_vault_authority";
+pub const SEED_VERIFIER_REGISTRY: &[u8] = b"verifier_registry";
+pub const SEED_STAKE: &[u8] = b"stake";
+pub const SEED_SNAPSHOT: &[u8] = b"stake_snapshot";
+pub const SEED_SLASH: &[u8] = b"slash";
 
 #[program]
 pub mod ddns_stake_gov {
@@ -7,11 +7,11 @@ declare_id!("Bf3Who54hZMHxubT3YgPZRHNitdQLqKA8HR9Hzbmog3e");
 const MAX_WATCHDOGS: usize = 64;
 const MAX_SUBMITTERS: usize = 16;
 
-const SEED_CONFIG: &[u8] = b"policy_config";
-const SEED_WATCHDOG: &[u8] = b"watchdog";
-const SEED_NAME_POLICY: &[u8] = b"name_policy";
-const SEED_ATTEST_LOG: &[u8] = b"attest_log";
-const SEED_ATTEST_MARK: &[u8] = b"attest_mark";
+pub const SEED_CONFIG: &[u8] = b"policy_config";
+pub const SEED_WATCHDOG: &[u8] = b"watchdog";
+pub const SEED_NAME_POLICY: &[u8] = b"name_policy";
+pub const SEED_ATTEST_LOG: &[u8] = b"attest_log";
+pub const SEED_ATTEST_MARK: &[u8] = b"attest_mark";
 
 #[program]
 pub mod ddns_watchdog_policy {
@@ -232,9 +232,9 @@ pub mod ddns_watchdog_policy {
             let fail_w = (ps.rolling_fail as u64).saturating_mul(2);
             let mismatch_w = (ps.rolling_mismatch as u64).saturating_mul(3);
             let denom = ok_w.saturating_add(fail_w).saturating_add(mismatch_w);
-            if denom > 0 {
-                let risk_num = fail_w.saturating_add(mismatch_w).saturating_mul(10_000);
-                let risk_bps = (risk_num / denom) as u16;
+            let risk_num = fail_w.saturating_add(mismatch_w).saturating_mul(10_000);
+            if let Some(risk) = risk_num.checked_div(denom) {
+                let risk_bps = risk as u16;
                 if risk_bps >= cfg.quarantine_threshold_bps {
                     ps.status = PolicyStatus::Quarantine as u8;
                 } else if risk_bps >= cfg.warn_threshold_bps {
@@ -246,9 +246,10 @@ pub mod ddns_watchdog_policy {
         }
 
         // Confidence grows with the number of observations (MVP heuristic).
-        let total = (ps.rolling_ok as u32)
-            .saturating_add(ps.rolling_fail as u32)
-            .saturating_add(ps.rolling_mismatch as u32);
+        let total = ps
+            .rolling_ok
+            .saturating_add(ps.rolling_fail)
+            .saturating_add(ps.rolling_mismatch);
         let conf = (total as u64).saturating_mul(500);
         ps.confidence_bps = conf.min(10_000) as u16;
 
@@ -1,12 +1,14 @@
 import fs from "node:fs";
 import path from "node:path";
 import crypto from "node:crypto";
+import { domainToASCII } from "node:url";
 import yargs from "yargs";
 import { hideBin } from "yargs/helpers";
 import * as anchor from "@coral-xyz/anchor";
 import { Connection, Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
 import BN from "bn.js";
-import { getAssociatedTokenAddressSync, TOKEN_PROGRAM_ID } from "./lib/token.js";
+import { createAssociatedTokenAccountInstruction, getAssociatedTokenAddressSync, TOKEN_PROGRAM_ID } from "./lib/token.js";
+import { MPL_TOKEN_METADATA_PROGRAM_ID } from "./lib/metadata.js";
 
 const DEFAULT_RPC =
   process.env.SOLANA_RPC_URL || process.env.ANCHOR_PROVIDER_URL || "https://api.devnet.solana.com";
@@ -44,12 +46,25 @@ function readProgramIdFromAnchorToml(rpcUrl: string, program = "ddns_names"): st
   }
 }
 
+// Unicode input is UTS-46 mapped to A-labels (`münchen` -> `xn--mnchen-3ya`),
+// the form the programs store and hash. Mixed-script and confusable labels
+// are rejected only by the Rust `ddns_idna` normalizer.
+function toAscii(input: string): string {
+  const trimmed = input.trim().replace(/\.+$/, "");
+  if (/^[\x00-\x7f]*$/.test(trimmed)) return trimmed.toLowerCase();
+  const ascii = domainToASCII(trimmed);
+  if (!ascii) throw new Error(`Invalid internationalized name: ${input}`);
+  return ascii;
+}
+
 function normalizeFullName(name: string): string {
-  return name.trim().toLowerCase().replace(/\.+$/, "");
+  return toAscii(name);
 }
 
 function normalizeLabel(label: string): string {
-  return label.trim().toLowerCase();
+  const ascii = toAscii(label);
+  if (ascii.includes(".")) throw new Error(`Label must not contain dots: ${label}`);
+  return ascii;
 }
 
 function sha256Bytes(data: Buffer | string): Buffer {
@@ -68,6 +83,83 @@ function hashSubdomainName(parentHash: Uint8Array, labelHash: Uint8Array): Uint8
   return Uint8Array.from(sha256Bytes(Buffer.concat([Buffer.from(parentHash), Buffer.from(labelHash)])));
 }
 
+// Hash of a name with `depth` subdomain labels: sha256(root), then one label folded in per level.
+function hashChainedName(name: string, depth: number): Uint8Array {
+  const normalized = normalizeFullName(name);
+  if (depth === 0) return hashName(normalized);
+  const dot = normalized.indexOf(".");
+  if (dot < 0) throw new Error(`${name} has fewer than ${depth} subdomain labels`);
+  return hashSubdomainName(hashChainedName(normalized.slice(dot + 1), depth - 1), hashLabel(normalized.slice(0, dot)));
+}
+
+// SubName PDA of a subdomain with `depth` labels below its root.
+function findSubPda(programId: PublicKey, name: string, depth: number): PublicKey {
+  const normalized = normalizeFullName(name);
+  const dot = normalized.indexOf(".");
+  const parentHash = hashChainedName(normalized.slice(dot + 1), depth - 1);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("sub"), Buffer.from(parentHash), Buffer.from(hashLabel(normalized.slice(0, dot)))],
+    programId
+  )[0];
+}
+
+// Ancestor SubName PDAs of a subdomain, parent first, passed as remaining accounts so the program can check them.
+function ancestorSubAccounts(programId: PublicKey, name: string, depth: number) {
+  const accounts = [];
+  let ancestor = normalizeFullName(name);
+  for (let d = depth - 1; d >= 1; d--) {
+    ancestor = ancestor.slice(ancestor.indexOf(".") + 1);
+    accounts.push({ pubkey: findSubPda(programId, ancestor, d), isSigner: false, isWritable: false });
+  }
+  return accounts;
+}
+
+// Subdomain sale allowlists: leaves are sha256(wallet), nodes sha256(min || max), odd levels
+// duplicate their last node. Returns the root and each wallet's proof.
+function buildAllowlist(wallets: PublicKey[]): { root: Uint8Array; proofs: Map<string, Uint8Array[]> } {
+  const pair = (a: Buffer, b: Buffer) => sha256Bytes(Buffer.compare(a, b) <= 0 ? Buffer.concat([a, b]) : Buffer.concat([b, a]));
+  let level = wallets.map((w) => sha256Bytes(w.toBuffer()));
+  if (level.length === 0) throw new Error("allowlist is empty");
+  const proofs = new Map(wallets.map((w) => [w.toBase58(), [] as Uint8Array[]]));
+  let positions = new Map(wallets.map((w, i) => [w.toBase58(), i]));
+  while (level.length > 1) {
+    if (level.length % 2 === 1) level.push(level[level.length - 1]);
+    for (const [wallet, i] of positions) proofs.get(wallet)!.push(Uint8Array.from(level[i ^ 1]));
+    level = Array.from({ length: level.length / 2 }, (_, i) => pair(level[2 * i], level[2 * i + 1]));
+    positions = new Map([...positions].map(([wallet, i]) => [wallet, i >> 1]));
+  }
+  return { root: Uint8Array.from(level[0]), proofs };
+}
+
+function loadAllowlist(file: string): PublicKey[] {
+  return (JSON.parse(fs.readFileSync(file, "utf8")) as string[]).map((w) => new PublicKey(w));
+}
+
+// Commit-reveal: commitment = sha256(name_hash || owner || salt).
+function commitmentHash(nameHash: Uint8Array, owner: PublicKey, salt: Uint8Array): Uint8Array {
+  return Uint8Array.from(sha256Bytes(Buffer.concat([Buffer.from(nameHash), owner.toBuffer(), Buffer.from(salt)])));
+}
+
+function parseSalt(hex: string): Uint8Array {
+  const salt = Buffer.from(hex.replace(/^0x/, ""), "hex");
+  if (salt.length !== 32) throw new Error("--salt must be 32 bytes of hex");
+  return Uint8Array.from(salt);
+}
+
+// Reveal instruction and commitment PDA for `--salt`, or nulls when committing is skipped.
+async function revealFor(program: anchor.Program, programId: PublicKey, owner: PublicKey, nameHash: Uint8Array, saltHex?: string) {
+  if (!saltHex) return { nameCommitment: null, preInstructions: [] };
+  const salt = parseSalt(saltHex);
+  const commitment = commitmentHash(nameHash, owner, salt);
+  const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+  const [nameCommitment] = PublicKey.findProgramAddressSync([Buffer.from("commit"), Buffer.from(commitment)], programId);
+  const reveal = await program.methods
+    .revealName(Array.from(commitment), Array.from(nameHash), Array.from(salt))
+    .accounts({ config: configPda, nameCommitment, owner })
+    .instruction();
+  return { nameCommitment, preInstructions: [reveal] };
+}
+
 function labelFromPremium(name: string): string {
   const normalized = normalizeFullName(name);
   const parts = normalized.split(".");
@@ -85,10 +177,87 @@ async function fetchTreasuryFromConfig(connection: Connection, configPda: Public
   return new PublicKey(info.data.subarray(8 + 32, 8 + 64));
 }
 
+// PremiumName layout: disc(8) name_hash(32) owner(32) nft_mint(32) purchase(8) created(8) transferable(1) nft_bound(1).
+async function fetchPremiumNft(connection: Connection, premiumPda: PublicKey): Promise<PublicKey | null> {
+  const info = await connection.getAccountInfo(premiumPda);
+  if (!info || info.data.length < 8 + 114) throw new Error(`premium name not found at ${premiumPda.toBase58()}`);
+  return info.data[8 + 113] === 1 ? new PublicKey(info.data.subarray(8 + 64, 8 + 96)) : null;
+}
+
+async function fetchPremiumCreatedAt(connection: Connection, premiumPda: PublicKey): Promise<bigint> {
+  const info = await connection.getAccountInfo(premiumPda);
+  if (!info || info.data.length < 8 + 112) throw new Error(`premium name not found at ${premiumPda.toBase58()}`);
+  return info.data.readBigInt64LE(8 + 104);
+}
+
+// The program mints one NFT per registration: the mint is seeded by the
+// registration's created_at, so a re-registered name gets a fresh mint.
+function findNftMintPda(programId: PublicKey, seeds: Uint8Array[], createdAt: bigint): PublicKey {
+  const generation = Buffer.alloc(8);
+  generation.writeBigInt64LE(createdAt);
+  return PublicKey.findProgramAddressSync([...seeds.map((seed) => Buffer.from(seed)), generation], programId)[0];
+}
+
+function findNftMetadataPda(mint: PublicKey, edition = false): PublicKey {
+  const seeds = [Buffer.from("metadata"), MPL_TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()];
+  if (edition) seeds.push(Buffer.from("edition"));
+  return PublicKey.findProgramAddressSync(seeds, MPL_TOKEN_METADATA_PROGRAM_ID)[0];
+}
+
+// Optional accounts that let renewals and transfers refresh the metadata of a
+// program-issued premium NFT; all null for names bound to an external NFT.
+function premiumNftRefreshAccounts(programId: PublicKey, nameHash: Uint8Array, nftMint: PublicKey | null, createdAt: bigint) {
+  const issuedMint = findNftMintPda(programId, [Buffer.from("premium_nft_mint"), nameHash], createdAt);
+  if (!nftMint || !nftMint.equals(issuedMint)) {
+    return { nftMetadata: null, nftAuthority: null, tokenMetadataProgram: null };
+  }
+  return {
+    nftMetadata: findNftMetadataPda(nftMint),
+    nftAuthority: PublicKey.findProgramAddressSync([Buffer.from("nft_authority")], programId)[0],
+    tokenMetadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+  };
+}
+
+// Listing layout: disc(8) name_hash(32) seller(32) ...
+async function fetchListingSeller(connection: Connection, listingPda: PublicKey): Promise<PublicKey> {
+  const info = await connection.getAccountInfo(listingPda);
+  if (!info || info.data.length < 8 + 64) throw new Error(`listing not found at ${listingPda.toBase58()}`);
+  return new PublicKey(info.data.subarray(8 + 32, 8 + 64));
+}
+
+async function fetchTreasuryVaultFromPremiumConfig(connection: Connection, premiumConfigPda: PublicKey): Promise<PublicKey> {
+  const info = await connection.getAccountInfo(premiumConfigPda);
+  if (!info || !info.data || info.data.length < 8 + 64) {
+    throw new Error(`premium config not found at ${premiumConfigPda.toBase58()}`);
+  }
+  return new PublicKey(info.data.subarray(8 + 32, 8 + 64));
+}
+
+// Auction layout: disc(8) name_hash(32) start_slot(8) ...
+async function fetchAuctionStartSlot(connection: Connection, auctionPda: PublicKey): Promise<bigint> {
+  const info = await connection.getAccountInfo(auctionPda);
+  if (!info || info.data.length < 8 + 40) throw new Error(`auction not found at ${auctionPda.toBase58()}`);
+  return info.data.readBigUInt64LE(8 + 32);
+}
+
+// Bid escrows, their vaults and sealed bids are per auction round: the seeds end with the round's start_slot.
+function bidPda(seed: string, nameHash: Uint8Array, bidder: PublicKey, startSlot: bigint, programId: PublicKey): PublicKey {
+  const round = Buffer.alloc(8);
+  round.writeBigUInt64LE(startSlot);
+  return PublicKey.findProgramAddressSync([Buffer.from(seed), Buffer.from(nameHash), bidder.toBuffer(), round], programId)[0];
+}
+
+// Sealed bids: commitment = sha256(name_hash || bidder || bid_lamports_le || salt).
+function sealedBidHash(nameHash: Uint8Array, bidder: PublicKey, lamports: bigint, salt: Uint8Array): Uint8Array {
+  const amount = Buffer.alloc(8);
+  amount.writeBigUInt64LE(lamports);
+  return Uint8Array.from(sha256Bytes(Buffer.concat([Buffer.from(nameHash), bidder.toBuffer(), amount, Buffer.from(salt)])));
+}
+
 async function fetchPrimaryRaw(connection: Connection, primaryPda: PublicKey) {
   const info = await connection.getAccountInfo(primaryPda);
   if (!info) return null;
-  if (info.data.length < 8 + 32 + 32 + 1 + 1 + 1) {
+  if (info.data.length < 8 + 32 + 32 + 1 + 1 + 1 + 1) {
     throw new Error(`invalid PrimaryName account data length at ${primaryPda.toBase58()}`);
   }
   const owner = new PublicKey(info.data.subarray(8, 8 + 32));
@@ -96,7 +265,10 @@ async function fetchPrimaryRaw(connection: Connection, primaryPda: PublicKey) {
   const kind = info.data[8 + 64];
   const isSet = info.data[8 + 65] === 1;
   const bump = info.data[8 + 66];
-  return { owner: owner.toBase58(), nameHashHex, kind, isSet, bump };
+  const nameLen = info.data[8 + 67];
+  const name = Buffer.from(info.data.subarray(8 + 68, 8 + 68 + nameLen)).toString("utf8");
+  const depth = info.data[8 + 68 + 253] ?? 0;
+  return { owner: owner.toBase58(), name, nameHashHex, kind, isSet, bump, depth };
 }
 
 async function main() {
@@ -202,10 +374,39 @@ async function main() {
         );
       }
     )
+    .command(
+      "commit-name",
+      "Commit to a premium name or subdomain before claiming it (commit-reveal)",
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true, describe: "premium name, or subdomain label with --parent" })
+          .option("parent", { type: "string" }),
+      async (args) => {
+        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const nameHash = args.parent
+          ? hashSubdomainName(hashName(String(args.parent)), hashLabel(String(args.name)))
+          : hashName(String(args.name));
+        const salt = crypto.randomBytes(32);
+        const commitment = commitmentHash(nameHash, payer.publicKey, salt);
+        const [nameCommitment] = PublicKey.findProgramAddressSync([Buffer.from("commit"), Buffer.from(commitment)], programId);
+
+        const sig = await program.methods
+          .commitName(Array.from(commitment))
+          .accounts({ nameCommitment, owner: payer.publicKey, systemProgram: SystemProgram.programId })
+          .rpc();
+
+        // Keep the salt: pass it as --salt to buy-premium / claim-sub once the commit window opens.
+        console.log(JSON.stringify({ tx: sig, nameCommitment: nameCommitment.toBase58(), salt: salt.toString("hex") }, null, 2));
+      }
+    )
     .command(
       "claim-sub",
       "Claim subdomain under parent (default user.dns)",
-      (y) => y.option("label", { type: "string", demandOption: true }).option("parent", { type: "string", default: "user.dns" }),
+      (y) =>
+        y
+          .option("label", { type: "string", demandOption: true })
+          .option("parent", { type: "string", default: "user.dns" })
+          .option("salt", { type: "string", describe: "reveal a commitment made with commit-name" }),
       async (args) => {
         const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
         const parent = normalizeFullName(String(args.parent));
@@ -217,6 +418,13 @@ async function main() {
         const [subPda] = PublicKey.findProgramAddressSync([Buffer.from("sub"), Buffer.from(parentHash), Buffer.from(labelHash)], programId);
         const [primaryPda] = PublicKey.findProgramAddressSync([Buffer.from("primary"), payer.publicKey.toBuffer()], programId);
         const treasury = await fetchTreasuryFromConfig(connection, configPda);
+        const reveal = await revealFor(
+          program,
+          programId,
+          payer.publicKey,
+          hashSubdomainName(parentHash, labelHash),
+          args.salt as string | undefined
+        );
 
         const sig = await program.methods
           .claimSubdomain(parent, label, Array.from(parentHash), Array.from(labelHash))
@@ -225,9 +433,11 @@ async function main() {
             treasury,
             subName: subPda,
             primary: primaryPda,
+            nameCommitment: reveal.nameCommitment,
             owner: payer.publicKey,
             systemProgram: SystemProgram.programId,
           })
+          .preInstructions(reveal.preInstructions)
           .rpc();
 
         console.log(JSON.stringify({ tx: sig, subPda: subPda.toBase58(), primaryPda: primaryPda.toBase58(), parent, label }, null, 2));
@@ -236,7 +446,10 @@ async function main() {
     .command(
       "buy-premium",
       "Purchase premium second-level .dns name",
-      (y) => y.option("name", { type: "string", demandOption: true }),
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true })
+          .option("salt", { type: "string", describe: "reveal a commitment made with commit-name" }),
       async (args) => {
         const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
         const name = normalizeFullName(String(args.name));
@@ -246,7 +459,9 @@ async function main() {
         const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
         const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), Buffer.from(nameHash)], programId);
         const [primaryPda] = PublicKey.findProgramAddressSync([Buffer.from("primary"), payer.publicKey.toBuffer()], programId);
+        const [namePricePda] = PublicKey.findProgramAddressSync([Buffer.from("name_price"), Buffer.from(nameHash)], programId);
         const treasury = await fetchTreasuryFromConfig(connection, configPda);
+        const reveal = await revealFor(program, programId, payer.publicKey, nameHash, args.salt as string | undefined);
 
         const sig = await program.methods
           .purchasePremium(name, Array.from(nameHash))
@@ -257,14 +472,254 @@ async function main() {
             premiumName: premiumPda,
             parentPolicy: policyPda,
             primary: primaryPda,
+            namePrice: namePricePda,
+            nameCommitment: reveal.nameCommitment,
             owner: payer.publicKey,
             systemProgram: SystemProgram.programId,
+            payerTollAccount: null,
+            tollTreasury: null,
+            tollMint: null,
+            tokenProgram: null,
           })
+          .preInstructions(reveal.preInstructions)
           .rpc();
 
         console.log(JSON.stringify({ tx: sig, premiumPda: premiumPda.toBase58(), policyPda: policyPda.toBase58(), primaryPda: primaryPda.toBase58(), name }, null, 2));
       }
     )
+    .command(
+      "quote-premium",
+      "Show the current price of a premium .dns name (simulated, no transaction)",
+      (y) => y.option("name", { type: "string", demandOption: true }),
+      async (args) => {
+        const { program, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+        const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
+        const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
+        const [namePricePda] = PublicKey.findProgramAddressSync([Buffer.from("name_price"), Buffer.from(nameHash)], programId);
+
+        const quote: any = await program.methods
+          .quotePremium(name, Array.from(nameHash))
+          .accounts({ config: configPda, premiumConfig: premiumConfigPda, premiumName: premiumPda, namePrice: namePricePda })
+          .view();
+
+        console.log(
+          JSON.stringify(
+            {
+              name,
+              labelLen: quote.labelLen,
+              priceLamports: quote.priceLamports.toString(),
+              tollMint: quote.tollMint.toBase58(),
+              tollUnits: quote.tollUnits.toString(),
+              listed: quote.listed,
+              reserved: quote.reserved,
+              auctionOnly: quote.auctionOnly,
+              available: quote.available,
+            },
+            null,
+            2
+          )
+        );
+      }
+    )
+    .command(
+      "renew-premium",
+      "Extend a premium .dns name by whole years (owner-only during grace)",
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true })
+          .option("years", { type: "number", default: 1 }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+        const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
+        const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
+        const treasury = await fetchTreasuryFromConfig(connection, configPda);
+        const nftMint = await fetchPremiumNft(connection, premiumPda);
+        const createdAt = await fetchPremiumCreatedAt(connection, premiumPda);
+
+        const sig = await program.methods
+          .renewPremium(Array.from(nameHash), Number(args.years))
+          .accounts({
+            config: configPda,
+            premiumConfig: premiumConfigPda,
+            premiumName: premiumPda,
+            treasury,
+            payer: payer.publicKey,
+            systemProgram: SystemProgram.programId,
+            ...premiumNftRefreshAccounts(programId, nameHash, nftMint, createdAt),
+          })
+          .rpc();
+
+        console.log(JSON.stringify({ tx: sig, premiumPda: premiumPda.toBase58(), name, years: args.years }, null, 2));
+      }
+    )
+    .command(
+      "release-premium",
+      "Release a premium .dns name whose grace period has ended",
+      (y) => y.option("name", { type: "string", demandOption: true }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
+        const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
+        const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), Buffer.from(nameHash)], programId);
+        const info = await connection.getAccountInfo(premiumPda);
+        if (!info || info.data.length < 8 + 64) throw new Error(`premium name not found at ${premiumPda.toBase58()}`);
+        const [listingPda] = PublicKey.findProgramAddressSync([Buffer.from("listing"), Buffer.from(nameHash)], programId);
+        // The seller stays owner while a name is listed; release closes an open listing with the name.
+        const listed = (await connection.getAccountInfo(listingPda)) !== null;
+        const previousOwner = new PublicKey(info.data.subarray(8 + 32, 8 + 64));
+        const nftMint = listed ? await fetchPremiumNft(connection, premiumPda) : null;
+
+        const sig = await program.methods
+          .releasePremium(Array.from(nameHash))
+          .accounts({
+            premiumConfig: premiumConfigPda,
+            premiumName: premiumPda,
+            parentPolicy: policyPda,
+            listing: listed ? listingPda : null,
+            previousOwner,
+            previousOwnerPrimary: PublicKey.findProgramAddressSync([Buffer.from("primary"), previousOwner.toBuffer()], programId)[0],
+            caller: payer.publicKey,
+            nftMint,
+            escrowNftAccount: nftMint ? getAssociatedTokenAddressSync(nftMint, listingPda, true) : null,
+            sellerNftAccount: nftMint ? getAssociatedTokenAddressSync(nftMint, previousOwner) : null,
+            tokenProgram: nftMint ? TOKEN_PROGRAM_ID : null,
+          })
+          .rpc();
+
+        console.log(JSON.stringify({ tx: sig, premiumPda: premiumPda.toBase58(), name, previousOwner: previousOwner.toBase58() }, null, 2));
+      }
+    )
+    .command(
+      "list-premium",
+      "List a premium .dns name for sale at a fixed price or a falling (Dutch) price",
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true })
+          .option("price-sol", { type: "number", demandOption: true, describe: "starting price" })
+          .option("end-price-sol", { type: "number", describe: "floor price (defaults to the starting price)" })
+          .option("duration-secs", { type: "number", default: 0, describe: "time to fall from start to end price" }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
+        const [listingPda] = PublicKey.findProgramAddressSync([Buffer.from("listing"), Buffer.from(nameHash)], programId);
+        const start = Math.round((args["price-sol"] as number) * 1e9);
+        const end = args["end-price-sol"] == null ? start : Math.round((args["end-price-sol"] as number) * 1e9);
+
+        // NFT-bound names move into a token account owned by the listing PDA.
+        const nftMint = await fetchPremiumNft(connection, premiumPda);
+        const escrowNftAccount = nftMint ? getAssociatedTokenAddressSync(nftMint, listingPda, true) : null;
+        const preInstructions = nftMint
+          ? [createAssociatedTokenAccountInstruction(payer.publicKey, escrowNftAccount!, listingPda, nftMint)]
+          : [];
+
+        const sig = await program.methods
+          .listPremium(Array.from(nameHash), new BN(start), new BN(end), new BN(Math.round(args["duration-secs"] as number)))
+          .accounts({
+            premiumName: premiumPda,
+            listing: listingPda,
+            seller: payer.publicKey,
+            systemProgram: SystemProgram.programId,
+            nftMint,
+            sellerNftAccount: nftMint ? getAssociatedTokenAddressSync(nftMint, payer.publicKey) : null,
+            escrowNftAccount,
+            tokenProgram: nftMint ? TOKEN_PROGRAM_ID : null,
+          })
+          .preInstructions(preInstructions)
+          .rpc();
+
+        console.log(JSON.stringify({ tx: sig, name, listingPda: listingPda.toBase58(), startLamports: start, endLamports: end }, null, 2));
+      }
+    )
+    .command(
+      "delist-premium",
+      "Cancel your listing and take the name (and NFT) back",
+      (y) => y.option("name", { type: "string", demandOption: true }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
+        const [listingPda] = PublicKey.findProgramAddressSync([Buffer.from("listing"), Buffer.from(nameHash)], programId);
+        const nftMint = await fetchPremiumNft(connection, premiumPda);
+
+        const sig = await program.methods
+          .delistPremium(Array.from(nameHash))
+          .accounts({
+            premiumName: premiumPda,
+            listing: listingPda,
+            seller: payer.publicKey,
+            nftMint,
+            escrowNftAccount: nftMint ? getAssociatedTokenAddressSync(nftMint, listingPda, true) : null,
+            sellerNftAccount: nftMint ? getAssociatedTokenAddressSync(nftMint, payer.publicKey) : null,
+            tokenProgram: nftMint ? TOKEN_PROGRAM_ID : null,
+          })
+          .rpc();
+
+        console.log(JSON.stringify({ tx: sig, name, listingPda: listingPda.toBase58() }, null, 2));
+      }
+    )
+    .command(
+      "buy-listed",
+      "Buy a listed premium .dns name at its current price",
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true })
+          .option("max-price-sol", { type: "number", demandOption: true }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+        const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
+        const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), Buffer.from(nameHash)], programId);
+        const [listingPda] = PublicKey.findProgramAddressSync([Buffer.from("listing"), Buffer.from(nameHash)], programId);
+        const seller = await fetchListingSeller(connection, listingPda);
+        const [sellerPrimary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), seller.toBuffer()], programId);
+        const [buyerPrimary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), payer.publicKey.toBuffer()], programId);
+        const treasury = await fetchTreasuryFromConfig(connection, configPda);
+        const nftMint = await fetchPremiumNft(connection, premiumPda);
+        const createdAt = await fetchPremiumCreatedAt(connection, premiumPda);
+        const buyerNftAccount = nftMint ? getAssociatedTokenAddressSync(nftMint, payer.publicKey) : null;
+        const preInstructions =
+          nftMint && !(await connection.getAccountInfo(buyerNftAccount!))
+            ? [createAssociatedTokenAccountInstruction(payer.publicKey, buyerNftAccount!, payer.publicKey, nftMint)]
+            : [];
+
+        const sig = await program.methods
+          .buyListedPremium(name, Array.from(nameHash), new BN(Math.round((args["max-price-sol"] as number) * 1e9)))
+          .accounts({
+            config: configPda,
+            premiumName: premiumPda,
+            parentPolicy: policyPda,
+            listing: listingPda,
+            seller,
+            sellerPrimary,
+            buyerPrimary,
+            treasury,
+            buyer: payer.publicKey,
+            systemProgram: SystemProgram.programId,
+            nftMint,
+            escrowNftAccount: nftMint ? getAssociatedTokenAddressSync(nftMint, listingPda, true) : null,
+            buyerNftAccount,
+            tokenProgram: nftMint ? TOKEN_PROGRAM_ID : null,
+            ...premiumNftRefreshAccounts(programId, nameHash, nftMint, createdAt),
+          })
+          .preInstructions(preInstructions)
+          .rpc();
+
+        console.log(JSON.stringify({ tx: sig, name, seller: seller.toBase58(), buyer: payer.publicKey.toBase58() }, null, 2));
+      }
+    )
     .command(
       "bind-premium-nft",
       "Bind a premium .dns name to an NFT mint held by the current owner wallet",
@@ -313,34 +768,89 @@ async function main() {
         );
       }
     )
+    .command(
+      "set-nft-metadata",
+      "Set the symbol and URI template ({mint}, {owner}, {expires}) for name NFTs (authority only)",
+      (y) =>
+        y
+          .option("symbol", { type: "string", default: "DNS" })
+          .option("uri-template", { type: "string", demandOption: true }),
+      async (args) => {
+        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+
+        const sig = await program.methods
+          .setNftMetadata(String(args.symbol), String(args["uri-template"]))
+          .accounts({ config: configPda, authority: payer.publicKey })
+          .rpc();
+        console.log(JSON.stringify({ tx: sig, symbol: args.symbol, uriTemplate: args["uri-template"] }, null, 2));
+      }
+    )
+    .command(
+      "migrate-account",
+      "Grow an account created with an older layout to the current one (authority only; migrate configs first)",
+      (y) => y.option("address", { type: "string", demandOption: true }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const target = new PublicKey(String(args.address));
+        const info = await connection.getAccountInfo(target);
+        if (!info) throw new Error(`account not found at ${target.toBase58()}`);
+        // A PremiumName takes its new expiry from the premium config.
+        const premiumNameDisc = (program.idl.accounts ?? []).find((a: any) => a.name.toLowerCase() === "premiumname")?.discriminator ?? [];
+        const isPremiumName = Buffer.from(premiumNameDisc).equals(info.data.subarray(0, 8));
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+        const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
+
+        const sig = await program.methods
+          .migrateAccount()
+          .accounts({
+            config: configPda,
+            premiumConfig: isPremiumName ? premiumConfigPda : null,
+            target,
+            authority: payer.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        const migrated = await connection.getAccountInfo(target);
+        console.log(JSON.stringify({ tx: sig, address: target.toBase58(), oldLen: info.data.length, newLen: migrated?.data.length }, null, 2));
+      }
+    )
     .command(
       "issue-premium-nft",
       "Mint the canonical premium-name NFT to the current premium owner wallet",
       (y) => y.option("name", { type: "string", demandOption: true }),
       async (args) => {
-        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
         const name = normalizeFullName(String(args.name));
         const nameHash = hashName(name);
         const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
-        const [nftMintPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_nft_mint"), Buffer.from(nameHash)], programId);
+        const createdAt = await fetchPremiumCreatedAt(connection, premiumPda);
+        const nftMintPda = findNftMintPda(programId, [Buffer.from("premium_nft_mint"), nameHash], createdAt);
         const [nftAuthorityPda] = PublicKey.findProgramAddressSync([Buffer.from("nft_authority")], programId);
         const ownerNftAccount = getAssociatedTokenAddressSync(nftMintPda, payer.publicKey);
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+        const nftMetadata = findNftMetadataPda(nftMintPda);
 
         const sig = await program.methods
-          .issuePremiumNft(Array.from(nameHash))
+          .issuePremiumNft(name, Array.from(nameHash))
           .accounts({
+            config: configPda,
             premiumName: premiumPda,
             nftMint: nftMintPda,
+            nftMetadata,
+            nftEdition: findNftMetadataPda(nftMintPda, true),
             ownerNftAccount,
             nftAuthority: nftAuthorityPda,
             owner: payer.publicKey,
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
+            tokenMetadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
           })
           .rpc();
 
-        console.log(JSON.stringify({ tx: sig, name, premiumPda: premiumPda.toBase58(), nftMint: nftMintPda.toBase58(), ownerNftAccount: ownerNftAccount.toBase58() }, null, 2));
+        console.log(JSON.stringify({ tx: sig, name, premiumPda: premiumPda.toBase58(), nftMint: nftMintPda.toBase58(), nftMetadata: nftMetadata.toBase58(), ownerNftAccount: ownerNftAccount.toBase58() }, null, 2));
       }
     )
     .command(
@@ -353,7 +863,7 @@ async function main() {
           .option("token-account", { type: "string", demandOption: true })
           .option("token-program", { type: "string", default: TOKEN_PROGRAM_ID.toBase58() }),
       async (args) => {
-        const { program, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const { program, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
         const name = normalizeFullName(String(args.name));
         const nameHash = hashName(name);
         const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
@@ -361,6 +871,12 @@ async function main() {
         const nftMint = new PublicKey(String(args.mint));
         const holderNftAccount = new PublicKey(String(args["token-account"]));
         const tokenProgram = new PublicKey(String(args["token-program"]));
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+        const premiumInfo = await connection.getAccountInfo(premiumPda);
+        if (!premiumInfo) throw new Error(`premium name not found at ${premiumPda.toBase58()}`);
+        const refresh = premiumNftRefreshAccounts(programId, nameHash, nftMint, premiumInfo.data.readBigInt64LE(8 + 104));
+        const previousOwner = new PublicKey(premiumInfo.data.subarray(8 + 32, 8 + 64));
+        const [previousOwnerPrimary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), previousOwner.toBuffer()], programId);
 
         const sig = await program.methods
           .syncPremiumOwnerFromNft()
@@ -370,6 +886,9 @@ async function main() {
             nftMint,
             holderNftAccount,
             tokenProgram,
+            previousOwnerPrimary,
+            config: refresh.nftMetadata ? configPda : null,
+            ...refresh,
           })
           .rpc();
 
@@ -395,22 +914,29 @@ async function main() {
       "Mint the canonical non-premium .dns NFT into program custody for a subdomain owner",
       (y) => y.option("label", { type: "string", demandOption: true }).option("parent", { type: "string", default: "user.dns" }),
       async (args) => {
-        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
         const parent = normalizeFullName(String(args.parent));
         const label = normalizeLabel(String(args.label));
         const parentHash = hashName(parent);
         const labelHash = hashLabel(label);
         const [subPda] = PublicKey.findProgramAddressSync([Buffer.from("sub"), Buffer.from(parentHash), Buffer.from(labelHash)], programId);
-        const [nftMintPda] = PublicKey.findProgramAddressSync([Buffer.from("sub_nft_mint"), Buffer.from(parentHash), Buffer.from(labelHash)], programId);
+        // SubName: disc(8) parent_hash(32) label_hash(32) owner(32) policy(1) parent_owner(32) transfers_enabled(1) created(8).
+        const subInfo = await connection.getAccountInfo(subPda);
+        if (!subInfo) throw new Error(`subdomain not found at ${subPda.toBase58()}`);
+        const nftMintPda = findNftMintPda(programId, [Buffer.from("sub_nft_mint"), parentHash, labelHash], subInfo.data.readBigInt64LE(8 + 130));
         const [nftAuthorityPda] = PublicKey.findProgramAddressSync([Buffer.from("nft_authority")], programId);
         const [nftCustodyAuthorityPda] = PublicKey.findProgramAddressSync([Buffer.from("nft_custody_authority")], programId);
         const custodyNftAccount = getAssociatedTokenAddressSync(nftMintPda, nftCustodyAuthorityPda, true);
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
 
         const sig = await program.methods
-          .issueSubdomainNft(Array.from(parentHash), Array.from(labelHash))
+          .issueSubdomainNft(parent, label, Array.from(parentHash), Array.from(labelHash))
           .accounts({
+            config: configPda,
             subName: subPda,
             nftMint: nftMintPda,
+            nftMetadata: findNftMetadataPda(nftMintPda),
+            nftEdition: findNftMetadataPda(nftMintPda, true),
             nftAuthority: nftAuthorityPda,
             nftCustodyAuthority: nftCustodyAuthorityPda,
             custodyNftAccount,
@@ -418,6 +944,8 @@ async function main() {
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
+            tokenMetadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
           })
           .rpc();
 
@@ -431,7 +959,9 @@ async function main() {
         y
           .option("name", { type: "string", demandOption: true })
           .option("min-bid-sol", { type: "number" })
-          .option("duration-slots", { type: "number" }),
+          .option("duration-slots", { type: "number" })
+          .option("sealed", { type: "boolean", default: false, describe: "second-price sealed-bid auction" })
+          .option("reveal-slots", { type: "number", describe: "sealed reveal phase length (defaults to duration)" }),
       async (args) => {
         const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
         const name = normalizeFullName(String(args.name));
@@ -439,13 +969,16 @@ async function main() {
         const nameHash = hashName(name);
         const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
         const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
+        const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
         const minBid = args["min-bid-sol"] == null ? null : new BN(Math.round((args["min-bid-sol"] as number) * 1e9));
         const duration = args["duration-slots"] == null ? null : new BN(Math.round(args["duration-slots"] as number));
+        const revealSlots = args["reveal-slots"] == null ? null : new BN(Math.round(args["reveal-slots"] as number));
 
         const sig = await program.methods
-          .createAuction(name, Array.from(nameHash), minBid, duration)
+          .createAuction(name, Array.from(nameHash), minBid, duration, args.sealed ? 1 : 0, revealSlots)
           .accounts({
             premiumConfig: premiumConfigPda,
+            premiumName: premiumPda,
             auction: auctionPda,
             authority: payer.publicKey,
             systemProgram: SystemProgram.programId,
@@ -454,6 +987,24 @@ async function main() {
         console.log(JSON.stringify({ tx: sig, name, auctionPda: auctionPda.toBase58() }, null, 2));
       }
     )
+    .command(
+      "cancel-auction",
+      "Cancel a premium auction that has no bids (authority only)",
+      (y) => y.option("name", { type: "string", demandOption: true }),
+      async (args) => {
+        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
+        const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
+
+        const sig = await program.methods
+          .cancelAuction(Array.from(nameHash))
+          .accounts({ premiumConfig: premiumConfigPda, auction: auctionPda, authority: payer.publicKey })
+          .rpc();
+        console.log(JSON.stringify({ tx: sig, name, auctionPda: auctionPda.toBase58() }, null, 2));
+      }
+    )
     .command(
       "bid",
       "Place bid into auction escrow",
@@ -463,21 +1014,16 @@ async function main() {
           .option("lamports", { type: "number" })
           .option("sol", { type: "number" }),
       async (args) => {
-        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
         const name = normalizeFullName(String(args.name));
         const nameHash = hashName(name);
         const lamports = args.lamports != null ? Math.round(args.lamports as number) : Math.round((args.sol as number) * 1e9);
         if (!Number.isFinite(lamports) || lamports <= 0) throw new Error("provide --lamports or --sol with a positive value");
         const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
         const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
-        const [escrowPda] = PublicKey.findProgramAddressSync(
-          [Buffer.from("escrow"), Buffer.from(nameHash), payer.publicKey.toBuffer()],
-          programId
-        );
-        const [escrowVaultPda] = PublicKey.findProgramAddressSync(
-          [Buffer.from("escrow_vault"), Buffer.from(nameHash), payer.publicKey.toBuffer()],
-          programId
-        );
+        const startSlot = await fetchAuctionStartSlot(connection, auctionPda);
+        const escrowPda = bidPda("escrow", nameHash, payer.publicKey, startSlot, programId);
+        const escrowVaultPda = bidPda("escrow_vault", nameHash, payer.publicKey, startSlot, programId);
         const sig = await program.methods
           .placeBid(Array.from(nameHash), new BN(lamports))
           .accounts({
@@ -495,20 +1041,19 @@ async function main() {
     .command(
       "withdraw-losing-bid",
       "Withdraw escrow from losing bid",
-      (y) => y.option("name", { type: "string", demandOption: true }),
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true })
+          .option("start-slot", { type: "number", describe: "auction round (its start_slot); defaults to the current round" }),
       async (args) => {
-        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
         const name = normalizeFullName(String(args.name));
         const nameHash = hashName(name);
         const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
-        const [escrowPda] = PublicKey.findProgramAddressSync(
-          [Buffer.from("escrow"), Buffer.from(nameHash), payer.publicKey.toBuffer()],
-          programId
-        );
-        const [escrowVaultPda] = PublicKey.findProgramAddressSync(
-          [Buffer.from("escrow_vault"), Buffer.from(nameHash), payer.publicKey.toBuffer()],
-          programId
-        );
+        const startSlot =
+          args["start-slot"] != null ? BigInt(args["start-slot"] as number) : await fetchAuctionStartSlot(connection, auctionPda);
+        const escrowPda = bidPda("escrow", nameHash, payer.publicKey, startSlot, programId);
+        const escrowVaultPda = bidPda("escrow_vault", nameHash, payer.publicKey, startSlot, programId);
         const sig = await program.methods
           .withdrawLosingBid(Array.from(nameHash))
           .accounts({
@@ -523,8 +1068,8 @@ async function main() {
       }
     )
     .command(
-      "settle-auction",
-      "Settle auction and mint premium to winner",
+      "withdraw-legacy-bid",
+      "Withdraw a bid escrowed before bid accounts were keyed by auction round",
       (y) => y.option("name", { type: "string", demandOption: true }),
       async (args) => {
         const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
@@ -533,14 +1078,40 @@ async function main() {
         const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
         const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
         const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
-        const [escrowPda] = PublicKey.findProgramAddressSync(
-          [Buffer.from("escrow"), Buffer.from(nameHash), payer.publicKey.toBuffer()],
-          programId
-        );
+        const [escrowPda] = PublicKey.findProgramAddressSync([Buffer.from("escrow"), Buffer.from(nameHash), payer.publicKey.toBuffer()], programId);
         const [escrowVaultPda] = PublicKey.findProgramAddressSync(
           [Buffer.from("escrow_vault"), Buffer.from(nameHash), payer.publicKey.toBuffer()],
           programId
         );
+        const treasury = await fetchTreasuryFromConfig(connection, configPda);
+        const sig = await program.methods
+          .withdrawLegacyBid(Array.from(nameHash))
+          .accounts({
+            premiumConfig: premiumConfigPda,
+            auction: auctionPda,
+            bidEscrow: escrowPda,
+            escrowVault: escrowVaultPda,
+            treasury,
+            bidder: payer.publicKey,
+          })
+          .rpc();
+        console.log(JSON.stringify({ tx: sig, escrowPda: escrowPda.toBase58(), escrowVaultPda: escrowVaultPda.toBase58() }, null, 2));
+      }
+    )
+    .command(
+      "settle-auction",
+      "Settle auction and mint premium to winner",
+      (y) => y.option("name", { type: "string", demandOption: true }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+        const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
+        const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
+        const startSlot = await fetchAuctionStartSlot(connection, auctionPda);
+        const escrowPda = bidPda("escrow", nameHash, payer.publicKey, startSlot, programId);
+        const escrowVaultPda = bidPda("escrow_vault", nameHash, payer.publicKey, startSlot, programId);
         const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
         const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), Buffer.from(nameHash)], programId);
         const [primaryPda] = PublicKey.findProgramAddressSync([Buffer.from("primary"), payer.publicKey.toBuffer()], programId);
@@ -564,34 +1135,375 @@ async function main() {
         console.log(JSON.stringify({ tx: sig, auctionPda: auctionPda.toBase58(), premiumPda: premiumPda.toBase58(), winnerEscrow: escrowPda.toBase58(), winnerEscrowVault: escrowVaultPda.toBase58() }, null, 2));
       }
     )
+    .command(
+      "sealed-bid",
+      "Commit a sealed bid with a deposit (sealed auctions only)",
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true })
+          .option("lamports", { type: "number", demandOption: true })
+          .option("deposit-lamports", { type: "number", describe: "defaults to the bid; a larger deposit hides the bid" }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const lamports = BigInt(Math.round(args.lamports as number));
+        const deposit = args["deposit-lamports"] == null ? lamports : BigInt(Math.round(args["deposit-lamports"] as number));
+        const salt = crypto.randomBytes(32);
+        const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
+        const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
+        const sealedBidPda = bidPda("sealed_bid", nameHash, payer.publicKey, await fetchAuctionStartSlot(connection, auctionPda), programId);
+
+        const sig = await program.methods
+          .commitSealedBid(Array.from(nameHash), Array.from(sealedBidHash(nameHash, payer.publicKey, lamports, salt)), new BN(deposit.toString()))
+          .accounts({
+            premiumConfig: premiumConfigPda,
+            auction: auctionPda,
+            sealedBid: sealedBidPda,
+            bidder: payer.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        // Keep the salt: reveal-bid needs it, and an unrevealed bid forfeits its deposit.
+        console.log(JSON.stringify({ tx: sig, sealedBidPda: sealedBidPda.toBase58(), lamports: lamports.toString(), salt: salt.toString("hex") }, null, 2));
+      }
+    )
+    .command(
+      "reveal-bid",
+      "Reveal a sealed bid during the reveal phase",
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true })
+          .option("lamports", { type: "number", demandOption: true })
+          .option("salt", { type: "string", demandOption: true }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
+        const sealedBidPda = bidPda("sealed_bid", nameHash, payer.publicKey, await fetchAuctionStartSlot(connection, auctionPda), programId);
+
+        const sig = await program.methods
+          .revealSealedBid(Array.from(nameHash), new BN(Math.round(args.lamports as number)), Array.from(parseSalt(String(args.salt))))
+          .accounts({ auction: auctionPda, sealedBid: sealedBidPda, bidder: payer.publicKey })
+          .rpc();
+        console.log(JSON.stringify({ tx: sig, sealedBidPda: sealedBidPda.toBase58() }, null, 2));
+      }
+    )
+    .command(
+      "settle-sealed-auction",
+      "Settle a sealed auction at the second-highest bid and mint premium to the winner",
+      (y) => y.option("name", { type: "string", demandOption: true }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
+        const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
+        const sealedBidPda = bidPda("sealed_bid", nameHash, payer.publicKey, await fetchAuctionStartSlot(connection, auctionPda), programId);
+        const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
+        const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), Buffer.from(nameHash)], programId);
+        const [primaryPda] = PublicKey.findProgramAddressSync([Buffer.from("primary"), payer.publicKey.toBuffer()], programId);
+        const treasury = await fetchTreasuryVaultFromPremiumConfig(connection, premiumConfigPda);
+
+        const sig = await program.methods
+          .settleSealedAuction(Array.from(nameHash))
+          .accounts({
+            premiumConfig: premiumConfigPda,
+            auction: auctionPda,
+            winnerBid: sealedBidPda,
+            treasury,
+            premiumName: premiumPda,
+            parentPolicy: policyPda,
+            primary: primaryPda,
+            winner: payer.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        console.log(JSON.stringify({ tx: sig, auctionPda: auctionPda.toBase58(), premiumPda: premiumPda.toBase58() }, null, 2));
+      }
+    )
+    .command(
+      "close-sealed-bid",
+      "Refund a losing revealed sealed bid, or forfeit an unrevealed one after the reveal phase",
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true })
+          .option("bidder", { type: "string", describe: "defaults to the wallet" })
+          .option("start-slot", { type: "number", describe: "auction round (its start_slot); defaults to the current round" }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const nameHash = hashName(name);
+        const bidder = args.bidder ? new PublicKey(String(args.bidder)) : payer.publicKey;
+        const [premiumConfigPda] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], programId);
+        const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), Buffer.from(nameHash)], programId);
+        const startSlot =
+          args["start-slot"] != null ? BigInt(args["start-slot"] as number) : await fetchAuctionStartSlot(connection, auctionPda);
+        const sealedBidPda = bidPda("sealed_bid", nameHash, bidder, startSlot, programId);
+        const treasury = await fetchTreasuryVaultFromPremiumConfig(connection, premiumConfigPda);
+
+        const sig = await program.methods
+          .closeSealedBid(Array.from(nameHash))
+          .accounts({ premiumConfig: premiumConfigPda, auction: auctionPda, sealedBid: sealedBidPda, bidder, treasury })
+          .rpc();
+        console.log(JSON.stringify({ tx: sig, sealedBidPda: sealedBidPda.toBase58(), bidder: bidder.toBase58() }, null, 2));
+      }
+    )
+    .command(
+      "set-sub-sale",
+      "Set the terms on which anyone may buy subdomains of a premium name you own",
+      (y) =>
+        y
+          .option("parent", { type: "string", demandOption: true })
+          .option("enabled", { type: "boolean", default: true })
+          .option("price", { type: "string", default: "0", describe: "per label: lamports, or base units of --mint" })
+          .option("mint", { type: "string", describe: "SPL mint to price in (default SOL)" })
+          .option("allowlist", { type: "string", describe: "JSON array of wallets allowed to claim" })
+          .option("max-sales", { type: "number", default: 0, describe: "0 for no limit" })
+          .option("period-secs", { type: "number", default: 0, describe: "subdomain lifetime; 0 never expires" }),
+      async (args) => {
+        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const parent = normalizeFullName(String(args.parent));
+        const parentHash = hashName(parent);
+        const allowlistRoot = args.allowlist ? buildAllowlist(loadAllowlist(String(args.allowlist))).root : new Uint8Array(32);
+        const terms = {
+          enabled: Boolean(args.enabled),
+          price: new BN(String(args.price)),
+          priceMint: args.mint ? new PublicKey(String(args.mint)) : PublicKey.default,
+          allowlistRoot: Array.from(allowlistRoot),
+          maxSales: Number(args["max-sales"]),
+          periodSecs: new BN(Number(args["period-secs"])),
+        };
+        const [premiumParent] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(parentHash)], programId);
+        const [parentPolicy] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), Buffer.from(parentHash)], programId);
+
+        const sig = await program.methods
+          .setSubdomainSaleTerms(Array.from(parentHash), terms)
+          .accounts({ premiumParent, parentPolicy, parentOwner: payer.publicKey, systemProgram: SystemProgram.programId })
+          .rpc();
+
+        console.log(JSON.stringify({ tx: sig, parent, allowlistRoot: Buffer.from(allowlistRoot).toString("hex") }, null, 2));
+      }
+    )
+    .command(
+      "set-sub-label-rule",
+      "Reserve a label under a premium name you own, or override its price (--clear removes the rule)",
+      (y) =>
+        y
+          .option("parent", { type: "string", demandOption: true })
+          .option("label", { type: "string", demandOption: true })
+          .option("reserved", { type: "boolean", default: false })
+          .option("price", { type: "string", default: "0", describe: "0 keeps the sale price" })
+          .option("clear", { type: "boolean", default: false }),
+      async (args) => {
+        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const parentHash = hashName(String(args.parent));
+        const labelHash = hashLabel(String(args.label));
+        const [premiumParent] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(parentHash)], programId);
+        const [labelRule] = PublicKey.findProgramAddressSync(
+          [Buffer.from("sub_label"), Buffer.from(parentHash), Buffer.from(labelHash)],
+          programId
+        );
+
+        const sig = args.clear
+          ? await program.methods
+              .clearSubLabelRule(Array.from(parentHash), Array.from(labelHash))
+              .accounts({ premiumParent, labelRule, parentOwner: payer.publicKey })
+              .rpc()
+          : await program.methods
+              .setSubLabelRule(Array.from(parentHash), Array.from(labelHash), Boolean(args.reserved), new BN(String(args.price)))
+              .accounts({ premiumParent, labelRule, parentOwner: payer.publicKey, systemProgram: SystemProgram.programId })
+              .rpc();
+
+        console.log(JSON.stringify({ tx: sig, labelRule: labelRule.toBase58() }, null, 2));
+      }
+    )
+    .command(
+      "claim-under-parent",
+      "Buy (or renew) a subdomain on its premium parent's sale terms",
+      (y) =>
+        y
+          .option("parent", { type: "string", demandOption: true })
+          .option("label", { type: "string", demandOption: true })
+          .option("allowlist", { type: "string", describe: "the parent's allowlist JSON, to build this wallet's proof" })
+          .option("salt", { type: "string", describe: "reveal a commitment made with commit-name" }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const parent = normalizeFullName(String(args.parent));
+        const label = normalizeLabel(String(args.label));
+        const parentHash = hashName(parent);
+        const labelHash = hashLabel(label);
+        const proof = args.allowlist ? buildAllowlist(loadAllowlist(String(args.allowlist))).proofs.get(payer.publicKey.toBase58()) : [];
+        if (!proof) throw new Error(`${payer.publicKey.toBase58()} is not in ${args.allowlist}`);
+
+        const [premiumParent] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(parentHash)], programId);
+        const [parentPolicy] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), Buffer.from(parentHash)], programId);
+        const [labelRule] = PublicKey.findProgramAddressSync([Buffer.from("sub_label"), Buffer.from(parentHash), Buffer.from(labelHash)], programId);
+        const [subPda] = PublicKey.findProgramAddressSync([Buffer.from("sub"), Buffer.from(parentHash), Buffer.from(labelHash)], programId);
+        const [primary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), payer.publicKey.toBuffer()], programId);
+        const premiumInfo = await connection.getAccountInfo(premiumParent);
+        const policyInfo = await connection.getAccountInfo(parentPolicy);
+        if (!premiumInfo || !policyInfo) throw new Error(`${parent} is not registered or has no sale terms`);
+        const parentWallet = new PublicKey(premiumInfo.data.subarray(8 + 32, 8 + 64));
+        // ParentPolicy: parent_hash, parent_owner, transfers_enabled, bump, then sale terms (enabled, price, price_mint, ...).
+        const priceMint = new PublicKey(policyInfo.data.subarray(8 + 66 + 9, 8 + 66 + 41));
+        const subInfo = await connection.getAccountInfo(subPda);
+        const holder = subInfo ? new PublicKey(subInfo.data.subarray(8 + 64, 8 + 96)) : PublicKey.default;
+        const [previousOwnerPrimary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), holder.toBuffer()], programId);
+
+        const tokenAccounts = priceMint.equals(PublicKey.default)
+          ? {}
+          : {
+              payerTokenAccount: getAssociatedTokenAddressSync(priceMint, payer.publicKey),
+              parentTokenAccount: getAssociatedTokenAddressSync(priceMint, parentWallet),
+              priceMint,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            };
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+        const reveal = await revealFor(
+          program,
+          programId,
+          payer.publicKey,
+          hashSubdomainName(parentHash, labelHash),
+          args.salt as string | undefined
+        );
+        const sig = await program.methods
+          .claimSubdomainUnderParent(parent, label, Array.from(parentHash), Array.from(labelHash), proof.map((p) => Array.from(p)))
+          .accounts({
+            config: configPda,
+            premiumParent,
+            parentPolicy,
+            labelRule,
+            subName: subPda,
+            previousOwnerPrimary,
+            primary,
+            parentWallet,
+            nameCommitment: reveal.nameCommitment,
+            claimer: payer.publicKey,
+            systemProgram: SystemProgram.programId,
+            ...tokenAccounts,
+          })
+          .preInstructions(reveal.preInstructions)
+          .rpc();
+
+        console.log(JSON.stringify({ tx: sig, subPda: subPda.toBase58(), name: `${label}.${parent}` }, null, 2));
+      }
+    )
+    .command(
+      "claim-nested-sub",
+      "Delegate label.parent under a subdomain you own",
+      (y) =>
+        y
+          .option("parent", { type: "string", demandOption: true, describe: "the subdomain you own, e.g. www.alice.dns" })
+          .option("parent-depth", { type: "number", default: 1, describe: "subdomain labels in --parent below its premium name or zone" })
+          .option("label", { type: "string", demandOption: true })
+          .option("owner", { type: "string", describe: "initial owner (default: wallet)" }),
+      async (args) => {
+        const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const parent = normalizeFullName(String(args.parent));
+        const parentDepth = Number(args["parent-depth"]);
+        const label = normalizeLabel(String(args.label));
+        const parentHash = hashChainedName(parent, parentDepth);
+        const labelHash = hashLabel(label);
+        const initialOwner = args.owner ? new PublicKey(String(args.owner)) : payer.publicKey;
+
+        const parentSub = findSubPda(programId, parent, parentDepth);
+        const [parentPolicy] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), Buffer.from(parentHash)], programId);
+        const [subPda] = PublicKey.findProgramAddressSync([Buffer.from("sub"), Buffer.from(parentHash), Buffer.from(labelHash)], programId);
+
+        const sig = await program.methods
+          .claimNestedSubdomain(parent, label, Array.from(parentHash), Array.from(labelHash), initialOwner)
+          .accounts({
+            parentSub,
+            parentPolicy,
+            subName: subPda,
+            parentOwner: payer.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(ancestorSubAccounts(programId, parent, parentDepth))
+          .rpc();
+
+        console.log(
+          JSON.stringify({ tx: sig, subPda: subPda.toBase58(), name: `${label}.${parent}`, depth: parentDepth + 1, owner: initialOwner.toBase58() }, null, 2)
+        );
+      }
+    )
+    .command(
+      "revoke-sub",
+      "Revoke a descendant subdomain of a premium name or subdomain you own",
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true, describe: "subdomain to revoke" })
+          .option("depth", { type: "number", demandOption: true, describe: "subdomain labels in --name below its premium name or zone" })
+          .option("ancestor", { type: "string", demandOption: true, describe: "premium name or subdomain you own above --name" }),
+      async (args) => {
+        const { program, payer, connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
+        const name = normalizeFullName(String(args.name));
+        const ancestor = normalizeFullName(String(args.ancestor));
+        const depth = Number(args.depth);
+        const labels = name.split(".");
+        const levels = labels.length - ancestor.split(".").length;
+        if (!name.endsWith(`.${ancestor}`) || levels < 1 || levels > depth) {
+          throw new Error(`${ancestor} is not an ancestor of ${name} at depth ${depth}`);
+        }
+        const ancestorDepth = depth - levels;
+        const ancestorHash = hashChainedName(ancestor, ancestorDepth);
+        // Labels strictly between the ancestor and the revoked name, top-down.
+        const intermediate = labels.slice(1, levels).reverse().map((l) => Array.from(hashLabel(l)));
+
+        const subPda = findSubPda(programId, name, depth);
+        const subInfo = await connection.getAccountInfo(subPda);
+        if (!subInfo) throw new Error(`${name} not found at ${subPda.toBase58()}`);
+        const holder = new PublicKey(subInfo.data.subarray(8 + 64, 8 + 96));
+        const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], programId);
+        const [ownerPrimary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), holder.toBuffer()], programId);
+        const ancestorPremium =
+          ancestorDepth === 0 ? PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(ancestorHash)], programId)[0] : null;
+        const ancestorSub = ancestorDepth === 0 ? null : findSubPda(programId, ancestor, ancestorDepth);
+
+        const sig = await program.methods
+          .revokeSubdomain(Array.from(ancestorHash), intermediate)
+          .accounts({
+            config: configPda,
+            subName: subPda,
+            ancestorPremium,
+            ancestorSub,
+            ownerPrimary,
+            authority: payer.publicKey,
+          })
+          .rpc();
+
+        console.log(JSON.stringify({ tx: sig, revoked: name, subPda: subPda.toBase58(), holder: holder.toBase58() }, null, 2));
+      }
+    )
     .command(
       "set-primary",
       "Set primary name for wallet",
-      (y) => y.option("name", { type: "string", demandOption: true }),
+      (y) =>
+        y
+          .option("name", { type: "string", demandOption: true })
+          .option("depth", { type: "number", describe: "subdomain labels below the premium name or parent zone (default 1 for *.user.dns, else 0)" }),
       async (args) => {
         const { program, payer, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
         const name = normalizeFullName(String(args.name));
         const nameHash = hashName(name);
         const [primaryPda] = PublicKey.findProgramAddressSync([Buffer.from("primary"), payer.publicKey.toBuffer()], programId);
+        const depth = args.depth === undefined ? (name.endsWith(".user.dns") ? 1 : 0) : Number(args.depth);
 
         let kind = 1;
         let premiumName: PublicKey | null = null;
         let subName: PublicKey | null = null;
-        if (name.endsWith(".user.dns")) {
+        if (depth > 0) {
           kind = 2;
-          const parts = name.split(".");
-          const label = parts[0];
-          const parent = "user.dns";
-          const parentHash = hashName(parent);
-          const labelHash = hashLabel(label);
-          nameHash.set(hashSubdomainName(parentHash, labelHash));
-          [subName] = PublicKey.findProgramAddressSync([Buffer.from("sub"), Buffer.from(parentHash), Buffer.from(labelHash)], programId);
+          nameHash.set(hashChainedName(name, depth));
+          subName = findSubPda(programId, name, depth);
         } else {
           [premiumName] = PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(nameHash)], programId);
         }
 
         const sig = await program.methods
-          .setPrimaryName(Array.from(nameHash), kind)
+          .setPrimaryName(name, Array.from(nameHash), kind)
           .accounts({
             primary: primaryPda,
             premiumName,
@@ -599,6 +1511,7 @@ async function main() {
             owner: payer.publicKey,
             systemProgram: SystemProgram.programId,
           })
+          .remainingAccounts(ancestorSubAccounts(programId, name, depth))
           .rpc();
 
         console.log(JSON.stringify({ tx: sig, primaryPda: primaryPda.toBase58(), name, kind }, null, 2));
@@ -606,7 +1519,7 @@ async function main() {
     )
     .command(
       "resolve-primary",
-      "Resolve wallet primary name hash",
+      "Resolve a wallet's primary name and check it is still owned and unexpired",
       (y) => y.option("owner", { type: "string", demandOption: true }),
       async (args) => {
         const { connection, programId } = await loadProgram(args.rpc as string, args.wallet as string, args["program-id"] as string | undefined);
@@ -614,7 +1527,24 @@ async function main() {
         const [primaryPda] = PublicKey.findProgramAddressSync([Buffer.from("primary"), owner.toBuffer()], programId);
 
         const acct = await fetchPrimaryRaw(connection, primaryPda);
-        console.log(JSON.stringify({ owner: owner.toBase58(), primaryPda: primaryPda.toBase58(), primary: acct }, null, 2));
+        let verified = false;
+        if (acct?.isSet && acct.owner === owner.toBase58()) {
+          // Same checks as the ddns_reverse crate: the name account must still belong to the wallet.
+          const nameAccount =
+            acct.kind === 1
+              ? PublicKey.findProgramAddressSync([Buffer.from("premium"), Buffer.from(acct.nameHashHex, "hex")], programId)[0]
+              : findSubPda(programId, acct.name, Math.max(acct.depth, 1));
+          const info = await connection.getAccountInfo(nameAccount);
+          if (info) {
+            const nameOwnerOffset = acct.kind === 1 ? 8 + 32 : 8 + 64;
+            const stillOwned = new PublicKey(info.data.subarray(nameOwnerOffset, nameOwnerOffset + 32)).equals(owner);
+            // SubName.expires_at is 0 for subdomains that never expire.
+            const expiresAt =
+              acct.kind === 1 ? Number(info.data.readBigInt64LE(8 + 115)) : Number(info.data.readBigInt64LE(8 + 204)) || Number.MAX_SAFE_INTEGER;
+            verified = stillOwned && Math.floor(Date.now() / 1000) < expiresAt;
+          }
+        }
+        console.log(JSON.stringify({ owner: owner.toBase58(), primaryPda: primaryPda.toBase58(), primary: acct, verified }, null, 2));
       }
     )
     .demandCommand(1)
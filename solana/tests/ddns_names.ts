@@ -5,6 +5,7 @@ import { expect } from "chai";
 import BN from "bn.js";
 import crypto from "node:crypto";
 import { createMint, getOrCreateAssociatedTokenAccount, mintTo, TOKEN_PROGRAM_ID } from "../scripts/lib/token.js";
+import { MPL_TOKEN_METADATA_PROGRAM_ID } from "../scripts/lib/metadata.js";
 
 function normalize(name: string): string {
   return name.trim().toLowerCase().replace(/\.+$/, "");
@@ -14,6 +15,14 @@ function hashName(name: string): Buffer {
   return crypto.createHash("sha256").update(normalize(name)).digest();
 }
 
+// Bid escrows, their vaults and sealed bids are per auction round (the auction's start_slot).
+function bidPda(seed: string, nameHash: Buffer, bidder: PublicKey, startSlot: BN, programId: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(seed), nameHash, bidder.toBuffer(), startSlot.toArrayLike(Buffer, "le", 8)],
+    programId
+  )[0];
+}
+
 async function sleep(ms: number) {
   await new Promise((r) => setTimeout(r, ms));
 }
@@ -33,6 +42,33 @@ function createSplTransferInstruction(source: PublicKey, destination: PublicKey,
   });
 }
 
+function metadataPda(mint: PublicKey, edition = false): PublicKey {
+  const seeds = [Buffer.from("metadata"), MPL_TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()];
+  if (edition) seeds.push(Buffer.from("edition"));
+  return PublicKey.findProgramAddressSync(seeds, MPL_TOKEN_METADATA_PROGRAM_ID)[0];
+}
+
+// The program-issued mint of a premium name, seeded by the registration's created_at.
+function premiumNftMintPda(nameHash: Buffer, createdAt: BN, programId: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("premium_nft_mint"), nameHash, createdAt.toArrayLike(Buffer, "le", 8)],
+    programId
+  )[0];
+}
+
+// Metadata layout: key(1) update_authority(32) mint(32) then name, symbol and uri as
+// u32-prefixed strings padded with NULs.
+function decodeMetadataStrings(data: Buffer): { name: string; symbol: string; uri: string } {
+  let offset = 1 + 32 + 32;
+  const next = () => {
+    const len = data.readUInt32LE(offset);
+    const value = data.subarray(offset + 4, offset + 4 + len).toString("utf8").replace(/\0+$/, "");
+    offset += 4 + len;
+    return value;
+  };
+  return { name: next(), symbol: next(), uri: next() };
+}
+
 describe("ddns_names premium auctions", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
@@ -167,31 +203,21 @@ describe("ddns_names premium auctions", () => {
     // Auction flow for 3-char.
     const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), threeHash], names.programId);
     await names.methods
-      .createAuction(threeName, [...threeHash], new BN(10_000_000), new BN(10))
+      .createAuction(threeName, [...threeHash], new BN(10_000_000), new BN(10), 0, null)
       .accounts({
         premiumConfig,
+        premiumName: threePremium,
         auction: auctionPda,
         authority,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
 
-    const [escrow1] = PublicKey.findProgramAddressSync(
-      [Buffer.from("escrow"), threeHash, bidder1.publicKey.toBuffer()],
-      names.programId
-    );
-    const [escrowVault1] = PublicKey.findProgramAddressSync(
-      [Buffer.from("escrow_vault"), threeHash, bidder1.publicKey.toBuffer()],
-      names.programId
-    );
-    const [escrow2] = PublicKey.findProgramAddressSync(
-      [Buffer.from("escrow"), threeHash, bidder2.publicKey.toBuffer()],
-      names.programId
-    );
-    const [escrowVault2] = PublicKey.findProgramAddressSync(
-      [Buffer.from("escrow_vault"), threeHash, bidder2.publicKey.toBuffer()],
-      names.programId
-    );
+    const threeRound: BN = ((await names.account.auction.fetch(auctionPda)) as any).startSlot;
+    const escrow1 = bidPda("escrow", threeHash, bidder1.publicKey, threeRound, names.programId);
+    const escrowVault1 = bidPda("escrow_vault", threeHash, bidder1.publicKey, threeRound, names.programId);
+    const escrow2 = bidPda("escrow", threeHash, bidder2.publicKey, threeRound, names.programId);
+    const escrowVault2 = bidPda("escrow_vault", threeHash, bidder2.publicKey, threeRound, names.programId);
 
     await names.methods
       .placeBid([...threeHash], new BN(11_000_000))
@@ -219,8 +245,43 @@ describe("ddns_names premium auctions", () => {
       .signers([bidder2])
       .rpc();
 
+    const withdraw = (bidder: Keypair, bidEscrow: PublicKey, escrowVault: PublicKey) =>
+      names.methods
+        .withdrawLosingBid([...threeHash])
+        .accounts({
+          auction: auctionPda,
+          bidEscrow,
+          escrowVault,
+          bidder: bidder.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([bidder])
+        .rpc();
+
+    // The leading bidder cannot pull out; the outbid one gets bid + rent back and the escrow closes.
+    let leaderBlocked = false;
+    try {
+      await withdraw(bidder2, escrow2, escrowVault2);
+    } catch {
+      leaderBlocked = true;
+    }
+    expect(leaderBlocked).to.equal(true);
+    const bidder1Before = await provider.connection.getBalance(bidder1.publicKey);
+    await withdraw(bidder1, escrow1, escrowVault1);
+    expect((await provider.connection.getBalance(bidder1.publicKey)) - bidder1Before).to.be.greaterThan(11_000_000);
+    expect(await provider.connection.getAccountInfo(escrow1)).to.equal(null);
+    expect(await provider.connection.getAccountInfo(escrowVault1)).to.equal(null);
+    let doubleWithdraw = false;
+    try {
+      await withdraw(bidder1, escrow1, escrowVault1);
+    } catch {
+      doubleWithdraw = true;
+    }
+    expect(doubleWithdraw).to.equal(true);
+
     await sleep(15000);
     const [bidder2Primary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), bidder2.publicKey.toBuffer()], names.programId);
+    const treasuryBefore = await provider.connection.getBalance(authority);
     await names.methods
       .settleAuction([...threeHash])
       .accounts({
@@ -241,6 +302,59 @@ describe("ddns_names premium auctions", () => {
 
     const threePremiumAcct: any = await names.account.premiumName.fetch(threePremium);
     expect(new PublicKey(threePremiumAcct.owner).equals(bidder2.publicKey)).to.equal(true);
+    // Winning bid reaches the treasury (less the fee the provider wallet paid); winner escrow is closed.
+    expect((await provider.connection.getBalance(authority)) - treasuryBefore).to.be.greaterThan(12_000_000 - 20_000);
+    expect(await provider.connection.getAccountInfo(escrow2)).to.equal(null);
+    expect(await provider.connection.getAccountInfo(escrowVault2)).to.equal(null);
+
+    // An auction without bids can be cancelled and listed again; one with bids cannot.
+    const fourName = `${token.slice(0, 4)}.dns`;
+    const fourHash = hashName(fourName);
+    const [fourPremium] = PublicKey.findProgramAddressSync([Buffer.from("premium"), fourHash], names.programId);
+    const [fourAuction] = PublicKey.findProgramAddressSync([Buffer.from("auction"), fourHash], names.programId);
+    const listFour = () =>
+      names.methods
+        .createAuction(fourName, [...fourHash], new BN(10_000_000), new BN(100), 0, null)
+        .accounts({
+          premiumConfig,
+          premiumName: fourPremium,
+          auction: fourAuction,
+          authority,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    const cancelFour = () =>
+      names.methods
+        .cancelAuction([...fourHash])
+        .accounts({ premiumConfig, auction: fourAuction, authority })
+        .rpc();
+
+    await listFour();
+    await cancelFour();
+    expect(await names.account.auction.fetchNullable(fourAuction)).to.equal(null);
+    await listFour();
+    const fourRound: BN = ((await names.account.auction.fetch(fourAuction)) as any).startSlot;
+    const fourEscrow = bidPda("escrow", fourHash, bidder1.publicKey, fourRound, names.programId);
+    const fourVault = bidPda("escrow_vault", fourHash, bidder1.publicKey, fourRound, names.programId);
+    await names.methods
+      .placeBid([...fourHash], new BN(10_000_000))
+      .accounts({
+        premiumConfig,
+        auction: fourAuction,
+        bidEscrow: fourEscrow,
+        escrowVault: fourVault,
+        bidder: bidder1.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bidder1])
+      .rpc();
+    let cancelWithBids = false;
+    try {
+      await cancelFour();
+    } catch {
+      cancelWithBids = true;
+    }
+    expect(cancelWithBids).to.equal(true);
   });
 
   it("binds premium ownership to an NFT holder wallet", async () => {
@@ -330,6 +444,7 @@ describe("ddns_names premium auctions", () => {
           parentPolicy: policyPda,
           currentOwner: owner.publicKey,
           newOwner: newOwner.publicKey,
+          currentOwnerPrimary: ownerPrimary,
         })
         .signers([owner])
         .rpc();
@@ -351,6 +466,7 @@ describe("ddns_names premium auctions", () => {
         nftMint,
         holderNftAccount: newOwnerAta.address,
         tokenProgram: TOKEN_PROGRAM_ID,
+        previousOwnerPrimary: ownerPrimary,
       })
       .rpc();
 
@@ -360,5 +476,1228 @@ describe("ddns_names premium auctions", () => {
     expect(new PublicKey(policyAcct.parentOwner).equals(newOwner.publicKey)).to.equal(true);
     expect(new PublicKey(premiumAcct.nftMint).equals(nftMint)).to.equal(true);
     expect(premiumAcct.nftBound).to.equal(true);
+    // The previous owner's reverse record pointed at the name and is cleared.
+    const stalePrimary: any = await names.account.primaryName.fetch(ownerPrimary);
+    expect(stalePrimary.isSet).to.equal(false);
+    expect(stalePrimary.nameLen).to.equal(0);
+  });
+
+  it("lets the name owner publish records without an admin co-signer", async () => {
+    const authority = provider.wallet.publicKey;
+    const owner = Keypair.generate();
+    const stranger = Keypair.generate();
+    await provider.connection.requestAirdrop(owner.publicKey, 3e9);
+    await provider.connection.requestAirdrop(stranger.publicKey, 1e9);
+    await sleep(1500);
+
+    const [namesConfig] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], names.programId);
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+
+    if (!(await names.account.namesConfig.fetchNullable(namesConfig))) {
+      await names.methods
+        .initNamesConfig(authority, "user.dns", new BN(100_000_000), new BN(0), true, true)
+        .accounts({
+          config: namesConfig,
+          authority,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    if (!(await names.account.premiumConfig.fetchNullable(premiumConfig))) {
+      await names.methods
+        .initPremiumConfig(authority, authority, new BN(50_000_000), new BN(2), new BN(0), true)
+        .accounts({
+          premiumConfig,
+          authority,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    const token = owner.publicKey
+      .toBase58()
+      .toLowerCase()
+      .replace(/[^a-z0-9]/g, "")
+      .slice(0, 6);
+    const name = `${token}.dns`;
+    const nameHash = hashName(name);
+    const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), nameHash], names.programId);
+    const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), nameHash], names.programId);
+    const [ownerPrimary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), owner.publicKey.toBuffer()], names.programId);
+
+    await names.methods
+      .purchasePremium(name, [...nameHash])
+      .accounts({
+        config: namesConfig,
+        premiumConfig,
+        treasury: authority,
+        premiumName: premiumPda,
+        parentPolicy: policyPda,
+        primary: ownerPrimary,
+        owner: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    // ddns_rrset encoding: version 1 | type A (1, LE) | 1 record | rdata len 4 (LE) | 203.0.113.7
+    const TYPE_A = 1;
+    const encoded = Buffer.from([1, 1, 0, 1, 4, 0, 203, 0, 113, 7]);
+    const typeLe = Buffer.alloc(2);
+    typeLe.writeUInt16LE(TYPE_A);
+    const [recordsPda] = PublicKey.findProgramAddressSync([Buffer.from("records"), nameHash, typeLe], names.programId);
+
+    let strangerBlocked = false;
+    try {
+      await names.methods
+        .setRecords([...nameHash], 1, TYPE_A, 300, encoded)
+        .accounts({
+          nameRecords: recordsPda,
+          premiumName: premiumPda,
+          subName: null,
+          owner: stranger.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([stranger])
+        .rpc();
+    } catch {
+      strangerBlocked = true;
+    }
+    expect(strangerBlocked).to.equal(true);
+
+    await names.methods
+      .setRecords([...nameHash], 1, TYPE_A, 300, encoded)
+      .accounts({
+        nameRecords: recordsPda,
+        premiumName: premiumPda,
+        subName: null,
+        owner: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const records: any = await names.account.nameRecords.fetch(recordsPda);
+    expect(records.version.toNumber()).to.equal(1);
+    expect(records.ttlS).to.equal(300);
+    expect(Buffer.from(records.data).equals(encoded)).to.equal(true);
+    expect(
+      Buffer.from(records.rrsetHash).equals(crypto.createHash("sha256").update(encoded).digest())
+    ).to.equal(true);
+
+    await names.methods
+      .clearRecords([...nameHash], 1, TYPE_A)
+      .accounts({
+        nameRecords: recordsPda,
+        premiumName: premiumPda,
+        subName: null,
+        owner: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+    expect(await names.account.nameRecords.fetchNullable(recordsPda)).to.equal(null);
+  });
+
+  it("expires premium names, renews during grace for the owner only, and releases after grace", async () => {
+    const authority = provider.wallet.publicKey;
+    const owner = Keypair.generate();
+    const stranger = Keypair.generate();
+    await provider.connection.requestAirdrop(owner.publicKey, 2e9);
+    await provider.connection.requestAirdrop(stranger.publicKey, 2e9);
+    await sleep(1500);
+
+    const [namesConfig] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], names.programId);
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+    const cfg: any = await names.account.namesConfig.fetch(namesConfig);
+
+    // Shrink the term so every transition happens within the test: 4s periods, 4s grace.
+    const PERIOD_S = 4;
+    const GRACE_S = 4;
+    const RENEWAL_LAMPORTS = 1_000_000;
+    await names.methods
+      .setPremiumTerms(new BN(PERIOD_S), new BN(RENEWAL_LAMPORTS), new BN(GRACE_S))
+      .accounts({ premiumConfig, authority })
+      .rpc();
+
+    const token = owner.publicKey
+      .toBase58()
+      .toLowerCase()
+      .replace(/[^a-z0-9]/g, "")
+      .slice(0, 7);
+    const name = `${token}.dns`;
+    const nameHash = hashName(name);
+    const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), nameHash], names.programId);
+    const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), nameHash], names.programId);
+    const purchase = (buyer: Keypair) => {
+      const [primary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), buyer.publicKey.toBuffer()], names.programId);
+      return names.methods
+        .purchasePremium(name, [...nameHash])
+        .accounts({
+          config: namesConfig,
+          premiumConfig,
+          treasury: cfg.treasury,
+          premiumName: premiumPda,
+          parentPolicy: policyPda,
+          primary,
+          owner: buyer.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+    };
+    const renew = (payer: Keypair) =>
+      names.methods
+        .renewPremium([...nameHash], 1)
+        .accounts({
+          config: namesConfig,
+          premiumConfig,
+          premiumName: premiumPda,
+          treasury: cfg.treasury,
+          payer: payer.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+    const release = (previousOwner: PublicKey) =>
+      names.methods
+        .releasePremium([...nameHash])
+        .accounts({
+          premiumConfig,
+          premiumName: premiumPda,
+          parentPolicy: policyPda,
+          previousOwner,
+          previousOwnerPrimary: PublicKey.findProgramAddressSync(
+            [Buffer.from("primary"), previousOwner.toBuffer()],
+            names.programId
+          )[0],
+          caller: stranger.publicKey,
+        })
+        .signers([stranger])
+        .rpc();
+    const fails = async (p: Promise<unknown>) => {
+      try {
+        await p;
+        return false;
+      } catch {
+        return true;
+      }
+    };
+
+    await purchase(owner);
+    const bought: any = await names.account.premiumName.fetch(premiumPda);
+    expect(bought.expiresAt.toNumber() - bought.createdAt.toNumber()).to.equal(PERIOD_S);
+
+    // Active: anyone may pay to extend, and it extends from the current expiry.
+    await renew(stranger);
+    const gifted: any = await names.account.premiumName.fetch(premiumPda);
+    expect(gifted.expiresAt.toNumber()).to.equal(bought.expiresAt.toNumber() + PERIOD_S);
+    expect(gifted.owner.toBase58()).to.equal(owner.publicKey.toBase58());
+
+    // Grace: only the owner may renew, and the name cannot be released yet.
+    await sleep((gifted.expiresAt.toNumber() - Math.floor(Date.now() / 1000) + 1) * 1000);
+    expect(await fails(renew(stranger))).to.equal(true);
+    expect(await fails(release(owner.publicKey))).to.equal(true);
+    await renew(owner);
+    const renewed: any = await names.account.premiumName.fetch(premiumPda);
+    expect(renewed.expiresAt.toNumber()).to.equal(gifted.expiresAt.toNumber() + PERIOD_S);
+
+    // Past grace: nobody can renew, anyone can release, and the name can be bought again.
+    await sleep((renewed.expiresAt.toNumber() + GRACE_S - Math.floor(Date.now() / 1000) + 1) * 1000);
+    expect(await fails(renew(owner))).to.equal(true);
+    expect(await fails(release(stranger.publicKey))).to.equal(true);
+    await release(owner.publicKey);
+    expect(await names.account.premiumName.fetchNullable(premiumPda)).to.equal(null);
+
+    await purchase(stranger);
+    const rebought: any = await names.account.premiumName.fetch(premiumPda);
+    expect(rebought.owner.toBase58()).to.equal(stranger.publicKey.toBase58());
+
+    await names.methods
+      .setPremiumTerms(new BN(365 * 24 * 60 * 60), new BN(0), new BN(30 * 24 * 60 * 60))
+      .accounts({ premiumConfig, authority })
+      .rpc();
+  });
+
+  it("prices premium labels by length tier and listing, payable in SOL or the toll mint", async () => {
+    const authority = provider.wallet.publicKey;
+    const payer = (provider.wallet as any).payer;
+    const buyer = Keypair.generate();
+    await provider.connection.requestAirdrop(buyer.publicKey, 2e9);
+    await sleep(1500);
+
+    const [namesConfig] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], names.programId);
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+    const cfg: any = await names.account.namesConfig.fetch(namesConfig);
+
+    // 1 toll token (6 decimals) per SOL.
+    const tollMint = await createMint(provider.connection, payer, authority, null, 6);
+    const treasuryToll = await getOrCreateAssociatedTokenAccount(provider.connection, payer, tollMint, authority);
+    const buyerToll = await getOrCreateAssociatedTokenAccount(provider.connection, payer, tollMint, buyer.publicKey);
+    await mintTo(provider.connection, payer, tollMint, buyerToll.address, payer, 1_000_000n);
+
+    const tiers = Array.from({ length: 8 }, () => new BN(0));
+    tiers[5] = new BN(7_000_000); // 6-char labels
+    await names.methods
+      .setPremiumPricing(tiers, tollMint, treasuryToll.address, new BN(1_000_000))
+      .accounts({ premiumConfig, authority })
+      .rpc();
+
+    const token = buyer.publicKey
+      .toBase58()
+      .toLowerCase()
+      .replace(/[^a-z0-9]/g, "")
+      .slice(0, 7);
+    const pdas = (name: string) => {
+      const nameHash = hashName(name);
+      const [premiumName] = PublicKey.findProgramAddressSync([Buffer.from("premium"), nameHash], names.programId);
+      const [parentPolicy] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), nameHash], names.programId);
+      const [namePrice] = PublicKey.findProgramAddressSync([Buffer.from("name_price"), nameHash], names.programId);
+      return { nameHash, premiumName, parentPolicy, namePrice };
+    };
+    const [buyerPrimary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), buyer.publicKey.toBuffer()], names.programId);
+    const quote = (name: string) => {
+      const { nameHash, premiumName, namePrice } = pdas(name);
+      return names.methods
+        .quotePremium(name, [...nameHash])
+        .accounts({ config: namesConfig, premiumConfig, premiumName, namePrice })
+        .view();
+    };
+
+    // Length tier, paid in SOL.
+    const sixName = `${token.slice(0, 6)}.dns`;
+    const six = pdas(sixName);
+    const sixQuote: any = await quote(sixName);
+    expect(sixQuote.priceLamports.toNumber()).to.equal(7_000_000);
+    expect(sixQuote.tollUnits.toNumber()).to.equal(7_000);
+    expect(sixQuote.listed).to.equal(false);
+    expect(sixQuote.available).to.equal(true);
+
+    const treasuryBefore = await provider.connection.getBalance(cfg.treasury);
+    await names.methods
+      .purchasePremium(sixName, [...six.nameHash])
+      .accounts({
+        config: namesConfig,
+        premiumConfig,
+        treasury: cfg.treasury,
+        premiumName: six.premiumName,
+        parentPolicy: six.parentPolicy,
+        primary: buyerPrimary,
+        namePrice: six.namePrice,
+        owner: buyer.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        payerTollAccount: null,
+        tollTreasury: null,
+        tollMint: null,
+        tokenProgram: null,
+      })
+      .signers([buyer])
+      .rpc();
+    expect((await provider.connection.getBalance(cfg.treasury)) - treasuryBefore).to.equal(7_000_000);
+    const sixPremium: any = await names.account.premiumName.fetch(six.premiumName);
+    expect(sixPremium.purchaseLamports.toNumber()).to.equal(7_000_000);
+    expect(((await quote(sixName)) as any).available).to.equal(false);
+
+    // Listed price overrides the tier, paid in toll tokens.
+    const sevenName = `${token}.dns`;
+    const seven = pdas(sevenName);
+    await names.methods
+      .setNamePrice([...seven.nameHash], new BN(123_000_000))
+      .accounts({
+        premiumConfig,
+        namePrice: seven.namePrice,
+        authority,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const sevenQuote: any = await quote(sevenName);
+    expect(sevenQuote.listed).to.equal(true);
+    expect(sevenQuote.priceLamports.toNumber()).to.equal(123_000_000);
+
+    await names.methods
+      .purchasePremium(sevenName, [...seven.nameHash])
+      .accounts({
+        config: namesConfig,
+        premiumConfig,
+        treasury: cfg.treasury,
+        premiumName: seven.premiumName,
+        parentPolicy: seven.parentPolicy,
+        primary: buyerPrimary,
+        namePrice: seven.namePrice,
+        owner: buyer.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        payerTollAccount: buyerToll.address,
+        tollTreasury: treasuryToll.address,
+        tollMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([buyer])
+      .rpc();
+    const buyerTollAfter = await getOrCreateAssociatedTokenAccount(provider.connection, payer, tollMint, buyer.publicKey);
+    expect(Number(buyerTollAfter.amount)).to.equal(1_000_000 - 123_000);
+
+    await names.methods
+      .clearNamePrice([...seven.nameHash])
+      .accounts({ premiumConfig, namePrice: seven.namePrice, authority })
+      .rpc();
+    await names.methods
+      .setPremiumPricing(
+        Array.from({ length: 8 }, () => new BN(0)),
+        PublicKey.default,
+        PublicKey.default,
+        new BN(0)
+      )
+      .accounts({ premiumConfig, authority })
+      .rpc();
+  });
+
+  it("requires an aged, revealed commitment when commit-reveal is enabled", async () => {
+    const authority = provider.wallet.publicKey;
+    const buyer = Keypair.generate();
+    const sniper = Keypair.generate();
+    await provider.connection.requestAirdrop(buyer.publicKey, 2e9);
+    await provider.connection.requestAirdrop(sniper.publicKey, 2e9);
+    await sleep(1500);
+
+    const [namesConfig] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], names.programId);
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+    const cfg: any = await names.account.namesConfig.fetch(namesConfig);
+
+    await names.methods.setCommitWindow(new BN(4), new BN(1_000)).accounts({ config: namesConfig, authority }).rpc();
+
+    const token = buyer.publicKey
+      .toBase58()
+      .toLowerCase()
+      .replace(/[^a-z0-9]/g, "")
+      .slice(0, 8);
+    const name = `${token}.dns`;
+    const nameHash = hashName(name);
+    const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), nameHash], names.programId);
+    const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), nameHash], names.programId);
+    const salt = crypto.randomBytes(32);
+    const commitment = crypto
+      .createHash("sha256")
+      .update(Buffer.concat([nameHash, buyer.publicKey.toBuffer(), salt]))
+      .digest();
+    const [nameCommitment] = PublicKey.findProgramAddressSync([Buffer.from("commit"), commitment], names.programId);
+
+    const purchase = (owner: Keypair, withCommitment: PublicKey | null) => {
+      const [primary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), owner.publicKey.toBuffer()], names.programId);
+      return names.methods.purchasePremium(name, [...nameHash]).accounts({
+        config: namesConfig,
+        premiumConfig,
+        treasury: cfg.treasury,
+        premiumName: premiumPda,
+        parentPolicy: policyPda,
+        primary,
+        nameCommitment: withCommitment,
+        owner: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      });
+    };
+    const reveal = () =>
+      names.methods
+        .revealName([...commitment], [...nameHash], [...salt])
+        .accounts({ config: namesConfig, nameCommitment, owner: buyer.publicKey })
+        .instruction();
+    const fails = async (p: Promise<unknown>) => {
+      try {
+        await p;
+        return false;
+      } catch {
+        return true;
+      }
+    };
+
+    await names.methods
+      .commitName([...commitment])
+      .accounts({ nameCommitment, owner: buyer.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+      .signers([buyer])
+      .rpc();
+
+    // The plaintext path is closed, and a fresh commitment cannot be revealed yet.
+    expect(await fails(purchase(sniper, null).signers([sniper]).rpc())).to.equal(true);
+    expect(await fails(purchase(buyer, nameCommitment).preInstructions([await reveal()]).signers([buyer]).rpc())).to.equal(
+      true
+    );
+
+    await sleep(4000);
+    // Someone else cannot spend the buyer's commitment.
+    expect(await fails(purchase(sniper, nameCommitment).signers([sniper]).rpc())).to.equal(true);
+
+    await purchase(buyer, nameCommitment).preInstructions([await reveal()]).signers([buyer]).rpc();
+    const premium: any = await names.account.premiumName.fetch(premiumPda);
+    expect(premium.owner.toBase58()).to.equal(buyer.publicKey.toBase58());
+    expect(await names.account.nameCommitment.fetchNullable(nameCommitment)).to.equal(null);
+
+    await names.methods.setCommitWindow(new BN(0), new BN(0)).accounts({ config: namesConfig, authority }).rpc();
+  });
+
+  it("runs a sealed-bid auction at the second price and forfeits unrevealed deposits", async () => {
+    const authority = provider.wallet.publicKey;
+    const [alice, bob, carol] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    for (const k of [alice, bob, carol]) await provider.connection.requestAirdrop(k.publicKey, 2e9);
+    await sleep(1500);
+
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+    const pcfg: any = await names.account.premiumConfig.fetch(premiumConfig);
+    const treasury: PublicKey = pcfg.treasuryVault;
+
+    const token = carol.publicKey
+      .toBase58()
+      .toLowerCase()
+      .replace(/[^a-z0-9]/g, "")
+      .slice(0, 4);
+    const name = `${token}.dns`;
+    const nameHash = hashName(name);
+    const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), nameHash], names.programId);
+    const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), nameHash], names.programId);
+    const [auctionPda] = PublicKey.findProgramAddressSync([Buffer.from("auction"), nameHash], names.programId);
+    let round = new BN(0);
+    const sealedBid = (k: Keypair) => bidPda("sealed_bid", nameHash, k.publicKey, round, names.programId);
+    const salts = new Map<Keypair, Buffer>();
+    const bidHash = (k: Keypair, lamports: number) => {
+      const amount = Buffer.alloc(8);
+      amount.writeBigUInt64LE(BigInt(lamports));
+      return crypto
+        .createHash("sha256")
+        .update(Buffer.concat([nameHash, k.publicKey.toBuffer(), amount, salts.get(k)!]))
+        .digest();
+    };
+    const waitForSlot = async (slot: number) => {
+      while ((await provider.connection.getSlot()) < slot) await sleep(400);
+    };
+    const fails = async (p: Promise<unknown>) => {
+      try {
+        await p;
+        return false;
+      } catch {
+        return true;
+      }
+    };
+
+    await names.methods
+      .createAuction(name, [...nameHash], new BN(10_000_000), new BN(30), 1, new BN(30))
+      .accounts({
+        premiumConfig,
+        premiumName: premiumPda,
+        auction: auctionPda,
+        authority,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    round = ((await names.account.auction.fetch(auctionPda)) as any).startSlot;
+
+    // Bid phase: deposits may exceed (and so hide) the bid.
+    const commit = (k: Keypair, lamports: number, deposit: number) => {
+      salts.set(k, crypto.randomBytes(32));
+      return names.methods
+        .commitSealedBid([...nameHash], [...bidHash(k, lamports)], new BN(deposit))
+        .accounts({
+          premiumConfig,
+          auction: auctionPda,
+          sealedBid: sealedBid(k),
+          bidder: k.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([k])
+        .rpc();
+    };
+    await commit(alice, 30_000_000, 40_000_000);
+    await commit(bob, 20_000_000, 20_000_000);
+    await commit(carol, 25_000_000, 25_000_000);
+
+    const reveal = (k: Keypair, lamports: number) =>
+      names.methods
+        .revealSealedBid([...nameHash], new BN(lamports), [...salts.get(k)!])
+        .accounts({ auction: auctionPda, sealedBid: sealedBid(k), bidder: k.publicKey })
+        .signers([k])
+        .rpc();
+    const close = (k: Keypair) =>
+      names.methods
+        .closeSealedBid([...nameHash])
+        .accounts({ premiumConfig, auction: auctionPda, sealedBid: sealedBid(k), bidder: k.publicKey, treasury })
+        .rpc();
+
+    expect(await fails(reveal(alice, 30_000_000))).to.equal(true);
+
+    let auction: any = await names.account.auction.fetch(auctionPda);
+    await waitForSlot(auction.endSlot.toNumber());
+    expect(await fails(reveal(bob, 21_000_000))).to.equal(true);
+    await reveal(alice, 30_000_000);
+    await reveal(bob, 20_000_000);
+    auction = await names.account.auction.fetch(auctionPda);
+    expect(auction.highestBidder.toBase58()).to.equal(alice.publicKey.toBase58());
+    expect(auction.highestBidLamports.toNumber()).to.equal(30_000_000);
+    expect(auction.secondBidLamports.toNumber()).to.equal(20_000_000);
+
+    // Revealed losers are refunded right away; unrevealed bids wait for the reveal phase to end.
+    const bobBefore = await provider.connection.getBalance(bob.publicKey);
+    await close(bob);
+    expect((await provider.connection.getBalance(bob.publicKey)) - bobBefore).to.be.greaterThan(20_000_000);
+    expect(await fails(close(carol))).to.equal(true);
+    expect(await fails(close(alice))).to.equal(true);
+
+    await waitForSlot(auction.revealEndSlot.toNumber());
+    const carolBefore = await provider.connection.getBalance(carol.publicKey);
+    await close(carol);
+    const carolRefund = (await provider.connection.getBalance(carol.publicKey)) - carolBefore;
+    expect(carolRefund).to.be.greaterThan(0);
+    expect(carolRefund).to.be.lessThan(25_000_000);
+
+    const [alicePrimary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), alice.publicKey.toBuffer()], names.programId);
+    await names.methods
+      .settleSealedAuction([...nameHash])
+      .accounts({
+        premiumConfig,
+        auction: auctionPda,
+        winnerBid: sealedBid(alice),
+        treasury,
+        premiumName: premiumPda,
+        parentPolicy: policyPda,
+        primary: alicePrimary,
+        winner: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([alice])
+      .rpc();
+    const premium: any = await names.account.premiumName.fetch(premiumPda);
+    expect(premium.owner.toBase58()).to.equal(alice.publicKey.toBase58());
+    expect(premium.purchaseLamports.toNumber()).to.equal(20_000_000);
+    expect(await provider.connection.getAccountInfo(sealedBid(alice))).to.equal(null);
+  });
+
+  it("lists, delists and sells premium names with a protocol fee and primary name handoff", async () => {
+    const authority = provider.wallet.publicKey;
+    const seller = Keypair.generate();
+    const buyer = Keypair.generate();
+    await provider.connection.requestAirdrop(seller.publicKey, 2e9);
+    await provider.connection.requestAirdrop(buyer.publicKey, 2e9);
+    await sleep(1500);
+
+    const [namesConfig] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], names.programId);
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+    const cfg: any = await names.account.namesConfig.fetch(namesConfig);
+    await names.methods.setMarketFee(250).accounts({ config: namesConfig, authority }).rpc();
+
+    const name = `${seller.publicKey.toBase58().toLowerCase().replace(/[^a-z0-9]/g, "").slice(0, 7)}.dns`;
+    const nameHash = hashName(name);
+    const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), nameHash], names.programId);
+    const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), nameHash], names.programId);
+    const [listingPda] = PublicKey.findProgramAddressSync([Buffer.from("listing"), nameHash], names.programId);
+    const primaryOf = (k: Keypair) =>
+      PublicKey.findProgramAddressSync([Buffer.from("primary"), k.publicKey.toBuffer()], names.programId)[0];
+
+    await names.methods
+      .purchasePremium(name, [...nameHash])
+      .accounts({
+        config: namesConfig,
+        premiumConfig,
+        treasury: cfg.treasury,
+        premiumName: premiumPda,
+        parentPolicy: policyPda,
+        primary: primaryOf(seller),
+        owner: seller.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([seller])
+      .rpc();
+
+    const list = (start: number, end: number, duration: number) =>
+      names.methods
+        .listPremium([...nameHash], new BN(start), new BN(end), new BN(duration))
+        .accounts({
+          premiumName: premiumPda,
+          listing: listingPda,
+          seller: seller.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([seller])
+        .rpc();
+
+    // Listing locks the name while the seller stays its owner; delisting unlocks it.
+    await list(200_000_000, 100_000_000, 3600);
+    let premium: any = await names.account.premiumName.fetch(premiumPda);
+    expect(premium.owner.toBase58()).to.equal(seller.publicKey.toBase58());
+    expect(premium.listed).to.equal(true);
+    expect((await names.account.parentPolicy.fetch(policyPda)).parentOwner.toBase58()).to.equal(seller.publicKey.toBase58());
+    await names.methods
+      .delistPremium([...nameHash])
+      .accounts({ premiumName: premiumPda, listing: listingPda, seller: seller.publicKey })
+      .signers([seller])
+      .rpc();
+    premium = await names.account.premiumName.fetch(premiumPda);
+    expect(premium.owner.toBase58()).to.equal(seller.publicKey.toBase58());
+    expect(premium.listed).to.equal(false);
+    expect(await provider.connection.getAccountInfo(listingPda)).to.equal(null);
+
+    // Fixed price sale: 2.5% to the treasury, the rest to the seller.
+    const PRICE = 100_000_000;
+    await list(PRICE, PRICE, 0);
+    const buy = (maxPrice: number) =>
+      names.methods
+        .buyListedPremium(name, [...nameHash], new BN(maxPrice))
+        .accounts({
+          config: namesConfig,
+          premiumName: premiumPda,
+          parentPolicy: policyPda,
+          listing: listingPda,
+          seller: seller.publicKey,
+          sellerPrimary: primaryOf(seller),
+          buyerPrimary: primaryOf(buyer),
+          treasury: cfg.treasury,
+          buyer: buyer.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+    let rejected = false;
+    try {
+      await buy(PRICE - 1);
+    } catch {
+      rejected = true;
+    }
+    expect(rejected).to.equal(true);
+
+    const treasuryBefore = await provider.connection.getBalance(cfg.treasury);
+    const sellerBefore = await provider.connection.getBalance(seller.publicKey);
+    const listingRent = await provider.connection.getBalance(listingPda);
+    await buy(PRICE);
+    const fee = (PRICE * 250) / 10_000;
+    expect((await provider.connection.getBalance(cfg.treasury)) - treasuryBefore).to.equal(fee);
+    expect((await provider.connection.getBalance(seller.publicKey)) - sellerBefore).to.equal(PRICE - fee + listingRent);
+
+    premium = await names.account.premiumName.fetch(premiumPda);
+    expect(premium.owner.toBase58()).to.equal(buyer.publicKey.toBase58());
+    expect(premium.listed).to.equal(false);
+    expect((await names.account.parentPolicy.fetch(policyPda)).parentOwner.toBase58()).to.equal(buyer.publicKey.toBase58());
+    const sellerPrimary: any = await names.account.primaryName.fetch(primaryOf(seller));
+    expect(sellerPrimary.isSet).to.equal(false);
+    const buyerPrimary: any = await names.account.primaryName.fetch(primaryOf(buyer));
+    expect(Buffer.from(buyerPrimary.nameHash).equals(nameHash)).to.equal(true);
+    expect(Buffer.from(buyerPrimary.nameBytes.slice(0, buyerPrimary.nameLen)).toString()).to.equal(name);
+
+    await names.methods.setMarketFee(0).accounts({ config: namesConfig, authority }).rpc();
+  });
+
+  it("issues premium NFTs with token metadata and a master edition, refreshed on renewal and transfer", async () => {
+    const authority = provider.wallet.publicKey;
+    const owner = Keypair.generate();
+    const holder = Keypair.generate();
+    await provider.connection.requestAirdrop(owner.publicKey, 2e9);
+    await sleep(1500);
+
+    const [namesConfig] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], names.programId);
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+    const [nftAuthority] = PublicKey.findProgramAddressSync([Buffer.from("nft_authority")], names.programId);
+    const cfg: any = await names.account.namesConfig.fetch(namesConfig);
+    await names.methods
+      .setNftMetadata("DNS", "https://names.example/{mint}.json?owner={owner}&exp={expires}")
+      .accounts({ config: namesConfig, authority })
+      .rpc();
+
+    const name = `${owner.publicKey.toBase58().toLowerCase().replace(/[^a-z0-9]/g, "").slice(0, 7)}.dns`;
+    const nameHash = hashName(name);
+    const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), nameHash], names.programId);
+    const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), nameHash], names.programId);
+    const [primary] = PublicKey.findProgramAddressSync([Buffer.from("primary"), owner.publicKey.toBuffer()], names.programId);
+
+    await names.methods
+      .purchasePremium(name, [...nameHash])
+      .accounts({
+        config: namesConfig,
+        premiumConfig,
+        treasury: cfg.treasury,
+        premiumName: premiumPda,
+        parentPolicy: policyPda,
+        primary,
+        owner: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+    const purchased: any = await names.account.premiumName.fetch(premiumPda);
+    const nftMint = premiumNftMintPda(nameHash, purchased.createdAt, names.programId);
+    const nftMetadata = metadataPda(nftMint);
+    const nftEdition = metadataPda(nftMint, true);
+
+    const ownerAta = anchor.utils.token.associatedAddress({ mint: nftMint, owner: owner.publicKey });
+    await names.methods
+      .issuePremiumNft(name, [...nameHash])
+      .accounts({
+        config: namesConfig,
+        premiumName: premiumPda,
+        nftMint,
+        nftMetadata,
+        nftEdition,
+        ownerNftAccount: ownerAta,
+        nftAuthority,
+        owner: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenMetadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([owner])
+      .rpc();
+
+    let premium: any = await names.account.premiumName.fetch(premiumPda);
+    let md = decodeMetadataStrings((await provider.connection.getAccountInfo(nftMetadata))!.data);
+    expect(md.name).to.equal(name);
+    expect(md.symbol).to.equal("DNS");
+    expect(md.uri).to.equal(
+      `https://names.example/${nftMint.toBase58()}.json?owner=${owner.publicKey.toBase58()}&exp=${premium.expiresAt.toString()}`
+    );
+    // The master edition owns the mint: supply is fixed at one.
+    expect(await provider.connection.getAccountInfo(nftEdition)).to.not.equal(null);
+    const mintInfo = (await provider.connection.getAccountInfo(nftMint))!.data;
+    expect(new PublicKey(mintInfo.subarray(4, 36)).equals(nftEdition)).to.equal(true);
+
+    // A program-issued NFT must be refreshed: renewing without its metadata fails.
+    let skipped = false;
+    try {
+      await names.methods
+        .renewPremium([...nameHash], 1)
+        .accounts({
+          config: namesConfig,
+          premiumConfig,
+          premiumName: premiumPda,
+          treasury: cfg.treasury,
+          payer: owner.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          nftMetadata: null,
+          nftAuthority: null,
+          tokenMetadataProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+      skipped = true;
+    } catch (e: any) {
+      expect(String(e)).to.contain("NftMetadataRequired");
+    }
+    expect(skipped).to.equal(false);
+
+    const refresh = { nftMetadata, nftAuthority, tokenMetadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID };
+    await names.methods
+      .renewPremium([...nameHash], 1)
+      .accounts({
+        config: namesConfig,
+        premiumConfig,
+        premiumName: premiumPda,
+        treasury: cfg.treasury,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        ...refresh,
+      })
+      .signers([owner])
+      .rpc();
+    premium = await names.account.premiumName.fetch(premiumPda);
+    md = decodeMetadataStrings((await provider.connection.getAccountInfo(nftMetadata))!.data);
+    expect(md.uri.endsWith(`&exp=${premium.expiresAt.toString()}`)).to.equal(true);
+
+    const holderAta = await getOrCreateAssociatedTokenAccount(provider.connection, (provider.wallet as any).payer, nftMint, holder.publicKey);
+    await provider.sendAndConfirm(
+      new Transaction().add(createSplTransferInstruction(ownerAta, holderAta.address, owner.publicKey, 1n)),
+      [owner]
+    );
+    await names.methods
+      .syncPremiumOwnerFromNft()
+      .accounts({
+        premiumName: premiumPda,
+        parentPolicy: policyPda,
+        nftMint,
+        holderNftAccount: holderAta.address,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        previousOwnerPrimary: primary,
+        config: namesConfig,
+        ...refresh,
+      })
+      .rpc();
+    md = decodeMetadataStrings((await provider.connection.getAccountInfo(nftMetadata))!.data);
+    expect(md.uri).to.contain(`owner=${holder.publicKey.toBase58()}`);
+
+    await names.methods.setNftMetadata("DNS", "").accounts({ config: namesConfig, authority }).rpc();
+  });
+
+  it("issues a fresh NFT mint when a released premium name is registered again", async () => {
+    const authority = provider.wallet.publicKey;
+    const first = Keypair.generate();
+    const second = Keypair.generate();
+    for (const k of [first, second]) await provider.connection.requestAirdrop(k.publicKey, 2e9);
+    await sleep(1500);
+
+    const [namesConfig] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], names.programId);
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+    const [nftAuthority] = PublicKey.findProgramAddressSync([Buffer.from("nft_authority")], names.programId);
+    const cfg: any = await names.account.namesConfig.fetch(namesConfig);
+
+    // 4s periods with 1s grace so the name can be released within the test.
+    await names.methods
+      .setPremiumTerms(new BN(4), new BN(0), new BN(1))
+      .accounts({ premiumConfig, authority })
+      .rpc();
+
+    const name = `${first.publicKey.toBase58().toLowerCase().replace(/[^a-z0-9]/g, "").slice(0, 7)}.dns`;
+    const nameHash = hashName(name);
+    const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), nameHash], names.programId);
+    const [policyPda] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), nameHash], names.programId);
+    const primaryOf = (owner: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("primary"), owner.toBuffer()], names.programId)[0];
+    const purchaseAndIssue = async (owner: Keypair) => {
+      await names.methods
+        .purchasePremium(name, [...nameHash])
+        .accounts({
+          config: namesConfig,
+          premiumConfig,
+          treasury: cfg.treasury,
+          premiumName: premiumPda,
+          parentPolicy: policyPda,
+          primary: primaryOf(owner.publicKey),
+          owner: owner.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      const premium: any = await names.account.premiumName.fetch(premiumPda);
+      const nftMint = premiumNftMintPda(nameHash, premium.createdAt, names.programId);
+      await names.methods
+        .issuePremiumNft(name, [...nameHash])
+        .accounts({
+          config: namesConfig,
+          premiumName: premiumPda,
+          nftMint,
+          nftMetadata: metadataPda(nftMint),
+          nftEdition: metadataPda(nftMint, true),
+          ownerNftAccount: anchor.utils.token.associatedAddress({ mint: nftMint, owner: owner.publicKey }),
+          nftAuthority,
+          owner: owner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenMetadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([owner])
+        .rpc();
+      return (await names.account.premiumName.fetch(premiumPda)) as any;
+    };
+
+    const firstReg = await purchaseAndIssue(first);
+    await sleep((firstReg.expiresAt.toNumber() + 1 - Math.floor(Date.now() / 1000) + 1) * 1000);
+    await names.methods
+      .releasePremium([...nameHash])
+      .accounts({
+        premiumConfig,
+        premiumName: premiumPda,
+        parentPolicy: policyPda,
+        previousOwner: first.publicKey,
+        previousOwnerPrimary: primaryOf(first.publicKey),
+        caller: second.publicKey,
+      })
+      .signers([second])
+      .rpc();
+
+    // The first registration's mint still exists; the new one must not collide with it.
+    const secondReg = await purchaseAndIssue(second);
+    expect(secondReg.owner.toBase58()).to.equal(second.publicKey.toBase58());
+    expect(secondReg.nftMint.equals(firstReg.nftMint)).to.equal(false);
+    expect(secondReg.nftMint.toBase58()).to.equal(premiumNftMintPda(nameHash, secondReg.createdAt, names.programId).toBase58());
+    expect(await provider.connection.getAccountInfo(firstReg.nftMint)).to.not.equal(null);
+
+    await names.methods
+      .setPremiumTerms(new BN(365 * 24 * 60 * 60), new BN(0), new BN(30 * 24 * 60 * 60))
+      .accounts({ premiumConfig, authority })
+      .rpc();
+  });
+
+  it("delegates subdomains below subdomains with chained hashes and lets any ancestor revoke them", async () => {
+    const alice = Keypair.generate();
+    const bob = Keypair.generate();
+    const carol = Keypair.generate();
+    const dave = Keypair.generate();
+    for (const k of [alice, bob, carol, dave]) await provider.connection.requestAirdrop(k.publicKey, 2e9);
+    await sleep(1500);
+
+    const [namesConfig] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], names.programId);
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+    const cfg: any = await names.account.namesConfig.fetch(namesConfig);
+    const chain = (parentHash: Buffer, label: string) =>
+      crypto.createHash("sha256").update(Buffer.concat([parentHash, hashName(label)])).digest();
+    const subPda = (parentHash: Buffer, label: string) =>
+      PublicKey.findProgramAddressSync([Buffer.from("sub"), parentHash, hashName(label)], names.programId)[0];
+    const policyOf = (hash: Buffer) =>
+      PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), hash], names.programId)[0];
+    const primaryOf = (k: Keypair) =>
+      PublicKey.findProgramAddressSync([Buffer.from("primary"), k.publicKey.toBuffer()], names.programId)[0];
+
+    const root = `${alice.publicKey.toBase58().toLowerCase().replace(/[^a-z0-9]/g, "").slice(0, 7)}.dns`;
+    const rootHash = hashName(root);
+    const [premiumPda] = PublicKey.findProgramAddressSync([Buffer.from("premium"), rootHash], names.programId);
+    await names.methods
+      .purchasePremium(root, [...rootHash])
+      .accounts({
+        config: namesConfig,
+        premiumConfig,
+        treasury: cfg.treasury,
+        premiumName: premiumPda,
+        parentPolicy: policyOf(rootHash),
+        primary: primaryOf(alice),
+        owner: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([alice])
+      .rpc();
+
+    // alice -> www (bob) -> api (carol)
+    const wwwPda = subPda(rootHash, "www");
+    await names.methods
+      .claimDelegatedSubdomain(root, "www", [...rootHash], [...hashName("www")], bob.publicKey)
+      .accounts({
+        config: namesConfig,
+        premiumParent: premiumPda,
+        parentPolicy: policyOf(rootHash),
+        subName: wwwPda,
+        parentOwner: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([alice])
+      .rpc();
+    const wwwHash = chain(rootHash, "www");
+    const apiPda = subPda(wwwHash, "api");
+    await names.methods
+      .claimNestedSubdomain(`www.${root}`, "api", [...wwwHash], [...hashName("api")], carol.publicKey)
+      .accounts({
+        parentSub: wwwPda,
+        parentPolicy: policyOf(wwwHash),
+        subName: apiPda,
+        parentOwner: bob.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([bob])
+      .rpc();
+    const api: any = await names.account.subName.fetch(apiPda);
+    expect(api.depth).to.equal(2);
+    expect(api.transferPolicy).to.equal(1);
+    expect(api.parentOwner.toBase58()).to.equal(bob.publicKey.toBase58());
+    expect(api.owner.toBase58()).to.equal(carol.publicKey.toBase58());
+
+    const apiHash = chain(wwwHash, "api");
+    const setApiPrimary = (ancestors: PublicKey[]) =>
+      names.methods
+        .setPrimaryName(`api.www.${root}`, [...apiHash], 2)
+        .accounts({
+          primary: primaryOf(carol),
+          premiumName: null,
+          subName: apiPda,
+          owner: carol.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts(ancestors.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+        .signers([carol])
+        .rpc();
+    let missingAncestor = false;
+    try {
+      await setApiPrimary([]);
+    } catch {
+      missingAncestor = true;
+    }
+    expect(missingAncestor).to.equal(true);
+    await setApiPrimary([wwwPda]);
+    expect((await names.account.primaryName.fetch(primaryOf(carol)) as any).depth).to.equal(2);
+
+    // www moves to dave; from then on dave, not bob, approves transfers of api.
+    const transferSub = (parent: string, parentHash: Buffer, label: string, from: Keypair, to: Keypair, approver: Keypair, ancestors: PublicKey[]) =>
+      names.methods
+        .transferSubdomain(parent, label, [...parentHash], [...hashName(label)])
+        .accounts({
+          subName: subPda(parentHash, label),
+          currentOwner: from.publicKey,
+          newOwner: to.publicKey,
+          currentOwnerPrimary: primaryOf(from),
+          parentOwner: approver.publicKey,
+          parentPolicy: null,
+          config: null,
+          nftMetadata: null,
+          nftAuthority: null,
+          tokenMetadataProgram: null,
+        })
+        .remainingAccounts(ancestors.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+        .signers([from, approver])
+        .rpc();
+    await transferSub(root, rootHash, "www", bob, dave, alice, []);
+    let staleApprover = false;
+    try {
+      await transferSub(`www.${root}`, wwwHash, "api", carol, bob, bob, [wwwPda]);
+    } catch {
+      staleApprover = true;
+    }
+    expect(staleApprover).to.equal(true);
+    await transferSub(`www.${root}`, wwwHash, "api", carol, bob, dave, [wwwPda]);
+    expect((await names.account.subName.fetch(apiPda) as any).owner.toBase58()).to.equal(bob.publicKey.toBase58());
+    expect((await names.account.primaryName.fetch(primaryOf(carol)) as any).isSet).to.equal(false);
+
+    const revoke = (signer: Keypair) =>
+      names.methods
+        .revokeSubdomain([...rootHash], [[...hashName("www")]])
+        .accounts({
+          config: namesConfig,
+          subName: apiPda,
+          ancestorPremium: premiumPda,
+          ancestorSub: null,
+          ownerPrimary: primaryOf(bob),
+          authority: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc();
+    let rejected = false;
+    try {
+      await revoke(bob);
+    } catch {
+      rejected = true;
+    }
+    expect(rejected).to.equal(true);
+
+    // The grandparent revokes across the intermediate level.
+    await revoke(alice);
+    expect(await provider.connection.getAccountInfo(apiPda)).to.equal(null);
+  });
+
+  it("sells subdomains on the parent's terms with an allowlist, reserved labels, a cap and expiry", async () => {
+    const alice = Keypair.generate();
+    const bob = Keypair.generate();
+    const carol = Keypair.generate();
+    const dave = Keypair.generate();
+    for (const k of [alice, bob, carol, dave]) await provider.connection.requestAirdrop(k.publicKey, 2e9);
+    await sleep(1500);
+
+    const [namesConfig] = PublicKey.findProgramAddressSync([Buffer.from("names_config")], names.programId);
+    const [premiumConfig] = PublicKey.findProgramAddressSync([Buffer.from("premium_config")], names.programId);
+    const cfg: any = await names.account.namesConfig.fetch(namesConfig);
+    const primaryOf = (k: PublicKey) => PublicKey.findProgramAddressSync([Buffer.from("primary"), k.toBuffer()], names.programId)[0];
+
+    const parent = `${alice.publicKey.toBase58().toLowerCase().replace(/[^a-z0-9]/g, "").slice(0, 7)}.dns`;
+    const parentHash = hashName(parent);
+    const [premiumParent] = PublicKey.findProgramAddressSync([Buffer.from("premium"), parentHash], names.programId);
+    const [parentPolicy] = PublicKey.findProgramAddressSync([Buffer.from("parent_policy"), parentHash], names.programId);
+    await names.methods
+      .purchasePremium(parent, [...parentHash])
+      .accounts({
+        config: namesConfig,
+        premiumConfig,
+        treasury: cfg.treasury,
+        premiumName: premiumParent,
+        parentPolicy,
+        primary: primaryOf(alice.publicKey),
+        owner: alice.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([alice])
+      .rpc();
+
+    // Two-leaf allowlist: root = sha256(min || max) of the leaves, each proof is the other leaf.
+    const leafB = crypto.createHash("sha256").update(bob.publicKey.toBuffer()).digest();
+    const leafC = crypto.createHash("sha256").update(carol.publicKey.toBuffer()).digest();
+    const root = crypto.createHash("sha256").update(Buffer.concat(Buffer.compare(leafB, leafC) <= 0 ? [leafB, leafC] : [leafC, leafB])).digest();
+    const proofs = new Map([
+      [bob.publicKey.toBase58(), [[...leafC]]],
+      [carol.publicKey.toBase58(), [[...leafB]]],
+    ]);
+    const PRICE = 50_000_000;
+    const PERIOD = 3600;
+    await names.methods
+      .setSubdomainSaleTerms([...parentHash], {
+        enabled: true,
+        price: new BN(PRICE),
+        priceMint: PublicKey.default,
+        allowlistRoot: [...root],
+        maxSales: 2,
+        periodSecs: new BN(PERIOD),
+      })
+      .accounts({ premiumParent, parentPolicy, parentOwner: alice.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+      .signers([alice])
+      .rpc();
+    const adminHash = hashName("admin");
+    const [adminRule] = PublicKey.findProgramAddressSync([Buffer.from("sub_label"), parentHash, adminHash], names.programId);
+    await names.methods
+      .setSubLabelRule([...parentHash], [...adminHash], true, new BN(0))
+      .accounts({ premiumParent, labelRule: adminRule, parentOwner: alice.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+      .signers([alice])
+      .rpc();
+
+    const subPda = (label: string) =>
+      PublicKey.findProgramAddressSync([Buffer.from("sub"), parentHash, hashName(label)], names.programId)[0];
+    const claim = async (k: Keypair, label: string) => {
+      const labelHash = hashName(label);
+      const sub = subPda(label);
+      const existing = await provider.connection.getAccountInfo(sub);
+      const holder = existing ? new PublicKey(existing.data.subarray(8 + 64, 8 + 96)) : PublicKey.default;
+      return names.methods
+        .claimSubdomainUnderParent(parent, label, [...parentHash], [...labelHash], proofs.get(k.publicKey.toBase58()) ?? [])
+        .accounts({
+          config: namesConfig,
+          premiumParent,
+          parentPolicy,
+          labelRule: PublicKey.findProgramAddressSync([Buffer.from("sub_label"), parentHash, labelHash], names.programId)[0],
+          subName: sub,
+          previousOwnerPrimary: primaryOf(holder),
+          primary: primaryOf(k.publicKey),
+          parentWallet: alice.publicKey,
+          nameCommitment: null,
+          claimer: k.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([k])
+        .rpc();
+    };
+    const rejects = async (p: Promise<unknown>) => {
+      try {
+        await p;
+      } catch {
+        return true;
+      }
+      return false;
+    };
+
+    expect(await rejects(claim(dave, "dave1"))).to.equal(true);
+    expect(await rejects(claim(carol, "admin"))).to.equal(true);
+
+    const aliceBefore = await provider.connection.getBalance(alice.publicKey);
+    await claim(bob, "shop");
+    expect((await provider.connection.getBalance(alice.publicKey)) - aliceBefore).to.equal(PRICE);
+    let shop: any = await names.account.subName.fetch(subPda("shop"));
+    expect(shop.owner.toBase58()).to.equal(bob.publicKey.toBase58());
+    expect(shop.transferPolicy).to.equal(1);
+    const firstExpiry = shop.expiresAt.toNumber();
+    expect(firstExpiry).to.be.greaterThan(Math.floor(Date.now() / 1000));
+    const bobPrimary: any = await names.account.primaryName.fetch(primaryOf(bob.publicKey));
+    expect(Buffer.from(bobPrimary.nameBytes.slice(0, bobPrimary.nameLen)).toString()).to.equal(`shop.${parent}`);
+
+    await claim(carol, "blog");
+    expect(await rejects(claim(bob, "more"))).to.equal(true);
+    expect(((await names.account.parentPolicy.fetch(parentPolicy)) as any).soldCount).to.equal(2);
+
+    // Renewing extends from the current expiry and does not count as a sale.
+    await claim(bob, "shop");
+    shop = await names.account.subName.fetch(subPda("shop"));
+    expect(shop.expiresAt.toNumber()).to.equal(firstExpiry + PERIOD);
+    expect(((await names.account.parentPolicy.fetch(parentPolicy)) as any).soldCount).to.equal(2);
+    expect(await rejects(claim(carol, "shop"))).to.equal(true);
+
+    // A new parent owner starts with sales off and a fresh count.
+    await names.methods
+      .transferPremium()
+      .accounts({
+        premiumName: premiumParent,
+        parentPolicy,
+        currentOwner: alice.publicKey,
+        newOwner: dave.publicKey,
+        currentOwnerPrimary: primaryOf(alice.publicKey),
+      })
+      .signers([alice])
+      .rpc();
+    const handedOver: any = await names.account.parentPolicy.fetch(parentPolicy);
+    expect(handedOver.sale.enabled).to.equal(false);
+    expect(handedOver.soldCount).to.equal(0);
   });
 });
@@ -6,14 +6,22 @@ import {
   TOKEN_PROGRAM_ID,
   createAssociatedTokenAccountInstruction,
   createInitializeMintInstruction,
+  createMint,
   getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
 } from "../scripts/lib/token.js";
+import BN from "bn.js";
 import crypto from "crypto";
 
 import { DdnsRegistry } from "../target/types/ddns_registry";
 import { DdnsQuorum } from "../target/types/ddns_quorum";
 import { DdnsStake } from "../target/types/ddns_stake";
 
+const BPF_LOADER_UPGRADEABLE_PROGRAM_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
+
 function u64LE(n: bigint) {
   const b = Buffer.alloc(8);
   b.writeBigUInt64LE(n);
@@ -35,6 +43,7 @@ describe("design3 (registry/quorum/stake)", () => {
   const registry = anchor.workspace.DdnsRegistry as Program<DdnsRegistry>;
   const quorum = anchor.workspace.DdnsQuorum as Program<DdnsQuorum>;
   const stake = anchor.workspace.DdnsStake as Program<DdnsStake>;
+  const stakeGov = anchor.workspace.DdnsStakeGov as Program;
 
   it("stakes + claims rewards; finalizes canonical route via quorum CPI", async () => {
     const payer = provider.wallet.publicKey;
@@ -50,11 +59,34 @@ describe("design3 (registry/quorum/stake)", () => {
       quorum.programId
     );
 
+    // Quorum config names the governance authority for verifier sets; only the
+    // program's upgrade authority (the deploy wallet here) may create it.
+    const [quorumConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("quorum_config")],
+      quorum.programId
+    );
+    const [quorumProgramData] = PublicKey.findProgramAddressSync(
+      [quorum.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+    );
+    await quorum.methods
+      .initQuorumConfig(payer, new anchor.BN(10))
+      .accounts({
+        payer,
+        program: quorum.programId,
+        programData: quorumProgramData,
+        quorumConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
     // Initialize quorum authority PDA (must exist on-chain to be used as signer).
     await quorum.methods
       .initQuorumAuthority()
       .accounts({
         payer,
+        authority: payer,
+        quorumConfig,
         quorumAuthority,
         systemProgram: SystemProgram.programId,
       })
@@ -77,6 +109,21 @@ describe("design3 (registry/quorum/stake)", () => {
       })
       .rpc();
 
+    // Rollback window lives in its own PDA so `Config` keeps its layout.
+    const [disputeConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("dispute_config")],
+      registry.programId
+    );
+    await registry.methods
+      .initDisputeConfig(new anchor.BN(epochLenSlots))
+      .accounts({
+        authority: payer,
+        config: registryConfig,
+        disputeConfig,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
     // Initialize stake config + reward mint.
     const rewardMint = Keypair.generate();
     const [stakeConfig] = PublicKey.findProgramAddressSync(
@@ -175,7 +222,99 @@ describe("design3 (registry/quorum/stake)", () => {
       throw new Error("expected reward token balance > 0");
     }
 
-    // Quorum path: init verifier set for current epoch, submit aggregate, finalize.
+    // Verifier weights come from ddns_stake_gov: register payer as a verifier and self-stake.
+    const payerKp = (provider.wallet as any).payer as Keypair;
+    const [govConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake_gov_config")],
+      stakeGov.programId
+    );
+    const [govVaultAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake_gov_vault_authority")],
+      stakeGov.programId
+    );
+    const [verifierRegistry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("verifier_registry")],
+      stakeGov.programId
+    );
+    // The config is a singleton; reuse it when another suite already created it.
+    let govCfg: any = await stakeGov.account.stakeGovConfig.fetchNullable(govConfig);
+    if (!govCfg) {
+      const govMint = await createMint(provider.connection, payerKp, payer, null, 9);
+      const govVault = Keypair.generate();
+      await stakeGov.methods
+        .initConfig(
+          govMint,
+          new BN(epochLenSlots),
+          new BN(1_000_000_000),
+          64,
+          new BN(10),
+          new BN(2),
+          new BN(1),
+          new BN(365),
+          new BN(10),
+          5000,
+          Array.from({ length: 6 }, () => ({ lockEpochs: new BN(1), multiplierBps: 10000 })) as any,
+          [payer],
+          [payer]
+        )
+        .accounts({
+          config: govConfig,
+          verifierRegistry,
+          vaultAuthority: govVaultAuthority,
+          vault: govVault.publicKey,
+          stakeMint: govMint,
+          authority: payer,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([govVault])
+        .rpc();
+      govCfg = await stakeGov.account.stakeGovConfig.fetch(govConfig);
+    }
+    const reg: any = await stakeGov.account.verifierRegistry.fetch(verifierRegistry);
+    if (!reg.verifiers.some((v: any) => v.verifier.equals(payer))) {
+      await stakeGov.methods
+        .registerVerifier(payer, 0)
+        .accounts({ config: govConfig, verifierRegistry, authority: payer })
+        .rpc();
+    }
+    const [govPosition] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake"), payer.toBuffer()],
+      stakeGov.programId
+    );
+    if (!(await provider.connection.getAccountInfo(govPosition))) {
+      await stakeGov.methods
+        .initStakePosition()
+        .accounts({
+          config: govConfig,
+          stakePosition: govPosition,
+          staker: payer,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+    const govAta = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      payerKp,
+      govCfg.stakeMint,
+      payer
+    );
+    await mintTo(provider.connection, payerKp, govCfg.stakeMint, govAta.address, payer, 2_000_000_000n);
+    await stakeGov.methods
+      .stake(new BN(2_000_000_000))
+      .accounts({
+        config: govConfig,
+        vaultAuthority: govVaultAuthority,
+        vault: govCfg.vault,
+        stakePosition: govPosition,
+        stakerAta: govAta.address,
+        staker: payer,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    // Quorum path: build verifier set for current epoch, submit aggregate, finalize.
     const slot = await provider.connection.getSlot();
     const epochId = Math.floor(slot / epochLenSlots);
 
@@ -184,13 +323,24 @@ describe("design3 (registry/quorum/stake)", () => {
       quorum.programId
     );
     await quorum.methods
-      .initVerifierSet(new anchor.BN(epochId), new anchor.BN(0), [payer])
+      .initVerifierSet(new anchor.BN(epochId), 6667)
       .accounts({
-        admin: payer,
+        payer,
+        authority: payer,
+        quorumConfig,
+        registryConfig,
         verifierSet,
+        stakeGovConfig: govConfig,
+        verifierRegistry,
         systemProgram: SystemProgram.programId,
       })
+      .remainingAccounts([{ pubkey: govPosition, isSigner: false, isWritable: false }])
       .rpc();
+    const vs: any = await quorum.account.verifierSet.fetch(verifierSet);
+    const payerMember = vs.members.find((m: any) => m.verifier.equals(payer));
+    if (!payerMember || payerMember.stakeWeight.isZero()) {
+      throw new Error("expected payer to carry delegated stake weight");
+    }
 
     const nameHash = crypto.randomBytes(32);
     const destHash = crypto.randomBytes(32);
@@ -224,6 +374,10 @@ describe("design3 (registry/quorum/stake)", () => {
       [Buffer.from("canonical"), nameHash],
       registry.programId
     );
+    const [routeHistory] = PublicKey.findProgramAddressSync(
+      [Buffer.from("route_history"), nameHash],
+      registry.programId
+    );
 
     await quorum.methods
       .finalizeIfQuorum(new anchor.BN(epochId), Array.from(nameHash), Array.from(destHash), 300)
@@ -231,12 +385,15 @@ describe("design3 (registry/quorum/stake)", () => {
         payer,
         registryConfig,
         verifierSet,
-        aggregate: agg,
+        quorumConfig,
         quorumAuthority,
         canonicalRoute,
+        routeHistory,
         ddnsRegistryProgram: registry.programId,
         systemProgram: SystemProgram.programId,
       })
+      // One aggregate per submitter; weights come from the verifier set, not the aggregate.
+      .remainingAccounts([{ pubkey: agg, isSigner: false, isWritable: false }])
       .rpc();
 
     const route = await registry.account.canonicalRoute.fetch(canonicalRoute);
@@ -244,5 +401,10 @@ describe("design3 (registry/quorum/stake)", () => {
     if (!gotDest.equals(destHash)) {
       throw new Error("canonical route dest_hash mismatch");
     }
+
+    const history = await registry.account.routeHistory.fetch(routeHistory);
+    if (route.version.toNumber() !== 1 || history.len !== 1) {
+      throw new Error("route history not recorded");
+    }
   });
 });
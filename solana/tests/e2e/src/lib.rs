@@ -0,0 +1,154 @@
+//! End-to-end harness: runs the SBF programs built by `anchor build` under
+//! litesvm, so instructions execute with real CPIs, rent and signer checks.
+//! The `.so` files are read from `solana/target/deploy`.
+//!
+//! Instructions are built from the program crate's own `accounts::*` and
+//! `instruction::*` types with addresses from [`pda`], so the harness does
+//! not depend on `ddns_client`; only the suite that exercises that client
+//! uses it.
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::solana_program::instruction::error::InstructionError;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use litesvm::LiteSVM;
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+pub use solana_transaction_error::TransactionError;
+
+pub mod names;
+pub mod pda;
+pub mod token;
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+pub struct Harness {
+    pub svm: LiteSVM,
+    /// Pays for every transaction and is the authority of every config the
+    /// setup helpers create.
+    pub payer: Keypair,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    /// A fresh chain with `ddns_names` deployed; [`Harness::load`] adds others.
+    pub fn new() -> Self {
+        let svm = LiteSVM::new();
+        let payer = Keypair::new();
+        let mut h = Self { svm, payer };
+        h.svm.airdrop(&h.payer.pubkey(), 100 * LAMPORTS_PER_SOL).expect("airdrop to payer");
+        h.load("ddns_names", ddns_names::ID);
+        h
+    }
+
+    /// Deploys `target/deploy/<name>.so` at `id`.
+    pub fn load(&mut self, name: &str, id: Pubkey) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("../../target/deploy/{name}.so"));
+        self.svm
+            .add_program_from_file(id, &path)
+            .unwrap_or_else(|e| panic!("loading {}: {e}; run `anchor build` first", path.display()));
+    }
+
+    pub fn funded(&mut self, lamports: u64) -> Keypair {
+        let wallet = Keypair::new();
+        self.svm.airdrop(&wallet.pubkey(), lamports).expect("airdrop");
+        wallet
+    }
+
+    /// Sends `ixs` as one transaction. `payer` pays the fee, so balances of
+    /// the other `signers` only move by what the programs do. The blockhash
+    /// is rotated afterwards so the same transaction can be sent again.
+    pub fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> Result<(), TransactionError> {
+        let mut all = vec![&self.payer];
+        all.extend_from_slice(signers);
+        let message = Message::new(ixs, Some(&self.payer.pubkey()));
+        let tx = Transaction::new(&all[..], message, self.svm.latest_blockhash());
+        let result = self.svm.send_transaction(tx).map(|_| ()).map_err(|failed| failed.err);
+        self.svm.expire_blockhash();
+        result
+    }
+
+    pub fn balance(&self, address: &Pubkey) -> u64 {
+        self.svm.get_balance(address).unwrap_or(0)
+    }
+
+    /// Closed accounts are drained to zero lamports.
+    pub fn exists(&self, address: &Pubkey) -> bool {
+        self.balance(address) > 0
+    }
+
+    pub fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self
+            .svm
+            .get_account(address)
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        T::try_deserialize(&mut &account.data[..]).unwrap_or_else(|e| panic!("decoding {address}: {e}"))
+    }
+
+    /// Cuts an account's data down to `len` bytes, as if it had been created
+    /// with an older, shorter layout.
+    pub fn truncate(&mut self, address: &Pubkey, len: usize) {
+        let mut account = self
+            .svm
+            .get_account(address)
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        account.data.truncate(len);
+        self.svm.set_account(*address, account).expect("set truncated account");
+    }
+
+    /// Writes an account directly, for state that no current instruction
+    /// creates any more.
+    pub fn plant(&mut self, address: Pubkey, owner: Pubkey, lamports: u64, data: Vec<u8>) {
+        let account = solana_account::Account { lamports, data, owner, executable: false, rent_epoch: 0 };
+        self.svm.set_account(address, account).expect("plant account");
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.svm.get_sysvar::<Clock>()
+    }
+
+    pub fn warp_slots(&mut self, slots: u64) {
+        let slot = self.clock().slot + slots;
+        self.svm.warp_to_slot(slot);
+    }
+
+    pub fn warp_secs(&mut self, secs: i64) {
+        let mut clock = self.clock();
+        clock.unix_timestamp += secs;
+        self.svm.set_sysvar(&clock);
+    }
+}
+
+/// An instruction from a program's typed account list and arguments.
+pub fn instruction(program_id: Pubkey, accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+/// Normalizes `name` and returns it with its `name_hash`.
+pub fn name_hash(name: &str) -> (String, [u8; 32]) {
+    let normalized = ddns_names_core::normalize_name(name).unwrap_or_else(|e| panic!("{name}: {e}"));
+    let hash = ddns_names_core::hash_name(&normalized);
+    (normalized, hash)
+}
+
+/// The program error code a failed transaction ended with, if it was a
+/// custom one (Anchor constraint or program error).
+pub fn error_code(err: &TransactionError) -> Option<u32> {
+    match err {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(*code),
+        _ => None,
+    }
+}
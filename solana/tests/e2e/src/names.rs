@@ -0,0 +1,444 @@
+//! `ddns_names` setup and the auction and listing instructions the suites
+//! send. Bid accounts take the auction round, the `Auction.start_slot` from
+//! [`round`].
+
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, Discriminator};
+use ddns_names as names;
+use sha2::{Digest, Sha256};
+use solana_signer::Signer;
+
+use crate::{instruction, name_hash, pda, Harness, LAMPORTS_PER_SOL};
+
+pub const PREMIUM_PRICE: u64 = 100_000_000;
+pub const MIN_BID: u64 = 10_000_000;
+pub const AUCTION_SLOTS: u64 = 10;
+pub const AUCTION_OPEN: u8 = 0;
+pub const AUCTION_SEALED: u8 = 1;
+/// Default premium term plus grace period: after this a name is releasable.
+pub const TERM_AND_GRACE_SECS: i64 = (365 + 30) * 24 * 60 * 60;
+
+/// Creates `NamesConfig` (parent zone `user.dns`, no commit-reveal) and
+/// `PremiumConfig`, both with the harness payer as authority and treasury
+/// authority and paying into a new treasury wallet, which is returned.
+pub fn init(h: &mut Harness) -> Pubkey {
+    let treasury = h.funded(LAMPORTS_PER_SOL).pubkey();
+    let authority = h.payer.pubkey();
+    let ixs = [
+        instruction(
+            names::ID,
+            names::accounts::InitNamesConfig {
+                config: pda::config(),
+                authority,
+                system_program: system_program::ID,
+            },
+            names::instruction::InitNamesConfig {
+                treasury_pubkey: treasury,
+                parent_zone: "user.dns".to_string(),
+                premium_price_lamports: PREMIUM_PRICE,
+                subdomain_bond_lamports: 0,
+                enable_subdomains: true,
+                enable_premium: true,
+            },
+        ),
+        instruction(
+            names::ID,
+            names::accounts::InitPremiumConfig {
+                premium_config: pda::premium_config(),
+                authority,
+                system_program: system_program::ID,
+            },
+            names::instruction::InitPremiumConfig {
+                treasury_vault: treasury,
+                treasury_authority: authority,
+                min_bid_lamports_default: MIN_BID,
+                auction_duration_slots_default: AUCTION_SLOTS,
+                anti_sniping_extension_slots: 0,
+                enabled: true,
+            },
+        ),
+    ];
+    h.send(&ixs, &[]).expect("init names configs");
+    treasury
+}
+
+/// Buys `name` at the config price, with no commitment or toll payment.
+pub fn purchase_premium(owner: &Pubkey, treasury: &Pubkey, name: &str) -> Instruction {
+    let (normalized, hash) = name_hash(name);
+    instruction(
+        names::ID,
+        names::accounts::PurchasePremium {
+            config: pda::config(),
+            premium_config: pda::premium_config(),
+            treasury: *treasury,
+            premium_name: pda::premium_name(&hash),
+            parent_policy: pda::parent_policy(&hash),
+            primary: pda::primary_name(owner),
+            name_price: pda::name_price(&hash),
+            name_commitment: None,
+            owner: *owner,
+            system_program: system_program::ID,
+            payer_toll_account: None,
+            toll_treasury: None,
+            toll_mint: None,
+            token_program: None,
+        },
+        names::instruction::PurchasePremium { name: normalized, name_hash: hash },
+    )
+}
+
+/// Start slot of the name's current auction round.
+pub fn round(h: &Harness, name_hash: &[u8; 32]) -> u64 {
+    h.account::<names::Auction>(&pda::auction(name_hash)).start_slot
+}
+
+/// `sha256(name_hash || bidder || bid_lamports_le || salt)`.
+pub fn sealed_commitment(name_hash: &[u8; 32], bidder: &Pubkey, bid_lamports: u64, salt: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(name_hash)
+        .chain_update(bidder.as_ref())
+        .chain_update(bid_lamports.to_le_bytes())
+        .chain_update(salt)
+        .finalize()
+        .into()
+}
+
+pub fn create_auction(authority: &Pubkey, name: &str, name_hash: &[u8; 32], mode: u8) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::CreateAuction {
+            premium_config: pda::premium_config(),
+            premium_name: pda::premium_name(name_hash),
+            auction: pda::auction(name_hash),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        names::instruction::CreateAuction {
+            name: name.to_string(),
+            name_hash: *name_hash,
+            min_bid_lamports: None,
+            duration_slots: None,
+            mode,
+            reveal_slots: None,
+        },
+    )
+}
+
+pub fn cancel_auction(authority: &Pubkey, name_hash: &[u8; 32]) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::CancelAuction {
+            premium_config: pda::premium_config(),
+            auction: pda::auction(name_hash),
+            authority: *authority,
+        },
+        names::instruction::CancelAuction { name_hash: *name_hash },
+    )
+}
+
+pub fn place_bid(bidder: &Pubkey, name_hash: &[u8; 32], round: u64, lamports: u64) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::PlaceBid {
+            premium_config: pda::premium_config(),
+            auction: pda::auction(name_hash),
+            bid_escrow: pda::bid_escrow(name_hash, bidder, round),
+            escrow_vault: pda::bid_escrow_vault(name_hash, bidder, round),
+            bidder: *bidder,
+            system_program: system_program::ID,
+        },
+        names::instruction::PlaceBid { name_hash: *name_hash, lamports },
+    )
+}
+
+pub fn withdraw_losing_bid(bidder: &Pubkey, name_hash: &[u8; 32], round: u64) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::WithdrawLosingBid {
+            auction: pda::auction(name_hash),
+            bid_escrow: pda::bid_escrow(name_hash, bidder, round),
+            escrow_vault: pda::bid_escrow_vault(name_hash, bidder, round),
+            bidder: *bidder,
+            system_program: system_program::ID,
+        },
+        names::instruction::WithdrawLosingBid { name_hash: *name_hash },
+    )
+}
+
+/// Escrow and vault addresses used before bid accounts were keyed by round.
+pub fn legacy_escrow(name_hash: &[u8; 32], bidder: &Pubkey) -> (Pubkey, Pubkey) {
+    let seeds = |prefix: &[u8]| Pubkey::find_program_address(&[prefix, name_hash, bidder.as_ref()], &names::ID).0;
+    (seeds(names::SEED_ESCROW), seeds(names::SEED_ESCROW_VAULT))
+}
+
+/// Writes an active old-layout `BidEscrow` of `amount` and its vault at the
+/// pre-round seeds, as `place_bid` used to leave them.
+pub fn plant_legacy_escrow(h: &mut Harness, name_hash: &[u8; 32], bidder: &Pubkey, amount: u64) -> (Pubkey, Pubkey) {
+    let (escrow, vault) = legacy_escrow(name_hash, bidder);
+    let mut data = names::BidEscrow::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(name_hash);
+    data.extend_from_slice(bidder.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&[1, 0, 255]);
+    let rent = Rent::default();
+    h.plant(escrow, names::ID, rent.minimum_balance(data.len()), data);
+    h.plant(vault, names::ID, rent.minimum_balance(0) + amount, Vec::new());
+    (escrow, vault)
+}
+
+pub fn withdraw_legacy_bid(bidder: &Pubkey, treasury: &Pubkey, name_hash: &[u8; 32]) -> Instruction {
+    let (bid_escrow, escrow_vault) = legacy_escrow(name_hash, bidder);
+    instruction(
+        names::ID,
+        names::accounts::WithdrawLegacyBid {
+            premium_config: pda::premium_config(),
+            auction: pda::auction(name_hash),
+            bid_escrow,
+            escrow_vault,
+            treasury: *treasury,
+            bidder: *bidder,
+        },
+        names::instruction::WithdrawLegacyBid { name_hash: *name_hash },
+    )
+}
+
+pub fn settle_auction(winner: &Pubkey, treasury: &Pubkey, name_hash: &[u8; 32], round: u64) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::SettleAuction {
+            config: pda::config(),
+            premium_config: pda::premium_config(),
+            auction: pda::auction(name_hash),
+            winner_escrow: pda::bid_escrow(name_hash, winner, round),
+            winner_escrow_vault: pda::bid_escrow_vault(name_hash, winner, round),
+            treasury: *treasury,
+            premium_name: pda::premium_name(name_hash),
+            parent_policy: pda::parent_policy(name_hash),
+            primary: pda::primary_name(winner),
+            winner: *winner,
+            system_program: system_program::ID,
+        },
+        names::instruction::SettleAuction { name_hash: *name_hash },
+    )
+}
+
+pub fn commit_sealed_bid(
+    bidder: &Pubkey,
+    name_hash: &[u8; 32],
+    round: u64,
+    commitment: [u8; 32],
+    deposit_lamports: u64,
+) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::CommitSealedBid {
+            premium_config: pda::premium_config(),
+            auction: pda::auction(name_hash),
+            sealed_bid: pda::sealed_bid(name_hash, bidder, round),
+            bidder: *bidder,
+            system_program: system_program::ID,
+        },
+        names::instruction::CommitSealedBid { name_hash: *name_hash, commitment, deposit_lamports },
+    )
+}
+
+pub fn reveal_sealed_bid(bidder: &Pubkey, name_hash: &[u8; 32], round: u64, bid_lamports: u64, salt: [u8; 32]) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::RevealSealedBid {
+            auction: pda::auction(name_hash),
+            sealed_bid: pda::sealed_bid(name_hash, bidder, round),
+            bidder: *bidder,
+        },
+        names::instruction::RevealSealedBid { name_hash: *name_hash, bid_lamports, salt },
+    )
+}
+
+pub fn settle_sealed_auction(winner: &Pubkey, treasury: &Pubkey, name_hash: &[u8; 32], round: u64) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::SettleSealedAuction {
+            premium_config: pda::premium_config(),
+            auction: pda::auction(name_hash),
+            winner_bid: pda::sealed_bid(name_hash, winner, round),
+            treasury: *treasury,
+            premium_name: pda::premium_name(name_hash),
+            parent_policy: pda::parent_policy(name_hash),
+            primary: pda::primary_name(winner),
+            winner: *winner,
+            system_program: system_program::ID,
+        },
+        names::instruction::SettleSealedAuction { name_hash: *name_hash },
+    )
+}
+
+pub fn close_sealed_bid(bidder: &Pubkey, treasury: &Pubkey, name_hash: &[u8; 32], round: u64) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::CloseSealedBid {
+            premium_config: pda::premium_config(),
+            auction: pda::auction(name_hash),
+            sealed_bid: pda::sealed_bid(name_hash, bidder, round),
+            bidder: *bidder,
+            treasury: *treasury,
+        },
+        names::instruction::CloseSealedBid { name_hash: *name_hash },
+    )
+}
+
+pub fn renew_premium(payer: &Pubkey, treasury: &Pubkey, name_hash: &[u8; 32], years: u8) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::RenewPremium {
+            config: pda::config(),
+            premium_config: pda::premium_config(),
+            premium_name: pda::premium_name(name_hash),
+            treasury: *treasury,
+            payer: *payer,
+            system_program: system_program::ID,
+            nft_metadata: None,
+            nft_authority: None,
+            token_metadata_program: None,
+        },
+        names::instruction::RenewPremium { name_hash: *name_hash, years },
+    )
+}
+
+pub fn transfer_premium(owner: &Pubkey, new_owner: &Pubkey, name_hash: &[u8; 32]) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::TransferPremium {
+            premium_name: pda::premium_name(name_hash),
+            parent_policy: pda::parent_policy(name_hash),
+            current_owner: *owner,
+            new_owner: *new_owner,
+            current_owner_primary: pda::primary_name(owner),
+        },
+        names::instruction::TransferPremium {},
+    )
+}
+
+/// Permissionless release of a name past its grace period; `owner` is the
+/// lapsed `PremiumName.owner`.
+pub fn release_premium(caller: &Pubkey, owner: &Pubkey, name_hash: &[u8; 32]) -> Instruction {
+    release(caller, owner, name_hash, None)
+}
+
+/// [`release_premium`] for a listed name: the listing closes with it and the
+/// rent goes to `seller`, still the name's owner.
+pub fn release_listed_premium(caller: &Pubkey, seller: &Pubkey, name_hash: &[u8; 32]) -> Instruction {
+    release(caller, seller, name_hash, Some(pda::listing(name_hash)))
+}
+
+fn release(caller: &Pubkey, owner: &Pubkey, name_hash: &[u8; 32], listing: Option<Pubkey>) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::ReleasePremium {
+            premium_config: pda::premium_config(),
+            premium_name: pda::premium_name(name_hash),
+            parent_policy: pda::parent_policy(name_hash),
+            listing,
+            previous_owner: *owner,
+            previous_owner_primary: pda::primary_name(owner),
+            caller: *caller,
+            nft_mint: None,
+            escrow_nft_account: None,
+            seller_nft_account: None,
+            token_program: None,
+        },
+        names::instruction::ReleasePremium { name_hash: *name_hash },
+    )
+}
+
+/// Grows `target` to its type's current layout. `premium_config` is only
+/// needed, and only passed, when `target` is a `PremiumName`.
+pub fn migrate_account(authority: &Pubkey, target: &Pubkey, premium_config: bool) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::MigrateAccount {
+            config: pda::config(),
+            premium_config: premium_config.then(pda::premium_config),
+            target: *target,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        names::instruction::MigrateAccount {},
+    )
+}
+
+/// Fixed-price listing of a name without a bound NFT.
+pub fn list_premium(seller: &Pubkey, name_hash: &[u8; 32], price_lamports: u64) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::ListPremium {
+            premium_name: pda::premium_name(name_hash),
+            listing: pda::listing(name_hash),
+            seller: *seller,
+            system_program: system_program::ID,
+            nft_mint: None,
+            seller_nft_account: None,
+            escrow_nft_account: None,
+            token_program: None,
+        },
+        names::instruction::ListPremium {
+            name_hash: *name_hash,
+            start_price_lamports: price_lamports,
+            end_price_lamports: price_lamports,
+            duration_secs: 0,
+        },
+    )
+}
+
+pub fn delist_premium(seller: &Pubkey, name_hash: &[u8; 32]) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::DelistPremium {
+            premium_name: pda::premium_name(name_hash),
+            listing: pda::listing(name_hash),
+            seller: *seller,
+            nft_mint: None,
+            escrow_nft_account: None,
+            seller_nft_account: None,
+            token_program: None,
+        },
+        names::instruction::DelistPremium { name_hash: *name_hash },
+    )
+}
+
+pub fn buy_listed_premium(
+    buyer: &Pubkey,
+    seller: &Pubkey,
+    treasury: &Pubkey,
+    name: &str,
+    name_hash: &[u8; 32],
+    max_price_lamports: u64,
+) -> Instruction {
+    instruction(
+        names::ID,
+        names::accounts::BuyListedPremium {
+            config: pda::config(),
+            premium_name: pda::premium_name(name_hash),
+            parent_policy: pda::parent_policy(name_hash),
+            listing: pda::listing(name_hash),
+            seller: *seller,
+            seller_primary: pda::primary_name(seller),
+            buyer_primary: pda::primary_name(buyer),
+            treasury: *treasury,
+            buyer: *buyer,
+            system_program: system_program::ID,
+            nft_mint: None,
+            escrow_nft_account: None,
+            buyer_nft_account: None,
+            token_program: None,
+            nft_metadata: None,
+            nft_authority: None,
+            token_metadata_program: None,
+        },
+        names::instruction::BuyListedPremium {
+            name: name.to_string(),
+            name_hash: *name_hash,
+            max_price_lamports,
+        },
+    )
+}
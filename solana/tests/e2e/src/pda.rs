@@ -0,0 +1,57 @@
+//! `ddns_names` addresses used by the suites, derived from the program's
+//! own seed constants.
+
+use anchor_lang::prelude::Pubkey;
+use ddns_names::{
+    ID, SEED_AUCTION, SEED_CONFIG, SEED_ESCROW, SEED_ESCROW_VAULT, SEED_LISTING, SEED_NAME_PRICE, SEED_POLICY,
+    SEED_PREMIUM, SEED_PREMIUM_CONFIG, SEED_PRIMARY, SEED_SEALED_BID,
+};
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+pub fn config() -> Pubkey {
+    find(&[SEED_CONFIG])
+}
+
+pub fn premium_config() -> Pubkey {
+    find(&[SEED_PREMIUM_CONFIG])
+}
+
+pub fn premium_name(name_hash: &[u8; 32]) -> Pubkey {
+    find(&[SEED_PREMIUM, name_hash])
+}
+
+pub fn parent_policy(name_hash: &[u8; 32]) -> Pubkey {
+    find(&[SEED_POLICY, name_hash])
+}
+
+pub fn primary_name(owner: &Pubkey) -> Pubkey {
+    find(&[SEED_PRIMARY, owner.as_ref()])
+}
+
+pub fn name_price(name_hash: &[u8; 32]) -> Pubkey {
+    find(&[SEED_NAME_PRICE, name_hash])
+}
+
+pub fn auction(name_hash: &[u8; 32]) -> Pubkey {
+    find(&[SEED_AUCTION, name_hash])
+}
+
+/// Bid accounts are keyed by the auction round, `Auction.start_slot`.
+pub fn bid_escrow(name_hash: &[u8; 32], bidder: &Pubkey, round: u64) -> Pubkey {
+    find(&[SEED_ESCROW, name_hash, bidder.as_ref(), &round.to_le_bytes()])
+}
+
+pub fn bid_escrow_vault(name_hash: &[u8; 32], bidder: &Pubkey, round: u64) -> Pubkey {
+    find(&[SEED_ESCROW_VAULT, name_hash, bidder.as_ref(), &round.to_le_bytes()])
+}
+
+pub fn sealed_bid(name_hash: &[u8; 32], bidder: &Pubkey, round: u64) -> Pubkey {
+    find(&[SEED_SEALED_BID, name_hash, bidder.as_ref(), &round.to_le_bytes()])
+}
+
+pub fn listing(name_hash: &[u8; 32]) -> Pubkey {
+    find(&[SEED_LISTING, name_hash])
+}
@@ -0,0 +1,51 @@
+//! SPL token setup for the suites: mints and associated token accounts
+//! created through the token programs litesvm ships with.
+
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::associated_token::{get_associated_token_address, spl_associated_token_account};
+use anchor_spl::token::spl_token;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::Harness;
+
+/// Creates a 0-decimal mint, paid for by the harness payer.
+pub fn create_mint(h: &mut Harness, mint_authority: &Pubkey, freeze_authority: Option<&Pubkey>) -> Pubkey {
+    let mint = Keypair::new();
+    let lamports = h.svm.get_sysvar::<Rent>().minimum_balance(spl_token::state::Mint::LEN);
+    let ixs = [
+        system_instruction::create_account(
+            &h.payer.pubkey(),
+            &mint.pubkey(),
+            lamports,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), mint_authority, freeze_authority, 0)
+            .expect("initialize_mint2"),
+    ];
+    h.send(&ixs, &[&mint]).expect("create mint");
+    mint.pubkey()
+}
+
+/// Creates `owner`'s associated token account for `mint` and returns it.
+pub fn create_ata(h: &mut Harness, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &h.payer.pubkey(),
+        owner,
+        mint,
+        &spl_token::ID,
+    );
+    h.send(&[ix], &[]).expect("create associated token account");
+    get_associated_token_address(owner, mint)
+}
+
+pub fn token_amount(h: &Harness, token_account: &Pubkey) -> u64 {
+    let account = h
+        .svm
+        .get_account(token_account)
+        .unwrap_or_else(|| panic!("token account {token_account} does not exist"));
+    spl_token::state::Account::unpack(&account.data).expect("token account").amount
+}
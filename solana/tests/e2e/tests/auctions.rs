@@ -0,0 +1,234 @@
+//! Auction fund flow: where bids go on settle, withdraw and cancel, and how
+//! bid accounts stay separate across rounds of a re-auctioned name.
+
+use ddns_e2e::names::{
+    cancel_auction, close_sealed_bid, commit_sealed_bid, create_auction, init, place_bid, plant_legacy_escrow,
+    release_premium, reveal_sealed_bid, round, sealed_commitment, settle_auction, settle_sealed_auction,
+    withdraw_legacy_bid, withdraw_losing_bid, AUCTION_OPEN, AUCTION_SEALED, AUCTION_SLOTS, MIN_BID,
+    TERM_AND_GRACE_SECS,
+};
+use ddns_e2e::{error_code, name_hash, pda, Harness, LAMPORTS_PER_SOL};
+use ddns_names::{self as names, NamesError};
+use solana_signer::Signer;
+
+fn code(e: NamesError) -> Option<u32> {
+    Some(e.into())
+}
+
+#[test]
+fn settle_pays_the_winning_bid_to_the_treasury_and_closes_the_winner_escrow() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let (name, hash) = name_hash("abc.dns");
+    let authority = h.payer.pubkey();
+    h.send(&[create_auction(&authority, &name, &hash, AUCTION_OPEN)], &[]).unwrap();
+    let r = round(&h, &hash);
+
+    let alice = h.funded(LAMPORTS_PER_SOL);
+    let bob = h.funded(LAMPORTS_PER_SOL);
+    h.send(&[place_bid(&alice.pubkey(), &hash, r, MIN_BID + 1_000_000)], &[&alice]).unwrap();
+    h.send(&[place_bid(&bob.pubkey(), &hash, r, MIN_BID + 2_000_000)], &[&bob]).unwrap();
+
+    let err = h.send(&[settle_auction(&bob.pubkey(), &treasury, &hash, r)], &[&bob]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::AuctionNotEnded));
+    h.warp_slots(AUCTION_SLOTS);
+    let err = h.send(&[settle_auction(&alice.pubkey(), &treasury, &hash, r)], &[&alice]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::Unauthorized));
+
+    let treasury_before = h.balance(&treasury);
+    h.send(&[settle_auction(&bob.pubkey(), &treasury, &hash, r)], &[&bob]).unwrap();
+
+    assert_eq!(h.balance(&treasury) - treasury_before, MIN_BID + 2_000_000);
+    assert!(!h.exists(&pda::bid_escrow(&hash, &bob.pubkey(), r)));
+    assert!(!h.exists(&pda::bid_escrow_vault(&hash, &bob.pubkey(), r)));
+    let premium: names::PremiumName = h.account(&pda::premium_name(&hash));
+    assert_eq!(premium.owner, bob.pubkey());
+    let auction: names::Auction = h.account(&pda::auction(&hash));
+    assert!(auction.settled);
+    // The outbid escrow is untouched by settlement.
+    assert!(h.exists(&pda::bid_escrow_vault(&hash, &alice.pubkey(), r)));
+}
+
+#[test]
+fn withdraw_returns_bid_and_rent_to_the_outbid_bidder_and_closes_both_accounts() {
+    let mut h = Harness::new();
+    init(&mut h);
+    let (name, hash) = name_hash("abcd.dns");
+    let authority = h.payer.pubkey();
+    h.send(&[create_auction(&authority, &name, &hash, AUCTION_OPEN)], &[]).unwrap();
+    let r = round(&h, &hash);
+
+    let alice = h.funded(LAMPORTS_PER_SOL);
+    let bob = h.funded(LAMPORTS_PER_SOL);
+    let alice_start = h.balance(&alice.pubkey());
+    h.send(&[place_bid(&alice.pubkey(), &hash, r, MIN_BID)], &[&alice]).unwrap();
+    h.send(&[place_bid(&bob.pubkey(), &hash, r, MIN_BID + 1)], &[&bob]).unwrap();
+
+    let err = h.send(&[withdraw_losing_bid(&bob.pubkey(), &hash, r)], &[&bob]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::WinnerCannotWithdraw));
+
+    let escrow = pda::bid_escrow(&hash, &alice.pubkey(), r);
+    let vault = pda::bid_escrow_vault(&hash, &alice.pubkey(), r);
+    h.send(&[withdraw_losing_bid(&alice.pubkey(), &hash, r)], &[&alice]).unwrap();
+
+    // Bid, vault rent and escrow rent all come back.
+    assert_eq!(h.balance(&alice.pubkey()), alice_start);
+    assert!(!h.exists(&escrow));
+    assert!(!h.exists(&vault));
+    assert!(h.send(&[withdraw_losing_bid(&alice.pubkey(), &hash, r)], &[&alice]).is_err());
+}
+
+#[test]
+fn cancelled_auction_can_be_listed_again_until_it_draws_a_bid() {
+    let mut h = Harness::new();
+    init(&mut h);
+    let (name, hash) = name_hash("xyz.dns");
+    let authority = h.payer.pubkey();
+    let auction = pda::auction(&hash);
+
+    h.send(&[create_auction(&authority, &name, &hash, AUCTION_OPEN)], &[]).unwrap();
+    let err = h.send(&[create_auction(&authority, &name, &hash, AUCTION_OPEN)], &[]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::AuctionInProgress));
+
+    h.send(&[cancel_auction(&authority, &hash)], &[]).unwrap();
+    assert!(!h.exists(&auction));
+
+    h.send(&[create_auction(&authority, &name, &hash, AUCTION_OPEN)], &[]).unwrap();
+    let relisted: names::Auction = h.account(&auction);
+    assert!(!relisted.settled);
+    assert_eq!(relisted.highest_bid_lamports, 0);
+
+    let bidder = h.funded(LAMPORTS_PER_SOL);
+    let r = round(&h, &hash);
+    h.send(&[place_bid(&bidder.pubkey(), &hash, r, MIN_BID)], &[&bidder]).unwrap();
+    let err = h.send(&[cancel_auction(&authority, &hash)], &[]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::AuctionHasBids));
+    assert!(h.exists(&auction));
+}
+
+#[test]
+fn escrow_from_an_earlier_round_stays_out_of_the_next_and_withdrawable() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let (name, hash) = name_hash("rnd.dns");
+    let authority = h.payer.pubkey();
+    let alice = h.funded(LAMPORTS_PER_SOL);
+    let bob = h.funded(LAMPORTS_PER_SOL);
+
+    h.send(&[create_auction(&authority, &name, &hash, AUCTION_OPEN)], &[]).unwrap();
+    let first = round(&h, &hash);
+    h.send(&[place_bid(&alice.pubkey(), &hash, first, MIN_BID)], &[&alice]).unwrap();
+    h.send(&[place_bid(&bob.pubkey(), &hash, first, MIN_BID + 1)], &[&bob]).unwrap();
+    h.warp_slots(AUCTION_SLOTS);
+    h.send(&[settle_auction(&bob.pubkey(), &treasury, &hash, first)], &[&bob]).unwrap();
+
+    h.warp_secs(TERM_AND_GRACE_SECS + 1);
+    h.send(&[release_premium(&authority, &bob.pubkey(), &hash)], &[]).unwrap();
+    h.send(&[create_auction(&authority, &name, &hash, AUCTION_OPEN)], &[]).unwrap();
+    let second = round(&h, &hash);
+    assert_ne!(first, second);
+
+    // Alice's unwithdrawn first-round bid does not count toward the new round.
+    h.send(&[place_bid(&alice.pubkey(), &hash, second, MIN_BID)], &[&alice]).unwrap();
+    let escrow: names::BidEscrow = h.account(&pda::bid_escrow(&hash, &alice.pubkey(), second));
+    assert_eq!(escrow.amount_lamports, MIN_BID);
+    assert_eq!(escrow.auction_start_slot, second);
+
+    // Leading the new round does not lock the old escrow.
+    let before = h.balance(&alice.pubkey());
+    h.send(&[withdraw_losing_bid(&alice.pubkey(), &hash, first)], &[&alice]).unwrap();
+    assert!(h.balance(&alice.pubkey()) - before > MIN_BID);
+    assert!(!h.exists(&pda::bid_escrow(&hash, &alice.pubkey(), first)));
+    assert!(!h.exists(&pda::bid_escrow_vault(&hash, &alice.pubkey(), first)));
+    let err = h.send(&[withdraw_losing_bid(&alice.pubkey(), &hash, second)], &[&alice]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::WinnerCannotWithdraw));
+}
+
+#[test]
+fn sealed_bid_from_an_earlier_round_neither_blocks_nor_counts_in_the_next() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let (name, hash) = name_hash("sld.dns");
+    let authority = h.payer.pubkey();
+    let alice = h.funded(LAMPORTS_PER_SOL);
+    let bob = h.funded(LAMPORTS_PER_SOL);
+    let salt = [7u8; 32];
+
+    // First round: Alice never reveals, Bob wins.
+    h.send(&[create_auction(&authority, &name, &hash, AUCTION_SEALED)], &[]).unwrap();
+    let first = round(&h, &hash);
+    let alice_commit = sealed_commitment(&hash, &alice.pubkey(), MIN_BID, &salt);
+    h.send(&[commit_sealed_bid(&alice.pubkey(), &hash, first, alice_commit, MIN_BID)], &[&alice]).unwrap();
+    let bob_commit = sealed_commitment(&hash, &bob.pubkey(), MIN_BID, &salt);
+    h.send(&[commit_sealed_bid(&bob.pubkey(), &hash, first, bob_commit, MIN_BID)], &[&bob]).unwrap();
+    h.warp_slots(AUCTION_SLOTS);
+    h.send(&[reveal_sealed_bid(&bob.pubkey(), &hash, first, MIN_BID, salt)], &[&bob]).unwrap();
+    h.warp_slots(AUCTION_SLOTS);
+    h.send(&[settle_sealed_auction(&bob.pubkey(), &treasury, &hash, first)], &[&bob]).unwrap();
+
+    h.warp_secs(TERM_AND_GRACE_SECS + 1);
+    h.send(&[release_premium(&authority, &bob.pubkey(), &hash)], &[]).unwrap();
+    h.send(&[create_auction(&authority, &name, &hash, AUCTION_SEALED)], &[]).unwrap();
+    let second = round(&h, &hash);
+
+    // Alice can bid again while her first-round bid is still open.
+    h.send(&[commit_sealed_bid(&alice.pubkey(), &hash, second, alice_commit, MIN_BID)], &[&alice]).unwrap();
+    let err = h.send(&[reveal_sealed_bid(&alice.pubkey(), &hash, first, MIN_BID, salt)], &[&alice]);
+    assert!(err.is_err(), "a first-round bid cannot be revealed into the second round");
+
+    // The stale unrevealed bid forfeits its deposit without touching the new round's count.
+    let treasury_before = h.balance(&treasury);
+    h.send(&[close_sealed_bid(&alice.pubkey(), &treasury, &hash, first)], &[]).unwrap();
+    assert_eq!(h.balance(&treasury) - treasury_before, MIN_BID);
+    assert!(!h.exists(&pda::sealed_bid(&hash, &alice.pubkey(), first)));
+    assert!(h.exists(&pda::sealed_bid(&hash, &alice.pubkey(), second)));
+    let auction: names::Auction = h.account(&pda::auction(&hash));
+    assert_eq!(auction.sealed_bids, 1);
+}
+
+#[test]
+fn escrow_at_the_pre_round_seeds_is_refunded_and_closed() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let (_, hash) = name_hash("old.dns");
+    let alice = h.funded(LAMPORTS_PER_SOL);
+    let (escrow, vault) = plant_legacy_escrow(&mut h, &hash, &alice.pubkey(), MIN_BID);
+    let held = h.balance(&escrow) + h.balance(&vault);
+
+    let before = h.balance(&alice.pubkey());
+    h.send(&[withdraw_legacy_bid(&alice.pubkey(), &treasury, &hash)], &[&alice]).unwrap();
+    assert_eq!(h.balance(&alice.pubkey()) - before, held);
+    assert!(!h.exists(&escrow));
+    assert!(!h.exists(&vault));
+
+    let err = h
+        .send(&[withdraw_legacy_bid(&alice.pubkey(), &treasury, &hash)], &[&alice])
+        .unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::NothingToWithdraw));
+}
+
+#[test]
+fn uncollected_winning_bid_at_the_pre_round_seeds_goes_to_the_treasury() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let (name, hash) = name_hash("won.dns");
+    let authority = h.payer.pubkey();
+    h.send(&[create_auction(&authority, &name, &hash, AUCTION_OPEN)], &[]).unwrap();
+    let r = round(&h, &hash);
+    let bob = h.funded(LAMPORTS_PER_SOL);
+    h.send(&[place_bid(&bob.pubkey(), &hash, r, MIN_BID)], &[&bob]).unwrap();
+    h.warp_slots(AUCTION_SLOTS);
+    h.send(&[settle_auction(&bob.pubkey(), &treasury, &hash, r)], &[&bob]).unwrap();
+
+    // The old settle left the winning bid in the winner's vault.
+    let (escrow, vault) = plant_legacy_escrow(&mut h, &hash, &bob.pubkey(), MIN_BID);
+    let rent = h.balance(&escrow) + h.balance(&vault) - MIN_BID;
+    let treasury_before = h.balance(&treasury);
+    let bob_before = h.balance(&bob.pubkey());
+    h.send(&[withdraw_legacy_bid(&bob.pubkey(), &treasury, &hash)], &[&bob]).unwrap();
+
+    assert_eq!(h.balance(&treasury) - treasury_before, MIN_BID);
+    assert_eq!(h.balance(&bob.pubkey()) - bob_before, rent);
+    assert!(!h.exists(&escrow));
+    assert!(!h.exists(&vault));
+}
@@ -0,0 +1,176 @@
+//! The `ddns_client::flows` builders sent as-is: premium registration, a
+//! route write for a toll pass holder, and staking through a reward claim.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{solana_program::sysvar, system_program};
+use anchor_spl::token::spl_token;
+use ddns_client::programs::{anchor as toll, names, stake};
+use ddns_client::{flows, instruction, pda};
+use ddns_e2e::names::{init, PREMIUM_PRICE};
+use ddns_e2e::token::{create_ata, create_mint, token_amount};
+use ddns_e2e::{error_code, Harness, LAMPORTS_PER_SOL};
+use solana_signer::Signer;
+
+const EPOCH_SLOTS: u64 = 10;
+const REWARD_PER_EPOCH: u64 = 1_000_000;
+
+/// The harness with the other programs these flows call deployed.
+fn harness() -> Harness {
+    let mut h = Harness::new();
+    h.load("ddns_anchor", toll::ID);
+    h.load("ddns_stake", stake::ID);
+    h
+}
+
+#[test]
+fn register_premium_charges_the_price_and_sets_owner_and_primary() {
+    let mut h = harness();
+    let treasury = init(&mut h);
+    let owner = h.funded(LAMPORTS_PER_SOL);
+    let (name, hash) = ddns_client::name_hash("Echo.dns").unwrap();
+
+    let before = h.balance(&treasury);
+    let ixs = flows::register_premium(&owner.pubkey(), &treasury, "Echo.dns", None).unwrap();
+    h.send(&ixs, &[&owner]).unwrap();
+
+    assert_eq!(h.balance(&treasury) - before, PREMIUM_PRICE);
+    let premium: names::PremiumName = h.account(&pda::names::premium_name(&hash).0);
+    assert_eq!(premium.owner, owner.pubkey());
+    let primary: names::PrimaryName = h.account(&pda::names::primary_name(&owner.pubkey()).0);
+    assert!(primary.is_set);
+    assert_eq!(primary.name_hash, hash);
+    assert_eq!(&primary.name_bytes[..primary.name_len as usize], name.as_bytes());
+
+    // The name is taken until it lapses.
+    let other = h.funded(LAMPORTS_PER_SOL);
+    let ixs = flows::register_premium(&other.pubkey(), &treasury, &name, None).unwrap();
+    assert!(h.send(&ixs, &[&other]).is_err());
+}
+
+#[test]
+fn set_route_writes_and_updates_the_route_of_a_toll_pass_holder() {
+    let mut h = harness();
+    let admin = h.payer.pubkey();
+    let owner = h.funded(LAMPORTS_PER_SOL);
+    issue_toll_pass(&mut h, &owner.pubkey(), "foxtrot");
+    let (_, hash) = ddns_client::name_hash("foxtrot.dns").unwrap();
+    let record = pda::anchor::route_record(&owner.pubkey(), &hash).0;
+
+    h.send(&[flows::set_route(&admin, &owner.pubkey(), "foxtrot.dns", [1; 32], 300).unwrap()], &[])
+        .unwrap();
+    let route: toll::RouteRecord = h.account(&record);
+    assert_eq!(route.owner, owner.pubkey());
+    assert_eq!(route.name_hash, hash);
+    assert_eq!(route.dest_hash, [1; 32]);
+    assert_eq!(route.ttl, 300);
+
+    h.send(&[flows::set_route(&admin, &owner.pubkey(), "foxtrot.dns", [2; 32], 60).unwrap()], &[])
+        .unwrap();
+    let route: toll::RouteRecord = h.account(&record);
+    assert_eq!((route.dest_hash, route.ttl), ([2; 32], 60));
+
+    // Only the config admin writes routes.
+    let ix = flows::set_route(&owner.pubkey(), &owner.pubkey(), "foxtrot.dns", [3; 32], 60).unwrap();
+    let err = h.send(&[ix], &[&owner]).unwrap_err();
+    assert_eq!(error_code(&err), Some(toll::ErrorCode::Unauthorized.into()));
+}
+
+#[test]
+fn stake_and_claim_mint_the_epoch_reward_to_a_sole_staker() {
+    let mut h = harness();
+    let reward_mint = init_stake(&mut h);
+    let owner = h.funded(2 * LAMPORTS_PER_SOL);
+    let vault = pda::stake::stake_vault().0;
+
+    let vault_before = h.balance(&vault);
+    h.send(&[flows::stake(&owner.pubkey(), LAMPORTS_PER_SOL)], &[&owner]).unwrap();
+    assert_eq!(h.balance(&vault) - vault_before, LAMPORTS_PER_SOL);
+    let position: stake::StakePosition = h.account(&pda::stake::stake_position(&owner.pubkey()).0);
+    assert_eq!(position.staked_amount, LAMPORTS_PER_SOL);
+
+    let ata = create_ata(&mut h, &owner.pubkey(), &reward_mint);
+    let err = h.send(&[flows::claim_stake_rewards(&owner.pubkey(), &reward_mint)], &[&owner]).unwrap_err();
+    assert_eq!(error_code(&err), Some(stake::StakeError::NothingToClaim.into()));
+
+    h.warp_slots(EPOCH_SLOTS);
+    h.send(&[flows::claim_stake_rewards(&owner.pubkey(), &reward_mint)], &[&owner]).unwrap();
+    assert_eq!(token_amount(&h, &ata), REWARD_PER_EPOCH);
+    let position: stake::StakePosition = h.account(&pda::stake::stake_position(&owner.pubkey()).0);
+    assert_eq!(position.last_claimed_epoch, h.clock().slot / EPOCH_SLOTS);
+}
+
+/// Initializes the `ddns_anchor` config with the harness payer as admin and
+/// issues `owner` a toll pass for `<label>.dns`.
+fn issue_toll_pass(h: &mut Harness, owner: &Pubkey, label: &str) {
+    let admin = h.payer.pubkey();
+    h.send(
+        &[instruction(
+            toll::ID,
+            toll::accounts::Initialize {
+                config: pda::anchor::config().0,
+                admin,
+                system_program: system_program::ID,
+            },
+            toll::instruction::Initialize { version: 1 },
+        )],
+        &[],
+    )
+    .expect("init anchor config");
+
+    let mint = create_mint(h, &admin, Some(&admin));
+    let token_account = create_ata(h, owner, &mint);
+    let (_, name_hash) = ddns_client::name_hash(&format!("{label}.dns")).unwrap();
+    h.send(
+        &[instruction(
+            toll::ID,
+            toll::accounts::IssueTollPass {
+                config: pda::anchor::config().0,
+                toll_pass: pda::anchor::toll_pass(owner).0,
+                name_record: pda::anchor::name_record(&name_hash).0,
+                nft_mint: mint,
+                nft_token_account: token_account,
+                owner_wallet: *owner,
+                authority: admin,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            toll::instruction::IssueTollPass {
+                label: label.to_string(),
+                name_hash,
+                page_cid_hash: [0; 32],
+                metadata_hash: [0; 32],
+            },
+        )],
+        &[],
+    )
+    .expect("issue toll pass");
+}
+
+/// Initializes `ddns_stake` with no lock and a new reward mint owned by the
+/// program's mint authority PDA, which is returned.
+fn init_stake(h: &mut Harness) -> Pubkey {
+    let reward_mint = create_mint(h, &pda::stake::mint_authority().0, None);
+    h.send(
+        &[instruction(
+            stake::ID,
+            stake::accounts::InitStakeConfig {
+                authority: h.payer.pubkey(),
+                stake_config: pda::stake::config().0,
+                stake_vault: pda::stake::stake_vault().0,
+                mint_authority: pda::stake::mint_authority().0,
+                reward_mint,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            stake::instruction::InitStakeConfig {
+                epoch_len_slots: EPOCH_SLOTS,
+                reward_rate_per_epoch: REWARD_PER_EPOCH,
+                min_lock_epochs: 0,
+            },
+        )],
+        &[],
+    )
+    .expect("init stake config");
+    reward_mint
+}
@@ -0,0 +1,140 @@
+//! Secondary-market listings: the seller keeps the name behind a `listed`
+//! lock, release closes the listing with the name, and a listing can only act
+//! on the name it holds.
+
+use ddns_e2e::names::{
+    buy_listed_premium, delist_premium, init, list_premium, purchase_premium, release_listed_premium, release_premium,
+    renew_premium, transfer_premium, TERM_AND_GRACE_SECS,
+};
+use ddns_e2e::{error_code, name_hash, pda, Harness, LAMPORTS_PER_SOL};
+use ddns_names::{self as names, NamesError};
+use solana_signer::Signer;
+
+const LIST_PRICE: u64 = 500_000_000;
+/// Default premium term: after this a name is in its grace period.
+const TERM_SECS: i64 = 365 * 24 * 60 * 60;
+
+fn code(e: NamesError) -> Option<u32> {
+    Some(e.into())
+}
+
+#[test]
+fn release_of_a_listed_name_closes_the_listing_and_refunds_the_seller() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let (name, hash) = name_hash("alpha.dns");
+    let seller = h.funded(LAMPORTS_PER_SOL);
+    let caller = h.payer.pubkey();
+    let listing = pda::listing(&hash);
+    let premium = pda::premium_name(&hash);
+    let policy = pda::parent_policy(&hash);
+
+    h.send(&[purchase_premium(&seller.pubkey(), &treasury, &name)], &[&seller]).unwrap();
+    h.send(&[list_premium(&seller.pubkey(), &hash, LIST_PRICE)], &[&seller]).unwrap();
+    h.warp_secs(TERM_AND_GRACE_SECS + 1);
+
+    // The name is listed, so the listing has to be released along with it.
+    let err = h.send(&[release_premium(&caller, &seller.pubkey(), &hash)], &[]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::InvalidListing));
+
+    let before = h.balance(&seller.pubkey());
+    let rent = h.balance(&listing) + h.balance(&premium) + h.balance(&policy);
+    h.send(&[release_listed_premium(&caller, &seller.pubkey(), &hash)], &[]).unwrap();
+    assert_eq!(h.balance(&seller.pubkey()) - before, rent);
+    assert!(!h.exists(&listing));
+    assert!(!h.exists(&premium));
+    assert!(!h.exists(&policy));
+}
+
+#[test]
+fn after_lapse_release_and_re_register_the_old_seller_cannot_delist_or_sell() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let (name, hash) = name_hash("bravo.dns");
+    let seller = h.funded(LAMPORTS_PER_SOL);
+    let owner = h.funded(LAMPORTS_PER_SOL);
+    let buyer = h.funded(LAMPORTS_PER_SOL);
+    let caller = h.payer.pubkey();
+
+    h.send(&[purchase_premium(&seller.pubkey(), &treasury, &name)], &[&seller]).unwrap();
+    h.send(&[list_premium(&seller.pubkey(), &hash, LIST_PRICE)], &[&seller]).unwrap();
+    h.warp_secs(TERM_AND_GRACE_SECS + 1);
+    h.send(&[release_listed_premium(&caller, &seller.pubkey(), &hash)], &[]).unwrap();
+    h.send(&[purchase_premium(&owner.pubkey(), &treasury, &name)], &[&owner]).unwrap();
+
+    // Nothing is left for the old seller to act on.
+    assert!(h.send(&[delist_premium(&seller.pubkey(), &hash)], &[&seller]).is_err());
+    let buy = buy_listed_premium(&buyer.pubkey(), &seller.pubkey(), &treasury, &name, &hash, LIST_PRICE);
+    assert!(h.send(&[buy], &[&buyer]).is_err());
+    let premium: names::PremiumName = h.account(&pda::premium_name(&hash));
+    assert_eq!(premium.owner, owner.pubkey());
+
+    // The new owner's own listing works as usual.
+    h.send(&[list_premium(&owner.pubkey(), &hash, LIST_PRICE)], &[&owner]).unwrap();
+    h.send(&[delist_premium(&owner.pubkey(), &hash)], &[&owner]).unwrap();
+    let premium: names::PremiumName = h.account(&pda::premium_name(&hash));
+    assert_eq!(premium.owner, owner.pubkey());
+}
+
+#[test]
+fn a_listing_that_no_longer_holds_the_name_cannot_delist_or_sell_it() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let (name, hash) = name_hash("charlie.dns");
+    let seller = h.funded(LAMPORTS_PER_SOL);
+    let buyer = h.funded(LAMPORTS_PER_SOL);
+
+    h.send(&[purchase_premium(&seller.pubkey(), &treasury, &name)], &[&seller]).unwrap();
+    h.send(&[list_premium(&seller.pubkey(), &hash, LIST_PRICE)], &[&seller]).unwrap();
+    let listing = pda::listing(&hash);
+    let premium = pda::premium_name(&hash);
+
+    // Unlock the name while the listing stays open, the state a listing from
+    // before a lapse and re-registration is left in.
+    let mut account = h.svm.get_account(&premium).unwrap();
+    let mut stale: names::PremiumName = h.account(&premium);
+    stale.listed = false;
+    let mut data = Vec::new();
+    anchor_lang::AccountSerialize::try_serialize(&stale, &mut data).unwrap();
+    account.data[..data.len()].copy_from_slice(&data);
+    h.svm.set_account(premium, account).unwrap();
+    assert!(h.exists(&listing));
+
+    let err = h.send(&[delist_premium(&seller.pubkey(), &hash)], &[&seller]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::InvalidListing));
+    let buy = buy_listed_premium(&buyer.pubkey(), &seller.pubkey(), &treasury, &name, &hash, LIST_PRICE);
+    let err = h.send(&[buy], &[&buyer]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::InvalidListing));
+    let premium: names::PremiumName = h.account(&premium);
+    assert_eq!(premium.owner, seller.pubkey());
+}
+
+#[test]
+fn a_listed_name_stays_with_the_seller_but_is_locked() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let (name, hash) = name_hash("delta.dns");
+    let seller = h.funded(LAMPORTS_PER_SOL);
+    let other = h.funded(LAMPORTS_PER_SOL);
+
+    h.send(&[purchase_premium(&seller.pubkey(), &treasury, &name)], &[&seller]).unwrap();
+    h.send(&[list_premium(&seller.pubkey(), &hash, LIST_PRICE)], &[&seller]).unwrap();
+    let premium: names::PremiumName = h.account(&pda::premium_name(&hash));
+    assert_eq!(premium.owner, seller.pubkey());
+    assert!(premium.listed);
+
+    let err = h.send(&[transfer_premium(&seller.pubkey(), &other.pubkey(), &hash)], &[&seller]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::NameListed));
+    let err = h.send(&[list_premium(&seller.pubkey(), &hash, LIST_PRICE)], &[&seller]).unwrap_err();
+    assert!(error_code(&err).is_some());
+
+    // The seller is still the owner, so they can renew during the grace period.
+    h.warp_secs(TERM_SECS);
+    h.send(&[renew_premium(&seller.pubkey(), &treasury, &hash, 1)], &[&seller]).unwrap();
+
+    h.send(&[delist_premium(&seller.pubkey(), &hash)], &[&seller]).unwrap();
+    h.send(&[transfer_premium(&seller.pubkey(), &other.pubkey(), &hash)], &[&seller]).unwrap();
+    let premium: names::PremiumName = h.account(&pda::premium_name(&hash));
+    assert_eq!(premium.owner, other.pubkey());
+    assert!(!premium.listed);
+}
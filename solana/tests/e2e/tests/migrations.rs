@@ -0,0 +1,86 @@
+//! `migrate_account` on accounts cut back to the layout they were first
+//! deployed with: they grow to the current size with usable defaults.
+
+use ddns_e2e::names::{init, migrate_account, purchase_premium};
+use ddns_e2e::{error_code, name_hash, pda, Harness, LAMPORTS_PER_SOL};
+use ddns_names::{self as names, NamesError};
+use solana_signer::Signer;
+
+// Account sizes, discriminator included, before fields were appended.
+const LEGACY_NAMES_CONFIG_LEN: usize = 8 + 180;
+const LEGACY_PREMIUM_CONFIG_LEN: usize = 8 + 125;
+const LEGACY_PREMIUM_NAME_LEN: usize = 8 + 115;
+
+const TERM_SECS: i64 = 365 * 24 * 60 * 60;
+const GRACE_SECS: i64 = 30 * 24 * 60 * 60;
+
+fn code(e: NamesError) -> Option<u32> {
+    Some(e.into())
+}
+
+fn data_len(h: &Harness, address: &anchor_lang::prelude::Pubkey) -> usize {
+    h.svm.get_account(address).map(|a| a.data.len()).unwrap_or(0)
+}
+
+#[test]
+fn legacy_configs_grow_with_default_symbol_and_terms() {
+    let mut h = Harness::new();
+    init(&mut h);
+    let authority = h.payer.pubkey();
+    let config = pda::config();
+    let premium_config = pda::premium_config();
+    h.truncate(&config, LEGACY_NAMES_CONFIG_LEN);
+    h.truncate(&premium_config, LEGACY_PREMIUM_CONFIG_LEN);
+
+    // Only the config authority may migrate.
+    let stranger = h.funded(LAMPORTS_PER_SOL);
+    let err = h
+        .send(&[migrate_account(&stranger.pubkey(), &config, false)], &[&stranger])
+        .unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::Unauthorized));
+
+    h.send(
+        &[
+            migrate_account(&authority, &config, false),
+            migrate_account(&authority, &premium_config, false),
+        ],
+        &[],
+    )
+    .unwrap();
+    assert_eq!(data_len(&h, &config), 8 + names::NamesConfig::SIZE);
+    let cfg: names::NamesConfig = h.account(&config);
+    assert_eq!(cfg.nft_symbol(), "DNS");
+    assert_eq!(cfg.market_fee_bps, 0);
+    let pcfg: names::PremiumConfig = h.account(&premium_config);
+    assert_eq!((pcfg.period_secs, pcfg.grace_period_secs), (TERM_SECS, GRACE_SECS));
+}
+
+#[test]
+fn legacy_premium_name_gets_a_full_term_and_migrates_once() {
+    let mut h = Harness::new();
+    let treasury = init(&mut h);
+    let authority = h.payer.pubkey();
+    let owner = h.funded(LAMPORTS_PER_SOL);
+    let (_, hash) = name_hash("legacy.dns");
+    let ixs = [purchase_premium(&owner.pubkey(), &treasury, "legacy.dns")];
+    h.send(&ixs, &[&owner]).unwrap();
+    let premium_name = pda::premium_name(&hash);
+    h.truncate(&premium_name, LEGACY_PREMIUM_NAME_LEN);
+
+    // The new expiry comes from the premium config.
+    let err = h.send(&[migrate_account(&authority, &premium_name, false)], &[]).unwrap_err();
+    assert_eq!(error_code(&err), code(NamesError::InvalidMigration));
+
+    h.send(&[migrate_account(&authority, &premium_name, true)], &[]).unwrap();
+    assert_eq!(data_len(&h, &premium_name), 8 + names::PremiumName::SIZE);
+    let premium: names::PremiumName = h.account(&premium_name);
+    assert_eq!(premium.owner, owner.pubkey());
+    assert_eq!(premium.expires_at, h.clock().unix_timestamp + TERM_SECS);
+    assert!(!premium.listed);
+
+    // Already current: a second run changes nothing.
+    h.warp_secs(60);
+    h.send(&[migrate_account(&authority, &premium_name, true)], &[]).unwrap();
+    let again: names::PremiumName = h.account(&premium_name);
+    assert_eq!(again.expires_at, premium.expires_at);
+}